@@ -0,0 +1,104 @@
+//! Freehand and straight-line annotations drawn over the simulation, like a whiteboard. They
+//! survive resets so they can mark features across runs.
+
+use std::fs;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use serde::Serialize;
+
+use crate::{
+    cursor_world_position, keybindings::KeyBindings, measure::MeasureMode, settings::PointerOverUi,
+    InGameCamera, OuterCamera,
+};
+
+const ANNOTATION_COLOR: Color = Color::YELLOW;
+const EXPORT_PATH: &str = "annotations.json";
+/// Cursor movement, in world units, needed before another point is added to a freehand stroke.
+const MIN_POINT_SPACING: f32 = 2.;
+
+/// A line through `points`, in world coordinates.
+#[derive(Component, Serialize)]
+pub struct Annotation {
+    points: Vec<Vec2>,
+    #[serde(skip)]
+    straight: bool,
+}
+
+/// Draws while the annotate key and the left mouse button are held. Adding shift draws a straight
+/// line.
+pub fn annotate(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    measure: Res<MeasureMode>,
+    pointer: Res<PointerOverUi>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    mut commands: Commands,
+    mut annotation_query: Query<&mut Annotation>,
+    mut drawing: Local<Option<Entity>>,
+) {
+    if !keys.pressed(bindings.annotate) || !mouse.pressed(MouseButton::Left) || measure.is_active()
+    {
+        *drawing = None;
+        return;
+    }
+    let Some(cursor) = cursor_world_position(&windows, &camera_query, &in_game_camera_query) else {
+        return;
+    };
+
+    let stroke = drawing.and_then(|entity| annotation_query.get_mut(entity).ok());
+    match stroke {
+        Some(mut annotation) if annotation.straight => {
+            annotation.points[1] = cursor;
+        }
+        Some(mut annotation) => {
+            let last = annotation.points[annotation.points.len() - 1];
+            if last.distance(cursor) >= MIN_POINT_SPACING {
+                annotation.points.push(cursor);
+            }
+        }
+        None if mouse.just_pressed(MouseButton::Left) && !pointer.0 => {
+            let straight = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            let points = if straight {
+                vec![cursor, cursor]
+            } else {
+                vec![cursor]
+            };
+            *drawing = Some(commands.spawn(Annotation { points, straight }).id());
+        }
+        None => {}
+    }
+}
+
+pub fn draw_annotations(annotation_query: Query<&Annotation>, mut gizmos: Gizmos) {
+    for annotation in annotation_query.iter() {
+        gizmos.linestrip_2d(annotation.points.iter().copied(), ANNOTATION_COLOR);
+    }
+}
+
+/// Clears every annotation or writes them to `annotations.json`.
+pub fn manage_annotations(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut commands: Commands,
+    annotation_query: Query<(Entity, &Annotation)>,
+) {
+    if keys.just_pressed(bindings.clear_annotations) {
+        for (entity, _) in annotation_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if keys.just_pressed(bindings.export_annotations) {
+        let annotations: Vec<_> = annotation_query.iter().map(|(_, a)| a).collect();
+        let result = serde_json::to_string_pretty(&annotations)
+            .map_err(|err| err.to_string())
+            .and_then(|json| fs::write(EXPORT_PATH, json).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => info!("Annotations saved to {EXPORT_PATH}"),
+            Err(err) => error!("Cannot save annotations to {EXPORT_PATH}: {err}"),
+        }
+    }
+}
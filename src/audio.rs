@@ -0,0 +1,68 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+};
+
+/// Looping background sound, only spawned with the `audio` feature.
+#[derive(Component)]
+pub struct Ambience {
+    enabled: bool,
+}
+
+pub fn setup_ambience(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    mut hud: ResMut<Hud>,
+) {
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load(config.ambience_path.clone()),
+            settings: PlaybackSettings::LOOP,
+        },
+        Ambience { enabled: true },
+    ));
+    hud.set(
+        "ambience",
+        format!("Press {} to toggle the music", key_name(bindings.music)),
+    );
+}
+
+pub fn toggle_ambience(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut query: Query<&mut Ambience>,
+) {
+    if input.just_pressed(bindings.music) {
+        for mut ambience in query.iter_mut() {
+            ambience.enabled = !ambience.enabled;
+        }
+    }
+}
+
+/// Plays the ambience only while it's switched on and the simulation isn't paused.
+pub fn sync_ambience(time: Res<Time<Virtual>>, query: Query<(&AudioSink, &Ambience)>) {
+    for (sink, ambience) in query.iter() {
+        let should_play = ambience.enabled && !time.is_paused();
+        if should_play && sink.is_paused() {
+            sink.play();
+        } else if !should_play && !sink.is_paused() {
+            sink.pause();
+        }
+    }
+}
+
+pub fn stop_ambience_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    query: Query<&AudioSink, With<Ambience>>,
+) {
+    if exit_events.read().count() > 0 {
+        for sink in query.iter() {
+            sink.stop();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Batch figure generation: a JSON file lists scenarios with their settings, and the batch key
+//! runs each in turn, lets it settle, saves a canvas snapshot named after it and exits once the
+//! last one has been written.
+
+use std::{fs, path::Path};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    capture::{pending_screenshots, SnapshotCanvas},
+    config::SimConfig,
+    keybindings::{key_name, KeyBindings},
+    SimulationReset,
+};
+
+/// One figure of a batch.
+#[derive(Deserialize)]
+pub struct BatchEntry {
+    /// File name of its screenshot, without the `.png`.
+    name: String,
+    /// Seconds of simulation time to run before capturing.
+    #[serde(default = "default_settle_secs")]
+    settle_secs: f32,
+    /// Settings to run it with. Anything left out takes its default, not the current value.
+    #[serde(default)]
+    config: SimConfig,
+}
+
+/// Frames to wait after asking for a snapshot before checking whether it has been written.
+const CAPTURE_FRAMES: u32 = 2;
+
+fn default_settle_secs() -> f32 {
+    3.
+}
+
+/// Where a batch has got to with one of its entries.
+enum Stage {
+    /// Running entry `index`, which started `elapsed` seconds ago.
+    Settling { index: usize, elapsed: f32 },
+    /// Waiting `frames` so far for entry `index`'s snapshot to be written, before moving on.
+    Capturing { index: usize, frames: u32 },
+}
+
+/// The batch being run, if any.
+#[derive(Resource, Default)]
+pub struct Batch(Option<(Vec<BatchEntry>, Stage)>);
+
+pub fn batch_help(config: &SimConfig, bindings: &KeyBindings) -> String {
+    format!(
+        "Press {} to capture every figure in {}",
+        key_name(bindings.run_batch),
+        config.batch_file.display()
+    )
+}
+
+/// Reads the entries of a batch file: a JSON list of objects with a `name`, optionally a
+/// `settle_secs` and a partial `config`, e.g.
+/// `[{"name": "converging", "config": {"scenario": "Converging"}}]`.
+pub fn read_batch(path: &Path) -> Result<Vec<BatchEntry>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let entries: Vec<BatchEntry> =
+        serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+    if entries.is_empty() {
+        return Err("it has no entries".to_string());
+    }
+    Ok(entries)
+}
+
+/// Starts the batch when its key is pressed, then moves it along: each entry's settings are
+/// applied with a reset, and once it has settled a snapshot is requested and the next begins.
+pub fn run_batch(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    time: Res<Time>,
+    mut config: ResMut<SimConfig>,
+    mut batch: ResMut<Batch>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut snapshots: EventWriter<SnapshotCanvas>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if batch.0.is_none() && input.just_pressed(bindings.run_batch) {
+        match read_batch(&config.batch_file) {
+            Ok(entries) => {
+                info!(
+                    "Capturing {} figures from {}",
+                    entries.len(),
+                    config.batch_file.display()
+                );
+                start_entry(&entries[0], &mut config, &mut reset_events);
+                batch.0 = Some((
+                    entries,
+                    Stage::Settling {
+                        index: 0,
+                        elapsed: 0.,
+                    },
+                ));
+            }
+            Err(err) => error!("Cannot run batch {}: {err}", config.batch_file.display()),
+        }
+        return;
+    }
+
+    let Some((entries, stage)) = batch.0.as_mut() else {
+        return;
+    };
+    match *stage {
+        Stage::Settling { index, elapsed } => {
+            let elapsed = elapsed + time.delta_seconds();
+            let entry = &entries[index];
+            if elapsed < entry.settle_secs {
+                *stage = Stage::Settling { index, elapsed };
+                return;
+            }
+            let path = config.screenshot_dir.join(format!("{}.png", entry.name));
+            snapshots.send(SnapshotCanvas(path));
+            *stage = Stage::Capturing { index, frames: 0 };
+        }
+        Stage::Capturing { index, frames } => {
+            // The snapshot is only counted as pending once it has been requested, which can be a
+            // frame after it was asked for, so wait at least that long.
+            if frames < CAPTURE_FRAMES || pending_screenshots() > 0 {
+                *stage = Stage::Capturing {
+                    index,
+                    frames: frames + 1,
+                };
+                return;
+            }
+            match entries.get(index + 1) {
+                Some(next) => {
+                    start_entry(next, &mut config, &mut reset_events);
+                    *stage = Stage::Settling {
+                        index: index + 1,
+                        elapsed: 0.,
+                    };
+                }
+                None => {
+                    info!("Batch complete");
+                    exit.send(AppExit);
+                }
+            }
+        }
+    }
+}
+
+/// Swaps in `entry`'s settings, keeping where screenshots go and the batch file itself.
+fn start_entry(
+    entry: &BatchEntry,
+    config: &mut SimConfig,
+    reset_events: &mut EventWriter<SimulationReset>,
+) {
+    info!("Running {}", entry.name);
+    let mut entry_config = entry.config.clone().validated();
+    entry_config
+        .screenshot_dir
+        .clone_from(&config.screenshot_dir);
+    entry_config.batch_file.clone_from(&config.batch_file);
+    *config = entry_config;
+    reset_events.send(SimulationReset);
+}
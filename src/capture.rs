@@ -0,0 +1,568 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+            ImageDataLayout, Maintain, MapMode, TextureFormat,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        view::screenshot::ScreenshotManager,
+        Render, RenderApp, RenderSet,
+    },
+    tasks::AsyncComputeTaskPool,
+    utils::{Duration, Instant},
+    window::PrimaryWindow,
+};
+use image::{imageops::FilterType, ImageFormat, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig, keybindings::KeyBindings, Canvas, OuterCamera, Receiver, RECEIVER_WIDTH,
+    RES_HEIGHT, RES_WIDTH,
+};
+
+/// Brightness and contrast adjustment applied to exported frames before they're saved. The live
+/// view is left as it is.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExportContrast {
+    /// Saved as rendered.
+    #[default]
+    Off,
+    /// Every channel becomes `value * gain + offset`, in 0 to 1, clamped.
+    Manual { gain: f32, offset: f32 },
+    /// Stretches the brightness histogram so its darkest and brightest 1% land on black and
+    /// white.
+    Auto,
+}
+
+/// Fraction of pixels at each end of the histogram [`ExportContrast::Auto`] lets clip.
+const AUTO_CONTRAST_CLIP: f32 = 0.01;
+
+impl ExportContrast {
+    /// Adjusts `image` in place.
+    fn apply(self, image: &mut RgbImage) {
+        let (gain, offset) = match self {
+            ExportContrast::Off => return,
+            ExportContrast::Manual { gain, offset } => (gain, offset),
+            ExportContrast::Auto => {
+                let Some((low, high)) = brightness_range(image, AUTO_CONTRAST_CLIP) else {
+                    return;
+                };
+                let gain = 1. / (high - low);
+                (gain, -low * gain)
+            }
+        };
+        for channel in image.iter_mut() {
+            let value = *channel as f32 / 255. * gain + offset;
+            *channel = (value.clamp(0., 1.) * 255.).round() as u8;
+        }
+    }
+}
+
+/// Brightness, in 0 to 1, below which and above which `clip` of `image`'s pixels fall. `None` if
+/// the image is blank or flat, when there's nothing to stretch.
+fn brightness_range(image: &RgbImage, clip: f32) -> Option<(f32, f32)> {
+    let mut histogram = [0usize; 256];
+    for pixel in image.pixels() {
+        let [r, g, b] = pixel.0.map(u32::from);
+        histogram[((r * 299 + g * 587 + b * 114) / 1000) as usize] += 1;
+    }
+    let pixels = (image.width() * image.height()) as usize;
+    let clipped = (pixels as f32 * clip) as usize;
+    // First level, counting from one end, past the clipped pixels.
+    let level_at = |levels: Vec<usize>| {
+        let mut seen = 0;
+        levels.into_iter().find(|&level| {
+            seen += histogram[level];
+            seen > clipped
+        })
+    };
+    let low = level_at((0..256).collect())?;
+    let high = level_at((0..256).rev().collect())?;
+    (high > low).then(|| (low as f32 / 255., high as f32 / 255.))
+}
+
+/// Asks [`snapshot_canvas`] to save the canvas to the given path.
+#[derive(Event)]
+pub struct SnapshotCanvas(pub PathBuf);
+
+/// What a screenshot is of, substituted for `{kind}` in the filename template.
+#[derive(Clone, Copy, Debug)]
+pub enum ScreenshotKind {
+    /// A frame of the `gifcreate` sequence.
+    Sequence,
+    /// A snapshot of the canvas.
+    Canvas,
+    /// The canvas captured once every receiver finished plotting.
+    Complete,
+    /// The whole window with the figure's annotations.
+    Figure,
+    /// A frame of a parameter sweep.
+    Sweep,
+    /// The wave field exported for NumPy, saved with its own extension.
+    Field,
+    /// The phase map's long exposure exported for NumPy, saved with its own extension.
+    PhaseMap,
+    /// The canvas captured when the wavefront reached the `phase_lock` line.
+    PhaseLock,
+}
+
+impl ScreenshotKind {
+    fn name(self) -> &'static str {
+        match self {
+            ScreenshotKind::Sequence => "screenshot",
+            ScreenshotKind::Canvas => "canvas",
+            ScreenshotKind::Complete => "complete",
+            ScreenshotKind::Figure => "figure",
+            ScreenshotKind::Sweep => "sweep",
+            ScreenshotKind::Field => "field",
+            ScreenshotKind::PhaseMap => "phase_map",
+            ScreenshotKind::PhaseLock => "phase_lock",
+        }
+    }
+}
+
+/// Where to save the `num`th screenshot of `kind`: the `screenshot_template` in the
+/// `screenshot_dir`, with `{kind}`, `{num}` (zero padded to three digits), `{scenario}` and
+/// `{timestamp}` (seconds since the Unix epoch) filled in.
+pub fn screenshot_path(config: &SimConfig, kind: ScreenshotKind, num: u32) -> PathBuf {
+    fill_template(config, kind, &format!("{num:0>3}"))
+}
+
+/// Where to save frame `frame` of the `gifcreate` sequence when it's named by the frame clock,
+/// like [`screenshot_path`] but with `{num}` padded to six digits so a long sequence still sorts
+/// in order.
+pub fn sequence_frame_path(config: &SimConfig, frame: u32) -> PathBuf {
+    fill_template(config, ScreenshotKind::Sequence, &format!("{frame:0>6}"))
+}
+
+fn fill_template(config: &SimConfig, kind: ScreenshotKind, num: &str) -> PathBuf {
+    let mut name = config
+        .screenshot_template
+        .replace("{kind}", kind.name())
+        .replace("{num}", num)
+        .replace("{scenario}", &format!("{:?}", config.scenario));
+    if name.contains("{timestamp}") {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        name = name.replace("{timestamp}", &timestamp.to_string());
+    }
+    config.screenshot_dir.join(name)
+}
+
+/// Region of a window screenshot to keep, in physical pixels, and the size to resample it to.
+#[derive(Clone, Copy)]
+pub struct Crop {
+    pub min: UVec2,
+    pub size: UVec2,
+    pub output_size: UVec2,
+}
+
+/// A crop of the whole of a window `size` physical pixels across, scaled down to fit within
+/// `max_size` if it's given, keeping its aspect ratio. `None` if it already fits.
+pub fn fit_within(size: UVec2, max_size: Option<[u32; 2]>) -> Option<Crop> {
+    let [max_width, max_height] = max_size?;
+    let scale = (max_width as f32 / size.x as f32).min(max_height as f32 / size.y as f32);
+    if scale >= 1. {
+        return None;
+    }
+    let output_size = (size.as_vec2() * scale).round().as_uvec2().max(UVec2::ONE);
+    Some(Crop {
+        min: UVec2::ZERO,
+        size,
+        output_size,
+    })
+}
+
+/// Screenshots requested but not yet written. They're written from a callback once the frame has
+/// been read back from the GPU, which can be after the app has been asked to exit.
+static PENDING_SCREENSHOTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Longest exiting waits for the screenshots still being written.
+const EXIT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of screenshots requested that haven't been written yet.
+pub fn pending_screenshots() -> usize {
+    PENDING_SCREENSHOTS.load(Ordering::Acquire)
+}
+
+/// Writes a file by calling `write` with a temporary path next to `path`, then renaming it into
+/// place once `write` has succeeded. Exiting or failing part way through leaves at most a stray
+/// `.partial` file, never a truncated `path`: any earlier file at `path` stays intact until the
+/// new one is complete, so whatever is at `path` can always be read back in full.
+pub fn write_via_temp(
+    path: &Path,
+    write: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("cannot create {}: {err}", dir.display()))?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".partial");
+    let temp = PathBuf::from(temp);
+    if let Err(err) = write(&temp) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    fs::rename(&temp, path).map_err(|err| err.to_string())
+}
+
+/// Holds up exiting until the screenshots still being written have been, for up to
+/// [`EXIT_WRITE_TIMEOUT`], and warns about any left after that, since those are lost. Runs in
+/// `Last`, after closing the window has sent `AppExit`, since the app stops after that update.
+pub fn finish_screenshots_on_exit(mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    // The callbacks write from the async compute threads, so they carry on while this waits.
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let deadline = Instant::now() + EXIT_WRITE_TIMEOUT;
+        while pending_screenshots() > 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    let pending = pending_screenshots();
+    if pending > 0 {
+        warn!("Exiting with {pending} screenshot(s) still being written; they won't be saved");
+    }
+}
+
+/// Takes a screenshot of `window` at the end of this frame and writes it to `path` as a PNG,
+/// optionally cropping it first, with `contrast` applied.
+pub fn save_window(
+    screenshot_manager: &mut ScreenshotManager,
+    window: Entity,
+    path: PathBuf,
+    crop: Option<Crop>,
+    contrast: ExportContrast,
+) {
+    let result = screenshot_manager.take_screenshot(window, move |image| {
+        let image = match image.try_into_dynamic() {
+            Ok(image) => image,
+            Err(err) => {
+                error!("Cannot convert screenshot {}: {err}", path.display());
+                PENDING_SCREENSHOTS.fetch_sub(1, Ordering::AcqRel);
+                return;
+            }
+        };
+        let image = match crop {
+            Some(crop) => image
+                .crop_imm(crop.min.x, crop.min.y, crop.size.x, crop.size.y)
+                .resize_exact(crop.output_size.x, crop.output_size.y, FilterType::Triangle),
+            None => image,
+        };
+        // The alpha channel is dropped since it holds brightness values rather than
+        // transparency when HDR is enabled.
+        let mut image = image.to_rgb8();
+        contrast.apply(&mut image);
+        write_png(image, &path);
+        PENDING_SCREENSHOTS.fetch_sub(1, Ordering::AcqRel);
+    });
+    match result {
+        Ok(()) => {
+            PENDING_SCREENSHOTS.fetch_add(1, Ordering::AcqRel);
+        }
+        Err(_) => warn!("A screenshot was already requested this frame"),
+    }
+}
+
+/// Writes `image` to `path` with [`write_via_temp`].
+fn write_png(image: RgbImage, path: &Path) {
+    let result = write_via_temp(path, |temp| {
+        image
+            .save_with_format(temp, ImageFormat::Png)
+            .map_err(|err| err.to_string())
+    });
+    match result {
+        Ok(()) => info!("Screenshot saved to {}", path.display()),
+        Err(err) => error!("Cannot save screenshot {}: {err}", path.display()),
+    }
+}
+
+/// A canvas snapshot waiting to be read back from the GPU.
+#[derive(Clone)]
+struct CanvasReadback {
+    image: Handle<Image>,
+    path: PathBuf,
+    contrast: ExportContrast,
+}
+
+/// Canvas snapshots requested this frame, extracted to the render world to be read back once
+/// the frame has been drawn.
+#[derive(Resource, Clone, Default, ExtractResource)]
+pub struct CanvasReadbacks(Vec<CanvasReadback>);
+
+/// Reads the canvas render target back from the GPU for [`snapshot_canvas`].
+pub struct CanvasReadbackPlugin;
+
+impl Plugin for CanvasReadbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CanvasReadbacks>()
+            .add_plugins(ExtractResourcePlugin::<CanvasReadbacks>::default())
+            .add_systems(First, clear_canvas_readbacks);
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(
+                Render,
+                read_back_canvases
+                    .after(RenderSet::Render)
+                    .before(RenderSet::Cleanup),
+            );
+        }
+    }
+}
+
+/// Forgets last frame's requests once they've been extracted, so each is read back only once.
+fn clear_canvas_readbacks(mut readbacks: ResMut<CanvasReadbacks>) {
+    if !readbacks.0.is_empty() {
+        readbacks.0.clear();
+    }
+}
+
+/// Reads back each requested canvas now this frame has been drawn, and writes it out from the
+/// async compute threads.
+fn read_back_canvases(
+    mut readbacks: ResMut<CanvasReadbacks>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for CanvasReadback {
+        image,
+        path,
+        contrast,
+    } in readbacks.0.drain(..)
+    {
+        match read_back_image(&image, &images, &render_device, &render_queue) {
+            Ok(mut image) => {
+                AsyncComputeTaskPool::get()
+                    .spawn(async move {
+                        contrast.apply(&mut image);
+                        write_png(image, &path);
+                        PENDING_SCREENSHOTS.fetch_sub(1, Ordering::AcqRel);
+                    })
+                    .detach();
+            }
+            Err(err) => {
+                error!("Cannot read back the canvas for {}: {err}", path.display());
+                PENDING_SCREENSHOTS.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+    }
+}
+
+/// Copies the 8-bit RGBA or BGRA texture of `image` into a buffer and blocks until it can be read.
+fn read_back_image(
+    image: &Handle<Image>,
+    images: &RenderAssets<Image>,
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+) -> Result<RgbImage, String> {
+    let gpu_image = images.get(image).ok_or("it hasn't been drawn yet")?;
+    let bgra = match gpu_image.texture_format {
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        format => return Err(format!("{format:?} textures aren't supported")),
+    };
+    let size = gpu_image.size.as_uvec2();
+    let row_bytes = size.x as usize * 4;
+    // Each row of a texture copy has to start on an aligned offset, so rows are padded.
+    let padded_row_bytes = RenderDevice::align_copy_bytes_per_row(row_bytes);
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("canvas_readback"),
+        size: (padded_row_bytes * size.y as usize) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        gpu_image.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes as u32),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+    );
+    render_queue.submit([encoder.finish()]);
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    render_device.poll(Maintain::Wait);
+    receiver
+        .try_recv()
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())?;
+
+    let mut rgb = RgbImage::new(size.x, size.y);
+    let data = slice.get_mapped_range();
+    for (y, row) in data.chunks_exact(padded_row_bytes).enumerate() {
+        for (x, pixel) in row[..row_bytes].chunks_exact(4).enumerate() {
+            let pixel = if bgra {
+                [pixel[2], pixel[1], pixel[0]]
+            } else {
+                [pixel[0], pixel[1], pixel[2]]
+            };
+            rgb.put_pixel(x as u32, y as u32, Rgb(pixel));
+        }
+    }
+    drop(data);
+    buffer.unmap();
+    Ok(rgb)
+}
+
+/// Saves only the pixel-perfect canvas, without any of the UI drawn over it, at the simulation's
+/// native `RES_WIDTH` x `RES_HEIGHT` resolution.
+///
+/// The canvas render target is read back from the GPU once the frame has been drawn, so it's
+/// saved pixel for pixel however the window is sized or scaled. The `Direct` render pipeline has
+/// no canvas, so there the window is captured with the UI hidden for one frame instead, and the
+/// canvas area is cropped out and resampled back to its native size.
+///
+/// Snapshots are taken when the snapshot key (F12 by default) is pressed or a [`SnapshotCanvas`]
+/// event is sent.
+pub fn snapshot_canvas(
+    config: Res<SimConfig>,
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut requests: EventReader<SnapshotCanvas>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    mut ui_query: Query<(Entity, &mut Visibility), (With<Node>, Without<Parent>)>,
+    canvas_query: Query<&Handle<Image>, With<Canvas>>,
+    mut readbacks: ResMut<CanvasReadbacks>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut hidden_ui: Local<Vec<Entity>>,
+    mut counter: Local<u32>,
+) {
+    // Bring back the UI hidden for last frame's snapshot.
+    for entity in hidden_ui.drain(..) {
+        if let Ok((_, mut visibility)) = ui_query.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+
+    let requested = requests.read().last().map(|request| request.0.clone());
+    // With shift it's the field export's key instead.
+    if requested.is_none()
+        && (!input.just_pressed(bindings.snapshot)
+            || input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]))
+    {
+        return;
+    }
+    let path = match requested {
+        Some(path) => path,
+        None => {
+            let path = screenshot_path(&config, ScreenshotKind::Canvas, *counter);
+            *counter += 1;
+            path
+        }
+    };
+    if let Ok(canvas) = canvas_query.get_single() {
+        // Counted now so exiting before it's been read back still waits for it.
+        PENDING_SCREENSHOTS.fetch_add(1, Ordering::AcqRel);
+        readbacks.0.push(CanvasReadback {
+            image: canvas.clone(),
+            path,
+            contrast: config.export_contrast,
+        });
+        return;
+    }
+
+    let Ok((window_entity, window)) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let half_canvas = Vec3::new(RES_WIDTH as f32, RES_HEIGHT as f32, 0.) / 2.;
+    let (Some(top_left), Some(bottom_right)) = (
+        camera.world_to_viewport(
+            camera_transform,
+            Vec3::new(-half_canvas.x, half_canvas.y, 0.),
+        ),
+        camera.world_to_viewport(
+            camera_transform,
+            Vec3::new(half_canvas.x, -half_canvas.y, 0.),
+        ),
+    ) else {
+        return;
+    };
+
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    let min = (top_left * window.scale_factor()).clamp(Vec2::ZERO, window_size);
+    let max = (bottom_right * window.scale_factor()).clamp(Vec2::ZERO, window_size);
+    let size = (max - min).as_uvec2();
+    if size.x == 0 || size.y == 0 {
+        return;
+    }
+
+    for (entity, mut visibility) in ui_query.iter_mut() {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+            hidden_ui.push(entity);
+        }
+    }
+
+    save_window(
+        &mut screenshot_manager,
+        window_entity,
+        path,
+        Some(Crop {
+            min: min.as_uvec2(),
+            size,
+            output_size: UVec2::new(RES_WIDTH, RES_HEIGHT),
+        }),
+        config.export_contrast,
+    );
+}
+
+/// Snapshots the canvas as a `complete` screenshot once every receiver has plotted across its
+/// full width. Enabled by `auto_capture_on_complete`.
+pub fn capture_on_complete(
+    config: Res<SimConfig>,
+    rx_query: Query<&Receiver>,
+    mut requests: EventWriter<SnapshotCanvas>,
+    mut captured: Local<bool>,
+    mut counter: Local<u32>,
+) {
+    if !config.auto_capture_on_complete {
+        return;
+    }
+    let complete = !rx_query.is_empty()
+        && rx_query
+            .iter()
+            .all(|rx| rx.current_draw_position > 2. * RECEIVER_WIDTH);
+    // Receivers are rebuilt on reset, so the next run clears this and gets captured too.
+    if complete && !*captured {
+        let path = screenshot_path(&config, ScreenshotKind::Complete, *counter);
+        requests.send(SnapshotCanvas(path));
+        *counter += 1;
+    }
+    *captured = complete;
+}
@@ -0,0 +1,71 @@
+//! Which clock drives the simulation. Every system reads bevy's `Time`, so rather than threading
+//! a separate clock through all of them the frame clock swaps how bevy advances time: a fixed
+//! step per frame instead of the wall-clock time since the last one. Timers, phases and easing
+//! all follow, so a run is the same frame for frame on any machine.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+};
+
+/// What time advances by each frame.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockMode {
+    /// The wall-clock time since the last frame, so the simulation keeps up with real time.
+    #[default]
+    RealTime,
+    /// Exactly one step of `1 / frame_clock_fps` seconds per frame, however long the frame took.
+    Frames,
+}
+
+impl ClockMode {
+    fn next(self) -> Self {
+        match self {
+            ClockMode::RealTime => ClockMode::Frames,
+            ClockMode::Frames => ClockMode::RealTime,
+        }
+    }
+
+    /// How bevy should advance its clocks in this mode.
+    pub fn update_strategy(self, config: &SimConfig) -> TimeUpdateStrategy {
+        match self {
+            ClockMode::RealTime => TimeUpdateStrategy::Automatic,
+            ClockMode::Frames => {
+                TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(config.frame_step()))
+            }
+        }
+    }
+}
+
+pub fn clock_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.clock_mode);
+    match config.clock_mode {
+        ClockMode::RealTime => format!("Clock: real time (press {key} to step by frames)"),
+        ClockMode::Frames => format!(
+            "Clock: {} fps frames (press {key} for real time)",
+            config.frame_clock_fps
+        ),
+    }
+}
+
+/// Switches between the real-time and frame clocks. The switch takes effect from the next frame,
+/// and the simulation carries on from where it is rather than restarting.
+pub fn toggle_clock_mode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.clock_mode) {
+        config.clock_mode = config.clock_mode.next();
+        *strategy = config.clock_mode.update_strategy(&config);
+        hud.set("clock_mode", clock_label(&config, &bindings));
+    }
+}
@@ -0,0 +1,184 @@
+//! Compare mode: the scenario runs twice, squeezed into the left and right halves of the canvas,
+//! with its own frequency and receiver speed on each side. Both halves share the same clock.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    SignalParticle, SimulationReset, SimulationSpec, Transmitter, PARTICLE_FREQUENCY,
+    RECEIVER_SPEED, RES_HEIGHT, RES_WIDTH,
+};
+
+const DIVIDER_COLOR: Color = Color::rgba(1., 1., 1., 0.5);
+const FREQUENCY_STEP: f32 = 0.5;
+const RECEIVER_SPEED_STEP: f32 = 25.;
+
+/// Which half of the canvas a transmitter or receiver belongs to in compare mode: 0 for left, 1
+/// for right.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct Side(pub usize);
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct SideParams {
+    pub frequency: f32,
+    pub receiver_speed: f32,
+}
+
+#[derive(Resource)]
+pub struct CompareMode {
+    active: bool,
+    /// Side the parameter keys currently edit.
+    editing: usize,
+    sides: [SideParams; 2],
+}
+
+impl Default for CompareMode {
+    fn default() -> Self {
+        let params = SideParams {
+            frequency: PARTICLE_FREQUENCY,
+            receiver_speed: RECEIVER_SPEED,
+        };
+        Self {
+            active: false,
+            editing: 0,
+            sides: [params; 2],
+        }
+    }
+}
+
+impl CompareMode {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Parameters of the left and right sides while compare mode is on.
+    pub fn active_sides(&self) -> Option<[SideParams; 2]> {
+        self.active.then_some(self.sides)
+    }
+
+    /// Turns compare mode on with `sides`, or off with `None`.
+    pub fn set_active_sides(&mut self, sides: Option<[SideParams; 2]>) {
+        self.active = sides.is_some();
+        if let Some(sides) = sides {
+            self.sides = sides;
+        }
+    }
+
+    /// Two copies of `specs`, one per side, with their x positions halved and shifted into that
+    /// side's half of the canvas.
+    pub fn split(&self, specs: Vec<SimulationSpec>) -> Vec<SimulationSpec> {
+        let quarter_width = RES_WIDTH as f32 / 4.;
+        let mut split = Vec::with_capacity(specs.len() * 2);
+        for (side, params) in self.sides.iter().enumerate() {
+            let center = if side == 0 {
+                -quarter_width
+            } else {
+                quarter_width
+            };
+            split.extend(specs.iter().map(|spec| SimulationSpec {
+                rx_start_x: center + spec.rx_start_x / 2.,
+                tx_x: center + spec.tx_x / 2.,
+                frequency: params.frequency,
+                rx_speed: params.receiver_speed,
+                side: Some(side),
+                ..*spec
+            }));
+        }
+        split
+    }
+}
+
+/// The compare key switches compare mode on and off. While it's on, the left and right keys
+/// (the arrows by default) pick the side to edit, up and down change its frequency, and shift
+/// with up and down change its receiver speed. Every change restarts the simulation.
+pub fn control_compare_mode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut compare: ResMut<CompareMode>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.compare) {
+        compare.active = !compare.active;
+        reset_events.send(SimulationReset);
+    } else if !compare.active {
+        return;
+    } else if input.just_pressed(bindings.compare_left) {
+        compare.editing = 0;
+    } else if input.just_pressed(bindings.compare_right) {
+        compare.editing = 1;
+    } else {
+        let step = if input.just_pressed(bindings.compare_increase) {
+            1.
+        } else if input.just_pressed(bindings.compare_decrease) {
+            -1.
+        } else {
+            return;
+        };
+        let editing = compare.editing;
+        let params = &mut compare.sides[editing];
+        if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            params.receiver_speed = (params.receiver_speed + step * RECEIVER_SPEED_STEP).max(0.);
+        } else {
+            params.frequency = (params.frequency + step * FREQUENCY_STEP).max(FREQUENCY_STEP);
+        }
+        reset_events.send(SimulationReset);
+    }
+    hud.set("compare", compare_label(&compare, &bindings));
+}
+
+pub fn compare_label(compare: &CompareMode, bindings: &KeyBindings) -> String {
+    let toggle = key_name(bindings.compare);
+    if !compare.active {
+        return format!("Press {toggle} to compare two parameter sets side by side");
+    }
+    let side = |index: usize| {
+        let params = compare.sides[index];
+        let marker = if compare.editing == index { "*" } else { "" };
+        format!(
+            "{marker}{:.1} Hz, rx {:.0} px/s",
+            params.frequency, params.receiver_speed
+        )
+    };
+    format!(
+        "Compare: left {} | right {} ({}/{}/{}/{} to edit, {toggle} to exit)",
+        side(0),
+        side(1),
+        key_name(bindings.compare_left),
+        key_name(bindings.compare_right),
+        key_name(bindings.compare_increase),
+        key_name(bindings.compare_decrease),
+    )
+}
+
+/// Draws the line between the halves and removes particles that cross it, so each side's
+/// receivers only hear their own transmitters.
+pub fn separate_sides(
+    compare: Res<CompareMode>,
+    mut commands: Commands,
+    tx_query: Query<(Entity, &Side, &Children), With<Transmitter>>,
+    particle_query: Query<(Entity, &GlobalTransform), With<SignalParticle>>,
+    mut gizmos: Gizmos,
+) {
+    if !compare.active {
+        return;
+    }
+    let half_height = RES_HEIGHT as f32 / 2.;
+    gizmos.line_2d(
+        Vec2::new(0., -half_height),
+        Vec2::new(0., half_height),
+        DIVIDER_COLOR,
+    );
+
+    for (tx_entity, side, children) in tx_query.iter() {
+        for (entity, transform) in particle_query.iter_many(children) {
+            let x = transform.translation().x;
+            if (side.0 == 0 && x > 0.) || (side.0 == 1 && x < 0.) {
+                commands.entity(tx_entity).remove_children(&[entity]);
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
@@ -0,0 +1,1300 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capture::ExportContrast,
+    clock::ClockMode,
+    field_export::FieldExportFormat,
+    heatmap::FieldMode,
+    hud::HudAnchor,
+    keybindings::{key_name, KeyBindings},
+    lesson::LessonKind,
+    marker::{ParticleFill, ParticleShape, PlotMarker},
+    palette::Palette,
+    param_sweep::ParameterSweep,
+    physics::{self, ClipShape, Dispersion, DopplerFormula, Harmonic, WaveShape},
+    placement::{PathArc, Placement, WorldBounds},
+    receiver_view::{ReceiverOrientation, ReceiverView},
+    scenario::Scenario,
+};
+
+/// Config file read at startup when `DOPPL_CONFIG` isn't set.
+const DEFAULT_CONFIG_PATH: &str = "doppl.ron";
+/// Batch of figures the batch key reads unless `batch_file` is set.
+const DEFAULT_BATCH_FILE: &str = "batch.json";
+const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+const DEFAULT_PRESET_DIR: &str = "presets";
+/// Elements and degrees of phase between them of each transmitter's array in the
+/// [`Scenario::BeamSteering`] scenario, unless an array is configured.
+const BEAM_STEERING_ELEMENTS: u32 = 8;
+const BEAM_STEERING_PHASE_STEP: f32 = 60.;
+
+/// Runtime settings for the simulation.
+///
+/// Loaded from a RON file at startup (see [`SimConfig::load`]); any field missing from the file
+/// keeps its default value.
+#[derive(Resource, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct SimConfig {
+    /// Give every transmitter (and its particles and plot points) its own color instead of the
+    /// shared particle color.
+    pub color_per_transmitter: bool,
+    /// Colors used for particles, transmitters, receivers and plots. Switch at runtime with 'b'.
+    pub palette: Palette,
+    /// RGB color for every particle, overriding the palette and `color_per_transmitter`. `None`
+    /// takes the color from those.
+    pub particle_color: Option<[f32; 3]>,
+    /// Radius of newly spawned particles, in pixels.
+    pub particle_radius: f32,
+    /// Shape of the particles. Only read at startup.
+    pub particle_shape: ParticleShape,
+    /// Draws particles solid or hollow. Toggle at runtime with Insert.
+    pub particle_fill: ParticleFill,
+    /// Fades particles in as they leave their transmitter and out as they near a receiver, for
+    /// a softer looking wave. Toggle at runtime with ';'.
+    pub age_fade: bool,
+    /// Seconds a particle takes to fade in after it's emitted, with `age_fade`.
+    pub fade_in_secs: f32,
+    /// Seconds before reaching a receiver that a particle starts fading out, with `age_fade`.
+    pub fade_out_secs: f32,
+    /// Font in the assets folder for all on-screen text, e.g. `"fonts/NotoSans.ttf"`, in place
+    /// of the built-in one, which only covers Latin characters. Only read at startup.
+    pub font: Option<String>,
+    /// Color particles by their frequency instead, like visible light: red at `spectrum_range[0]`
+    /// and below through to violet at `spectrum_range[1]` and above.
+    pub spectrum_colors: bool,
+    /// Frequencies, in Hz, mapped onto the ends of the visible spectrum.
+    pub spectrum_range: [f32; 2],
+    /// Color each of a transmitter's emissions the next of this many hues round the color wheel,
+    /// so successive wavefronts can be told apart and one crest followed all the way to the
+    /// receiver. Takes over from `spectrum_colors`. `None` leaves the particles their usual color.
+    pub emission_hues: Option<u32>,
+    /// Seconds between automatic resets of the simulation, or `None` to never reset on a timer.
+    pub reset_interval: Option<f32>,
+    /// Seconds of simulation time after each start or reset to pause the simulation at, leaving
+    /// a still final state on screen, or `None` to keep running. Resume with the pause key.
+    pub freeze_after: Option<f32>,
+    /// Keep each run's receiver traces, greyed out, after a reset instead of clearing them.
+    pub persist_traces: bool,
+    /// How many old traces to keep when `persist_traces` is on.
+    pub max_persisted_traces: usize,
+    /// Speed waves travel at, in pixels per second. Streams travel towards -x when negative. 0
+    /// gives a standing wave where particles oscillate in place; other speeds are kept at least
+    /// `physics::MIN_WAVE_SPEED` from zero.
+    pub wave_speed: f32,
+    /// Formula used to predict the frequency each receiver observes.
+    pub doppler_formula: DopplerFormula,
+    /// How the wave's frequency depends on its wavelength. Dispersive waves are drawn as packets
+    /// that travel at the group speed while the particles travel at the wave speed. Scenarios
+    /// may set it when this is `None`.
+    pub dispersion: Dispersion,
+    /// Place moving receivers by how long they've been moving rather than adding up each frame's
+    /// movement, so they don't drift over long runs.
+    pub drift_free_motion: bool,
+    /// Left and right x positions that receivers moving back and forth turn round at, in place
+    /// of their scenario's.
+    pub ping_pong_bounds: Option<(f32, f32)>,
+    /// Steps each frame's particle movement is split into. Particles stop at the first step that
+    /// reaches a receiver, so with more steps fast particles or long frames are sampled closer to
+    /// the receiver's edge rather than wherever the whole frame's movement left them.
+    pub particle_substeps: u32,
+    /// Signal the transmitters send. Scenarios may set it when this is `Sine`.
+    pub waveform: Waveform,
+    /// Signal each transmitter, in the order they were created, sends in place of `waveform`.
+    /// Transmitters without an entry send `waveform`.
+    pub transmitter_waveforms: Vec<Waveform>,
+    /// Largest signal receivers can register, like a saturating detector. Larger values are
+    /// clipped to it as they're sampled, which distorts the trace and adds harmonics. `inf`
+    /// never clips.
+    pub clip_level: f32,
+    /// Whether signals are cut off flat at `clip_level` or rounded off into it.
+    pub clip_shape: ClipShape,
+    /// Speed, in pixels per second, that no transmitter or receiver should reach. A warning is
+    /// shown when one gets close to it. Defaults to the wave speed, where the classical formula
+    /// diverges.
+    pub speed_limit: Option<f32>,
+    /// Asset path of the looping background sound played with the `audio` feature.
+    pub ambience_path: String,
+    /// Part of the name of the MIDI output port the `midi` feature plays the selected receiver's
+    /// observed frequency on. The first port is used if it isn't set.
+    pub midi_port: Option<String>,
+    /// MIDI channel notes are sent on, from 0 to 15.
+    pub midi_channel: u8,
+    /// Observed frequencies are multiplied by this to bring them up into the audible range
+    /// before being rounded to the nearest MIDI note. The default plays 2 Hz as A4.
+    pub midi_pitch_scale: f32,
+    /// Seconds a particle takes to shrink away after reaching a receiver. 0 removes it instantly,
+    /// which is cheaper.
+    pub despawn_animation_secs: f32,
+    /// Upper bound on particles alive at once. When full, the oldest particle is removed to make
+    /// room for each new one.
+    pub max_particles: usize,
+    /// Upper bound on receivers alive at once, or `None` for no limit. When exceeded, the oldest
+    /// receivers are removed along with their transmitters and plots.
+    pub max_receivers: Option<usize>,
+    /// What receivers plot when the simulation starts. Each receiver can be switched at runtime.
+    pub receiver_view: ReceiverView,
+    /// Which way receivers lie. `Vertical` turns every receiver on end; otherwise scenarios may
+    /// turn some of them.
+    pub receiver_orientation: ReceiverOrientation,
+    /// Pixels around each receiver's catch area in which particles are already sampled, each just
+    /// once, rather than when they reach it. Picks up particles that would pass close by without
+    /// hitting, such as point-source rays, for a denser trace at the cost of reading values a
+    /// little early or off to the side. 0 only samples what the receiver catches.
+    pub capture_band: f32,
+    /// Log the world position of every left click to the console, along with the phase,
+    /// velocity and frequency of the nearest particle, receiver and transmitter.
+    pub click_probe: bool,
+    /// How far from a click, in pixels, the probe looks for things to log.
+    pub probe_radius: f32,
+    /// Pixels apart that overlapping particles are drawn while spread apart for debugging with
+    /// numpad '.'. Their actual positions don't change.
+    pub explode_spacing: f32,
+    /// Most samples each receiver keeps behind its plot, or `None` for no limit. Once full, the
+    /// oldest is dropped for each new one, so a plot that never finishes doesn't grow without
+    /// bound. Views and statistics only see the samples kept.
+    pub receiver_buffer_size: Option<usize>,
+    /// Number of samples in the sliding window used by the RMS and envelope views.
+    pub receiver_window: usize,
+    /// How far above and below the source frequency the frequency view reaches at the edges of a
+    /// receiver, as a fraction of the source frequency.
+    pub frequency_plot_span: f32,
+    /// Shades the samples making up the latest value of the RMS and envelope views. Toggle at
+    /// runtime with '''.
+    pub window_band: bool,
+    /// RGBA color of the window band; its alpha sets how strongly it shades the trace.
+    pub window_band_color: [f32; 4],
+    /// RGBA color of the panel drawn behind each receiver's plot, or `None` for no panel.
+    pub plot_panel: Option<[f32; 4]>,
+    /// Shape of the points receivers plot.
+    pub plot_marker: PlotMarker,
+    /// Draws a Catmull-Rom spline through each raw plot's samples over the points. Toggle at
+    /// runtime with F4.
+    pub smooth_trace: bool,
+    /// Line segments the smooth trace draws between each pair of samples.
+    pub smooth_trace_steps: u32,
+    /// Stretches or squashes each receiver's plot vertically so its trace fills the receiver,
+    /// for signals made much smaller or larger by clipping or amplitude changes. Plots are no
+    /// longer comparable with each other while it's on. Toggle at runtime with F11.
+    pub auto_scale_plots: bool,
+    /// Receivers only plot every this many particles they receive, lowering their sample rate to
+    /// show aliasing. Change at runtime with 'd' and shift+'d'.
+    pub plot_decimation: u32,
+    /// Seconds a receiver takes to recover after each sample, like a detector's dead time.
+    /// Particles arriving in that time are ignored, so once it nears the wave period the trace
+    /// is undersampled and aliases. 0 samples every particle.
+    pub dead_time_secs: f32,
+    /// Color each plot point by how fast the particle it came from was moving across its path as
+    /// it arrived: blue falling, white at a crest or trough, red rising. Shows the slope of the
+    /// wave at each sample as well as its value.
+    pub velocity_colored_plot: bool,
+    /// Where the samples go when several particles reach a receiver in one frame.
+    pub overlap_handling: OverlapHandling,
+    /// Oscilloscope-style trigger: receivers only start plotting once the signal crosses the
+    /// trigger level, so every run's trace starts at the same phase. `None` plots from the first
+    /// particle.
+    pub trigger: Option<Trigger>,
+    /// What receivers do once their plot is full. Each receiver can be switched at runtime.
+    pub sweep_mode: SweepMode,
+    /// Receivers that wait for a trigger, plot one sweep, save it to `screenshot_dir` and stop.
+    pub single_captures: Vec<SingleCapture>,
+    /// Plot points fade out over this many seconds, like the phosphor of an analog oscilloscope,
+    /// or `None` to keep them. With a continuous sweep the old trace is left fading under the
+    /// new one rather than cleared.
+    pub phosphor_decay_secs: Option<f32>,
+    /// Only show particles near a crest or trough of the wave, so the wavefronts stand out. Toggle
+    /// at runtime with 'w'.
+    pub crests_only: bool,
+    /// How close to a crest or trough a particle has to be to show in crests-only mode, as a
+    /// fraction of the amplitude from 0 to 1.
+    pub crest_threshold: f32,
+    /// Hide some of the particles close to receivers, where fast streams bunch up. Only what's
+    /// drawn changes: hidden particles are still received. `None` shows them all.
+    pub thinning: Option<Thinning>,
+    /// Draw faint markers where the particles and each wavefront will be `ghost_lookahead_secs`
+    /// from now. Toggle at runtime with 'i'.
+    pub ghost_preview: bool,
+    pub ghost_lookahead_secs: f32,
+    /// Mark each receiver's plot with ticks one source period apart and, below them, one
+    /// expected observed period apart, to show how the Doppler shift squeezes or stretches the
+    /// trace. Toggle at runtime with F7.
+    pub period_markers: bool,
+    /// Label the time axis under each receiver's plot in half periods of its transmitter's wave,
+    /// "0", "T/2", "T" and so on. Toggle at runtime with numpad '7'.
+    pub time_axis_labels: bool,
+    /// How transmitters emit particles. Scenarios may set it when this is `Stream`; read it
+    /// with [`SimConfig::emission_mode`].
+    pub emission_mode: EmissionMode,
+    /// Streams particles both ways along the x axis from every transmitter, in step at the
+    /// source. Receivers to the right of their transmitter pick up the ones going right. Only
+    /// affects [`EmissionMode::Stream`]; scenarios may turn it on for their own transmitters.
+    pub bidirectional: bool,
+    /// Whether transmitters emit continuously or in bursts separated by silence. Scenarios may
+    /// override it.
+    pub emission_pattern: EmissionPattern,
+    /// Beats per minute transmitters with an `OnBeat` pattern emit at, from 20 to 300. Change at
+    /// runtime with numpad '8' and '9'.
+    pub tempo_bpm: f32,
+    /// Flash a ring out from each transmitter as it emits on a beat.
+    pub beat_flash: bool,
+    /// Seconds a transmitter's output takes to ramp from silent to full strength when its gain is
+    /// changed with the numpad '+' and '-' keys. 0 changes it straight away.
+    pub amplitude_ramp_secs: f32,
+    /// Milliseconds between emissions in stream mode.
+    pub spawn_rate_ms: u64,
+    /// Number of particles each transmitter should have in flight. When set, the time between
+    /// its emissions is the time its particles spend in flight spread over that many, keeping the
+    /// wave equally dense whatever the speed. Every particle counts, so a point source's rays
+    /// each count. Until the first particle has arrived it emits no faster than the configured
+    /// rate. `None` emits at the configured rate.
+    pub target_in_flight: Option<usize>,
+    /// Number of particles emitted at once, spread across the beam, in point-source mode.
+    pub point_source_rays: usize,
+    /// Milliseconds between emissions in point-source mode.
+    pub point_source_spawn_rate_ms: u64,
+    /// Pixels between the particles of each column in plane-wave mode.
+    pub plane_wave_spacing: f32,
+    /// Milliseconds between emissions in plane-wave mode.
+    pub plane_wave_spawn_rate_ms: u64,
+    /// Elements in each transmitter, in a line across the propagation axis centred on it, each
+    /// emitting its own wave like a phased array. 1 is an ordinary transmitter.
+    pub array_elements: u32,
+    /// Pixels between neighbouring elements of an array. Keep it under half a wavelength to
+    /// avoid extra beams.
+    pub array_spacing: f32,
+    /// Degrees of phase each element of an array lags behind the one before. Steps away from 0
+    /// steer the combined beam towards the side of the elements lagging most.
+    pub array_phase_step: f32,
+    /// Full angle of each transmitter's emission cone in point-source mode, in degrees. 360 emits
+    /// in every direction. Amplitude tapers to zero towards the edges of narrower beams.
+    pub beam_width: f32,
+    /// Direction the emission cone points in, in degrees anticlockwise from +x.
+    pub beam_direction: f32,
+    /// Direction waves travel in, in degrees anticlockwise from +x. Streams and point-source beams
+    /// turn with it, and receivers catch particles on a plane across it. Rotate at runtime with '-'
+    /// and '='.
+    pub propagation_angle: f32,
+    /// Degrees the propagation axis turns by on each key press.
+    pub propagation_angle_step: f32,
+    /// Random variation in the time between emissions, as a fraction of the nominal interval, to
+    /// model an imperfect oscillator. 0 emits at exactly the nominal rate.
+    pub emission_jitter: f32,
+    /// Seed for everything random in a run, so far just the emission jitter. Every restart
+    /// reseeds with it, so a run can be replayed exactly; roll a new one at runtime with End.
+    pub seed: u64,
+    /// Seconds after a start or reset before any transmitter emits, so a recording opens on an
+    /// empty scene and then catches the wave turning on. Adds to the `emission_delays`.
+    pub start_delay_secs: f32,
+    /// Open with the scene built but paused behind a notice, until the pause key starts it, so a
+    /// presenter can set up the display and start on cue.
+    pub start_paused: bool,
+    /// Pause while the window is in the background, and carry on when it's back in focus.
+    pub pause_on_blur: bool,
+    /// Most milliseconds one frame can advance the simulation by. A longer gap between frames,
+    /// such as after the window has been in the background, is dropped rather than caught up, so
+    /// transmitters don't emit a backlog all at once.
+    pub max_frame_delta_ms: u64,
+    /// A guided lesson to give, stepping through scenarios and settings with an explanation of
+    /// each, advanced with the lesson key.
+    pub lesson: Option<LessonKind>,
+    /// Seconds between the starts of successive groups' emission, so in a multi-panel view each
+    /// wave starts a beat after the one before, in the order the groups were created. The first
+    /// group starts on time. Unlike `emission_delays` it applies whether or not `sync_emission`
+    /// is on.
+    pub group_stagger_secs: f32,
+    /// Keep every transmitter's emissions in step: they share each jittered interval, and each
+    /// one waits its `emission_delays` entry before it starts emitting.
+    pub sync_emission: bool,
+    /// Seconds each transmitter, in the order they were created, waits after a start or reset
+    /// before emitting when `sync_emission` is on. Its wave lags by the same time, so a delay is
+    /// a phase offset for interference. Transmitters without an entry don't wait.
+    pub emission_delays: Vec<f32>,
+    /// Save a snapshot of the canvas when every receiver has finished plotting, named by
+    /// `screenshot_template` with `complete` as its kind, `complete-000.png` by default. Put
+    /// `{scenario}` in the template to name it after the scenario.
+    pub auto_capture_on_complete: bool,
+    /// Seconds a held scrub key takes to cross-fade all the way between two stored traces.
+    pub crossfade_secs: f32,
+    /// Pause and snapshot the canvas each run when a transmitter's leading wavefront reaches a
+    /// marked line, so runs with different parameters are captured at the same point in the
+    /// wave.
+    pub phase_lock: Option<PhaseLock>,
+    /// Directory screenshots are saved in. Created at startup if it doesn't exist.
+    pub screenshot_dir: PathBuf,
+    /// Directory the preset slots are saved in, one RON file per slot. Created when a slot is
+    /// first saved.
+    pub preset_dir: PathBuf,
+    /// Filename of each screenshot. `{kind}` is replaced by what it's of (`screenshot` for the gif
+    /// sequence, `canvas`, `complete`, `figure` or `sweep`), `{num}` by a zero padded count,
+    /// `{scenario}` by the scenario and `{timestamp}` by the seconds since the Unix epoch.
+    pub screenshot_template: String,
+    /// With the `gifcreate` feature, save only every this many frames of the sequence. 1 saves
+    /// every frame.
+    pub gif_frame_skip: u32,
+    /// With the `gifcreate` feature and the `Frames` clock, number the sequence's frames by how
+    /// many updates into the sequence they are, padded to six digits, rather than counting the
+    /// frames saved. Rerunning the same sequence then writes identical frames under identical
+    /// names, and skipped frames leave gaps in the numbering.
+    pub gif_sim_frame_names: bool,
+    /// With the `gifcreate` feature, largest width and height the sequence's frames are saved
+    /// at. Frames are scaled down to fit, keeping their aspect ratio. `None` saves them at the
+    /// window's size.
+    pub gif_max_size: Option<[u32; 2]>,
+    /// Brightness and contrast adjustment for every saved screenshot, e.g.
+    /// `Manual(gain: 1.5, offset: -0.1)` or `Auto` to stretch each frame's histogram. The live
+    /// view isn't affected.
+    pub export_contrast: ExportContrast,
+    /// JSON file of figures to capture one after another with the batch key ('/'). See
+    /// [`crate::batch::read_batch`] for its format.
+    pub batch_file: PathBuf,
+    /// Setting swept, saving every frame, when the sweep key (backslash) is pressed.
+    pub parameter_sweep: ParameterSweep,
+    /// Seconds taken to ramp to a new simulation speed. 0 changes speed instantly.
+    pub sim_speed_easing_secs: f32,
+    /// Log a summary of particle counts and received frequencies every second. Useful for
+    /// headless runs.
+    pub stats_log: bool,
+    /// CSV file each receiver's observed frequency is appended to over time, with the wall clock
+    /// and simulation times, for plotting afterwards. `None` logs nothing.
+    pub frequency_log: Option<PathBuf>,
+    /// Milliseconds of simulation time between rows of the frequency log.
+    pub frequency_log_interval_ms: u64,
+    /// Size in bytes the frequency log is allowed to grow to before it's moved aside to the same
+    /// name with `.1` before the extension, replacing any earlier one, and a new file started.
+    /// `None` lets it grow without limit.
+    pub frequency_log_max_bytes: Option<u64>,
+    /// Show a table of every receiver's position, velocity, observed frequency and sample count
+    /// in the bottom right corner. Toggle at runtime with numpad '/'.
+    pub receiver_table: bool,
+    /// How much of the previous observed frequency estimate each new one keeps, from 0 to just
+    /// under 1, smoothing the frequencies shown in the receiver table and differential readout.
+    /// 0 shows each estimate as it comes.
+    pub frequency_smoothing: f32,
+    /// Half the width of a dead band around zero that a receiver's signal has to leave on the
+    /// far side before a zero crossing counts, in the units of its samples, so noise around zero
+    /// doesn't add crossings to frequency estimates. 0 counts every crossing.
+    pub crossing_hysteresis: f32,
+    /// Show a line under each receiver saying whether it samples fast enough for the frequency
+    /// it should observe, in green, or is aliasing, in red. Toggle at runtime with numpad '0'.
+    pub nyquist_indicator: bool,
+    /// Nudge receivers' labels down out of each other's way when they overlap, with a faint line
+    /// back to where each belongs. Toggle at runtime with numpad '5'.
+    pub label_spacing: bool,
+    /// Show a heatmap of the combined wave field behind the particles. Toggle at runtime with
+    /// 'h'.
+    pub heatmap: bool,
+    /// Columns and rows of cells in the heatmap. More cells show finer detail but cost more to
+    /// compute. Only read at startup.
+    pub heatmap_cells: [u32; 2],
+    /// Milliseconds between heatmap updates.
+    pub heatmap_refresh_ms: u64,
+    /// Draw contour lines of the combined wave field. Toggle at runtime with shift+'h'.
+    pub contours: bool,
+    /// Field values the contour lines follow, in multiples of the amplitude.
+    pub contour_levels: Vec<f32>,
+    /// Columns and rows of cells the field is sampled over for the contours. More cells give
+    /// smoother lines but cost more to draw each frame.
+    pub contour_cells: [u32; 2],
+    /// Whether the heatmap and contours show every transmitter's field summed or the difference
+    /// of two. Toggle at runtime with numpad '*'.
+    pub field_mode: FieldMode,
+    /// Indices of the transmitters whose fields [`FieldMode::Difference`] subtracts, the second
+    /// from the first.
+    pub difference_sources: [usize; 2],
+    /// Build up a long exposure of the particles' displacements behind them, showing the wave's
+    /// shape in space. Toggle at runtime with Caps Lock, and save it with shift+Caps Lock.
+    pub phase_map: bool,
+    /// Columns and rows of cells in the phase map. Only read at startup.
+    pub phase_map_cells: [u32; 2],
+    /// Seconds for the phase map's exposure to fade to half.
+    pub phase_map_half_life: f32,
+    /// Columns and rows of cells the field is sampled over across the canvas when it's exported
+    /// with shift+F12.
+    pub field_export_cells: [u32; 2],
+    /// Whether the field is exported as a NumPy `.npy` array or a CSV matrix.
+    pub field_export_format: FieldExportFormat,
+    /// Marks the nodes and antinodes of the standing wave between the two lowest numbered
+    /// transmitters. Toggle at runtime with numpad '6'.
+    pub standing_wave_nodes: bool,
+    /// How close, as a fraction of the largest swing along the line, the field's swing must come
+    /// to zero for a node or to the largest for an antinode. Between 0 and 0.5.
+    pub standing_wave_threshold: f32,
+    /// How the low resolution canvas is drawn to the window. Only read at startup.
+    pub presentation_style: PresentationStyle,
+    /// How the canvas is scaled to fill the window.
+    pub canvas_scaling: CanvasScaling,
+    /// Rasterises text drawn over the canvas at the window's resolution, so labels stay sharp
+    /// however far the canvas is scaled up rather than being magnified with it.
+    pub crisp_text: bool,
+    /// RGB color of the bars around the canvas when the window's aspect ratio doesn't match it.
+    pub letterbox_color: [f32; 3],
+    /// Whether the HUD stays in the corner of the window or is pinned to the world, moving with
+    /// the camera. Toggle at runtime with Scroll Lock.
+    pub hud_anchor: HudAnchor,
+    /// How the simulation is drawn to the window. Only read at startup.
+    pub render_pipeline: RenderPipeline,
+    /// How strictly the systems run each frame are ordered. Only read at startup.
+    pub system_ordering: SystemOrdering,
+    /// What time advances by each frame: the real time that passed, or a fixed step so recordings
+    /// come out the same on every machine. Switch at runtime with Backspace.
+    pub clock_mode: ClockMode,
+    /// Frames per simulated second with the `Frames` clock.
+    pub frame_clock_fps: f32,
+    /// Arrangement of transmitters and receivers. Switch at runtime with the number keys.
+    pub scenario: Scenario,
+    /// Whether pairs go where the scenario puts them or are spread evenly over `world_bounds`.
+    pub placement: Placement,
+    /// Size of the region, centred on the origin, that `Auto` placement spreads pairs over.
+    pub world_bounds: WorldBounds,
+    /// Radius and angles of the arc the 'EqualPaths' scenario puts its receivers on.
+    pub path_arc: PathArc,
+    /// Particles leaving `world_bounds` on the left come back on the right and vice versa, and
+    /// pass through receivers rather than being used up, so once a transmitter's wave has gone
+    /// all the way round it stops emitting and the same particles circulate. Toggle at runtime
+    /// with Enter.
+    pub wrap_world: bool,
+    /// Whether dragged transmitters and receivers snap to the grid. Holding alt while dragging
+    /// does the opposite.
+    pub snap_to_grid: bool,
+    /// Spacing of the grid drags snap to, in pixels.
+    pub snap_grid: f32,
+    /// Draws lines across the propagation axis one wavelength apart, counted from the selected
+    /// receiver's transmitter. Toggle at runtime with shift+'u'.
+    pub wavelength_grid: bool,
+    /// Every this many lines of the wavelength grid is labelled with its count of wavelengths.
+    pub wavelength_grid_label_every: u32,
+    /// Depth of each kind of thing drawn in the simulation, to change what's drawn on top.
+    pub z_layers: ZLayers,
+    /// Key for each action, by name. Actions left out keep their default key. Only read at
+    /// startup.
+    pub key_bindings: KeyBindings,
+}
+
+/// How a receiver lays out the samples of particles that reach it in the same frame.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlapHandling {
+    /// In the order they were emitted, evenly spaced over the distance the plot swept since the
+    /// last samples.
+    #[default]
+    Even,
+    /// In the order they crossed into the receiver, each where the plot was at that moment,
+    /// worked out from how far past the receiver's edge it got during the frame.
+    CrossingTime,
+}
+
+/// How strictly the `Update` systems are ordered.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SystemOrdering {
+    /// Only orderings the simulation depends on are enforced, so independent systems can run in
+    /// parallel.
+    #[default]
+    Parallel,
+    /// Every system runs in a fixed order, one after another. Slower, but every frame runs the
+    /// same way, which helps when chasing an ordering bug.
+    Chained,
+}
+
+/// How the low resolution canvas is scaled up to the window.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentationStyle {
+    /// Nearest-neighbour scaling with MSAA off: crisp, blocky pixels, but thin diagonal edges
+    /// shimmer as things move.
+    Pixelated,
+    /// Linear filtering with MSAA: softer edges and smoother motion, at the cost of a slightly
+    /// blurry canvas when the window isn't an exact multiple of its resolution.
+    #[default]
+    Smooth,
+}
+
+/// World depths of everything drawn in the simulation; higher values are drawn on top. By default,
+/// from back to front: the heatmap, the phase map, particles, transmitters and receivers, each
+/// receiver's plot panel, the RMS and envelope window band, old traces, then the live plot points
+/// and labels.
+///
+/// These are depths in the world even for things that are children of a transmitter or receiver,
+/// so they compare directly with each other.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZLayers {
+    pub heatmap: f32,
+    pub phase_map: f32,
+    pub particles: f32,
+    pub transmitters: f32,
+    pub receivers: f32,
+    pub plot_panel: f32,
+    pub window_band: f32,
+    pub persisted_traces: f32,
+    pub plot_points: f32,
+    pub labels: f32,
+}
+
+impl Default for ZLayers {
+    fn default() -> Self {
+        Self {
+            heatmap: -2.,
+            phase_map: -1.5,
+            particles: 0.,
+            transmitters: 1.,
+            receivers: 1.,
+            plot_panel: 1.25,
+            window_band: 1.375,
+            persisted_traces: 1.5,
+            plot_points: 3.,
+            labels: 3.,
+        }
+    }
+}
+
+impl ZLayers {
+    /// Local depth that puts a child of a receiver at world depth `z`.
+    pub fn relative_to_receiver(&self, z: f32) -> f32 {
+        z - self.receivers
+    }
+}
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CanvasScaling {
+    /// Scale the canvas as large as fits in the window.
+    #[default]
+    Fit,
+    /// Only scale by whole numbers, so every canvas pixel covers the same number of window
+    /// pixels. Leaves wider bars, and the canvas is shown at 1x if the window is smaller.
+    Integer,
+}
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderPipeline {
+    /// Render the world to a low resolution canvas texture, then scale that up to the window.
+    #[default]
+    Canvas,
+    /// Render the world straight to the window with a single camera, skipping the extra render
+    /// target. Nothing is pixelated, and the scenario transitions don't fade.
+    Direct,
+}
+
+/// Which particles near a receiver are drawn.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+pub struct Thinning {
+    /// How far outside a receiver particles are thinned, in pixels.
+    pub margin: f32,
+    /// Only one in this many of the particles within the margin is drawn.
+    pub keep_one_in: u64,
+}
+
+/// What a receiver does once it has plotted across its full width.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SweepMode {
+    /// Keep the plot and stop moving.
+    #[default]
+    OneShot,
+    /// Clear the plot and start again from the left, like a repeating oscilloscope sweep, waiting
+    /// for the trigger again if there is one. With `return_to_start` a moving receiver also jumps
+    /// back to where it started and keeps moving; otherwise it stops where it is.
+    Continuous { return_to_start: bool },
+}
+
+impl SweepMode {
+    pub fn next(self) -> Self {
+        match self {
+            SweepMode::OneShot => SweepMode::Continuous {
+                return_to_start: false,
+            },
+            SweepMode::Continuous {
+                return_to_start: false,
+            } => SweepMode::Continuous {
+                return_to_start: true,
+            },
+            SweepMode::Continuous {
+                return_to_start: true,
+            } => SweepMode::OneShot,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SweepMode::OneShot => "one-shot",
+            SweepMode::Continuous {
+                return_to_start: false,
+            } => "repeating",
+            SweepMode::Continuous {
+                return_to_start: true,
+            } => "repeating, returning to start",
+        }
+    }
+}
+
+/// Signal level and direction of crossing that starts a receiver's trace.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub level: f32,
+    pub slope: TriggerSlope,
+}
+
+/// Where the wave is captured for `phase_lock`, e.g. `(x: 200.)`.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+pub struct PhaseLock {
+    /// Position of the marked line across the canvas, in pixels from its centre.
+    pub x: f32,
+    /// Index of the transmitter whose wavefront is waited for, in the order they were created.
+    #[serde(default)]
+    pub transmitter: usize,
+}
+
+/// A receiver that captures one triggered sweep and saves it as `<name>.csv`, its samples, and
+/// `<name>.png`, the canvas, e.g. `(receiver: 0, name: "approaching", trigger: Some((level: 0.,
+/// slope: Rising)))`.
+#[derive(Clone, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+pub struct SingleCapture {
+    /// Index of the receiver, in the order they were created.
+    pub receiver: usize,
+    pub name: String,
+    /// Trigger it waits for in place of `trigger`. With neither it captures from its first
+    /// sample.
+    #[serde(default)]
+    pub trigger: Option<Trigger>,
+}
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerSlope {
+    /// Fires when the signal goes from below the level to at or above it.
+    #[default]
+    Rising,
+    /// Fires when the signal goes from above the level to at or below it.
+    Falling,
+}
+
+impl Trigger {
+    /// Whether going from `prev` to `value` crosses the trigger level with the right slope.
+    pub fn fires(&self, prev: f32, value: f32) -> bool {
+        match self.slope {
+            TriggerSlope::Rising => prev < self.level && value >= self.level,
+            TriggerSlope::Falling => prev > self.level && value <= self.level,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmissionMode {
+    /// A single stream of particles travelling along the x axis.
+    #[default]
+    Stream,
+    /// Particles radiating outwards from the transmitter, limited to its beam.
+    PointSource,
+    /// A column of particles across the propagation axis, `world_bounds.height` tall and centred
+    /// on the transmitter, so each wavefront is a flat line like the plane waves from a distant
+    /// source. Receivers pick up the part of the wave level with them.
+    PlaneWave,
+}
+
+/// A periodic signal for transmitters to send in place of a sine wave.
+#[derive(Clone, Debug, Reflect, Default, PartialEq, Serialize, Deserialize)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    /// Samples spread evenly over one period. They're scaled so the largest reaches the
+    /// amplitude.
+    Samples(Vec<f32>),
+    /// A file of samples like `Samples`, separated by commas, whitespace or new lines.
+    Csv(PathBuf),
+    /// A fundamental and its overtones, such as the first few odd harmonics with amplitudes
+    /// `1/n` for a square wave, or notes of a chord as multiples of a common root. The sum is
+    /// scaled so its peak reaches the amplitude.
+    Harmonics(Vec<Harmonic>),
+}
+
+impl Waveform {
+    /// The shape transmitters send, or the reason it can't be used.
+    pub fn try_shape(&self) -> Result<WaveShape, String> {
+        match self {
+            Waveform::Sine => Ok(WaveShape::Sine),
+            Waveform::Samples(samples) => WaveShape::from_samples(samples),
+            Waveform::Csv(path) => WaveShape::from_samples(&read_samples(path)?),
+            Waveform::Harmonics(harmonics) => WaveShape::from_harmonics(harmonics),
+        }
+    }
+
+    /// This waveform with a CSV read into samples, so it isn't read again every time the
+    /// transmitters are rebuilt, or a sine if it can't be used.
+    fn validated(self, name: &str) -> Waveform {
+        match self.try_shape() {
+            Ok(WaveShape::Table(samples)) => Waveform::Samples(samples.to_vec()),
+            Ok(_) => self,
+            Err(err) => {
+                eprintln!("Cannot use {name}: {err}, using a sine wave");
+                Waveform::Sine
+            }
+        }
+    }
+
+    /// The shape transmitters send, falling back to a sine if it can't be used.
+    pub fn shape(&self) -> WaveShape {
+        self.try_shape().unwrap_or_default()
+    }
+}
+
+/// Reads numbers separated by commas, whitespace or new lines from `path`.
+fn read_samples(path: &Path) -> Result<Vec<f32>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    contents
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .parse()
+                .map_err(|_| format!("{}: {token:?} isn't a number", path.display()))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Serialize, Deserialize)]
+pub enum EmissionPattern {
+    /// Emit for as long as the transmitter is switched on.
+    #[default]
+    Continuous,
+    /// Emit `count` times, then stay silent for `gap` seconds, repeating, like a pulsed radar or
+    /// sonar.
+    Burst { count: u32, gap: f32 },
+    /// Emit only when the fire key is pressed, a single wavefront per press from the selected
+    /// receiver's transmitter, each the next of `emission_hues` (6 unless set), so each pulse
+    /// can be followed to the receiver and picked out in its plot.
+    Fired,
+    /// Emit a single wavefront on every beat of the tempo, `tempo_bpm` unless it's been changed
+    /// with numpad '8' and '9', for rhythmic demonstrations or keeping time with music.
+    OnBeat,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            color_per_transmitter: true,
+            palette: Palette::Standard,
+            particle_color: None,
+            particle_radius: crate::PARTICLE_RADIUS,
+            particle_shape: ParticleShape::Circle,
+            particle_fill: ParticleFill::Filled,
+            age_fade: false,
+            fade_in_secs: 0.5,
+            fade_out_secs: 0.5,
+            font: None,
+            spectrum_colors: false,
+            spectrum_range: [0.5, 4.],
+            emission_hues: None,
+            reset_interval: Some(10.),
+            freeze_after: None,
+            persist_traces: false,
+            max_persisted_traces: 3,
+            wave_speed: -200.,
+            doppler_formula: DopplerFormula::Classical,
+            dispersion: Dispersion::None,
+            particle_substeps: 1,
+            drift_free_motion: true,
+            ping_pong_bounds: None,
+            waveform: Waveform::Sine,
+            transmitter_waveforms: Vec::new(),
+            clip_level: f32::INFINITY,
+            clip_shape: ClipShape::Hard,
+            speed_limit: None,
+            ambience_path: "sounds/ambience.ogg".to_string(),
+            midi_port: None,
+            midi_channel: 0,
+            midi_pitch_scale: 220.,
+            despawn_animation_secs: 0.,
+            max_particles: 5000,
+            max_receivers: None,
+            receiver_view: ReceiverView::Raw,
+            receiver_orientation: ReceiverOrientation::Horizontal,
+            capture_band: 0.,
+            click_probe: false,
+            probe_radius: 20.,
+            explode_spacing: 3.,
+            receiver_buffer_size: Some(10_000),
+            receiver_window: 50,
+            frequency_plot_span: 0.5,
+            window_band: false,
+            window_band_color: [1., 0.85, 0.2, 0.3],
+            plot_panel: Some([1., 1., 1., 0.85]),
+            plot_marker: PlotMarker::Dot,
+            smooth_trace: false,
+            smooth_trace_steps: 8,
+            auto_scale_plots: false,
+            plot_decimation: 1,
+            dead_time_secs: 0.,
+            velocity_colored_plot: false,
+            overlap_handling: OverlapHandling::Even,
+            trigger: None,
+            sweep_mode: SweepMode::OneShot,
+            single_captures: Vec::new(),
+            phosphor_decay_secs: None,
+            crests_only: false,
+            crest_threshold: 0.1,
+            thinning: None,
+            ghost_preview: false,
+            ghost_lookahead_secs: 1.,
+            period_markers: false,
+            time_axis_labels: false,
+            emission_mode: EmissionMode::Stream,
+            bidirectional: false,
+            emission_pattern: EmissionPattern::Continuous,
+            tempo_bpm: 120.,
+            beat_flash: true,
+            amplitude_ramp_secs: 2.,
+            spawn_rate_ms: crate::PARTICLE_SPAWN_RATE_MS,
+            target_in_flight: None,
+            point_source_rays: 16,
+            point_source_spawn_rate_ms: 50,
+            plane_wave_spacing: 40.,
+            array_elements: 1,
+            array_spacing: 40.,
+            array_phase_step: 0.,
+            plane_wave_spawn_rate_ms: 50,
+            beam_width: 360.,
+            beam_direction: 180.,
+            propagation_angle: 0.,
+            propagation_angle_step: 5.,
+            emission_jitter: 0.,
+            start_delay_secs: 0.,
+            start_paused: false,
+            pause_on_blur: false,
+            max_frame_delta_ms: 100,
+            lesson: None,
+            group_stagger_secs: 0.,
+            sync_emission: false,
+            emission_delays: Vec::new(),
+            seed: 0,
+            auto_capture_on_complete: false,
+            crossfade_secs: 2.,
+            phase_lock: None,
+            screenshot_dir: PathBuf::from(DEFAULT_SCREENSHOT_DIR),
+            preset_dir: PathBuf::from(DEFAULT_PRESET_DIR),
+            batch_file: PathBuf::from(DEFAULT_BATCH_FILE),
+            parameter_sweep: ParameterSweep::default(),
+            screenshot_template: "{kind}-{num}.png".into(),
+            gif_frame_skip: 1,
+            gif_sim_frame_names: false,
+            gif_max_size: None,
+            export_contrast: ExportContrast::Off,
+            sim_speed_easing_secs: 0.5,
+            stats_log: false,
+            frequency_log: None,
+            frequency_log_interval_ms: 250,
+            frequency_log_max_bytes: Some(10_000_000),
+            receiver_table: false,
+            frequency_smoothing: 0.9,
+            crossing_hysteresis: 0.,
+            nyquist_indicator: false,
+            label_spacing: true,
+            heatmap: false,
+            heatmap_cells: [160, 90],
+            heatmap_refresh_ms: 100,
+            contours: false,
+            contour_levels: vec![-1., -0.5, 0., 0.5, 1.],
+            contour_cells: [128, 72],
+            field_export_cells: [256, 144],
+            phase_map: false,
+            phase_map_cells: [160, 90],
+            phase_map_half_life: 2.,
+            field_export_format: FieldExportFormat::Npy,
+            standing_wave_nodes: false,
+            standing_wave_threshold: 0.1,
+            field_mode: FieldMode::Sum,
+            difference_sources: [0, 1],
+            presentation_style: PresentationStyle::Smooth,
+            canvas_scaling: CanvasScaling::Fit,
+            crisp_text: true,
+            letterbox_color: [0., 0., 0.],
+            hud_anchor: HudAnchor::Screen,
+            render_pipeline: RenderPipeline::Canvas,
+            system_ordering: SystemOrdering::Parallel,
+            clock_mode: ClockMode::RealTime,
+            frame_clock_fps: 60.,
+            scenario: Scenario::ThreeReceivers,
+            placement: Placement::Manual,
+            world_bounds: WorldBounds::default(),
+            path_arc: PathArc::default(),
+            wrap_world: false,
+            snap_to_grid: false,
+            snap_grid: 25.,
+            wavelength_grid: false,
+            wavelength_grid_label_every: 5,
+            z_layers: ZLayers::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl SimConfig {
+    /// Fixes up values that would break the simulation, reporting each change.
+    pub fn validated(mut self) -> Self {
+        let wave_speed = physics::clamp_wave_speed(self.wave_speed);
+        if wave_speed != self.wave_speed {
+            eprintln!(
+                "wave_speed {} is too close to zero, using {wave_speed}",
+                self.wave_speed
+            );
+            self.wave_speed = wave_speed;
+        }
+        if !(self.particle_radius.is_finite() && self.particle_radius > 0.) {
+            eprintln!(
+                "particle_radius {} must be positive, using {}",
+                self.particle_radius,
+                crate::PARTICLE_RADIUS
+            );
+            self.particle_radius = crate::PARTICLE_RADIUS;
+        }
+        self.waveform = self.waveform.validated("waveform");
+        self.transmitter_waveforms = std::mem::take(&mut self.transmitter_waveforms)
+            .into_iter()
+            .enumerate()
+            .map(|(i, waveform)| waveform.validated(&format!("transmitter_waveforms[{i}]")))
+            .collect();
+        if let Some(decay) = self.phosphor_decay_secs {
+            if decay.is_nan() || decay <= 0. {
+                eprintln!("phosphor_decay_secs {decay} must be positive, not fading plots");
+                self.phosphor_decay_secs = None;
+            }
+        }
+        if self.dead_time_secs.is_nan() || self.dead_time_secs < 0. {
+            eprintln!(
+                "dead_time_secs {} can't be negative, sampling every particle",
+                self.dead_time_secs
+            );
+            self.dead_time_secs = 0.;
+        }
+        if let Some(limit) = self.freeze_after {
+            if limit.is_nan() || limit <= 0. {
+                eprintln!("freeze_after {limit} must be positive, not freezing");
+                self.freeze_after = None;
+            }
+        }
+        if let Some(interval) = self.reset_interval {
+            if !interval.is_finite() || interval <= 0. {
+                eprintln!("reset_interval {interval} must be positive, not resetting on a timer");
+                self.reset_interval = None;
+            }
+        }
+        if self.emission_hues == Some(0) {
+            eprintln!("emission_hues must be at least 1, not coloring emissions");
+            self.emission_hues = None;
+        }
+        if self.target_in_flight == Some(0) {
+            eprintln!("target_in_flight must be at least 1, emitting at the configured rate");
+            self.target_in_flight = None;
+        }
+        if self.difference_sources[0] == self.difference_sources[1] {
+            eprintln!(
+                "difference_sources can't subtract transmitter {} from itself, using 0 and 1",
+                self.difference_sources[0]
+            );
+            self.difference_sources = [0, 1];
+        }
+        if self.amplitude_ramp_secs.is_nan() || self.amplitude_ramp_secs < 0. {
+            eprintln!(
+                "amplitude_ramp_secs {} can't be negative, changing gain straight away",
+                self.amplitude_ramp_secs
+            );
+            self.amplitude_ramp_secs = 0.;
+        }
+        if !self.crossing_hysteresis.is_finite() || self.crossing_hysteresis < 0. {
+            eprintln!(
+                "crossing_hysteresis {} can't be negative, counting every crossing",
+                self.crossing_hysteresis
+            );
+            self.crossing_hysteresis = 0.;
+        }
+        if !(0. ..=1.).contains(&self.crest_threshold) {
+            eprintln!(
+                "crest_threshold {} must be from 0 to 1, using 0.1",
+                self.crest_threshold
+            );
+            self.crest_threshold = 0.1;
+        }
+        if !(0. ..1.).contains(&self.frequency_smoothing) {
+            eprintln!(
+                "frequency_smoothing {} must be from 0 to under 1, not smoothing frequencies",
+                self.frequency_smoothing
+            );
+            self.frequency_smoothing = 0.;
+        }
+        for (i, delay) in self.emission_delays.iter_mut().enumerate() {
+            if !delay.is_finite() || *delay < 0. {
+                eprintln!(
+                    "emission_delays[{i}] {delay} can't be negative, starting that transmitter \
+                     straight away"
+                );
+                *delay = 0.;
+            }
+        }
+        if !self.start_delay_secs.is_finite() || self.start_delay_secs < 0. {
+            eprintln!(
+                "start_delay_secs {} can't be negative, starting straight away",
+                self.start_delay_secs
+            );
+            self.start_delay_secs = 0.;
+        }
+        if !self.group_stagger_secs.is_finite() || self.group_stagger_secs < 0. {
+            eprintln!(
+                "group_stagger_secs {} can't be negative, starting every group together",
+                self.group_stagger_secs
+            );
+            self.group_stagger_secs = 0.;
+        }
+        if let ExportContrast::Manual { gain, offset } = self.export_contrast {
+            if !gain.is_finite() || !offset.is_finite() {
+                eprintln!(
+                    "export_contrast gain {gain} and offset {offset} must be finite numbers, \
+                     saving frames as rendered"
+                );
+                self.export_contrast = ExportContrast::Off;
+            }
+        }
+        if self.path_arc.radius.is_nan() || self.path_arc.radius <= 0. {
+            eprintln!(
+                "path_arc radius {} must be positive, using {}",
+                self.path_arc.radius,
+                PathArc::default().radius
+            );
+            self.path_arc.radius = PathArc::default().radius;
+        }
+        if self.path_arc.angles.iter().any(|angle| !angle.is_finite()) {
+            eprintln!("path_arc angles must be finite numbers, using the default angles");
+            self.path_arc.angles = PathArc::default().angles;
+        }
+        if self.probe_radius.is_nan() || self.probe_radius < 0. {
+            eprintln!(
+                "probe_radius {} can't be negative, only logging click positions",
+                self.probe_radius
+            );
+            self.probe_radius = 0.;
+        }
+        if self.capture_band.is_nan() || self.capture_band < 0. {
+            eprintln!(
+                "capture_band {} can't be negative, only sampling caught particles",
+                self.capture_band
+            );
+            self.capture_band = 0.;
+        }
+        if !(self.frame_clock_fps.is_finite() && self.frame_clock_fps > 0.) {
+            eprintln!(
+                "frame_clock_fps {} must be positive, using 60",
+                self.frame_clock_fps
+            );
+            self.frame_clock_fps = 60.;
+        }
+        if !self.propagation_angle.is_finite() {
+            eprintln!(
+                "propagation_angle {} must be finite, using 0",
+                self.propagation_angle
+            );
+            self.propagation_angle = 0.;
+        }
+        if let Some((left, right)) = self.ping_pong_bounds {
+            if left.is_nan() || right.is_nan() || left >= right {
+                eprintln!(
+                    "ping_pong_bounds ({left}, {right}) must be left then right, ignoring them"
+                );
+                self.ping_pong_bounds = None;
+            }
+        }
+        if self.clip_level.is_nan() || self.clip_level <= 0. {
+            eprintln!(
+                "clip_level {} must be positive, not clipping",
+                self.clip_level
+            );
+            self.clip_level = f32::INFINITY;
+        }
+        if !self.crossfade_secs.is_finite() || self.crossfade_secs <= 0. {
+            eprintln!(
+                "crossfade_secs {} must be positive, using 2",
+                self.crossfade_secs
+            );
+            self.crossfade_secs = 2.;
+        }
+        if self.frequency_log_interval_ms == 0 {
+            eprintln!("frequency_log_interval_ms can't be 0, using 250");
+            self.frequency_log_interval_ms = 250;
+        }
+        if self.max_frame_delta_ms == 0 {
+            eprintln!("max_frame_delta_ms can't be 0, using 100");
+            self.max_frame_delta_ms = 100;
+        }
+        if !self.phase_map_half_life.is_finite() || self.phase_map_half_life <= 0. {
+            eprintln!(
+                "phase_map_half_life {} must be positive, using 2",
+                self.phase_map_half_life
+            );
+            self.phase_map_half_life = 2.;
+        }
+        if !(0. ..=0.5).contains(&self.standing_wave_threshold) {
+            eprintln!(
+                "standing_wave_threshold {} must be between 0 and 0.5, using 0.1",
+                self.standing_wave_threshold
+            );
+            self.standing_wave_threshold = 0.1;
+        }
+        if !self.explode_spacing.is_finite() || self.explode_spacing < 0. {
+            eprintln!(
+                "explode_spacing {} can't be negative, using 3",
+                self.explode_spacing
+            );
+            self.explode_spacing = 3.;
+        }
+        if self.midi_channel > 15 {
+            eprintln!(
+                "midi_channel {} must be from 0 to 15, using 0",
+                self.midi_channel
+            );
+            self.midi_channel = 0;
+        }
+        if !self.midi_pitch_scale.is_finite() || self.midi_pitch_scale <= 0. {
+            eprintln!(
+                "midi_pitch_scale {} must be positive, using 220",
+                self.midi_pitch_scale
+            );
+            self.midi_pitch_scale = 220.;
+        }
+        let (slowest, fastest) = crate::tempo::TEMPO_RANGE;
+        if !(slowest..=fastest).contains(&self.tempo_bpm) {
+            eprintln!(
+                "tempo_bpm {} must be from {slowest} to {fastest}, using 120",
+                self.tempo_bpm
+            );
+            self.tempo_bpm = 120.;
+        }
+        if let EmissionPattern::Burst { count, gap } = &mut self.emission_pattern {
+            if *count == 0 || gap.is_nan() || *gap < 0. {
+                eprintln!("Burst of {count} emissions with a {gap}s gap is invalid, clamping");
+                *count = (*count).max(1);
+                *gap = gap.max(0.);
+            }
+        }
+        if let ParticleShape::Sprite(path) = &self.particle_shape {
+            // Assets are fetched over HTTP on the web, so only check the path natively.
+            let missing = path.is_empty()
+                || (!cfg!(target_arch = "wasm32") && !Path::new("assets").join(path).is_file());
+            if missing {
+                eprintln!("Particle sprite {path:?} not found in assets, using circles");
+                self.particle_shape = ParticleShape::Circle;
+            }
+        }
+        if let Some(path) = &self.font {
+            let missing = path.is_empty()
+                || (!cfg!(target_arch = "wasm32") && !Path::new("assets").join(path).is_file());
+            if missing {
+                eprintln!("Font {path:?} not found in assets, using the default font");
+                self.font = None;
+            }
+        }
+        // Nothing is saved to disk on the web.
+        if !cfg!(target_arch = "wasm32") {
+            if let Err(err) = fs::create_dir_all(&self.screenshot_dir) {
+                eprintln!(
+                    "Cannot create screenshot directory {}: {err}, using {DEFAULT_SCREENSHOT_DIR}",
+                    self.screenshot_dir.display()
+                );
+                self.screenshot_dir = PathBuf::from(DEFAULT_SCREENSHOT_DIR);
+            }
+        }
+        if !["{num}", "{timestamp}"]
+            .iter()
+            .any(|placeholder| self.screenshot_template.contains(placeholder))
+        {
+            eprintln!(
+                "screenshot_template {:?} has no {{num}} or {{timestamp}}, so each screenshot will \
+                 overwrite the last",
+                self.screenshot_template
+            );
+        }
+        for (key, actions) in self.key_bindings.shared_keys() {
+            eprintln!(
+                "{} is bound to {}, which will all happen together",
+                key_name(key),
+                actions.join(", ")
+            );
+        }
+        self
+    }
+
+    /// Scale of a particle's unit sized mesh for `particle_radius`.
+    pub fn particle_size(&self) -> Vec3 {
+        Vec2::splat(self.particle_radius).extend(1.)
+    }
+
+    /// Unit vector waves travel along, at `propagation_angle` from +x. Rotating by it takes a
+    /// direction along x to the same direction relative to the axis.
+    pub fn propagation_axis(&self) -> Vec2 {
+        Vec2::from_angle(self.propagation_angle.to_radians())
+    }
+
+    /// Seconds each frame advances time by with the `Frames` clock.
+    pub fn frame_step(&self) -> f32 {
+        1. / self.frame_clock_fps
+    }
+
+    /// How transmitters emit particles: `emission_mode`, unless that's `Stream` and the scenario
+    /// sends plane waves or needs a point source to reach receivers off its axis.
+    pub fn emission_mode(&self) -> EmissionMode {
+        match (self.emission_mode, self.scenario) {
+            (EmissionMode::Stream, Scenario::PlaneWave) => EmissionMode::PlaneWave,
+            (EmissionMode::Stream, Scenario::EqualPaths | Scenario::BeamSteering) => {
+                EmissionMode::PointSource
+            }
+            (mode, _) => mode,
+        }
+    }
+
+    /// Elements in each transmitter's array: `array_elements`, unless that's 1 and the scenario
+    /// demonstrates an array.
+    pub fn array_elements(&self) -> u32 {
+        match (self.array_elements, self.scenario) {
+            (1, Scenario::BeamSteering) => BEAM_STEERING_ELEMENTS,
+            (elements, _) => elements.max(1),
+        }
+    }
+
+    /// Degrees of phase between neighbouring elements of an array: `array_phase_step`, unless
+    /// that's 0 and the scenario demonstrates a steered beam.
+    pub fn array_phase_step(&self) -> f32 {
+        if self.array_phase_step == 0. && self.scenario == Scenario::BeamSteering {
+            BEAM_STEERING_PHASE_STEP
+        } else {
+            self.array_phase_step
+        }
+    }
+
+    /// Nominal time between emissions from each transmitter.
+    pub fn spawn_interval(&self) -> Duration {
+        Duration::from_millis(match self.emission_mode() {
+            EmissionMode::Stream => self.spawn_rate_ms,
+            EmissionMode::PointSource => self.point_source_spawn_rate_ms,
+            EmissionMode::PlaneWave => self.plane_wave_spawn_rate_ms,
+        })
+    }
+
+    /// Reads the config from the file named by `DOPPL_CONFIG`, or `doppl.ron` in the working
+    /// directory. A missing file gives the defaults; a malformed one is reported and ignored.
+    pub fn load() -> Self {
+        let path = env::var_os("DOPPL_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<Self>(&contents) {
+            Ok(config) => config.validated(),
+            Err(err) => {
+                // The log plugin isn't running yet, so this has to go straight to stderr.
+                eprintln!("Ignoring invalid config {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Contour lines of the summed wave field from every transmitter, an alternative to the heatmap
+//! that reads as a topographic map of the interference pattern and prints well in black and
+//! white.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    heatmap,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics, CurrentAmplitude, Transmitter, RES_HEIGHT, RES_WIDTH,
+};
+
+const NEGATIVE_COLOR: Color = Color::rgb(0.4, 0.6, 1.);
+const ZERO_COLOR: Color = Color::WHITE;
+const POSITIVE_COLOR: Color = Color::rgb(1., 0.5, 0.4);
+
+pub fn contour_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.contours { "hide" } else { "show" };
+    format!(
+        "Press shift+{} to {action} contour lines of the wave field",
+        key_name(bindings.heatmap)
+    )
+}
+
+/// Shift with the heatmap key shows or hides the contours.
+pub fn toggle_contours(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.heatmap)
+        && input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        config.contours = !config.contours;
+        hud.set("contours", contour_label(&config, &bindings));
+    }
+}
+
+/// Samples the field at the corners of a `contour_cells` grid over the view and draws a line
+/// along each of the `contour_levels`, in multiples of the amplitude.
+pub fn draw_contours(
+    config: Res<SimConfig>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    amplitude: Res<CurrentAmplitude>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+) {
+    if !config.contours || amplitude.0 == 0. {
+        return;
+    }
+    // The same sources as the heatmap, so the two agree.
+    let sources = heatmap::field_sources(tx_query.iter(), &config, amplitude.0);
+    if sources.is_empty() {
+        return;
+    }
+
+    let [columns, rows] = config.contour_cells.map(|cells| cells.max(1) as usize);
+    let view = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+    let cell = view / Vec2::new(columns as f32, rows as f32);
+    let bottom_left = -view / 2.;
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    let values: Vec<f32> = (0..=rows)
+        .flat_map(|row| (0..=columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let point = bottom_left + Vec2::new(column as f32, row as f32) * cell;
+            physics::sample_field(point, &sources, t)
+        })
+        .collect();
+
+    for &level in &config.contour_levels {
+        let color = if level > 0. {
+            POSITIVE_COLOR
+        } else if level < 0. {
+            NEGATIVE_COLOR
+        } else {
+            ZERO_COLOR
+        };
+        for (start, end) in contour_segments(&values, columns, rows, level * amplitude.0) {
+            gizmos.line_2d(bottom_left + start * cell, bottom_left + end * cell, color);
+        }
+    }
+}
+
+/// Line segments where the field crosses `level`, by marching squares over a grid of `values`
+/// sampled at the `(columns + 1) x (rows + 1)` corners of its cells, row by row from the bottom.
+/// Points are in cells from the bottom left corner.
+///
+/// Where a cell's corners alternate above and below the level, the value at its centre decides
+/// which pair of corners the lines cut off.
+fn contour_segments(values: &[f32], columns: usize, rows: usize, level: f32) -> Vec<(Vec2, Vec2)> {
+    let value = |column: usize, row: usize| values[row * (columns + 1) + column];
+    let mut segments = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            // Corners anticlockwise from the bottom left.
+            let corners = [(0, 0), (1, 0), (1, 1), (0, 1)].map(|(dx, dy)| {
+                (
+                    Vec2::new((column + dx) as f32, (row + dy) as f32),
+                    value(column + dx, row + dy),
+                )
+            });
+            // Where the level crosses each edge, from corner `i` to the next.
+            let crossings = [0, 1, 2, 3].map(|i| {
+                let (p1, v1) = corners[i];
+                let (p2, v2) = corners[(i + 1) % 4];
+                ((v1 > level) != (v2 > level)).then(|| p1 + (p2 - p1) * (level - v1) / (v2 - v1))
+            });
+            match crossings {
+                [Some(a), Some(b), Some(c), Some(d)] => {
+                    let centre = corners.iter().map(|(_, v)| v).sum::<f32>() / 4.;
+                    if (centre > level) == (corners[0].1 > level) {
+                        // The bottom left and top right corners join up through the centre, so
+                        // the lines cut off the other two.
+                        segments.push((a, b));
+                        segments.push((c, d));
+                    } else {
+                        segments.push((d, a));
+                        segments.push((b, c));
+                    }
+                }
+                crossings => {
+                    let mut points = crossings.into_iter().flatten();
+                    if let (Some(start), Some(end)) = (points.next(), points.next()) {
+                        segments.push((start, end));
+                    }
+                }
+            }
+        }
+    }
+    segments
+}
@@ -0,0 +1,92 @@
+//! Keeping text drawn over the canvas sharp. The canvas is scaled up to fill the window, and text
+//! on the high-res layer is scaled up with it, so its glyphs would be rasterised at their size in
+//! canvas pixels and magnified into a blur. Instead each label's font is rasterised at the size
+//! it's shown at in the window, and the label scaled back down by as much: it covers the same
+//! part of the canvas as before, but is drawn at the window's full resolution, in screenshots and
+//! recordings too.
+
+use bevy::{prelude::*, render::view::RenderLayers, text::Text2dBounds, window::PrimaryWindow};
+
+use crate::{config::SimConfig, OuterCamera, HIGH_RES_LAYERS};
+
+/// Zoom is rounded to steps of this, so fonts aren't rasterised again at every size the window
+/// passes through while it's resized or the camera eases in and out.
+const ZOOM_STEP: f32 = 0.25;
+
+/// The font sizes a label was given, and the zoom they're currently scaled up by.
+#[derive(Component)]
+pub struct CrispText {
+    font_sizes: Vec<f32>,
+    zoom: f32,
+}
+
+/// Logical window pixels each unit of the high-res world covers, rounded to a [`ZOOM_STEP`].
+fn window_zoom(window: &Window, projection: &OrthographicProjection, transform: &Transform) -> f32 {
+    let width = projection.area.width() * transform.scale.x;
+    if width <= 0. {
+        return 1.;
+    }
+    ((window.width() / width / ZOOM_STEP).round() * ZOOM_STEP).max(ZOOM_STEP)
+}
+
+/// Scales up the fonts of text on [`HIGH_RES_LAYERS`] by how far the window zooms in on it, and
+/// scales the text down to match, while `crisp_text` is on. Runs before text layout so the new
+/// sizes are rasterised the same frame.
+pub fn sharpen_text(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&OrthographicProjection, &Transform), With<OuterCamera>>,
+    mut text_query: Query<
+        (
+            Entity,
+            &mut Text,
+            &mut Transform,
+            &RenderLayers,
+            Option<&mut CrispText>,
+        ),
+        (With<Text2dBounds>, Without<OuterCamera>),
+    >,
+) {
+    let zoom = match (
+        config.crisp_text,
+        windows.get_single(),
+        camera_query.get_single(),
+    ) {
+        (true, Ok(window), Ok((projection, transform))) => {
+            window_zoom(window, projection, transform)
+        }
+        _ => 1.,
+    };
+    for (entity, mut text, mut transform, layers, crisp) in text_query.iter_mut() {
+        if !layers.intersects(&HIGH_RES_LAYERS) {
+            continue;
+        }
+        let font_sizes = match crisp {
+            Some(crisp) if crisp.zoom == zoom => continue,
+            Some(mut crisp) => {
+                crisp.zoom = zoom;
+                crisp.font_sizes.clone()
+            }
+            None => {
+                let font_sizes: Vec<f32> = text
+                    .sections
+                    .iter()
+                    .map(|section| section.style.font_size)
+                    .collect();
+                commands.entity(entity).insert(CrispText {
+                    font_sizes: font_sizes.clone(),
+                    zoom,
+                });
+                if zoom == 1. {
+                    continue;
+                }
+                font_sizes
+            }
+        };
+        for (section, font_size) in text.sections.iter_mut().zip(font_sizes) {
+            section.style.font_size = font_size * zoom;
+        }
+        transform.scale = Vec3::new(zoom.recip(), zoom.recip(), 1.);
+    }
+}
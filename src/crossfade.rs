@@ -0,0 +1,168 @@
+//! Morphing one waveform into another: two of a receiver's traces are stored, say before and
+//! after a parameter change, and a curve blended between them is drawn over its plot, scrubbed
+//! from one to the other by holding a key, to show how the change reshapes the wave.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    Receiver, SelectedReceiver,
+};
+
+const BEFORE_COLOR: Vec3 = Vec3::new(0.3, 0.8, 1.);
+const AFTER_COLOR: Vec3 = Vec3::new(1., 0.6, 0.2);
+/// Pixels along the plot between the points the blended curve is drawn through.
+const CURVE_STEP: f32 = 1.;
+
+/// A stored trace, as `(plot_x, value)` points in order along the plot.
+type Trace = Vec<Vec2>;
+
+/// The traces being cross-faded between, and how far across it is.
+#[derive(Resource, Default)]
+pub struct CrossFade {
+    /// Index of the receiver the traces were taken from.
+    receiver: usize,
+    before: Option<Trace>,
+    after: Option<Trace>,
+    /// 0 shows the trace before and 1 the one after.
+    blend: f32,
+}
+
+pub fn crossfade_label(crossfade: &CrossFade, bindings: &KeyBindings) -> String {
+    let store = format!("shift+{}", key_name(bindings.save_capture));
+    match (&crossfade.before, &crossfade.after) {
+        (None, _) => format!("Press {store} to store a trace to cross-fade from"),
+        (Some(_), None) => format!(
+            "Receiver {} trace stored (press {store} to store one to cross-fade to)",
+            crossfade.receiver + 1
+        ),
+        (Some(_), Some(_)) => format!(
+            "Cross-fade: {:.0}% of the way to the second trace (hold {} and {} to scrub, {store} \
+             to start again)",
+            crossfade.blend * 100.,
+            key_name(bindings.crossfade_back),
+            key_name(bindings.crossfade_forward)
+        ),
+    }
+}
+
+/// Value of `trace` at `x` along the plot, interpolated between its nearest points, or `None`
+/// beyond its ends.
+fn value_at(trace: &[Vec2], x: f32) -> Option<f32> {
+    let after = trace.partition_point(|point| point.x < x);
+    let next = trace.get(after)?;
+    if next.x == x {
+        return Some(next.y);
+    }
+    let previous = trace.get(after.checked_sub(1)?)?;
+    let t = (x - previous.x) / (next.x - previous.x);
+    Some(previous.y + (next.y - previous.y) * t)
+}
+
+/// Shift with the save capture key stores the selected receiver's trace: first the one to fade
+/// from, then the one to fade to. Once both are stored, or if another receiver is selected, it
+/// starts again with a new first trace.
+pub fn store_crossfade_trace(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    rx_query: Query<&Receiver>,
+    mut crossfade: ResMut<CrossFade>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.save_capture)
+        || !input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+    let Some(rx) = rx_query.iter().find(|rx| rx.index == selected.0) else {
+        return;
+    };
+    let mut trace: Trace = rx
+        .samples
+        .iter()
+        .map(|sample| Vec2::new(sample.plot_x, sample.value))
+        .collect();
+    trace.sort_by(|a, b| a.x.total_cmp(&b.x));
+    if crossfade.after.is_some() || crossfade.receiver != rx.index {
+        *crossfade = CrossFade {
+            receiver: rx.index,
+            ..default()
+        };
+    }
+    if crossfade.before.is_none() {
+        crossfade.before = Some(trace);
+    } else {
+        crossfade.after = Some(trace);
+        crossfade.blend = 0.;
+    }
+    hud.set("crossfade", crossfade_label(&crossfade, &bindings));
+}
+
+/// Moves the blend towards the second trace while its key is held, or back towards the first,
+/// taking `crossfade_secs` to go all the way. Runs on real time so it can be scrubbed while
+/// paused.
+pub fn scrub_crossfade(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    time: Res<Time<Real>>,
+    mut crossfade: ResMut<CrossFade>,
+    mut hud: ResMut<Hud>,
+) {
+    if crossfade.after.is_none() {
+        return;
+    }
+    let direction = if input.pressed(bindings.crossfade_forward) {
+        1.
+    } else if input.pressed(bindings.crossfade_back) {
+        -1.
+    } else {
+        return;
+    };
+    let blend =
+        (crossfade.blend + direction * time.delta_seconds() / config.crossfade_secs).clamp(0., 1.);
+    if blend != crossfade.blend {
+        crossfade.blend = blend;
+        hud.set("crossfade", crossfade_label(&crossfade, &bindings));
+    }
+}
+
+/// Draws the curve blended between the two stored traces over their receiver's plot, where the
+/// traces overlap, colored from the first trace's color to the second's as it's scrubbed.
+pub fn draw_crossfade(
+    crossfade: Res<CrossFade>,
+    rx_query: Query<(&Receiver, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    let (Some(before), Some(after)) = (&crossfade.before, &crossfade.after) else {
+        return;
+    };
+    let Some((rx, rx_transform)) = rx_query
+        .iter()
+        .find(|(rx, _)| rx.index == crossfade.receiver)
+    else {
+        return;
+    };
+    let (Some(start), Some(end)) = (
+        before.first().zip(after.first()).map(|(a, b)| a.x.max(b.x)),
+        before.last().zip(after.last()).map(|(a, b)| a.x.min(b.x)),
+    ) else {
+        return;
+    };
+    let steps = ((end - start) / CURVE_STEP).floor().max(0.) as usize;
+    let t = crossfade.blend;
+    let points = (0..=steps).filter_map(|step| {
+        let x = start + step as f32 * CURVE_STEP;
+        let value = value_at(before, x)? * (1. - t) + value_at(after, x)? * t;
+        Some(
+            rx_transform
+                .transform_point(Vec3::new(x, rx.plot_y(value), 0.))
+                .xy(),
+        )
+    });
+    let [r, g, b] = BEFORE_COLOR.lerp(AFTER_COLOR, t).to_array();
+    gizmos.linestrip_2d(points, Color::rgb(r, g, b));
+}
@@ -0,0 +1,162 @@
+//! Self-check enabled with the `determinism_check` feature: runs the simulation twice from the
+//! same seed, each in a fresh headless app stepping a fixed time per frame, and checks that every
+//! receiver recorded exactly the same samples both times, then exits. Catches anything that makes
+//! a run not replay exactly, such as unseeded randomness or iteration order leaking into it.
+
+use std::{env, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    compare::CompareMode, config::SimConfig, headless_app, move_rx, move_tx, propagate_particle,
+    start_simulation, Receiver,
+};
+
+/// Frames each run lasts unless `DOPPL_DETERMINISM_FRAMES` says otherwise.
+const DEFAULT_FRAMES: u32 = 600;
+
+/// Every receiver's samples as raw bits, by receiver index, so they compare exactly.
+type Recording = Vec<(usize, Vec<[u32; 2]>)>;
+
+fn record<'a>(receivers: impl Iterator<Item = &'a Receiver>) -> Recording {
+    let mut recording: Recording = receivers
+        .map(|rx| {
+            let samples = rx
+                .samples
+                .iter()
+                .map(|sample| [sample.value.to_bits(), sample.plot_x.to_bits()])
+                .collect();
+            (rx.index, samples)
+        })
+        .collect();
+    recording.sort_unstable_by_key(|(index, _)| *index);
+    recording
+}
+
+/// Spawns the configured transmitters and receivers, as a reset would.
+fn spawn_simulation(
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<ColorMaterial>>,
+    commands: Commands,
+    config: Res<SimConfig>,
+) {
+    start_simulation(
+        meshes,
+        materials,
+        commands,
+        &config,
+        &CompareMode::default(),
+        &Handle::default(),
+    );
+}
+
+/// Runs the simulation for `frames` steps of `frame_step` in a fresh headless app and records
+/// what its receivers sampled.
+fn run(config: &SimConfig, frames: u32) -> Recording {
+    let mut app = headless_app(config.clone());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        config.frame_step(),
+    )))
+    .add_systems(Startup, spawn_simulation)
+    .add_systems(Update, (move_rx, move_tx).chain().after(propagate_particle));
+    for _ in 0..frames {
+        app.update();
+    }
+    let mut rx_query = app.world.query::<&Receiver>();
+    record(rx_query.iter(&app.world))
+}
+
+/// Where two recordings first differ, or `None` if they're the same.
+fn first_difference(first: &Recording, second: &Recording) -> Option<String> {
+    if first.len() != second.len() {
+        return Some(format!(
+            "{} receivers the first time, {} the second",
+            first.len(),
+            second.len()
+        ));
+    }
+    first.iter().zip(second).find_map(|((index, a), (_, b))| {
+        if a.len() != b.len() {
+            return Some(format!(
+                "receiver {index} took {} samples the first time, {} the second",
+                a.len(),
+                b.len()
+            ));
+        }
+        a.iter().zip(b).position(|(a, b)| a != b).map(|sample| {
+            let value = |bits: [u32; 2]| f32::from_bits(bits[0]);
+            format!(
+                "receiver {index} sample {sample} was {} the first time, {} the second",
+                value(a[sample]),
+                value(b[sample])
+            )
+        })
+    })
+}
+
+/// Runs the simulation twice and compares what the receivers sampled, printing the result.
+/// Returns whether they matched.
+pub fn check_determinism(config: &SimConfig) -> bool {
+    let frames = env::var("DOPPL_DETERMINISM_FRAMES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&frames| frames > 0)
+        .unwrap_or(DEFAULT_FRAMES);
+    let first = run(config, frames);
+    let second = run(config, frames);
+    if let Some(difference) = first_difference(&first, &second) {
+        eprintln!("Determinism check FAILED: {difference}");
+        return false;
+    }
+    let samples: usize = second.iter().map(|(_, samples)| samples.len()).sum();
+    println!("Determinism check passed: {samples} samples matched over {frames} frames");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(samples: &[f32]) -> Recording {
+        let bits = samples
+            .iter()
+            .enumerate()
+            .map(|(i, value)| [value.to_bits(), (i as f32).to_bits()])
+            .collect();
+        vec![(0, bits)]
+    }
+
+    #[test]
+    fn identical_recordings_match() {
+        let run = recording(&[0., 0.5, -0.5]);
+        assert_eq!(first_difference(&run, &run.clone()), None);
+    }
+
+    #[test]
+    fn reports_the_first_sample_that_differs() {
+        let difference =
+            first_difference(&recording(&[0., 0.5, -0.5]), &recording(&[0., 0.25, 1.]));
+        assert_eq!(
+            difference.as_deref(),
+            Some("receiver 0 sample 1 was 0.5 the first time, 0.25 the second")
+        );
+    }
+
+    #[test]
+    fn reports_a_different_number_of_samples() {
+        let difference = first_difference(&recording(&[0., 0.5]), &recording(&[0.]));
+        assert_eq!(
+            difference.as_deref(),
+            Some("receiver 0 took 2 samples the first time, 1 the second")
+        );
+    }
+
+    #[test]
+    fn reports_a_different_number_of_receivers() {
+        let difference = first_difference(&recording(&[0.]), &Vec::new());
+        assert_eq!(
+            difference.as_deref(),
+            Some("1 receivers the first time, 0 the second")
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Checks enabled with the `debug_checks` feature that catch bad state before it reaches the
+//! renderer, where it would otherwise just leave the screen blank.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{Receiver, SignalParticle};
+
+/// Whether any part of `transform` is NaN or infinite.
+///
+/// A frequency so large that `2πf * t` overflows, or a speed or amplitude of `NaN` in the config,
+/// shows up as a non-finite translation here: a translation of `Vec3::new(f32::NAN, 0., 0.)` is
+/// flagged while `Transform::IDENTITY` isn't.
+pub fn is_degenerate(transform: &Transform) -> bool {
+    !transform.translation.is_finite()
+        || !transform.rotation.is_finite()
+        || !transform.scale.is_finite()
+}
+
+/// Logs an error for every particle and receiver whose transform has gone NaN or infinite,
+/// along with the parameters that got it there. Each entity is only reported once.
+pub fn check_transforms(
+    particle_query: Query<(Entity, &Transform, &SignalParticle)>,
+    rx_query: Query<(Entity, &Transform, &Receiver)>,
+    mut reported: Local<HashSet<Entity>>,
+) {
+    for (entity, transform, particle) in particle_query.iter() {
+        if is_degenerate(transform) && reported.insert(entity) {
+            error!(
+                "Particle {entity:?} has a non-finite transform {transform:?}: speed {}, \
+                 amplitude {}, frequency {} Hz, origin {}, direction {}, displacement {}",
+                particle.speed,
+                particle.amplitude,
+                particle.frequency,
+                particle.origin,
+                particle.direction,
+                particle.displacement
+            );
+        }
+    }
+    for (entity, transform, rx) in rx_query.iter() {
+        if is_degenerate(transform) && reported.insert(entity) {
+            error!(
+                "Receiver {} ({entity:?}) has a non-finite transform {transform:?}: speed {}, \
+                 draw position {}",
+                rx.index + 1,
+                rx.speed,
+                rx.current_draw_position
+            );
+        }
+    }
+    // Despawned entities can't be reported again, so don't hold on to them.
+    reported.retain(|&entity| particle_query.contains(entity) || rx_query.contains(entity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{shaped_displacement, WaveShape};
+
+    /// Where a particle 100 pixels along its path is drawn after a second with these parameters.
+    fn particle_transform(amplitude: f32, frequency: f32, speed: f32) -> Transform {
+        let y = shaped_displacement(amplitude, frequency, speed, &WaveShape::Sine, 100., 1.);
+        Transform::from_xyz(100., y, 0.)
+    }
+
+    #[test]
+    fn degenerate_parameters_are_flagged() {
+        assert!(!is_degenerate(&particle_transform(50., 2., -200.)));
+        assert!(is_degenerate(&particle_transform(f32::NAN, 2., -200.)));
+        assert!(is_degenerate(&particle_transform(50., f32::MAX, -200.)));
+        assert!(is_degenerate(&particle_transform(50., 2., f32::NAN)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Readout for [`Scenario::Differential`]: how far apart the frequencies heard by an approaching
+//! and a receding receiver are, predicted and measured.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig, expected_frequency, scenario::Scenario, Mover, OverlayFont, Receiver,
+    Transmitter, TransmitterBody, HIGH_RES_LAYERS,
+};
+
+const READOUT_FONT_SIZE: f32 = 36.;
+const READOUT_COLOR: Color = Color::rgb(1., 0.85, 0.3);
+
+/// Text showing the frequency difference between the two receivers.
+#[derive(Component)]
+pub struct DifferentialText;
+
+pub fn spawn_differential_readout(mut commands: Commands, font: Res<OverlayFont>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.0.clone(),
+                font_size: READOUT_FONT_SIZE,
+                color: READOUT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(15.),
+            right: Val::Px(15.),
+            ..default()
+        }),
+        Visibility::Hidden,
+        DifferentialText,
+        HIGH_RES_LAYERS,
+    ));
+}
+
+/// Shows `Δf` between the first two receivers while the differential scenario is running.
+pub fn update_differential_readout(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DifferentialText>>,
+) {
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else {
+        return;
+    };
+    let shown = config.scenario == Scenario::Differential;
+    visibility.set_if_neq(if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if !shown {
+        return;
+    }
+
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by_key(|(rx, _)| rx.index);
+    let readings: Vec<(f32, Option<f32>)> = receivers
+        .iter()
+        .take(2)
+        .filter_map(|(rx, rx_mover)| {
+            let (tx, children) = tx_query.iter().find(|(tx, _)| tx.index == rx.index)?;
+            let tx_mover = body_query.iter_many(children).next();
+            let expected = expected_frequency(&config, tx, tx_mover, rx, *rx_mover);
+            Some((expected, rx.smoothed_frequency))
+        })
+        .collect();
+    let [(expected_a, measured_a), (expected_b, measured_b)] = readings[..] else {
+        return;
+    };
+
+    let measured = match (measured_a, measured_b) {
+        (Some(a), Some(b)) => format!("{:.2} Hz", a - b),
+        _ => "-".to_string(),
+    };
+    let readout = format!(
+        "Δf = {:.2} Hz expected\nΔf = {measured} measured",
+        expected_a - expected_b
+    );
+    if text.sections[0].value != readout {
+        text.sections[0].value = readout;
+    }
+}
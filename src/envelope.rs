@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use crate::{
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    SignalParticle, Transmitter,
+};
+
+const ENVELOPE_COLOR: Color = Color::rgba(1., 1., 1., 0.25);
+
+/// Whether the amplitude envelope of each transmitter's wave is drawn.
+#[derive(Resource, Default)]
+pub struct ShowEnvelope(bool);
+
+pub fn toggle_envelope(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut show: ResMut<ShowEnvelope>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.envelope) {
+        show.0 = !show.0;
+        hud.set("envelope", envelope_label(show.0, &bindings));
+    }
+}
+
+pub fn envelope_label(shown: bool, bindings: &KeyBindings) -> String {
+    let action = if shown { "hide" } else { "show" };
+    let key = key_name(bindings.envelope);
+    format!("Press {key} to {action} the wave envelope")
+}
+
+/// Draws lines through `±amplitude` either side of each particle's line of travel, joined from
+/// the oldest particle to the newest. For dispersive waves the lines follow the modulation
+/// instead, pinching together between wave packets and sliding along at the group speed.
+/// Particles are grouped by transmitter, direction and lane so each ray of a point source and
+/// each line of a plane wave gets its own envelope, and moving transmitters or changing
+/// amplitudes show up as bends in the lines.
+pub fn draw_envelope(
+    show: Res<ShowEnvelope>,
+    tx_query: Query<(&Children, &GlobalTransform), With<Transmitter>>,
+    particle_query: Query<(&Transform, &SignalParticle)>,
+    mut gizmos: Gizmos,
+) {
+    if !show.0 {
+        return;
+    }
+
+    for (children, tx_transform) in tx_query.iter() {
+        let mut rays: Vec<((Vec2, i32), Vec<(u64, Vec2, Vec2)>)> = Vec::new();
+        for (transform, particle) in particle_query.iter_many(children) {
+            let perp = particle.direction.perp();
+            // Particles are positioned relative to their transmitter, which doesn't move.
+            let center = tx_transform.translation().xy() + transform.translation.xy()
+                - perp * particle.displacement;
+            let bounds = (
+                particle.spawn_index,
+                center + perp * particle.envelope,
+                center - perp * particle.envelope,
+            );
+            let key = (particle.direction, particle.lane);
+            match rays.iter_mut().find(|(ray_key, _)| *ray_key == key) {
+                Some((_, ray)) => ray.push(bounds),
+                None => rays.push((key, vec![bounds])),
+            }
+        }
+
+        for (_, mut ray) in rays {
+            ray.sort_unstable_by_key(|(spawn_index, _, _)| *spawn_index);
+            gizmos.linestrip_2d(ray.iter().map(|(_, upper, _)| *upper), ENVELOPE_COLOR);
+            gizmos.linestrip_2d(ray.iter().map(|(_, _, lower)| *lower), ENVELOPE_COLOR);
+        }
+    }
+}
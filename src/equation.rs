@@ -0,0 +1,168 @@
+//! An overlay with the equation the particles follow, with the current values filled in.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{EmissionMode, SimConfig},
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics::{self, Dispersion, Harmonic, WaveShape},
+    CurrentAmplitude, OverlayFont, SelectedReceiver, Transmitter, HIGH_RES_LAYERS,
+};
+
+const EQUATION_FONT_SIZE: f32 = 20.;
+
+/// Text showing the wave equation of the selected receiver's transmitter.
+#[derive(Component)]
+pub struct EquationText;
+
+pub fn spawn_equation(mut commands: Commands, font: Res<OverlayFont>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.0.clone(),
+                font_size: EQUATION_FONT_SIZE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(15.),
+            left: Val::Px(15.),
+            ..default()
+        }),
+        Visibility::Hidden,
+        EquationText,
+        HIGH_RES_LAYERS,
+    ));
+}
+
+pub fn equation_label(shown: bool, bindings: &KeyBindings) -> String {
+    let action = if shown { "hide" } else { "show" };
+    format!(
+        "Press {} to {action} the wave equation",
+        key_name(bindings.wave_equation)
+    )
+}
+
+pub fn toggle_equation(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut query: Query<&mut Visibility, With<EquationText>>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.wave_equation) {
+        return;
+    }
+    for mut visibility in query.iter_mut() {
+        let shown = *visibility == Visibility::Hidden;
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        hud.set("wave_equation", equation_label(shown, &bindings));
+    }
+}
+
+/// The equation for displacement `y` at distance `x` along the wave and time `t`, first with
+/// symbols and then with the values substituted, followed by where the values come from. It's
+/// the same equation [`physics::wave_displacement`] and [`physics::envelope`] evaluate. Shapes
+/// other than a sine are written as `f`, with a line saying what `f` is.
+pub fn wave_equation(
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+    dispersion: Dispersion,
+    shape: &WaveShape,
+) -> String {
+    let (f, shape_note) = match shape {
+        WaveShape::Sine => ("sin", String::new()),
+        WaveShape::Table(samples) => (
+            "f",
+            format!("\nf = the {}-sample waveform, peaking at ±1", samples.len()),
+        ),
+        WaveShape::Harmonics { harmonics, .. } => (
+            "f",
+            format!("\nf(θ) = {}, scaled to peak at ±1", harmonic_sum(harmonics)),
+        ),
+    };
+    let omega = TAU * frequency;
+    if speed == 0. {
+        return format!(
+            "y = −A·{f}(−ωt)\n  = −{amplitude:.1}·{f}(−{omega:.2}t)\n\
+             standing wave: A = {amplitude:.1} px, ω = 2πf = {omega:.2} rad/s{shape_note}"
+        );
+    }
+    let k = omega / speed;
+    let carrier = format!("{f}({k:.4}x − {omega:.2}t)");
+    let values = format!(
+        "A = {amplitude:.1} px, k = 2πf/v = {k:.4} rad/px, ω = 2πf = {omega:.2} rad/s, \
+         v = {speed:.0} px/s"
+    );
+    match physics::modulation(frequency, speed, dispersion) {
+        Some((dk, d_omega)) => format!(
+            "y = −A·cos(Δk·x − Δω·t)·{f}(kx − ωt)\n  = −{amplitude:.1}·cos({dk:.4}x − \
+             {d_omega:.2}t)·{carrier}\n{values}, packets at Δω/Δk = {:.0} px/s{shape_note}",
+            d_omega / dk
+        ),
+        None => format!("y = −A·{f}(kx − ωt)\n  = −{amplitude:.1}·{carrier}\n{values}{shape_note}"),
+    }
+}
+
+/// `harmonics` written out as a sum of sines of the phase `θ`.
+fn harmonic_sum(harmonics: &[Harmonic]) -> String {
+    harmonics
+        .iter()
+        .map(|h| {
+            let multiple = if h.number == 1 {
+                String::new()
+            } else {
+                h.number.to_string()
+            };
+            let phase = if h.phase == 0. {
+                String::new()
+            } else {
+                format!(" + {:.0}°", h.phase)
+            };
+            format!("{:.2}·sin({multiple}θ{phase})", h.amplitude)
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Fills in the equation from the selected receiver's transmitter while it's shown.
+pub fn update_equation(
+    config: Res<SimConfig>,
+    amplitude: Res<CurrentAmplitude>,
+    selected: Res<SelectedReceiver>,
+    tx_query: Query<&Transmitter>,
+    mut text_query: Query<(&mut Text, &Visibility), With<EquationText>>,
+) {
+    let Some(tx) = tx_query.iter().find(|tx| tx.index == selected.0) else {
+        return;
+    };
+    // Point sources send particles outwards along every ray, so `x` is the distance travelled.
+    let speed = match config.emission_mode() {
+        EmissionMode::Stream | EmissionMode::PlaneWave => config.wave_speed,
+        EmissionMode::PointSource => config.wave_speed.abs(),
+    };
+    for (mut text, visibility) in text_query.iter_mut() {
+        if visibility == Visibility::Hidden {
+            continue;
+        }
+        let equation = wave_equation(
+            amplitude.0 * tx.gain,
+            tx.frequency,
+            speed,
+            tx.dispersion,
+            &tx.shape,
+        );
+        if text.sections[0].value != equation {
+            text.sections[0].value = equation;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! A debug view for dense scenes: particles drawn on top of each other are spread apart a few
+//! pixels vertically, by their spawn index, so each can be picked out. Only where they're drawn
+//! changes; their transforms, and so their motion and collisions, are left exactly as they are.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    SignalParticle,
+};
+
+/// Particles are spread over this many heights, repeating with their spawn index.
+const SPREAD_STEPS: u64 = 5;
+
+/// Whether particles are drawn spread apart.
+#[derive(Resource, Default)]
+pub struct DebugExplode {
+    pub active: bool,
+    /// Particles were drawn spread apart this frame, so need putting back before the next one.
+    spread: bool,
+}
+
+pub fn explode_label(explode: &DebugExplode, bindings: &KeyBindings) -> String {
+    let action = if explode.active { "stop" } else { "start" };
+    format!(
+        "Press {} to {action} spreading overlapping particles apart",
+        key_name(bindings.explode)
+    )
+}
+
+pub fn toggle_explode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut explode: ResMut<DebugExplode>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.explode) {
+        return;
+    }
+    explode.active = !explode.active;
+    hud.set("explode", explode_label(&explode, &bindings));
+}
+
+/// Moves where each particle is drawn up or down by `explode_spacing` for each step of its spawn
+/// index. Runs after transform propagation, so it's the drawn position that moves and not the
+/// particle.
+pub fn explode_particles(
+    config: Res<SimConfig>,
+    mut explode: ResMut<DebugExplode>,
+    mut particle_query: Query<(&SignalParticle, &mut GlobalTransform)>,
+) {
+    if !explode.active {
+        return;
+    }
+    let middle = (SPREAD_STEPS - 1) as f32 / 2.;
+    for (particle, mut global_transform) in particle_query.iter_mut() {
+        let step = (particle.spawn_index % SPREAD_STEPS) as f32 - middle;
+        let offset = Vec3::Y * step * config.explode_spacing;
+        *global_transform = GlobalTransform::from_translation(offset) * *global_transform;
+    }
+    explode.spread = true;
+}
+
+/// Puts particles spread apart last frame back where their transforms say they are, before
+/// anything reads their positions.
+pub fn restore_particles(
+    mut explode: ResMut<DebugExplode>,
+    parent_query: Query<&GlobalTransform, Without<SignalParticle>>,
+    mut particle_query: Query<(&Parent, &Transform, &mut GlobalTransform), With<SignalParticle>>,
+) {
+    if !explode.spread {
+        return;
+    }
+    for (parent, transform, mut global_transform) in particle_query.iter_mut() {
+        if let Ok(parent_transform) = parent_query.get(parent.get()) {
+            *global_transform = parent_transform.mul_transform(*transform);
+        }
+    }
+    explode.spread = false;
+}
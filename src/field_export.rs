@@ -0,0 +1,166 @@
+//! Saving the wave field for analysis elsewhere: the same field the heatmap shows is sampled over
+//! a grid across the canvas at the current moment and written as a NumPy array or a CSV matrix,
+//! with the extents and resolution alongside, ready to load in Python.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capture::{screenshot_path, ScreenshotKind},
+    config::SimConfig,
+    heatmap,
+    keybindings::{key_name, KeyBindings},
+    physics, CurrentAmplitude, Transmitter, RES_HEIGHT, RES_WIDTH,
+};
+
+/// File format the field is exported in.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldExportFormat {
+    /// A `.npy` array of 32-bit floats, read with `numpy.load`, with the extents in a `.json`
+    /// file of the same name.
+    #[default]
+    Npy,
+    /// A `.csv` matrix, read with `numpy.loadtxt(path, delimiter=",")`, with the extents in a
+    /// comment on its first line.
+    Csv,
+}
+
+/// Where an exported field was sampled. Rows run from the top of the canvas down and columns
+/// from left to right, each sample taken at the centre of its cell.
+#[derive(Serialize)]
+pub struct FieldExtents {
+    /// Left and right edges of the grid, in world pixels.
+    pub x: [f32; 2],
+    /// Top and bottom edges of the grid, in world pixels.
+    pub y: [f32; 2],
+    pub columns: u32,
+    pub rows: u32,
+    /// Simulation time it was sampled at, in seconds.
+    pub time: f32,
+}
+
+impl FieldExtents {
+    /// A grid of `columns` and `rows` over the whole canvas.
+    pub fn canvas(columns: u32, rows: u32, time: f32) -> Self {
+        let (x, y) = (RES_WIDTH as f32 / 2., RES_HEIGHT as f32 / 2.);
+        FieldExtents {
+            x: [-x, x],
+            y: [y, -y],
+            columns,
+            rows,
+            time,
+        }
+    }
+}
+
+pub fn export_field_help(bindings: &KeyBindings) -> String {
+    format!(
+        "Press shift+{} to save the wave field for NumPy",
+        key_name(bindings.snapshot)
+    )
+}
+
+/// Writes `values`, `rows` of `columns` each, as a version 1.0 `.npy` file of little-endian
+/// 32-bit floats.
+fn write_npy(path: &Path, rows: u32, columns: u32, values: &[f32]) -> io::Result<()> {
+    let mut header =
+        format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({rows}, {columns}), }}");
+    // The magic string, version and header length take 10 bytes, and the header is padded with
+    // spaces and a newline so the data starts on a 64 byte boundary.
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + values.len() * 4);
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fs::write(path, bytes)
+}
+
+/// Writes `values`, `columns` to a row, as CSV with `comment` on a first line starting with `#`.
+fn write_csv(path: &Path, columns: u32, values: &[f32], comment: &str) -> io::Result<()> {
+    let mut csv = format!("# {comment}\n");
+    for row in values.chunks(columns as usize) {
+        let row: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    fs::write(path, csv)
+}
+
+/// Saves `values`, a row at a time from the top, in the `field_export_format` as the `num`th
+/// file of `kind`, with `extents` beside it or in its first line, and returns where it went.
+pub fn save_grid(
+    config: &SimConfig,
+    kind: ScreenshotKind,
+    num: u32,
+    values: &[f32],
+    extents: &FieldExtents,
+) -> io::Result<PathBuf> {
+    let path = screenshot_path(config, kind, num);
+    let json = serde_json::to_string(extents).unwrap_or_default();
+    match config.field_export_format {
+        FieldExportFormat::Npy => {
+            let path = path.with_extension("npy");
+            write_npy(&path, extents.rows, extents.columns, values)?;
+            fs::write(path.with_extension("json"), json)?;
+            Ok(path)
+        }
+        FieldExportFormat::Csv => {
+            let path = path.with_extension("csv");
+            write_csv(&path, extents.columns, values, &json)?;
+            Ok(path)
+        }
+    }
+}
+
+/// Samples the field every transmitter makes, as the heatmap shows it, over `field_export_cells`
+/// columns and rows across the canvas when shift and the snapshot key are pressed, and saves it
+/// in the `field_export_format` alongside the screenshots.
+pub fn export_field(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    amplitude: Res<CurrentAmplitude>,
+    time: Res<Time>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut counter: Local<u32>,
+) {
+    if !input.just_pressed(bindings.snapshot)
+        || !input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+    let sources = heatmap::field_sources(tx_query.iter(), &config, amplitude.0);
+    let [columns, rows] = config.field_export_cells.map(|cells| cells.max(1));
+    let view = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+    let cell = view / Vec2::new(columns as f32, rows as f32);
+    let top_left = Vec2::new(-view.x, view.y) / 2.;
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    let values: Vec<f32> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let point = top_left + Vec2::new(column as f32 + 0.5, -(row as f32 + 0.5)) * cell;
+            physics::sample_field(point, &sources, t)
+        })
+        .collect();
+    let extents = FieldExtents::canvas(columns, rows, t);
+    let saved = save_grid(&config, ScreenshotKind::Field, *counter, &values, &extents);
+    *counter += 1;
+    match saved {
+        Ok(path) => info!(
+            "Saved the field over {columns}x{rows} cells to {}",
+            path.display()
+        ),
+        Err(err) => error!("Cannot save the field: {err}"),
+    }
+}
@@ -0,0 +1,135 @@
+//! One key that turns the current moment into a labelled figure: the simulation is paused, the
+//! wavelength rulers and a caption with each receiver's observed frequency and time of flight are
+//! shown in place of the HUD, and the window is saved. Everything goes back the next frame.
+
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+use crate::{
+    capture::{save_window, screenshot_path, ScreenshotKind},
+    config::SimConfig,
+    hud::{Hud, HudText},
+    keybindings::{key_name, KeyBindings},
+    ruler::ShowRuler,
+    OverlayFont, Receiver, HIGH_RES_LAYERS,
+};
+
+const CAPTION_FONT_SIZE: f32 = 20.;
+/// HUD lines copied into the caption, in order, after the observed frequencies.
+const CAPTION_LINES: [&str; 2] = ["expected_frequencies", "time_of_flight"];
+
+/// Text listing the figure's measurements, only present for the frame that's captured.
+#[derive(Component)]
+pub struct FigureCaption;
+
+/// What a figure changed, to put back once it's been captured.
+pub struct Restore {
+    paused: bool,
+    ruler: bool,
+}
+
+pub fn figure_help(bindings: &KeyBindings) -> String {
+    format!(
+        "Press {} to pause and save a labelled figure",
+        key_name(bindings.figure)
+    )
+}
+
+/// Captures a figure when the figure key is pressed, and puts things back the frame after. Runs
+/// before the rulers are drawn so they're in the captured frame.
+pub fn capture_figure(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    hud: Res<Hud>,
+    rx_query: Query<&Receiver>,
+    mut time: ResMut<Time<Virtual>>,
+    mut show_ruler: ResMut<ShowRuler>,
+    mut hud_query: Query<&mut Visibility, With<HudText>>,
+    caption_query: Query<Entity, With<FigureCaption>>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut restore: Local<Option<Restore>>,
+    mut counter: Local<u32>,
+) {
+    if let Some(previous) = restore.take() {
+        if !previous.paused {
+            time.unpause();
+        }
+        show_ruler.0 = previous.ruler;
+        for mut visibility in hud_query.iter_mut() {
+            *visibility = Visibility::Inherited;
+        }
+        for caption in caption_query.iter() {
+            commands.entity(caption).despawn_recursive();
+        }
+        return;
+    }
+
+    if !input.just_pressed(bindings.figure) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    *restore = Some(Restore {
+        paused: time.is_paused(),
+        ruler: show_ruler.0,
+    });
+    time.pause();
+    show_ruler.0 = true;
+    for mut visibility in hud_query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by_key(|rx| rx.index);
+    let observed: Vec<_> = receivers
+        .iter()
+        .map(|rx| {
+            rx.smoothed_frequency
+                .map_or("-".to_string(), |frequency| format!("{frequency:.2} Hz"))
+        })
+        .collect();
+    let mut caption = vec![
+        format!("{:?}", config.scenario),
+        format!("Observed frequencies: {}", observed.join(", ")),
+    ];
+    caption.extend(
+        CAPTION_LINES
+            .iter()
+            .filter_map(|&key| hud.get(key).map(String::from)),
+    );
+    commands.spawn((
+        TextBundle::from_section(
+            caption.join("\n"),
+            TextStyle {
+                font: font.0.clone(),
+                font_size: CAPTION_FONT_SIZE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(15.),
+            left: Val::Px(15.),
+            ..default()
+        }),
+        FigureCaption,
+        HIGH_RES_LAYERS,
+    ));
+
+    // The screenshot is of the end of this frame, by when the caption has been laid out and the
+    // rulers drawn.
+    let path = screenshot_path(&config, ScreenshotKind::Figure, *counter);
+    *counter += 1;
+    save_window(
+        &mut screenshot_manager,
+        window,
+        path,
+        None,
+        config.export_contrast,
+    );
+}
@@ -0,0 +1,83 @@
+//! Coping with the window losing focus. The OS may stop sending frames to a window in the
+//! background, so the first frame back can be seconds after the last, and every timer would try
+//! to catch up at once. The time a frame can advance the simulation by is capped, and the
+//! simulation can pause itself while the window's in the background.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{config::SimConfig, hud::Hud, keybindings::KeyBindings, pause_label};
+
+/// Caps how far one frame can advance the simulation at `max_frame_delta_ms`, so a long gap
+/// between frames is dropped instead of caught up in one go. Runs before the clock is advanced,
+/// and applies the cap again whenever the config changes, e.g. from a preset or the settings
+/// panel.
+pub fn limit_frame_delta(config: Res<SimConfig>, mut time: ResMut<Time<Virtual>>) {
+    if !config.is_changed() {
+        return;
+    }
+    time.set_max_delta(Duration::from_millis(config.max_frame_delta_ms));
+}
+
+/// Pauses the simulation when the window loses focus, if `pause_on_blur` is set, and resumes it
+/// when the window gets focus back. A pause from the pause key or a launch notice is left alone.
+pub fn pause_on_blur(
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hud: ResMut<Hud>,
+    mut paused_by_blur: Local<bool>,
+) {
+    let Some(event) = focus_events.read().last() else {
+        return;
+    };
+    if !config.pause_on_blur {
+        return;
+    }
+    if !event.focused && !time.is_paused() {
+        time.pause();
+        *paused_by_blur = true;
+    } else if event.focused && *paused_by_blur {
+        time.unpause();
+        *paused_by_blur = false;
+    } else {
+        return;
+    }
+    hud.set("pause", pause_label(time.is_paused(), &bindings));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::{TimePlugin, TimeSystem, TimeUpdateStrategy};
+
+    use super::*;
+
+    /// An app that only keeps time, advancing by `frame` every update.
+    fn app(config: SimConfig, frame: Duration) -> App {
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(config)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+            .add_systems(First, limit_frame_delta.before(TimeSystem));
+        // The first update only starts the clock.
+        app.update();
+        app
+    }
+
+    #[test]
+    fn cap_follows_config_changes() {
+        let config = SimConfig {
+            max_frame_delta_ms: 100,
+            ..default()
+        };
+        let mut app = app(config, Duration::from_secs(10));
+        app.world.resource_mut::<SimConfig>().max_frame_delta_ms = 40;
+        app.update();
+        assert_eq!(
+            app.world.resource::<Time<Virtual>>().delta(),
+            Duration::from_millis(40)
+        );
+    }
+}
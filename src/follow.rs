@@ -0,0 +1,241 @@
+//! Camera follow: the view can track a receiver as it moves so its plot never scrolls off screen.
+//!
+//! With the canvas pipeline it's the [`InGameCamera`] that moves, panning the world inside the
+//! canvas while the canvas itself stays put and keeps its `fit_canvas` scaling. With the direct
+//! pipeline the single [`OuterCamera`] moves instead.
+//!
+//! The camera can also zoom out to fit every transmitter and receiver in view, scaling whichever
+//! camera it moves.
+//!
+//! Labels on the high-res layers are drawn by the [`OuterCamera`], so with the canvas pipeline
+//! they're moved to wherever the [`InGameCamera`] shows the world they label.
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    InGameCamera, OuterCamera, Receiver, Transmitter, TransmitterBody, RES_HEIGHT, RES_WIDTH,
+};
+
+/// How quickly the camera catches up with its target, per second. Higher is snappier.
+const FOLLOW_RATE: f32 = 4.;
+/// Space left around the transmitters and receivers when fitting them in view, in pixels. Enough
+/// for a receiver centred on the edge of the box to be in view whichever way it's turned.
+const FIT_MARGIN: f32 = 150.;
+
+/// Receiver the camera is following, if any. When it's cleared the camera eases back to the
+/// origin.
+#[derive(Resource, Default)]
+pub struct CameraFollow(pub Option<Entity>);
+
+/// Whether the camera zooms out to keep every transmitter and receiver in view.
+#[derive(Resource, Default)]
+pub struct CameraFit(bool);
+
+/// High-res text that's a child of something in the pixel-perfect world, kept over it by
+/// [`place_world_labels`] wherever the view has panned and zoomed to.
+#[derive(Component)]
+pub struct WorldLabel;
+
+/// Where `point` in the pixel-perfect world is drawn on the canvas, which sits at the origin of
+/// the high-res world, through wherever the [`InGameCamera`] has panned and zoomed to. Without
+/// one, as with the direct pipeline, the two worlds are the same.
+pub fn world_to_canvas(in_game_camera: Option<&Transform>, point: Vec2) -> Vec2 {
+    in_game_camera.map_or(point, |camera| {
+        let to_canvas = camera.compute_affine().inverse();
+        to_canvas
+            .transform_point3(point.extend(camera.translation.z))
+            .truncate()
+    })
+}
+
+/// The follow key starts following the first receiver, or stops following. The next-target key
+/// moves on to the next receiver by index.
+pub fn control_camera_follow(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    rx_query: Query<(Entity, &Receiver)>,
+    mut follow: ResMut<CameraFollow>,
+    mut hud: ResMut<Hud>,
+) {
+    // Receivers are rebuilt on reset, so a followed one may be gone.
+    if follow.0.is_some_and(|entity| !rx_query.contains(entity)) {
+        follow.0 = None;
+        hud.set("follow", follow_label(None, &bindings));
+    }
+    // With shift the follow key fits the view instead.
+    if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_unstable_by_key(|(_, rx)| rx.index);
+    let target = if input.just_pressed(bindings.follow) {
+        match follow.0 {
+            Some(_) => None,
+            None => receivers.first().copied(),
+        }
+    } else if input.just_pressed(bindings.follow_next) && follow.0.is_some() {
+        let current = receivers
+            .iter()
+            .position(|(entity, _)| Some(*entity) == follow.0);
+        let next = current.map_or(0, |current| (current + 1) % receivers.len());
+        receivers.get(next).copied()
+    } else {
+        return;
+    };
+
+    follow.0 = target.map(|(entity, _)| entity);
+    let index = target.map(|(_, rx)| rx.index);
+    hud.set("follow", follow_label(index, &bindings));
+}
+
+pub fn follow_label(following: Option<usize>, bindings: &KeyBindings) -> String {
+    let toggle = key_name(bindings.follow);
+    match following {
+        Some(index) => format!(
+            "Camera following receiver {} (press {} for the next, {toggle} to stop)",
+            index + 1,
+            key_name(bindings.follow_next)
+        ),
+        None => format!("Press {toggle} to follow a receiver with the camera"),
+    }
+}
+
+pub fn camera_fit_label(fit: &CameraFit, bindings: &KeyBindings) -> String {
+    let action = if fit.0 { "stop fitting" } else { "fit" };
+    format!(
+        "Press shift+{} to {action} every transmitter and receiver in view",
+        key_name(bindings.follow)
+    )
+}
+
+/// Shift with the follow key switches fitting the view on or off.
+pub fn toggle_camera_fit(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut fit: ResMut<CameraFit>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.follow)
+        && input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        fit.0 = !fit.0;
+        hud.set("camera_fit", camera_fit_label(&fit, &bindings));
+    }
+}
+
+/// Centre and scale that fit every point of `points` in view with [`FIT_MARGIN`] to spare. Only
+/// ever zooms out, since zooming in on the pixel-perfect canvas would blur it.
+fn fit_view(points: impl Iterator<Item = Vec2>) -> Option<(Vec2, f32)> {
+    let (min, max) = points.fold(None, |bounds: Option<(Vec2, Vec2)>, point| {
+        Some(bounds.map_or((point, point), |(min, max)| {
+            (min.min(point), max.max(point))
+        }))
+    })?;
+    let size = max - min + Vec2::splat(2. * FIT_MARGIN);
+    let scale = (size.x / RES_WIDTH as f32)
+        .max(size.y / RES_HEIGHT as f32)
+        .max(1.);
+    Some(((min + max) / 2., scale))
+}
+
+/// Eases the camera towards the followed receiver, or back to the origin when not following.
+/// While fitting the view it eases out to take in every transmitter and receiver where they are
+/// now, centred on them unless following a receiver. Runs on real time so the camera still
+/// settles while paused.
+pub fn follow_camera(
+    time: Res<Time<Real>>,
+    follow: Res<CameraFollow>,
+    fit: Res<CameraFit>,
+    target_query: Query<&GlobalTransform, With<Receiver>>,
+    content_query: Query<
+        &GlobalTransform,
+        Or<(With<Transmitter>, With<TransmitterBody>, With<Receiver>)>,
+    >,
+    mut in_game_camera_query: Query<&mut Transform, (With<InGameCamera>, Without<OuterCamera>)>,
+    mut outer_camera_query: Query<&mut Transform, (With<OuterCamera>, Without<InGameCamera>)>,
+) {
+    let fitted = fit
+        .0
+        .then(|| fit_view(content_query.iter().map(|t| t.translation().xy())))
+        .flatten();
+    let target = follow
+        .0
+        .and_then(|entity| target_query.get(entity).ok())
+        .map(|transform| transform.translation().xy())
+        .or(fitted.map(|(centre, _)| centre))
+        .unwrap_or(Vec2::ZERO);
+    let target_scale = fitted.map_or(1., |(_, scale)| scale);
+
+    let mut camera = match in_game_camera_query.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => {
+            let Ok(camera) = outer_camera_query.get_single_mut() else {
+                return;
+            };
+            camera
+        }
+    };
+    let blend = 1. - (-FOLLOW_RATE * time.delta_seconds()).exp();
+    let position = camera.translation.xy().lerp(target, blend);
+    camera.translation.x = position.x;
+    camera.translation.y = position.y;
+    // Settles exactly on the target scale, so an unzoomed canvas stays pixel-perfect.
+    let scale = if (target_scale - camera.scale.x).abs() < 1e-3 {
+        target_scale
+    } else {
+        camera.scale.x + (target_scale - camera.scale.x) * blend
+    };
+    if scale != camera.scale.x {
+        camera.scale = Vec3::new(scale, scale, 1.);
+    }
+}
+
+/// Moves each [`WorldLabel`] from where its parent puts it in the world to where the canvas shows
+/// that. Labels keep their size, so they stay legible while zoomed out. Its global transform is
+/// worked out again from its parent's each frame, so it's never moved twice.
+pub fn place_world_labels(
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    parent_query: Query<&GlobalTransform, Without<WorldLabel>>,
+    mut label_query: Query<(&Parent, &Transform, &mut GlobalTransform), With<WorldLabel>>,
+) {
+    let Ok(camera) = in_game_camera_query.get_single() else {
+        return;
+    };
+    for (parent, transform, mut global_transform) in label_query.iter_mut() {
+        let Ok(parent_transform) = parent_query.get(parent.get()) else {
+            continue;
+        };
+        let (scale, rotation, translation) = parent_transform
+            .mul_transform(*transform)
+            .to_scale_rotation_translation();
+        let position = world_to_canvas(Some(camera), translation.truncate());
+        *global_transform = Transform {
+            translation: position.extend(translation.z),
+            rotation,
+            scale,
+        }
+        .into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_points_are_drawn_through_the_pan_and_zoom() {
+        let camera = Transform::from_xyz(100., -50., 999.9).with_scale(Vec3::new(2., 2., 1.));
+        // The point the camera's centred on is drawn in the middle of the canvas.
+        assert_eq!(
+            world_to_canvas(Some(&camera), Vec2::new(100., -50.)),
+            Vec2::ZERO
+        );
+        // Zoomed out to half size, 200 pixels of the world cover 100 of the canvas.
+        let drawn = world_to_canvas(Some(&camera), Vec2::new(300., -50.));
+        assert!((drawn - Vec2::new(100., 0.)).length() < 1e-3, "{drawn}");
+        assert_eq!(world_to_canvas(None, Vec2::new(3., 4.)), Vec2::new(3., 4.));
+    }
+}
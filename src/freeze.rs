@@ -0,0 +1,53 @@
+//! Freezing each run a set time after it starts, so an unattended display settles on a still final
+//! state that's ready for a screenshot, where auto-reset would start it over.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    SimulationReset,
+};
+
+/// Simulation time since the run started, and whether it's been frozen yet.
+#[derive(Resource, Default)]
+pub struct FreezeTimer {
+    elapsed: f32,
+    frozen: bool,
+}
+
+/// Pauses the simulation once `freeze_after` seconds of simulation time have gone by since the
+/// last reset. It only freezes once per run, so resuming with the pause key carries on until the
+/// next reset.
+pub fn freeze_run(
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    mut reset_events: EventReader<SimulationReset>,
+    mut freeze: ResMut<FreezeTimer>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hud: ResMut<Hud>,
+) {
+    if reset_events.read().count() > 0 {
+        *freeze = FreezeTimer::default();
+    }
+    let Some(limit) = config.freeze_after else {
+        return;
+    };
+    if freeze.frozen || time.is_paused() {
+        return;
+    }
+    freeze.elapsed += time.delta_seconds();
+    if freeze.elapsed >= limit {
+        freeze.frozen = true;
+        time.pause();
+        info!("Froze the simulation after {limit}s");
+        hud.set(
+            "pause",
+            format!(
+                "Frozen after {limit}s (press {} to resume)",
+                key_name(bindings.pause)
+            ),
+        );
+    }
+}
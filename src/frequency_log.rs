@@ -0,0 +1,165 @@
+//! Logging each receiver's observed frequency over time to a CSV file, for plotting a long
+//! demonstration afterwards. Rows are appended every `frequency_log_interval_ms` of simulation
+//! time, and the file is rotated once it reaches `frequency_log_max_bytes`.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{config::SimConfig, Receiver};
+
+const HEADER: &str = "unix_time,sim_time,receiver,frequency\n";
+
+/// The open log, if `frequency_log` is set and the file could be opened.
+#[derive(Resource)]
+pub struct FrequencyLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Size of the file so far, including what's still buffered.
+    bytes: u64,
+    timer: Timer,
+}
+
+impl FrequencyLog {
+    /// Opens `path` to append to, writing the header first if it's new or empty.
+    fn open(path: &Path, interval: Duration) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut bytes = file.metadata()?.len();
+        let mut writer = BufWriter::new(file);
+        if bytes == 0 {
+            writer.write_all(HEADER.as_bytes())?;
+            bytes = HEADER.len() as u64;
+        }
+        Ok(FrequencyLog {
+            path: path.to_path_buf(),
+            writer,
+            bytes,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        })
+    }
+
+    fn write_row(&mut self, row: &str) -> io::Result<()> {
+        self.writer.write_all(row.as_bytes())?;
+        self.bytes += row.len() as u64;
+        Ok(())
+    }
+
+    /// Moves the file aside to the same name with `.1` before its extension, replacing any
+    /// earlier one, and starts a new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        fs::rename(&self.path, rotated_path(&self.path))?;
+        *self = FrequencyLog::open(&self.path, self.timer.duration())?;
+        Ok(())
+    }
+}
+
+/// Where a full log is moved to: `frequencies.csv` becomes `frequencies.1.csv`.
+fn rotated_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}.1.{}", extension.to_string_lossy()),
+        None => format!("{stem}.1"),
+    };
+    path.with_file_name(name)
+}
+
+/// Opens the `frequency_log` file, if one is set, logging why not if it can't be.
+pub fn open_frequency_log(mut commands: Commands, config: Res<SimConfig>) {
+    let Some(path) = &config.frequency_log else {
+        return;
+    };
+    let interval = Duration::from_millis(config.frequency_log_interval_ms);
+    match FrequencyLog::open(path, interval) {
+        Ok(log) => commands.insert_resource(log),
+        Err(err) => error!("Cannot open frequency log {}: {err}", path.display()),
+    }
+}
+
+/// Appends a row for each receiver with its smoothed observed frequency, blank before it has
+/// measured one, every `frequency_log_interval_ms` of simulation time, so nothing is written
+/// while paused. Rotates the file once it reaches `frequency_log_max_bytes`.
+pub fn log_frequencies(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    log: Option<ResMut<FrequencyLog>>,
+    rx_query: Query<&Receiver>,
+) {
+    let Some(mut log) = log else {
+        return;
+    };
+    if !log.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let unix_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0., |since| since.as_secs_f64());
+    let sim_time = time.elapsed_seconds();
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by_key(|rx| rx.index);
+    let written = receivers.iter().try_for_each(|rx| {
+        let frequency = rx
+            .smoothed_frequency
+            .map_or(String::new(), |frequency| frequency.to_string());
+        log.write_row(&format!(
+            "{unix_time:.3},{sim_time:.3},{},{frequency}\n",
+            rx.index
+        ))
+    });
+    let written = written.and_then(|()| match config.frequency_log_max_bytes {
+        Some(max_bytes) if log.bytes >= max_bytes => log.rotate(),
+        _ => Ok(()),
+    });
+    if let Err(err) = written {
+        error!(
+            "Cannot write frequency log {}, stopping it: {err}",
+            log.path.display()
+        );
+        commands.remove_resource::<FrequencyLog>();
+    }
+}
+
+/// Writes out whatever is still buffered when the app exits.
+pub fn flush_frequency_log_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    log: Option<ResMut<FrequencyLog>>,
+) {
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    if let Some(mut log) = log {
+        if let Err(err) = log.writer.flush() {
+            error!("Cannot write frequency log {}: {err}", log.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_path_numbers_before_the_extension() {
+        assert_eq!(
+            rotated_path(Path::new("logs/frequencies.csv")),
+            PathBuf::from("logs/frequencies.1.csv")
+        );
+    }
+
+    #[test]
+    fn rotated_path_without_an_extension() {
+        assert_eq!(
+            rotated_path(Path::new("frequencies")),
+            PathBuf::from("frequencies.1")
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Heatmap of the summed wave field from every transmitter, drawn behind the particles to show
+//! interference patterns the particles alone can't. It can show the difference between two
+//! transmitters' fields instead, for noise cancelling.
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics::{self, FieldSource},
+    CurrentAmplitude, Transmitter, PIXEL_PERFECT_LAYERS, RES_HEIGHT, RES_WIDTH,
+};
+
+/// Colors for the most negative and most positive field values. Zero is black.
+const NEGATIVE_COLOR: [f32; 3] = [0.2, 0.4, 1.];
+const POSITIVE_COLOR: [f32; 3] = [1., 0.3, 0.2];
+
+/// Pixel for a field value from -1 to 1, brighter the further it is from zero.
+pub fn field_color(value: f32) -> [u8; 4] {
+    let color = if value < 0. {
+        NEGATIVE_COLOR
+    } else {
+        POSITIVE_COLOR
+    };
+    let [r, g, b] = color.map(|component| (component * value.abs() * 255.) as u8);
+    [r, g, b, 255]
+}
+
+/// Which field the heatmap and contours show.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldMode {
+    /// Every emitting transmitter's wave added up, where they interfere.
+    #[default]
+    Sum,
+    /// The first of `difference_sources` minus the second, which is zero wherever one cancels
+    /// the other out.
+    Difference,
+}
+
+#[derive(Resource)]
+pub struct Heatmap {
+    image: Handle<Image>,
+    visible: bool,
+    refresh: Timer,
+}
+
+#[derive(Component)]
+pub struct HeatmapSprite;
+
+pub fn setup_heatmap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<SimConfig>,
+) {
+    let [width, height] = config.heatmap_cells.map(|cells| cells.max(1));
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        SpriteBundle {
+            texture: image.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., config.z_layers.heatmap),
+            visibility: if config.heatmap {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+        HeatmapSprite,
+        PIXEL_PERFECT_LAYERS,
+    ));
+    commands.insert_resource(Heatmap {
+        image,
+        visible: config.heatmap,
+        refresh: Timer::new(
+            Duration::from_millis(config.heatmap_refresh_ms),
+            TimerMode::Repeating,
+        ),
+    });
+}
+
+pub fn toggle_heatmap(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut heatmap: ResMut<Heatmap>,
+    mut sprite_query: Query<&mut Visibility, With<HeatmapSprite>>,
+    mut hud: ResMut<Hud>,
+) {
+    // With shift it's the contours' key instead.
+    if !input.just_pressed(bindings.heatmap)
+        || input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+    heatmap.visible = !heatmap.visible;
+    for mut visibility in sprite_query.iter_mut() {
+        *visibility = if heatmap.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    hud.set("heatmap", heatmap_label(heatmap.visible, &bindings));
+}
+
+/// Switches the heatmap and contours between the summed field and the difference of two
+/// transmitters.
+pub fn toggle_field_mode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.field_mode) {
+        config.field_mode = match config.field_mode {
+            FieldMode::Sum => FieldMode::Difference,
+            FieldMode::Difference => FieldMode::Sum,
+        };
+        hud.set("field_mode", field_mode_label(&config, &bindings));
+    }
+}
+
+pub fn field_mode_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.field_mode);
+    let [a, b] = config.difference_sources.map(|index| index + 1);
+    match config.field_mode {
+        FieldMode::Sum => {
+            format!("Field: every transmitter summed (press {key} for transmitter {a} minus {b})")
+        }
+        FieldMode::Difference => {
+            format!("Field: transmitter {a} minus {b} (press {key} for the sum)")
+        }
+    }
+}
+
+/// Sources of the field the heatmap and contours show. For [`FieldMode::Difference`] the second
+/// source's amplitude is negated, so summing them gives the difference.
+pub fn field_sources<'a>(
+    transmitters: impl Iterator<Item = (&'a Transmitter, &'a GlobalTransform)>,
+    config: &SimConfig,
+    amplitude: f32,
+) -> Vec<FieldSource> {
+    transmitters
+        .filter(|(tx, _)| tx.emitting)
+        .flat_map(|(tx, transform)| {
+            let sign = match config.field_mode {
+                FieldMode::Sum => 1.,
+                FieldMode::Difference => {
+                    let [a, b] = config.difference_sources;
+                    if tx.index == a {
+                        1.
+                    } else if tx.index == b {
+                        -1.
+                    } else {
+                        return Vec::new();
+                    }
+                }
+            };
+            // Each element of an array is a source of its own.
+            crate::emission_elements(config, tx.frequency)
+                .into_iter()
+                .map(|(element, lead)| FieldSource {
+                    position: transform.translation().xy() + tx.spawn_point + element,
+                    amplitude: sign * amplitude * tx.gain,
+                    frequency: tx.frequency,
+                    wave_speed: config.wave_speed,
+                    delay: tx.delay.duration().as_secs_f32() - lead,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn heatmap_label(visible: bool, bindings: &KeyBindings) -> String {
+    let action = if visible { "hide" } else { "show" };
+    let key = key_name(bindings.heatmap);
+    format!("Press {key} to {action} the interference heatmap")
+}
+
+/// Recomputes the heatmap from the emitting transmitters every `heatmap_refresh_ms`, sampling
+/// the field at the center of each cell.
+pub fn update_heatmap(
+    mut heatmap: ResMut<Heatmap>,
+    mut images: ResMut<Assets<Image>>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    amplitude: Res<CurrentAmplitude>,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+) {
+    if !heatmap.visible || !heatmap.refresh.tick(time.delta()).finished() {
+        return;
+    }
+    let Some(image) = images.get_mut(&heatmap.image) else {
+        return;
+    };
+
+    let sources = field_sources(tx_query.iter(), &config, amplitude.0);
+    // Scaled to full strength, so a transmitter ramped down shows up fainter.
+    let max_value = amplitude.0 * sources.len() as f32;
+
+    let size = image.size();
+    let cell = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / size.as_vec2();
+    let top_left = Vec2::new(-(RES_WIDTH as f32), RES_HEIGHT as f32) / 2.;
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    for (i, pixel) in image.data.chunks_exact_mut(4).enumerate() {
+        let (column, row) = (i as u32 % size.x, i as u32 / size.x);
+        let point = top_left + Vec2::new(column as f32 + 0.5, -(row as f32 + 0.5)) * cell;
+        let value = if max_value > 0. {
+            physics::sample_field(point, &sources, t) / max_value
+        } else {
+            0.
+        };
+        pixel.copy_from_slice(&field_color(value));
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    follow,
+    keybindings::{key_name, KeyBindings},
+    InGameCamera, RES_HEIGHT, RES_WIDTH,
+};
+
+/// Gap in pixels between the HUD and the top left corner.
+pub const HUD_MARGIN: f32 = 15.;
+
+/// Text overlay in the top left corner of the window.
+#[derive(Component)]
+pub struct HudText;
+
+/// Copy of the [`HudText`] overlay drawn in the world, shown in its place with
+/// [`HudAnchor::World`].
+#[derive(Component)]
+pub struct WorldHudText;
+
+/// What the HUD stays fixed to.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HudAnchor {
+    /// The window: it stays in the corner whatever the camera does.
+    #[default]
+    Screen,
+    /// The world's top left corner: it pans away with the rest of the world when the camera
+    /// follows a receiver, and scales with the canvas.
+    World,
+}
+
+/// Lines shown in the [`HudText`] overlay. Each line is owned by a key so systems can update
+/// their own status without knowing about the others. Lines are shown in insertion order.
+#[derive(Resource, Default)]
+pub struct Hud {
+    lines: Vec<(&'static str, String)>,
+}
+
+impl Hud {
+    pub fn set(&mut self, key: &'static str, value: impl Into<String>) {
+        let value = value.into();
+        match self.lines.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, line)) => *line = value,
+            None => self.lines.push((key, value)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, line)| line.as_str())
+    }
+
+    pub fn remove(&mut self, key: &'static str) {
+        self.lines.retain(|(k, _)| *k != key);
+    }
+}
+
+pub fn hud_anchor_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.hud_anchor);
+    match config.hud_anchor {
+        HudAnchor::Screen => {
+            format!("HUD fixed to the screen (press {key} to pin it to the world)")
+        }
+        HudAnchor::World => {
+            format!("HUD pinned to the world (press {key} to fix it to the screen)")
+        }
+    }
+}
+
+pub fn toggle_hud_anchor(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.hud_anchor) {
+        config.hud_anchor = match config.hud_anchor {
+            HudAnchor::Screen => HudAnchor::World,
+            HudAnchor::World => HudAnchor::Screen,
+        };
+        hud.set("hud_anchor", hud_anchor_label(&config, &bindings));
+    }
+}
+
+/// Shows whichever copy of the HUD matches [`SimConfig::hud_anchor`], and keeps the world copy
+/// in the world's top left corner. With the canvas pipeline the canvas doesn't move when the
+/// [`InGameCamera`] pans or zooms, so the world copy is placed through the camera to match.
+pub fn place_hud(
+    config: Res<SimConfig>,
+    mut screen_query: Query<&mut Visibility, (With<HudText>, Without<WorldHudText>)>,
+    mut world_query: Query<(&mut Visibility, &mut Transform), With<WorldHudText>>,
+    in_game_camera_query: Query<&Transform, (With<InGameCamera>, Without<WorldHudText>)>,
+) {
+    let world = config.hud_anchor == HudAnchor::World;
+    let shown = |on: bool| {
+        if on {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+    for mut visibility in screen_query.iter_mut() {
+        visibility.set_if_neq(shown(!world));
+    }
+    let corner = Vec2::new(
+        -(RES_WIDTH as f32) / 2. + HUD_MARGIN,
+        RES_HEIGHT as f32 / 2. - HUD_MARGIN,
+    );
+    let corner = follow::world_to_canvas(in_game_camera_query.get_single().ok(), corner);
+    for (mut visibility, mut transform) in world_query.iter_mut() {
+        visibility.set_if_neq(shown(world));
+        let position = corner.extend(config.z_layers.labels);
+        if transform.translation != position {
+            transform.translation = position;
+        }
+    }
+}
+
+pub fn update_hud(hud: Res<Hud>, mut text_query: Query<&mut Text, With<HudText>>) {
+    if !hud.is_changed() {
+        return;
+    }
+
+    let value = hud
+        .lines
+        .iter()
+        .map(|(_, line)| line.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    for mut text in text_query.iter_mut() {
+        // Only touch the text when it differs so an unchanged HUD isn't laid out again.
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
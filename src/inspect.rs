@@ -0,0 +1,108 @@
+//! Reading values off a receiver's plot: clicking a plot point shows the sample it stands for.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    cursor_world_position, measure::MeasureMode, settings::PointerOverUi, stopwatch::PeriodMeasure,
+    InGameCamera, OuterCamera, OverlayFont, PlotPoint, Receiver, HIGH_RES_LAYERS,
+    RECEIVER_DELTA_X_PER_SECOND, RECEIVER_PLOT_RADIUS, RECEIVER_WIDTH,
+};
+
+/// How far from the cursor, in world units, a plot point can be and still be picked.
+const PICK_RADIUS: f32 = RECEIVER_PLOT_RADIUS;
+/// Offset of the tooltip from the cursor, in logical pixels, so it isn't hidden under it.
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(12., 12.);
+const TOOLTIP_FONT_SIZE: f32 = 16.;
+const TOOLTIP_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.75);
+
+/// Text shown next to the cursor with the value of the last plot point clicked.
+#[derive(Component)]
+pub struct PlotTooltip;
+
+pub fn setup_tooltip(mut commands: Commands, font: Res<OverlayFont>) {
+    let mut tooltip = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: font.0.clone(),
+            font_size: TOOLTIP_FONT_SIZE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        padding: UiRect::all(Val::Px(4.)),
+        ..default()
+    })
+    .with_background_color(TOOLTIP_BACKGROUND);
+    tooltip.visibility = Visibility::Hidden;
+    commands.spawn((tooltip, PlotTooltip, HIGH_RES_LAYERS));
+}
+
+/// Seconds into a receiver's plot that a point at `plot_x` was sampled. Plotting starts at
+/// `RECEIVER_WIDTH` and moves left at `RECEIVER_DELTA_X_PER_SECOND`.
+pub fn plot_time(plot_x: f32) -> f32 {
+    (RECEIVER_WIDTH - plot_x) / RECEIVER_DELTA_X_PER_SECOND
+}
+
+/// Left clicking shows the time and amplitude of the nearest visible plot point within
+/// [`PICK_RADIUS`], or hides the tooltip when there's none.
+pub fn inspect_plot_point(
+    mouse: Res<ButtonInput<MouseButton>>,
+    measure: Res<MeasureMode>,
+    period: Res<PeriodMeasure>,
+    pointer: Res<PointerOverUi>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    point_query: Query<(&PlotPoint, &Parent, &GlobalTransform, &ViewVisibility)>,
+    rx_query: Query<&Receiver>,
+    mut tooltip_query: Query<(&mut Text, &mut Style, &mut Visibility), With<PlotTooltip>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left)
+        || measure.is_active()
+        || period.is_active()
+        || pointer.0
+    {
+        return;
+    }
+    let Ok((mut text, mut style, mut visibility)) = tooltip_query.get_single_mut() else {
+        return;
+    };
+    *visibility = Visibility::Hidden;
+    let Some(cursor) = cursor_world_position(&windows, &camera_query, &in_game_camera_query) else {
+        return;
+    };
+
+    let nearest = point_query
+        .iter()
+        .filter(|(.., view_visibility)| view_visibility.get())
+        .map(|(point, parent, transform, _)| {
+            let distance = transform.translation().xy().distance(cursor);
+            (point, parent, distance)
+        })
+        .filter(|(.., distance)| *distance <= PICK_RADIUS)
+        .min_by(|(.., a), (.., b)| a.total_cmp(b));
+    let Some((point, parent, _)) = nearest else {
+        return;
+    };
+    let Ok(rx) = rx_query.get(parent.get()) else {
+        return;
+    };
+    let Some(sample) = rx.sample(point.index) else {
+        return;
+    };
+    let Some(screen_position) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+
+    text.sections[0].value = format!(
+        "Receiver {}, sample {}: t = {:.3}s, amplitude {:.2}",
+        rx.index + 1,
+        point.index + 1,
+        plot_time(sample.plot_x),
+        sample.value
+    );
+    style.left = Val::Px(screen_position.x + TOOLTIP_OFFSET.x);
+    style.top = Val::Px(screen_position.y + TOOLTIP_OFFSET.y);
+    *visibility = Visibility::Inherited;
+}
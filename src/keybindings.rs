@@ -0,0 +1,359 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Key for every keyboard action, so controls can be remapped from the config. The help lines in
+/// the HUD are built from the same table so they always show the keys actually in use.
+///
+/// Modifiers aren't remappable: shift reverses the amplitude and compare edits and draws
+/// straight annotations, and undo and redo are always used with ctrl.
+#[derive(Resource, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub restart: KeyCode,
+    pub pause: KeyCode,
+    pub auto_reset: KeyCode,
+    pub clear_traces: KeyCode,
+    pub music: KeyCode,
+    pub measure: KeyCode,
+    pub select_receiver: KeyCode,
+    pub receiver_view: KeyCode,
+    /// Shows or hides the smooth curve through the raw plots.
+    pub smooth_trace: KeyCode,
+    /// Shows or hides the source and observed period ticks on the plots.
+    pub period_markers: KeyCode,
+    /// Shows or hides the wave equation with the current values.
+    pub wave_equation: KeyCode,
+    /// Switches per-frame system timings on and off with the `debug_profiling` feature.
+    pub profiling: KeyCode,
+    /// Switches between plots scaled to fit their traces and a fixed scale.
+    pub auto_scale: KeyCode,
+    /// Switches particles between filled and outlined.
+    pub particle_fill: KeyCode,
+    /// Restarts with a new random seed.
+    pub new_seed: KeyCode,
+    /// Pauses and saves a screenshot with the wavelengths, frequencies and times of flight.
+    pub figure: KeyCode,
+    /// Switches particles fading in and out on and off.
+    pub age_fade: KeyCode,
+    /// Captures every figure in the batch file, then exits.
+    pub run_batch: KeyCode,
+    /// Shows or hides the band over the samples behind the RMS and envelope views.
+    pub window_band: KeyCode,
+    /// Turns the direction waves travel in clockwise.
+    pub axis_clockwise: KeyCode,
+    /// Turns the direction waves travel in anticlockwise.
+    pub axis_anticlockwise: KeyCode,
+    /// Switches between the real-time clock and a fixed step per frame.
+    pub clock_mode: KeyCode,
+    /// Starts or stops the configured parameter sweep, saving every frame.
+    pub parameter_sweep: KeyCode,
+    /// Switches particles wrapping round from one side of the world to the other.
+    pub wrap_world: KeyCode,
+    /// Switches the HUD between fixed to the screen and pinned to the world.
+    pub hud_anchor: KeyCode,
+    /// Opens the stopwatch, which times between two clicks. With shift, clears it.
+    pub period_measure: KeyCode,
+    /// Ramps the selected receiver's transmitter's output up.
+    pub gain_up: KeyCode,
+    /// Ramps the selected receiver's transmitter's output down.
+    pub gain_down: KeyCode,
+    /// Switches the heatmap and contours between the summed field and a difference of two.
+    pub field_mode: KeyCode,
+    /// Shows or hides the table of receiver positions, velocities and frequencies.
+    pub receiver_table: KeyCode,
+    /// Shows or hides each receiver's Nyquist indicator.
+    pub nyquist: KeyCode,
+    /// Turns moving overlapping receiver labels apart on or off.
+    pub label_spacing: KeyCode,
+    /// Shows or hides the markers on the nodes and antinodes of a standing wave.
+    pub standing_wave_nodes: KeyCode,
+    /// Shows or hides the time axis labels under each receiver.
+    pub time_axis: KeyCode,
+    /// Slow down and speed up the tempo of the `OnBeat` emission pattern.
+    pub tempo_down: KeyCode,
+    pub tempo_up: KeyCode,
+    /// Spreads overlapping particles apart where they're drawn, for debugging dense scenes.
+    pub explode: KeyCode,
+    /// Shows or hides the long exposure phase map, or with shift saves it.
+    pub phase_map: KeyCode,
+    /// Moves a lesson on to its next step.
+    pub lesson_next: KeyCode,
+    /// Saves the canvas as a PNG, or with shift the wave field over a grid for NumPy.
+    pub snapshot: KeyCode,
+    pub palette: KeyCode,
+    /// One key per scenario, in the order they're listed in.
+    pub scenarios: Vec<KeyCode>,
+    /// One key per preset slot: saves the config to it, or with shift loads it.
+    pub preset_slots: Vec<KeyCode>,
+    pub next_scenario: KeyCode,
+    pub previous_scenario: KeyCode,
+    pub undo: KeyCode,
+    pub redo: KeyCode,
+    pub envelope: KeyCode,
+    /// Saves every receiver's trace, or with shift stores the selected one's for the cross-fade.
+    pub save_capture: KeyCode,
+    pub load_capture: KeyCode,
+    pub toggle_transmitter: KeyCode,
+    /// Fires a single pulse from the selected receiver's transmitter with a `Fired` emission
+    /// pattern.
+    pub fire_pulse: KeyCode,
+    pub amplitude: KeyCode,
+    /// Shows or hides the interference heatmap, or with shift the field's contour lines.
+    pub heatmap: KeyCode,
+    pub speed_up: KeyCode,
+    pub slow_down: KeyCode,
+    pub compare: KeyCode,
+    pub compare_left: KeyCode,
+    pub compare_right: KeyCode,
+    pub compare_increase: KeyCode,
+    pub compare_decrease: KeyCode,
+    /// Held while dragging to draw annotations.
+    pub annotate: KeyCode,
+    pub clear_annotations: KeyCode,
+    pub export_annotations: KeyCode,
+    pub crests_only: KeyCode,
+    /// Plots fewer of the received particles, or more with shift.
+    pub decimation: KeyCode,
+    /// Starts or stops the camera following a receiver.
+    pub follow: KeyCode,
+    /// Moves the camera on to the next receiver while following.
+    pub follow_next: KeyCode,
+    /// Prints the current parameters as a shareable string and copies it to the clipboard.
+    pub copy_parameters: KeyCode,
+    /// Applies a parameter string from the clipboard.
+    pub paste_parameters: KeyCode,
+    /// Cycles the selected receiver between one-shot and repeating sweeps.
+    pub sweep_mode: KeyCode,
+    /// Shows or hides the preview of where the wave will be.
+    pub ghost_preview: KeyCode,
+    /// Shows or hides a ruler one wavelength long next to each transmitter, or with shift
+    /// gridlines a wavelength apart.
+    pub wavelength_ruler: KeyCode,
+    /// Freezes the particles in flight as reference markers.
+    pub pin_particles: KeyCode,
+    pub clear_pins: KeyCode,
+    /// Shows or hides the settings panel.
+    pub settings: KeyCode,
+    /// Draws each particle's speed along its line with the medium's transverse velocity where it
+    /// is, while held.
+    pub velocity_vectors: KeyCode,
+    pub rewind: KeyCode,
+    pub rewind_back: KeyCode,
+    pub rewind_forward: KeyCode,
+    /// Held, scrub the cross-fade between two stored traces back and forward. By default they're
+    /// the same keys as `rewind_back` and `rewind_forward`, the only keys shared: while rewinding
+    /// they scrub the rewind and leave the cross-fade alone.
+    pub crossfade_back: KeyCode,
+    pub crossfade_forward: KeyCode,
+    /// Starts the screenshot sequence with the `gifcreate` feature.
+    pub gif_capture: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            restart: KeyCode::KeyR,
+            pause: KeyCode::KeyP,
+            auto_reset: KeyCode::KeyT,
+            clear_traces: KeyCode::KeyC,
+            music: KeyCode::KeyM,
+            measure: KeyCode::KeyX,
+            select_receiver: KeyCode::Tab,
+            receiver_view: KeyCode::KeyV,
+            smooth_trace: KeyCode::F4,
+            period_markers: KeyCode::F7,
+            wave_equation: KeyCode::F8,
+            profiling: KeyCode::F10,
+            auto_scale: KeyCode::F11,
+            particle_fill: KeyCode::Insert,
+            new_seed: KeyCode::End,
+            figure: KeyCode::Backquote,
+            age_fade: KeyCode::Semicolon,
+            run_batch: KeyCode::Slash,
+            window_band: KeyCode::Quote,
+            axis_clockwise: KeyCode::Minus,
+            axis_anticlockwise: KeyCode::Equal,
+            clock_mode: KeyCode::Backspace,
+            parameter_sweep: KeyCode::Backslash,
+            wrap_world: KeyCode::Enter,
+            hud_anchor: KeyCode::ScrollLock,
+            period_measure: KeyCode::Pause,
+            gain_up: KeyCode::NumpadAdd,
+            gain_down: KeyCode::NumpadSubtract,
+            field_mode: KeyCode::NumpadMultiply,
+            receiver_table: KeyCode::NumpadDivide,
+            nyquist: KeyCode::Numpad0,
+            label_spacing: KeyCode::Numpad5,
+            standing_wave_nodes: KeyCode::Numpad6,
+            time_axis: KeyCode::Numpad7,
+            tempo_down: KeyCode::Numpad8,
+            tempo_up: KeyCode::Numpad9,
+            explode: KeyCode::NumpadDecimal,
+            phase_map: KeyCode::CapsLock,
+            lesson_next: KeyCode::Escape,
+            snapshot: KeyCode::F12,
+            palette: KeyCode::KeyB,
+            scenarios: vec![
+                KeyCode::Digit1,
+                KeyCode::Digit2,
+                KeyCode::Digit3,
+                KeyCode::Digit4,
+                KeyCode::Digit5,
+                KeyCode::Digit6,
+                KeyCode::Digit7,
+                KeyCode::Digit8,
+                KeyCode::Digit9,
+                KeyCode::Digit0,
+            ],
+            preset_slots: vec![
+                KeyCode::Numpad1,
+                KeyCode::Numpad2,
+                KeyCode::Numpad3,
+                KeyCode::Numpad4,
+            ],
+            next_scenario: KeyCode::PageDown,
+            previous_scenario: KeyCode::PageUp,
+            undo: KeyCode::KeyZ,
+            redo: KeyCode::KeyY,
+            envelope: KeyCode::KeyE,
+            save_capture: KeyCode::F5,
+            load_capture: KeyCode::F9,
+            toggle_transmitter: KeyCode::KeyO,
+            fire_pulse: KeyCode::NumpadEnter,
+            amplitude: KeyCode::KeyA,
+            heatmap: KeyCode::KeyH,
+            speed_up: KeyCode::BracketRight,
+            slow_down: KeyCode::BracketLeft,
+            compare: KeyCode::KeyK,
+            compare_left: KeyCode::ArrowLeft,
+            compare_right: KeyCode::ArrowRight,
+            compare_increase: KeyCode::ArrowUp,
+            compare_decrease: KeyCode::ArrowDown,
+            annotate: KeyCode::KeyN,
+            clear_annotations: KeyCode::Delete,
+            export_annotations: KeyCode::F6,
+            crests_only: KeyCode::KeyW,
+            decimation: KeyCode::KeyD,
+            follow: KeyCode::KeyF,
+            follow_next: KeyCode::KeyG,
+            copy_parameters: KeyCode::KeyS,
+            paste_parameters: KeyCode::KeyL,
+            sweep_mode: KeyCode::KeyJ,
+            ghost_preview: KeyCode::KeyI,
+            wavelength_ruler: KeyCode::KeyU,
+            pin_particles: KeyCode::F2,
+            clear_pins: KeyCode::F3,
+            settings: KeyCode::F1,
+            velocity_vectors: KeyCode::KeyQ,
+            rewind: KeyCode::Home,
+            rewind_back: KeyCode::Comma,
+            rewind_forward: KeyCode::Period,
+            crossfade_back: KeyCode::Comma,
+            crossfade_forward: KeyCode::Period,
+            gif_capture: KeyCode::Space,
+        }
+    }
+}
+
+/// Actions that share a key by default: the rewind keys only scrub the rewind while it's
+/// rewinding, and the cross-fade keys are ignored then.
+const SHARED_BY_DEFAULT: [[&str; 2]; 2] = [
+    ["rewind_back", "crossfade_back"],
+    ["rewind_forward", "crossfade_forward"],
+];
+
+impl KeyBindings {
+    /// Each key bound to more than one action, with the actions, leaving out the pairs in
+    /// [`SHARED_BY_DEFAULT`].
+    pub fn shared_keys(&self) -> Vec<(KeyCode, Vec<String>)> {
+        let mut bound: Vec<(KeyCode, Vec<String>)> = Vec::new();
+        for (i, field) in self.iter_fields().enumerate() {
+            let name = self.name_at(i).unwrap_or_default();
+            let keys = if let Some(key) = field.downcast_ref::<KeyCode>() {
+                vec![(*key, name.to_string())]
+            } else if let Some(keys) = field.downcast_ref::<Vec<KeyCode>>() {
+                keys.iter()
+                    .enumerate()
+                    .map(|(j, key)| (*key, format!("{name}[{j}]")))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            for (key, action) in keys {
+                match bound.iter_mut().find(|(bound_key, _)| *bound_key == key) {
+                    Some((_, actions)) => actions.push(action),
+                    None => bound.push((key, vec![action])),
+                }
+            }
+        }
+        bound
+            .into_iter()
+            .filter(|(_, actions)| {
+                actions.len() > 1
+                    && !SHARED_BY_DEFAULT.iter().any(|pair| {
+                        actions.len() == 2
+                            && pair
+                                .iter()
+                                .all(|action| actions.iter().any(|a| a == action))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// How a key is written in help text: letters, digits and symbols in quotes, other keys by name.
+pub fn key_name(key: KeyCode) -> String {
+    let symbol = match key {
+        KeyCode::BracketLeft => Some("["),
+        KeyCode::BracketRight => Some("]"),
+        KeyCode::Comma => Some(","),
+        KeyCode::Period => Some("."),
+        KeyCode::Minus => Some("-"),
+        KeyCode::Equal => Some("="),
+        KeyCode::Slash => Some("/"),
+        KeyCode::Backslash => Some("\\"),
+        KeyCode::Semicolon => Some(";"),
+        KeyCode::Quote => Some("'"),
+        KeyCode::Backquote => Some("`"),
+        _ => None,
+    };
+    if let Some(symbol) = symbol {
+        return format!("'{symbol}'");
+    }
+
+    let name = format!("{key:?}");
+    let short = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .filter(|short| short.len() == 1);
+    match short {
+        Some(short) => format!("'{}'", short.to_lowercase()),
+        None => name.to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_only_share_the_scrub_keys() {
+        assert!(KeyBindings::default().shared_keys().is_empty());
+    }
+
+    #[test]
+    fn a_key_bound_twice_is_reported() {
+        let bindings = KeyBindings {
+            pause: KeyCode::KeyQ,
+            ..default()
+        };
+        assert_eq!(
+            bindings.shared_keys(),
+            vec![(
+                KeyCode::KeyQ,
+                vec!["pause".to_string(), "velocity_vectors".to_string()]
+            )]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Keeping the labels floating by receivers legible: when receivers are close together or pass
+//! each other, their titles and readouts are nudged apart vertically each frame, with a faint
+//! leader line back to where each one belongs.
+
+use bevy::{
+    prelude::*,
+    text::{Text2dBounds, TextLayoutInfo},
+};
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    Receiver,
+};
+
+/// Space kept between labels once they've been moved apart.
+const LABEL_GAP: f32 = 4.;
+/// Labels moved less than this, in pixels, don't get a leader line.
+const MIN_LEADER_LENGTH: f32 = 2.;
+const LEADER_COLOR: Color = Color::rgba(1., 1., 1., 0.3);
+
+/// Where a receiver's label sits relative to it when nothing's in the way.
+#[derive(Component)]
+pub struct LabelHome(Vec3);
+
+pub fn label_spacing_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.label_spacing {
+        "stop"
+    } else {
+        "start"
+    };
+    format!(
+        "Press {} to {action} moving overlapping receiver labels apart",
+        key_name(bindings.label_spacing)
+    )
+}
+
+pub fn toggle_label_spacing(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.label_spacing) {
+        return;
+    }
+    config.label_spacing = !config.label_spacing;
+    hud.set("label_spacing", label_spacing_label(&config, &bindings));
+}
+
+/// Remembers where each new label on a receiver was put, to move it back to once it's clear.
+pub fn track_receiver_labels(
+    mut commands: Commands,
+    label_query: Query<(Entity, &Parent, &Transform), (With<Text2dBounds>, Without<LabelHome>)>,
+    rx_query: Query<(), With<Receiver>>,
+) {
+    for (entity, parent, transform) in label_query.iter() {
+        if rx_query.contains(parent.get()) {
+            commands
+                .entity(entity)
+                .insert(LabelHome(transform.translation));
+        }
+    }
+}
+
+/// Moves receivers' labels back to where they belong, then, while `label_spacing` is on, pushes
+/// each one down below any shown label it overlaps, working down from the highest. Runs after
+/// text layout so every label's size is known.
+pub fn spread_receiver_labels(
+    config: Res<SimConfig>,
+    rx_query: Query<&GlobalTransform, With<Receiver>>,
+    mut label_query: Query<(
+        Entity,
+        &LabelHome,
+        &Parent,
+        &mut Transform,
+        &TextLayoutInfo,
+        &Visibility,
+    )>,
+    mut gizmos: Gizmos,
+) {
+    // Each shown label with where it belongs, its receiver's rotation, and its centre and size in
+    // the world.
+    let mut labels = Vec::new();
+    for (entity, home, parent, mut transform, layout, visibility) in label_query.iter_mut() {
+        if transform.translation != home.0 {
+            transform.translation = home.0;
+        }
+        if !config.label_spacing || *visibility == Visibility::Hidden {
+            continue;
+        }
+        let Ok(rx_transform) = rx_query.get(parent.get()) else {
+            continue;
+        };
+        let centre = rx_transform.transform_point(home.0).truncate();
+        let rotation = rx_transform.compute_transform().rotation;
+        // Text scaled down to keep it sharp is laid out that much bigger.
+        let size = layout.logical_size * transform.scale.truncate();
+        labels.push((entity, home.0, rotation, centre, size));
+    }
+    if labels.len() < 2 {
+        return;
+    }
+    labels.sort_by(|(.., a, _), (.., b, _)| b.y.total_cmp(&a.y));
+
+    let mut placed: Vec<Rect> = Vec::with_capacity(labels.len());
+    let mut moves = Vec::new();
+    for (entity, home, rotation, centre, size) in labels {
+        let mut rect = Rect::from_center_size(centre, size);
+        // Pushing it below one label can land it on another, so keep going until it's clear.
+        while let Some(below) = placed
+            .iter()
+            .filter(|other| !other.intersect(rect).is_empty())
+            .map(|other| other.min.y - LABEL_GAP - size.y / 2.)
+            .reduce(f32::min)
+        {
+            rect = Rect::from_center_size(Vec2::new(centre.x, below), size);
+        }
+        placed.push(rect);
+        let shift = rect.center() - centre;
+        if shift != Vec2::ZERO {
+            // The label's a child of the receiver, so the shift is turned into its frame.
+            moves.push((entity, home + rotation.inverse() * shift.extend(0.)));
+        }
+        if shift.length() >= MIN_LEADER_LENGTH {
+            gizmos.line_2d(centre, rect.center(), LEADER_COLOR);
+        }
+    }
+
+    for (entity, translation) in moves {
+        if let Ok((_, _, _, mut transform, ..)) = label_query.get_mut(entity) {
+            transform.translation = translation;
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Launching paused: the scene is built but frozen behind a notice until the pause key is pressed,
+//! so a presenter can set up the display and start the wave on cue.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    keybindings::{key_name, KeyBindings},
+    OverlayFont, HIGH_RES_LAYERS,
+};
+
+const NOTICE_FONT_SIZE: f32 = 48.;
+const NOTICE_COLOR: Color = Color::WHITE;
+const BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.5);
+
+/// The notice covering the window until the simulation is first started.
+#[derive(Component)]
+pub struct LaunchNotice;
+
+/// Pauses the simulation before its first frame and puts up the notice, if `start_paused` is set.
+pub fn launch_paused(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    font: Res<OverlayFont>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !config.start_paused {
+        return;
+    }
+    time.pause();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            LaunchNotice,
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|notice| {
+            notice.spawn(TextBundle::from_section(
+                format!("PAUSED — press {} to start", key_name(bindings.pause)),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: NOTICE_FONT_SIZE,
+                    color: NOTICE_COLOR,
+                },
+            ));
+        });
+}
+
+/// Takes the notice down the first time the simulation runs.
+pub fn dismiss_launch_notice(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    notice_query: Query<Entity, With<LaunchNotice>>,
+) {
+    if time.is_paused() {
+        return;
+    }
+    for notice in notice_query.iter() {
+        commands.entity(notice).despawn_recursive();
+    }
+}
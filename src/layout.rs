@@ -0,0 +1,248 @@
+//! Interactive layout editing: transmitters and receivers can be dragged with the left mouse
+//! button (positions are local to the entity's parent, which only ever translates), and every
+//! drag can be undone with Ctrl+Z and redone with Ctrl+Y (or Ctrl+Shift+Z). The letters can be
+//! remapped in the key bindings but ctrl is always needed.
+//!
+//! Drags can snap to a grid of `snap_grid` pixels in world space, so positions come out round
+//! and symmetric layouts are easy to set up. `snap_to_grid` sets whether they do by default and
+//! holding alt while dragging does the opposite.
+//!
+//! Only moves are undoable, since they are the only layout edits that can be made interactively.
+//! The history is cleared whenever the simulation resets, because the entities it refers to are
+//! rebuilt.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    config::SimConfig, cursor_world_position, keybindings::KeyBindings, measure::MeasureMode,
+    settings::PointerOverUi, stopwatch::PeriodMeasure, InGameCamera, OuterCamera, SimulationReset,
+    RES_HEIGHT, RES_WIDTH,
+};
+
+/// Number of edits remembered for undo.
+const MAX_HISTORY: usize = 100;
+
+const SNAP_GRID_COLOR: Color = Color::rgba(1., 1., 1., 0.08);
+/// Smallest grid that gets drawn while dragging, in pixels. Finer grids still snap.
+const MIN_DRAWN_GRID: f32 = 4.;
+
+/// Something that can be picked up and moved with the mouse.
+#[derive(Component)]
+pub struct Draggable {
+    /// Half the size of the area around the entity's position that can be grabbed.
+    pub half_size: Vec2,
+}
+
+#[derive(Clone, Copy)]
+pub enum LayoutEdit {
+    Move {
+        entity: Entity,
+        from: Vec2,
+        to: Vec2,
+    },
+}
+
+/// Undo and redo stacks of layout edits, most recent last.
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: VecDeque<LayoutEdit>,
+    redo: Vec<LayoutEdit>,
+}
+
+impl EditHistory {
+    pub fn record(&mut self, edit: LayoutEdit) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// The entity currently being dragged.
+#[derive(Default)]
+pub struct DragState {
+    entity: Option<Entity>,
+    grab_offset: Vec2,
+    start: Vec2,
+    /// World position of the entity's parent, to snap in world rather than local space.
+    parent_offset: Vec2,
+}
+
+/// Rounds `position` to the nearest multiple of `grid` on each axis. A grid of zero or less
+/// leaves it alone.
+pub fn snap(position: Vec2, grid: f32) -> Vec2 {
+    if grid <= 0. {
+        return position;
+    }
+    (position / grid).round() * grid
+}
+
+pub fn drag_layout(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    measure: Res<MeasureMode>,
+    period: Res<PeriodMeasure>,
+    pointer: Res<PointerOverUi>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    mut draggable_query: Query<
+        (Entity, &mut Transform, &GlobalTransform, &Draggable),
+        Without<InGameCamera>,
+    >,
+    mut history: ResMut<EditHistory>,
+    mut drag: Local<DragState>,
+    mut gizmos: Gizmos,
+) {
+    let cursor = cursor_world_position(&windows, &camera_query, &in_game_camera_query);
+
+    if mouse.just_pressed(MouseButton::Left)
+        && !measure.is_active()
+        && !period.is_active()
+        && !pointer.0
+        && !keys.pressed(bindings.annotate)
+    {
+        if let Some(cursor) = cursor {
+            // Prefer whatever is drawn on top when things overlap.
+            let grabbed = draggable_query
+                .iter()
+                .filter(|(_, _, global_transform, draggable)| {
+                    let offset = (cursor - global_transform.translation().xy()).abs();
+                    offset.x <= draggable.half_size.x && offset.y <= draggable.half_size.y
+                })
+                .max_by(|(_, _, a, _), (_, _, b, _)| {
+                    a.translation().z.total_cmp(&b.translation().z)
+                });
+            if let Some((entity, transform, global_transform, _)) = grabbed {
+                let position = transform.translation.xy();
+                *drag = DragState {
+                    entity: Some(entity),
+                    grab_offset: position - cursor,
+                    start: position,
+                    parent_offset: global_transform.translation().xy() - position,
+                };
+            }
+        }
+    }
+
+    let Some(entity) = drag.entity else {
+        return;
+    };
+    let Ok((_, mut transform, _, _)) = draggable_query.get_mut(entity) else {
+        drag.entity = None;
+        return;
+    };
+
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let snapping = config.snap_to_grid != alt && config.snap_grid > 0.;
+    if snapping && config.snap_grid >= MIN_DRAWN_GRID {
+        let pan = in_game_camera_query
+            .get_single()
+            .map_or(Vec2::ZERO, |transform| transform.translation.xy());
+        draw_grid(&mut gizmos, pan, config.snap_grid);
+    }
+
+    if let Some(cursor) = cursor {
+        let mut position = cursor + drag.grab_offset;
+        if snapping {
+            position = snap(position + drag.parent_offset, config.snap_grid) - drag.parent_offset;
+        }
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+
+    if !mouse.pressed(MouseButton::Left) {
+        let end = transform.translation.xy();
+        if end != drag.start {
+            history.record(LayoutEdit::Move {
+                entity,
+                from: drag.start,
+                to: end,
+            });
+        }
+        drag.entity = None;
+    }
+}
+
+/// Draws the snapping grid over the view centred on `pan`.
+fn draw_grid(gizmos: &mut Gizmos, pan: Vec2, grid: f32) {
+    let half_view = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / 2.;
+    let min = snap(pan - half_view, grid);
+    let max = pan + half_view;
+    let mut x = min.x;
+    while x <= max.x {
+        gizmos.line_2d(
+            Vec2::new(x, pan.y - half_view.y),
+            Vec2::new(x, max.y),
+            SNAP_GRID_COLOR,
+        );
+        x += grid;
+    }
+    let mut y = min.y;
+    while y <= max.y {
+        gizmos.line_2d(
+            Vec2::new(pan.x - half_view.x, y),
+            Vec2::new(max.x, y),
+            SNAP_GRID_COLOR,
+        );
+        y += grid;
+    }
+}
+
+pub fn undo_redo(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut history: ResMut<EditHistory>,
+    mut transform_query: Query<&mut Transform, With<Draggable>>,
+) {
+    let ctrl = input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !ctrl {
+        return;
+    }
+
+    let redo = input.just_pressed(bindings.redo) || (shift && input.just_pressed(bindings.undo));
+    let undo = !redo && input.just_pressed(bindings.undo);
+
+    let edit = if undo {
+        history.undo.pop_back()
+    } else if redo {
+        history.redo.pop()
+    } else {
+        None
+    };
+    let Some(edit) = edit else {
+        return;
+    };
+
+    match edit {
+        LayoutEdit::Move { entity, from, to } => {
+            let Ok(mut transform) = transform_query.get_mut(entity) else {
+                return;
+            };
+            let position = if undo { from } else { to };
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+
+    if undo {
+        history.redo.push(edit);
+    } else {
+        history.undo.push_back(edit);
+    }
+}
+
+pub fn clear_history_on_reset(
+    mut reset_events: EventReader<SimulationReset>,
+    mut history: ResMut<EditHistory>,
+) {
+    if reset_events.read().count() > 0 {
+        *history = EditHistory::default();
+    }
+}
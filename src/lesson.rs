@@ -0,0 +1,233 @@
+//! Guided lessons: a scripted sequence of steps, each explained in a panel at the bottom of the
+//! window and switching scenario or changing a setting as it's reached, advanced a key press at a
+//! time, so the simulation can teach a topic on its own.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    keybindings::{key_name, KeyBindings},
+    scenario::{Scenario, ScenarioTransition},
+    OverlayFont, HIGH_RES_LAYERS,
+};
+
+const PANEL_FONT_SIZE: f32 = 22.;
+const PANEL_COLOR: Color = Color::WHITE;
+const PANEL_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.75);
+/// Percentage of the window's width the panel's text wraps at.
+const PANEL_WIDTH: f32 = 60.;
+
+/// The lessons there are to choose from with `lesson`.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LessonKind {
+    /// What the Doppler effect is, from a moving receiver through a moving source to one
+    /// outrunning its own waves.
+    Doppler,
+}
+
+impl LessonKind {
+    fn title(self) -> &'static str {
+        match self {
+            LessonKind::Doppler => "The Doppler effect",
+        }
+    }
+
+    fn steps(self) -> Vec<LessonStep> {
+        match self {
+            LessonKind::Doppler => vec![
+                LessonStep {
+                    text: "A transmitter on the right sends out a wave at a steady frequency. \
+                           Each receiver plots the wave as it arrives, newest on the left. The \
+                           top receiver stands still, so it hears exactly the frequency sent.",
+                    action: LessonAction::Scenario(Scenario::ThreeReceivers),
+                },
+                LessonStep {
+                    text: "The middle receiver moves towards its transmitter, so it meets each \
+                           crest sooner than if it stood still. Its plot is squeezed: it hears \
+                           a higher frequency. The bottom one moves away and hears a lower one.",
+                    action: LessonAction::None,
+                },
+                LessonStep {
+                    text: "Grey ticks now mark the period that was sent and red ticks the \
+                           period each receiver should hear. The red ticks bunch up on the \
+                           approaching receiver and spread out on the receding one by exactly \
+                           the Doppler shift.",
+                    action: LessonAction::Configure(|config| config.period_markers = true),
+                },
+                LessonStep {
+                    text: "Now the transmitter moves too, towards the receiver. Its crests are \
+                           sent from closer each time, so they're squeezed together before the \
+                           receiver even moves: both motions add to the shift.",
+                    action: LessonAction::Scenario(Scenario::Converging),
+                },
+                LessonStep {
+                    text: "The same transmitter heard from both sides: the receiver it moves \
+                           towards hears a higher frequency and the one behind it a lower one, \
+                           like a siren going past.",
+                    action: LessonAction::Scenario(Scenario::Bidirectional),
+                },
+                LessonStep {
+                    text: "This transmitter flies faster than its own waves. Its crests pile up \
+                           into a Mach cone, and the receiver hears nothing until the \
+                           transmitter has passed: a sonic boom.",
+                    action: LessonAction::Scenario(Scenario::Supersonic),
+                },
+                LessonStep {
+                    text: "That's the Doppler effect: motion towards a source raises the \
+                           frequency heard and motion away lowers it. Every scenario is still \
+                           there on the number keys to explore.",
+                    action: LessonAction::Configure(|config| config.period_markers = false),
+                },
+            ],
+        }
+    }
+}
+
+/// What reaching a step changes.
+pub enum LessonAction {
+    /// Nothing: the step explains what's already on screen.
+    None,
+    /// Switches to a scenario, fading across as the scenario keys do.
+    Scenario(Scenario),
+    /// Changes settings, such as turning an overlay on.
+    Configure(fn(&mut SimConfig)),
+}
+
+pub struct LessonStep {
+    pub text: &'static str,
+    pub action: LessonAction,
+}
+
+/// The lesson being given, and which step it's on.
+#[derive(Resource)]
+pub struct Lesson {
+    title: &'static str,
+    steps: Vec<LessonStep>,
+    current: usize,
+}
+
+impl Lesson {
+    fn text(&self, bindings: &KeyBindings) -> String {
+        let step = &self.steps[self.current];
+        let action = if self.current + 1 == self.steps.len() {
+            "finish"
+        } else {
+            "continue"
+        };
+        format!(
+            "{} — step {} of {}\n\n{}\n\nPress {} to {action}",
+            self.title,
+            self.current + 1,
+            self.steps.len(),
+            step.text,
+            key_name(bindings.lesson_next)
+        )
+    }
+}
+
+#[derive(Component)]
+pub struct LessonPanel;
+
+#[derive(Component)]
+pub struct LessonText;
+
+/// Starts the configured `lesson`, if any, before the scene is first built, so its first step's
+/// scenario and settings are what it's built with.
+pub fn start_lesson(
+    mut commands: Commands,
+    mut config: ResMut<SimConfig>,
+    bindings: Res<KeyBindings>,
+    font: Res<OverlayFont>,
+) {
+    let Some(kind) = config.lesson else {
+        return;
+    };
+    let lesson = Lesson {
+        title: kind.title(),
+        steps: kind.steps(),
+        current: 0,
+    };
+    match lesson.steps[0].action {
+        LessonAction::None => {}
+        LessonAction::Scenario(scenario) => config.scenario = scenario,
+        LessonAction::Configure(configure) => configure(&mut config),
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Px(20.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LessonPanel,
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Percent(PANEL_WIDTH),
+                        padding: UiRect::all(Val::Px(12.)),
+                        ..default()
+                    },
+                    background_color: PANEL_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|background| {
+                    background.spawn((
+                        TextBundle::from_section(
+                            lesson.text(&bindings),
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: PANEL_FONT_SIZE,
+                                color: PANEL_COLOR,
+                            },
+                        ),
+                        LessonText,
+                    ));
+                });
+        });
+    commands.insert_resource(lesson);
+}
+
+/// Moves the lesson on a step when its key is pressed, carrying out the new step's action, and
+/// takes the panel down after the last one.
+pub fn advance_lesson(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    lesson: Option<ResMut<Lesson>>,
+    mut config: ResMut<SimConfig>,
+    mut transition: ResMut<ScenarioTransition>,
+    mut text_query: Query<&mut Text, With<LessonText>>,
+    panel_query: Query<Entity, With<LessonPanel>>,
+) {
+    let Some(mut lesson) = lesson else {
+        return;
+    };
+    if !input.just_pressed(bindings.lesson_next) {
+        return;
+    }
+    lesson.current += 1;
+    if lesson.current == lesson.steps.len() {
+        for panel in panel_query.iter() {
+            commands.entity(panel).despawn_recursive();
+        }
+        commands.remove_resource::<Lesson>();
+        return;
+    }
+    match lesson.steps[lesson.current].action {
+        LessonAction::None => {}
+        LessonAction::Scenario(scenario) => transition.switch_to(scenario),
+        LessonAction::Configure(configure) => configure(&mut config),
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lesson.text(&bindings);
+    }
+}
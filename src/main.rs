@@ -1,29 +1,129 @@
-use std::{f32::consts::PI, time::Duration};
+// Bevy systems naturally take many parameters and complex query types.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod annotate;
+mod audio;
+mod batch;
+mod capture;
+mod clock;
+mod compare;
+mod config;
+mod contour;
+mod crisp_text;
+mod crossfade;
+mod determinism;
+mod diagnostics;
+mod differential;
+mod envelope;
+mod equation;
+mod explode;
+mod field_export;
+mod figure;
+mod focus;
+mod follow;
+mod freeze;
+mod frequency_log;
+mod heatmap;
+mod hud;
+mod inspect;
+mod keybindings;
+mod label_layout;
+mod launch;
+mod layout;
+mod lesson;
+mod marker;
+mod measure;
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+mod midi;
+mod nyquist;
+mod palette;
+mod param_sweep;
+mod phase_lock;
+mod phase_map;
+mod phosphor;
+mod physics;
+mod pin;
+mod placement;
+mod preset;
+mod probe;
+mod profiling;
+mod receiver_table;
+mod receiver_view;
+mod rewind;
+mod ruler;
+mod scenario;
+mod settings;
+mod share;
+mod single_capture;
+mod speed;
+mod standing_wave;
+mod stats;
+mod stopwatch;
+mod stress;
+mod tempo;
+mod time_axis;
+mod waveform;
+mod wavelength_grid;
+
+use std::{
+    collections::VecDeque,
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
 
 use bevy::{
+    core::FrameCount,
+    ecs::schedule::SystemConfigs,
     prelude::*,
     render::{
-        camera::RenderTarget,
+        camera::{CameraUpdateSystem, RenderTarget, ScalingMode},
         render_resource::{
             Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
         },
         view::{screenshot::ScreenshotManager, RenderLayers},
     },
-    sprite::MaterialMesh2dBundle,
+    sprite::{Anchor, MaterialMesh2dBundle, Mesh2dHandle},
+    time::{TimeSystem, TimeUpdateStrategy},
     transform::TransformSystem,
-    window::{PrimaryWindow, WindowResized},
+    ui::UiSystem,
+    utils::{HashMap, HashSet},
+    window::{PrimaryWindow, WindowRef, WindowResized, WindowScaleFactorChanged},
+};
+use compare::{CompareMode, Side};
+use config::{
+    CanvasScaling, EmissionMode, EmissionPattern, OverlapHandling, PresentationStyle,
+    RenderPipeline, SimConfig, SweepMode, SystemOrdering, Trigger, Waveform,
 };
+use hud::{Hud, HudText, WorldHudText};
+use keybindings::{key_name, KeyBindings};
+use layout::{Draggable, EditHistory};
+use marker::{ParticleAppearance, PlotMarkerMesh};
+use measure::MeasureMode;
+use physics::{Dispersion, DopplerFormula, SpeedCheck, WaveShape};
+use placement::Placement;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use receiver_view::{ReceiverFace, ReceiverOrientation, ReceiverView};
 
 // Colors
 const PARTICLE_AMPLITUDE: f32 = 50.;
 const PARTICLE_COLOR: Color = Color::GREEN;
+/// Default particle radius. Receivers are sized to fit particles this big.
 const PARTICLE_RADIUS: f32 = 5.;
-const PARTICLE_SIZE: Vec3 = Vec2::splat(PARTICLE_RADIUS).extend(1.0);
-const PARTICLE_SPEED: f32 = -200.;
 const PARTICLE_FREQUENCY: f32 = 2.;
+/// Change in amplitude per key press. Amplitude can be turned down to this but never above
+/// `PARTICLE_AMPLITUDE`, since the receivers are sized to fit that.
+const AMPLITUDE_STEP: f32 = 5.;
+/// Change in a transmitter's gain per key press, as a fraction of full strength.
+const GAIN_STEP: f32 = 0.25;
+/// Hues [`EmissionPattern::Fired`] pulses cycle through when `emission_hues` isn't set.
+const FIRED_HUES: u32 = 6;
 
 const TRANSMITTER_COLOR: Color = Color::ORANGE;
 const TRANSMITTER_SIZE: f32 = 25.;
+const BEAM_CONE_COLOR: Color = Color::rgba(1., 1., 1., 0.2);
+const BEAM_CONE_LENGTH: f32 = 1000.;
+
+const MACH_CONE_COLOR: Color = Color::ORANGE;
 
 const RECEIVER_COLOR: Color = Color::RED;
 const RECEIVER_WIDTH: f32 = 2. * PARTICLE_AMPLITUDE + 2. * PARTICLE_RADIUS;
@@ -35,8 +135,54 @@ const RECEIVER_PLOT_COLOR: Color = Color::BLACK;
 const RECEIVER_PLOT_RADIUS: f32 = 7.;
 const RECEIVER_PLOT_SIZE: Vec3 = Vec2::splat(RECEIVER_PLOT_RADIUS).extend(1.0);
 const RECEIVER_SPEED: f32 = 100.;
+/// Width of the receiver left showing around its plot panel.
+const PLOT_PANEL_INSET: f32 = 3.;
+const TRANSMITTER_SPEED: f32 = 50.;
+
+const SPEEDOMETER_COLOR: Color = Color::WHITE;
+/// Arrow length per unit of receiver speed, i.e. how many seconds of motion the arrow shows.
+const SPEEDOMETER_SCALE: f32 = 0.5;
+/// Gap between the top of a receiver and its speedometer arrow.
+const SPEEDOMETER_OFFSET: f32 = 20.;
+
+const VELOCITY_VECTOR_COLOR: Color = Color::YELLOW;
+/// Arrow length per unit of particle speed, i.e. how many seconds of motion the arrow shows.
+const VELOCITY_VECTOR_SCALE: f32 = 0.1;
+
+const GHOST_COLOR: Color = Color::rgba(1., 1., 1., 0.2);
+/// Only every this many particles gets a ghost, to keep the preview faint and uncluttered.
+const GHOST_STRIDE: u64 = 3;
+
+const GROUP_LABEL_COLOR: Color = Color::WHITE;
+const GROUP_LABEL_FONT_SIZE: f32 = 20.;
+/// Height of a simulation's title above the top of its receiver, clear of the speedometer.
+const GROUP_LABEL_GAP: f32 = 45.;
+
+const SOURCE_PERIOD_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.7);
+const OBSERVED_PERIOD_COLOR: Color = Color::rgba(1., 0.3, 0.3, 0.7);
+/// Closest two period ticks are drawn, in pixels. Any closer and they'd just fill the plot.
+const MIN_PERIOD_TICK_SPACING: f32 = 2.;
+
+const PERSISTED_TRACE_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.6);
 
 const PARTICLE_SPAWN_RATE_MS: u64 = 10;
+/// Shortest and longest time between emissions `target_in_flight` can tune a transmitter to, in
+/// seconds.
+const ADAPTIVE_INTERVAL_RANGE: (f32, f32) = (0.005, 1.);
+/// Largest `plot_decimation` reachable from the keyboard.
+const MAX_PLOT_DECIMATION: u32 = 256;
+/// Receivers' sample rates are measured over this many of the most recent seconds.
+const SAMPLE_RATE_WINDOW_SECS: f32 = 1.;
+
+/// Jump, in pixels, between a transmitter and its newest particle above which the emission is
+/// flagged as discontinuous.
+const CONTINUITY_THRESHOLD: f32 = 1.;
+const CONTINUITY_MARKER_COLOR: Color = Color::FUCHSIA;
+const CONTINUITY_MARKER_RADIUS: f32 = 8.;
+
+/// Auto-reset interval used when the auto-reset is switched on at runtime but no interval was
+/// configured.
+const DEFAULT_RESET_INTERVAL_SECS: f32 = 10.;
 
 /// In-game resolution width.
 const RES_WIDTH: u32 = 1280;
@@ -53,36 +199,385 @@ const PIXEL_PERFECT_LAYERS: RenderLayers = RenderLayers::layer(0);
 /// Render layers for high-resolution rendering.
 const HIGH_RES_LAYERS: RenderLayers = RenderLayers::layer(1);
 
+/// Render layer seen by the plot window with the `dual_window` feature.
+const PLOT_WINDOW_LAYER: u8 = 2;
+
+/// Plot points are drawn on the canvas and mirrored to the plot window.
+const PLOT_POINT_LAYERS: RenderLayers = PIXEL_PERFECT_LAYERS.with(PLOT_WINDOW_LAYER);
+
 #[derive(Resource)]
 struct ResetTimer {
     timer: Timer,
+    enabled: bool,
+}
+/// Sent to tear down and rebuild the simulation.
+#[derive(Event)]
+struct SimulationReset;
+
+/// Plot points from before earlier resets, kept when `persist_traces` is on. Oldest first.
+#[derive(Resource)]
+struct PersistedTraces {
+    traces: VecDeque<Entity>,
+    material: Handle<ColorMaterial>,
+}
+
+/// Font every piece of on-screen text is drawn in, the default one unless `font` is configured.
+#[derive(Resource)]
+struct OverlayFont(Handle<Font>);
+
+/// Source of the random emission jitter, seeded from the config and reseeded on every reset so
+/// runs are reproducible.
+#[derive(Resource)]
+struct EmissionRng(StdRng);
+
+impl EmissionRng {
+    fn new(config: &SimConfig) -> Self {
+        Self(StdRng::seed_from_u64(config.seed))
+    }
+}
+
+/// Amplitude given to newly emitted particles. Particles already in flight keep theirs.
+#[derive(Resource)]
+struct CurrentAmplitude(f32);
+
+impl Default for CurrentAmplitude {
+    fn default() -> Self {
+        Self(PARTICLE_AMPLITUDE)
+    }
+}
+
+/// A point plotted by a receiver for the current run.
+#[derive(Component)]
+struct PlotPoint {
+    /// Position of the point's sample among those its receiver has taken since the plot was
+    /// cleared. See [`Receiver::sample`].
+    index: usize,
 }
-#[derive(Component, Default)]
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 struct Receiver {
+    /// Position of the receiver in the order the simulations were created.
+    index: usize,
     prev_collision_time: Option<f32>,
     current_draw_position: f32,
+    view: ReceiverView,
+    /// Samples behind its plot, oldest first. Only the last `receiver_buffer_size` are kept.
+    samples: VecDeque<RxSample>,
+    /// Samples dropped from the front of `samples` since the plot was cleared.
+    dropped_samples: usize,
+    /// Speed it moves at when it has a [`Mover`], in pixels per second.
+    speed: f32,
+    /// Plotting waits until this fires, if set.
+    trigger: Option<Trigger>,
+    /// Value of the last particle received while waiting for the trigger.
+    last_value: Option<f32>,
+    triggered: bool,
+    /// Particles received so far, to pick out every `plot_decimation`th one to plot.
+    received: u32,
+    /// When each sample in the last [`SAMPLE_RATE_WINDOW_SECS`] was plotted, oldest first.
+    sample_times: VecDeque<f32>,
+    sweep: SweepMode,
+    /// Where it was created and how it moved, to go back to when a sweep repeats.
+    start_x: f32,
+    movement: Movement,
+    /// When the first particle reached it, from the same clock as [`Transmitter::first_emission`].
+    first_collision: Option<f32>,
+    orientation: ReceiverOrientation,
+    face: ReceiverFace,
+    anchor: Option<MotionAnchor>,
+    /// How much its plot is stretched vertically to fit, while auto-scaling. Samples keep their
+    /// actual values.
+    auto_scale: Option<f32>,
+    /// Particles crossing before this time arrive during the dead time after the last sample,
+    /// and are ignored.
+    dead_until: Option<f32>,
+    /// Its single capture, if it has one configured.
+    single_capture: Option<single_capture::SingleCaptureState>,
+    /// Frequency it's been hearing, averaged over recent estimates by `frequency_smoothing`.
+    /// This is what readouts show, since each estimate alone is noisy.
+    smoothed_frequency: Option<f32>,
+}
+
+impl Receiver {
+    /// The `index`th sample taken since the plot was cleared, unless it's been dropped.
+    fn sample(&self, index: usize) -> Option<&RxSample> {
+        self.samples.get(index.checked_sub(self.dropped_samples)?)
+    }
+
+    /// Index the next sample will have, counting from the last time the plot was cleared.
+    fn next_sample_index(&self) -> usize {
+        self.dropped_samples + self.samples.len()
+    }
+
+    /// Adds a sample, first dropping the oldest ones if there are already `capacity`. Points
+    /// plotted for dropped samples stay until the plot is cleared.
+    fn push_sample(&mut self, sample: RxSample, capacity: Option<usize>) {
+        if let Some(capacity) = capacity {
+            while self.samples.len() >= capacity.max(1) {
+                self.samples.pop_front();
+                self.dropped_samples += 1;
+            }
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Rate samples have been taken at over the recent sample times, or zero until there are
+    /// enough to tell.
+    fn sample_rate(&self) -> f32 {
+        match (self.sample_times.front(), self.sample_times.back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.sample_times.len() - 1) as f32 / (last - first)
+            }
+            _ => 0.,
+        }
+    }
+
+    /// Height in its plot of a sample with `value`.
+    fn plot_y(&self, value: f32) -> f32 {
+        value * self.auto_scale.unwrap_or(1.)
+    }
+}
+
+/// A sample captured by a receiver when a particle reached it.
+#[derive(Reflect)]
+struct RxSample {
+    value: f32,
+    /// Horizontal position of the sample in the receiver's plot, relative to the receiver.
+    plot_x: f32,
+    /// Pixels between the receiver and its transmitter's spawn point when it was taken. `None`
+    /// for samples loaded from a capture.
+    distance: Option<f32>,
+    /// Phase of the wave it caught against the source's own, from [`physics::relative_phase`].
+    /// `None` for samples loaded from a capture.
+    phase: Option<f32>,
+}
+
+/// Where a receiver was and when, to place it by its total time moving rather than adding up
+/// every frame's movement.
+#[derive(Clone, Copy, Reflect)]
+struct MotionAnchor {
+    x: f32,
+    /// Simulation time it was at `x`, in seconds.
+    since: f64,
+    velocity: f32,
+    /// Where it was last put, to notice when something else moves it.
+    placed_x: f32,
+}
+
+impl MotionAnchor {
+    /// Position at time `now`: `x + velocity * (now - since)`, worked out in `f64` so it's exact
+    /// to well under a pixel however long it has been moving. Adding up per-frame `f32` steps
+    /// instead drifts visibly over a long unattended run; this after ten minutes at 60 fps is
+    /// the analytic position to within rounding of the final `f32`.
+    fn position(&self, now: f64) -> f32 {
+        (self.x as f64 + self.velocity as f64 * (now - self.since)) as f32
+    }
 }
 
+/// Index of the receiver that per-receiver controls apply to.
+#[derive(Resource, Default)]
+struct SelectedReceiver(usize);
+
+#[derive(Clone, Copy, Default, Reflect)]
 enum Movement {
     Left,
     Right,
+    #[default]
     Stationary,
+    /// Back and forth between two x positions, turning around whenever it reaches one.
+    PingPong {
+        left: f32,
+        right: f32,
+        rightward: bool,
+    },
+}
+
+impl Movement {
+    fn direction(&self) -> f32 {
+        match self {
+            Movement::Left => -1.,
+            Movement::Right => 1.0,
+            Movement::Stationary => 0.,
+            Movement::PingPong { rightward, .. } => {
+                if *rightward {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+        }
+    }
+
+    /// Whether it keeps moving for the whole run rather than stopping when its plot is done.
+    fn oscillates(&self) -> bool {
+        matches!(self, Movement::PingPong { .. })
+    }
+
+    /// Turns a ping-pong movement that has reached or passed the bound it's heading for at `x`
+    /// around, returning that bound to put it back on.
+    fn bounce(&mut self, x: f32) -> Option<f32> {
+        let Movement::PingPong {
+            left,
+            right,
+            rightward,
+        } = self
+        else {
+            return None;
+        };
+        let bound = if *rightward {
+            (x >= *right).then_some(*right)
+        } else {
+            (x <= *left).then_some(*left)
+        }?;
+        *rightward = !*rightward;
+        Some(bound)
+    }
 }
 
 #[derive(Component)]
 struct Mover(Movement);
 
-#[derive(Component, Default)]
+/// The visible part of a transmitter, a child of its [`Transmitter`]. Particles are emitted
+/// from wherever the body currently is, so moving it moves the source without dragging along the
+/// particles it already emitted.
+#[derive(Component)]
+struct TransmitterBody;
+
+/// Emits particles. Its particles are its children, so it stays put while its
+/// [`TransmitterBody`] moves around.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 struct Transmitter {
+    /// Position of the transmitter in the order the simulations were created.
+    index: usize,
     spawn_point: Vec2,
     spawn_rate: Timer,
+    particle_material: Handle<ColorMaterial>,
+    plot_material: Handle<ColorMaterial>,
+    /// Full angle of the emission cone in point-source mode, in radians. `TAU` or more emits in
+    /// every direction.
+    beam_width: f32,
+    /// Direction the center of the emission cone points in, in radians from +x.
+    beam_direction: f32,
+    /// Frequency of the waves it emits, in Hz.
+    frequency: f32,
+    /// Switched off transmitters stop emitting, but particles already in flight carry on.
+    emitting: bool,
+    pattern: EmissionPattern,
+    burst: BurstState,
+    dispersion: Dispersion,
+    /// Holds off emitting after a start or reset until it finishes, for the quiet start, its
+    /// group's stagger and any synchronised emission delay. Its duration is how far the
+    /// transmitter's wave lags behind.
+    delay: Timer,
+    /// When it emitted its first particle, in seconds since the app started.
+    first_emission: Option<f32>,
+    /// Spawn index of its first particle, so `target_in_flight` can tell once it's arrived.
+    first_spawn_index: Option<u64>,
+    /// One of its particles has gone round the wrap-around world, so the world is full of its wave
+    /// and it stops emitting while `wrap_world` is on.
+    wrapped: bool,
+    /// Speed its body moves at when it has a [`Mover`], in pixels per second.
+    speed: f32,
+    /// Signal it sends.
+    #[reflect(ignore)]
+    shape: WaveShape,
+    /// Streams particles both ways, to receivers on either side.
+    bidirectional: bool,
+    /// Fraction of the amplitude it emits at, easing towards `target_gain`.
+    gain: f32,
+    /// Gain set with the gain keys.
+    target_gain: f32,
+    /// Seconds between emissions as tuned by `target_in_flight`, once it's started tuning.
+    adaptive_interval: Option<f32>,
+    /// Emissions it's made since it was created.
+    emissions: u32,
+    /// Pulses fired with [`EmissionPattern::Fired`] that it hasn't emitted yet.
+    pending_pulses: u32,
+    /// When it last emitted, in seconds since the app started.
+    last_emission: Option<f32>,
+}
+
+/// Where a transmitter is in a [`EmissionPattern::Burst`].
+#[derive(Clone, Copy, Debug, Reflect)]
+enum BurstState {
+    /// Part way through a burst, having made `emitted` emissions so far.
+    Emitting { emitted: u32 },
+    /// Between bursts, with `remaining` seconds of silence left.
+    Silent { remaining: f32 },
 }
 
-#[derive(Component, Default)]
+impl Default for BurstState {
+    fn default() -> Self {
+        BurstState::Emitting { emitted: 0 }
+    }
+}
+
+impl BurstState {
+    /// Advances the silence by `dt` seconds, returning whether the transmitter should still hold
+    /// off emitting. Starts the next burst once the silence is over.
+    fn silent(&mut self, dt: f32) -> bool {
+        if let BurstState::Silent { remaining } = self {
+            *remaining -= dt;
+            if *remaining > 0. {
+                return true;
+            }
+            *self = BurstState::default();
+        }
+        false
+    }
+
+    /// Records an emission, falling silent once `pattern`'s burst is complete.
+    fn record_emission(&mut self, pattern: EmissionPattern) {
+        let EmissionPattern::Burst { count, gap } = pattern else {
+            return;
+        };
+        if let BurstState::Emitting { emitted } = self {
+            *emitted += 1;
+            if *emitted >= count {
+                *self = BurstState::Silent { remaining: gap };
+            }
+        }
+    }
+}
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 struct SignalParticle {
     speed: f32,
     amplitude: f32,
     frequency: f32,
+    /// Increases with every particle spawned, so lower values are older particles. The particles
+    /// of a plane wave's column share one, as they're one emission.
+    spawn_index: u64,
+    /// Where the particle was emitted, relative to its transmitter.
+    origin: Vec2,
+    /// Which of a plane wave's lines of particles it's in, counting across its direction from 0
+    /// through the transmitter. Always 0 for the other emission modes.
+    lane: i32,
+    /// Unit vector the particle travels along. The wave oscillates perpendicular to it.
+    direction: Vec2,
+    /// Current displacement from the line of travel, i.e. the wave's value at this particle.
+    displacement: f32,
+    dispersion: Dispersion,
+    /// Seconds its wave lags behind, from its transmitter's delay.
+    delay: f32,
+    /// Current amplitude of the wave at this particle. The same as `amplitude` unless the wave is
+    /// dispersive, when the wave packets' envelope passes through the particle.
+    envelope: f32,
+    #[reflect(ignore)]
+    shape: WaveShape,
+    /// Its material at full opacity.
+    material: Handle<ColorMaterial>,
+    /// Opacity it's drawn at while age fading, out of [`phosphor::FADE_LEVELS`].
+    fade_level: u8,
+    /// Already plotted from within a receiver's `capture_band`, so reaching a receiver only
+    /// removes it.
+    sampled: bool,
+    /// Number of hues and which emission it was, when it's colored by emission, so the sample it
+    /// makes can be plotted in its color.
+    hue: Option<(u32, u32)>,
 }
 
 /// Camera that renders the pixel-perfect world to the [`Canvas`].
@@ -100,73 +595,711 @@ struct Canvas;
 
 fn main() {
     let mut app = App::new();
+    // Loaded before the plugins are added since some settings configure the plugins themselves.
+    let config = SimConfig::load();
+    if cfg!(feature = "determinism_check") {
+        // Runs headless, so there's no window to open.
+        let passed = determinism::check_determinism(&config);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
 
+    let image_plugin = match config.presentation_style {
+        PresentationStyle::Pixelated => ImagePlugin::default_nearest(),
+        PresentationStyle::Smooth => ImagePlugin::default_linear(),
+    };
     if cfg!(feature = "webdev") {
-        app.add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                canvas: Some("#doppl-rs".into()),
-                ..default()
-            }),
-            ..default()
-        }));
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        canvas: Some("#doppl-rs".into()),
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(image_plugin),
+        );
     } else {
-        // app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()));
-        app.add_plugins(DefaultPlugins);
+        app.add_plugins(DefaultPlugins.set(image_plugin));
+    }
+    app.add_plugins(capture::CanvasReadbackPlugin);
+    if config.presentation_style == PresentationStyle::Pixelated {
+        app.insert_resource(Msaa::Off);
     }
-    app.add_systems(Startup, (setup, setup_camera))
-        // .insert_resource(Msaa::Off)
+
+    let chained = config.system_ordering == SystemOrdering::Chained;
+    // Registered for reflection so an inspector can show and edit them live.
+    app.register_type::<SimConfig>()
+        .register_type::<KeyBindings>()
+        .register_type::<speed::SimSpeed>()
+        .register_type::<Transmitter>()
+        .register_type::<Receiver>()
+        .register_type::<SignalParticle>();
+    #[cfg(feature = "inspector")]
+    app.add_plugins(bevy_inspector_egui::quick::WorldInspectorPlugin::new());
+    let font = match &config.font {
+        Some(path) => app.world.resource::<AssetServer>().load(path.clone()),
+        None => Handle::default(),
+    };
+    app.insert_resource(EmissionRng::new(&config))
+        .insert_resource(config.clock_mode.update_strategy(&config))
+        .insert_resource(OverlayFont(font))
+        .insert_resource(config.key_bindings.clone())
+        .insert_resource(tempo::Tempo(config.tempo_bpm))
+        .insert_resource(config)
+        .init_resource::<Hud>()
+        .init_resource::<MeasureMode>()
+        .init_resource::<stopwatch::PeriodMeasure>()
+        .init_resource::<freeze::FreezeTimer>()
+        .init_resource::<preset::ActivePreset>()
+        .init_resource::<SelectedReceiver>()
+        .init_resource::<explode::DebugExplode>()
+        .init_resource::<EditHistory>()
+        .init_resource::<CurrentAmplitude>()
+        .init_resource::<follow::CameraFollow>()
+        .init_resource::<follow::CameraFit>()
+        .init_resource::<palette::SpectrumMaterials>()
+        .init_resource::<palette::VelocityMaterials>()
+        .init_resource::<palette::EmissionHueMaterials>()
+        .init_resource::<speed::SimSpeed>()
+        .init_resource::<CompareMode>()
+        .init_resource::<rewind::Rewind>()
+        .init_resource::<envelope::ShowEnvelope>()
+        .init_resource::<ruler::ShowRuler>()
+        .init_resource::<phosphor::FadedMaterials>()
+        .init_resource::<batch::Batch>()
+        .init_resource::<param_sweep::RunningSweep>()
+        .init_resource::<scenario::ScenarioTransition>()
+        .init_resource::<crossfade::CrossFade>()
+        .init_resource::<settings::PointerOverUi>()
+        .init_non_send_resource::<share::SharedClipboard>()
+        .add_event::<SimulationReset>()
+        .add_event::<capture::SnapshotCanvas>()
+        .add_systems(
+            Startup,
+            (
+                setup,
+                setup_camera,
+                heatmap::setup_heatmap,
+                phase_map::setup_phase_map,
+                frequency_log::open_frequency_log,
+                inspect::setup_tooltip,
+                settings::setup_settings_panel,
+                equation::spawn_equation,
+                differential::spawn_differential_readout,
+                receiver_table::spawn_receiver_table,
+                launch::launch_paused,
+                lesson::start_lesson.before(setup),
+            ),
+        )
+        // Clicks on the settings panel are told apart from clicks on the world, and its action
+        // buttons press their keys, before anything handles either.
+        .add_systems(
+            PreUpdate,
+            (
+                settings::track_pointer_over_ui,
+                settings::press_action_buttons,
+            )
+                .after(UiSystem::Focus),
+        )
+        // Orderings needed for correctness are spelled out below; anything else is only ordered
+        // when `system_ordering` is `Chained`. Everything runs after `restart_simulation`, whose
+        // despawns are applied before later systems run, so nothing queues commands for an
+        // entity a reset has already torn down (which would panic when the commands are applied).
+        // The order within each group is incidental: at worst a system sees another's change a
+        // frame later, e.g. the camera following a receiver from where it was last frame.
         .add_systems(
             Update,
             (
-                propagate_particle,
-                produce_particle,
-                move_rx,
-                reset_simulation,
-                reset_simulation_timer,
-                fit_canvas,
-                screenshot_window,
+                // A reset requested this frame is rebuilt this frame.
+                (reset_simulation, reset_simulation_timer).before(restart_simulation),
+                restart_simulation,
+            ),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    propagate_particle.run_if(rewind::is_live),
+                    produce_particle,
+                    move_rx,
+                    move_tx,
+                    toggle_pause,
+                    toggle_auto_reset,
+                    clear_persisted_traces,
+                    fit_canvas.run_if(uses_canvas),
+                    screenshot_window,
+                ),
+                chained,
+            )
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    show_expected_frequencies,
+                    show_time_of_flight,
+                    show_snr,
+                    draw_mach_cones,
+                    (draw_beam_cones, placement::draw_path_arcs),
+                    measure::toggle_measure_mode,
+                    measure::measure,
+                    stopwatch::toggle_period_measure,
+                    stopwatch::measure_period,
+                    select_receiver,
+                    receiver_view::cycle_receiver_view,
+                    receiver_view::show_raw_points,
+                    receiver_view::draw_receiver_view,
+                    capture::snapshot_canvas,
+                    layout::drag_layout,
+                    layout::undo_redo,
+                    layout::clear_history_on_reset,
+                    palette::switch_palette,
+                    scenario::select_scenario,
+                ),
+                chained,
+            )
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    check_speed_limit,
+                    envelope::toggle_envelope,
+                    envelope::draw_envelope,
+                    scenario::run_scenario_transition,
+                    waveform::save_capture,
+                    waveform::load_capture,
+                    stats::log_stats,
+                    (toggle_transmitter, ramp_transmitter_gain, fire_pulse),
+                    adjust_amplitude,
+                    draw_speedometers,
+                    (
+                        capture::capture_on_complete,
+                        single_capture::run_single_captures,
+                    ),
+                    animate_despawning,
+                    heatmap::toggle_heatmap,
+                    heatmap::update_heatmap,
+                    contour::toggle_contours,
+                    contour::draw_contours,
+                    speed::change_sim_speed,
+                    speed::ease_sim_speed,
+                    compare::control_compare_mode,
+                    compare::separate_sides,
+                ),
+                chained,
+            )
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    annotate::annotate,
+                    annotate::draw_annotations,
+                    annotate::manage_annotations,
+                    toggle_crests_only,
+                    rewind::record_history,
+                    rewind::control_rewind,
+                    rewind::despawn_retired.run_if(rewind::is_live),
+                    limit_receivers,
+                    // The particles' displacements have to be for this frame's time.
+                    check_emission_continuity
+                        .run_if(rewind::is_live)
+                        .after(propagate_particle),
+                    change_plot_decimation,
+                    show_sample_rate,
+                    follow::control_camera_follow,
+                    follow::toggle_camera_fit,
+                    follow::follow_camera,
+                    texture_particle_materials,
+                    share::share_parameters,
+                    (inspect::inspect_plot_point, probe::log_probe),
+                    draw_velocity_vectors,
+                    show_group_speeds,
+                    toggle_ghost_preview,
+                    draw_ghost_preview,
+                ),
+                chained,
             )
-                .chain(),
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    settings::toggle_settings_panel,
+                    settings::drag_sliders,
+                    settings::press_toggles,
+                    settings::sync_settings_panel,
+                    cycle_sweep_mode,
+                    ruler::toggle_ruler,
+                    ruler::spawn_ruler_labels,
+                    ruler::draw_rulers,
+                    wavelength_grid::toggle_wavelength_grid,
+                    wavelength_grid::draw_wavelength_grid,
+                    pin::pin_particles,
+                    receiver_view::toggle_smooth_trace,
+                    receiver_view::draw_smooth_trace,
+                    toggle_period_markers,
+                    draw_period_markers,
+                    equation::toggle_equation,
+                    equation::update_equation,
+                    phosphor::fade_phosphor,
+                    differential::update_differential_readout,
+                ),
+                chained,
+            )
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            ordered(
+                (
+                    receiver_view::toggle_auto_scale,
+                    receiver_view::auto_scale_plots,
+                    cycle_particle_fill,
+                    toggle_age_fade,
+                    batch::run_batch,
+                    receiver_view::toggle_window_band,
+                    receiver_view::update_window_band,
+                    receiver_view::spawn_distance_labels,
+                    receiver_view::label_distance_axis,
+                    rotate_propagation_axis,
+                    show_start_countdown,
+                    clock::toggle_clock_mode,
+                    param_sweep::run_parameter_sweep,
+                    toggle_wrap_world,
+                    hud::toggle_hud_anchor,
+                    roll_seed,
+                    freeze::freeze_run,
+                    heatmap::toggle_field_mode,
+                    preset::save_or_load_preset,
+                    (
+                        receiver_table::toggle_receiver_table,
+                        receiver_table::update_receiver_table,
+                        nyquist::toggle_nyquist_indicator,
+                        nyquist::spawn_nyquist_labels,
+                        nyquist::update_nyquist_labels,
+                        label_layout::toggle_label_spacing,
+                        label_layout::track_receiver_labels,
+                        standing_wave::toggle_standing_wave_nodes,
+                        standing_wave::draw_standing_wave_nodes,
+                        time_axis::toggle_time_axis,
+                        time_axis::label_time_axes,
+                        tempo::change_tempo,
+                        tempo::draw_beat_flashes,
+                        explode::toggle_explode,
+                        field_export::export_field,
+                        lesson::advance_lesson,
+                        (
+                            phase_map::toggle_phase_map,
+                            phase_map::expose_phase_map.run_if(rewind::is_live),
+                        ),
+                        focus::pause_on_blur,
+                        phase_lock::draw_phase_lock_marker,
+                        (
+                            crossfade::store_crossfade_trace,
+                            // Shares its keys with the rewind by default, so leaves them to it.
+                            crossfade::scrub_crossfade
+                                .run_if(rewind::is_live)
+                                .after(rewind::control_rewind),
+                            crossfade::draw_crossfade,
+                        ),
+                    ),
+                ),
+                chained,
+            )
+            .after(restart_simulation),
+        )
+        .add_systems(
+            Update,
+            frequency_log::log_frequencies.after(restart_simulation),
+        )
+        // Closing the window sends `AppExit` in `PostUpdate` and the app stops after that update,
+        // so anything finishing up on exit has to run in `Last`.
+        .add_systems(
+            Last,
+            (
+                capture::finish_screenshots_on_exit,
+                frequency_log::flush_frequency_log_on_exit,
+            ),
+        )
+        .add_systems(
+            Update,
+            figure::capture_figure
+                .after(restart_simulation)
+                .before(ruler::draw_rulers),
+        )
+        .add_systems(
+            Update,
+            launch::dismiss_launch_notice
+                .after(restart_simulation)
+                .after(toggle_pause),
+        )
+        .add_systems(
+            PostUpdate,
+            // Need to wait til bevy propagates the transform before using the global transform
+            handle_rx_collision
+                .run_if(rewind::is_live)
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_systems(PostUpdate, hud::update_hud.before(UiSystem::Layout))
+        .add_systems(
+            PostUpdate,
+            phase_lock::lock_phase
+                .run_if(rewind::is_live)
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_systems(First, explode::restore_particles)
+        .add_systems(First, focus::limit_frame_delta.before(TimeSystem))
+        .add_systems(
+            PostUpdate,
+            explode::explode_particles.after(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            PostUpdate,
+            crisp_text::sharpen_text
+                .after(CameraUpdateSystem)
+                .before(bevy::text::update_text2d_layout),
+        )
+        .add_systems(
+            PostUpdate,
+            label_layout::spread_receiver_labels
+                .after(bevy::text::update_text2d_layout)
+                .before(TransformSystem::TransformPropagate),
         )
         .add_systems(
             PostUpdate,
-            (handle_rx_collision).after(TransformSystem::TransformPropagate), // Need
-                                                                              // to wait til bevy propagates the transform before using the global transform
+            hud::place_hud.before(TransformSystem::TransformPropagate),
         )
-        .run();
+        .add_systems(
+            PostUpdate,
+            follow::place_world_labels.after(TransformSystem::TransformPropagate),
+        );
+
+    if cfg!(feature = "debug_checks") {
+        app.add_systems(
+            PostUpdate,
+            diagnostics::check_transforms.after(TransformSystem::TransformPropagate),
+        );
+    }
+
+    if cfg!(feature = "debug_profiling") {
+        app.add_systems(Update, profiling::toggle_profiling)
+            .add_systems(Last, profiling::report_timings);
+    }
+
+    #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+    {
+        let midi = midi::MidiOut::connect(app.world.resource::<SimConfig>());
+        app.insert_non_send_resource(midi)
+            .add_systems(Update, midi::play_observed_note.after(toggle_pause));
+    }
+
+    if cfg!(feature = "audio") {
+        app.add_systems(Startup, audio::setup_ambience).add_systems(
+            Update,
+            (
+                audio::toggle_ambience,
+                audio::sync_ambience,
+                audio::stop_ambience_on_exit,
+            )
+                .chain()
+                .after(toggle_pause),
+        );
+    }
+
+    app.run();
+}
+
+/// Chains `systems` when every system should run in a fixed order, otherwise leaves the scheduler
+/// free to run them in parallel.
+fn ordered<M>(systems: impl IntoSystemConfigs<M>, chained: bool) -> SystemConfigs {
+    if chained {
+        systems.chain()
+    } else {
+        systems.into_configs()
+    }
 }
 
 fn setup(
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    compare: Res<CompareMode>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
     mut commands: Commands,
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    asset_server: Res<AssetServer>,
+    font: Res<OverlayFont>,
+    mut hud: ResMut<Hud>,
 ) {
     if !cfg!(feature = "webdev") && !cfg!(feature = "gifcreate") {
+        hud.set(
+            "restart",
+            format!(
+                "Press {} to restart the simulation",
+                key_name(bindings.restart)
+            ),
+        );
+        hud.set("pause", pause_label(config.start_paused, &bindings));
+        hud.set(
+            "measure_help",
+            format!("Press {} to measure distances", key_name(bindings.measure)),
+        );
+        hud.set("period_measure", stopwatch::period_measure_help(&bindings));
+        hud.set(
+            "select_receiver",
+            format!(
+                "Press {} to select the next receiver",
+                key_name(bindings.select_receiver)
+            ),
+        );
+        hud.set(
+            "toggle_transmitter",
+            format!(
+                "Press {} to switch the selected receiver's transmitter off or on",
+                key_name(bindings.toggle_transmitter)
+            ),
+        );
+        hud.set("transmitter_gain", transmitter_gain_label(None, &bindings));
+        hud.set(
+            "snapshot",
+            format!(
+                "Press {} to save the canvas as a PNG",
+                key_name(bindings.snapshot)
+            ),
+        );
+        hud.set("figure", figure::figure_help(&bindings));
+        hud.set("export_field", field_export::export_field_help(&bindings));
+        hud.set(
+            "phase_map",
+            phase_map::phase_map_label(config.phase_map, &bindings),
+        );
+        hud.set("batch", batch::batch_help(&config, &bindings));
+        hud.set(
+            "window_band",
+            receiver_view::window_band_label(&config, &bindings),
+        );
+        hud.set("envelope", envelope::envelope_label(false, &bindings));
+        hud.set("amplitude", amplitude_label(PARTICLE_AMPLITUDE, &bindings));
+        hud.set("heatmap", heatmap::heatmap_label(config.heatmap, &bindings));
+        hud.set("contours", contour::contour_label(&config, &bindings));
+        hud.set("field_mode", heatmap::field_mode_label(&config, &bindings));
+        hud.set(
+            "receiver_table",
+            receiver_table::receiver_table_label(&config, &bindings),
+        );
+        hud.set("nyquist", nyquist::nyquist_label(&config, &bindings));
+        hud.set(
+            "label_spacing",
+            label_layout::label_spacing_label(&config, &bindings),
+        );
+        hud.set(
+            "standing_wave_nodes",
+            standing_wave::standing_wave_label(&config, &bindings),
+        );
+        hud.set("time_axis", time_axis::time_axis_label(&config, &bindings));
+        hud.set("tempo", tempo::tempo_label(config.tempo_bpm, &bindings));
+        hud.set(
+            "explode",
+            explode::explode_label(&explode::DebugExplode::default(), &bindings),
+        );
+        hud.set(
+            "crossfade",
+            crossfade::crossfade_label(&crossfade::CrossFade::default(), &bindings),
+        );
+        hud.set(
+            "presets",
+            preset::preset_label(&config, &bindings, &preset::ActivePreset::default()),
+        );
+        hud.set("sim_speed", speed::sim_speed_label(1., &bindings));
+        hud.set("compare", compare::compare_label(&compare, &bindings));
+        hud.set(
+            "crests_only",
+            crests_only_label(config.crests_only, &bindings),
+        );
+        hud.set("rewind", rewind::rewind_help(&bindings));
+        hud.set("follow", follow::follow_label(None, &bindings));
+        hud.set(
+            "camera_fit",
+            follow::camera_fit_label(&follow::CameraFit::default(), &bindings),
+        );
+        hud.set("settings", settings::settings_label(false, &bindings));
+        hud.set("ghost_preview", ghost_preview_label(&config, &bindings));
+        hud.set("period_markers", period_markers_label(&config, &bindings));
+        hud.set("wavelength_ruler", ruler::ruler_label(false, &bindings));
+        hud.set(
+            "wavelength_grid",
+            wavelength_grid::wavelength_grid_label(&config, &bindings),
+        );
+        hud.set("pin", pin::pin_help(&bindings));
+        hud.set("wave_equation", equation::equation_label(false, &bindings));
+        hud.set(
+            "smooth_trace",
+            receiver_view::smooth_trace_label(&config, &bindings),
+        );
+        hud.set(
+            "auto_scale",
+            receiver_view::auto_scale_label(&config, &bindings, &[]),
+        );
+        hud.set("particle_fill", particle_fill_label(&config, &bindings));
+        hud.set("seed", seed_label(&config, &bindings));
+        hud.set("age_fade", age_fade_label(&config, &bindings));
+        hud.set(
+            "propagation_axis",
+            propagation_axis_label(&config, &bindings),
+        );
+        hud.set("clock_mode", clock::clock_label(&config, &bindings));
+        hud.set("wrap_world", wrap_world_label(&config, &bindings));
+        hud.set("hud_anchor", hud::hud_anchor_label(&config, &bindings));
+        hud.set(
+            "parameter_sweep",
+            param_sweep::sweep_help(&config, &bindings),
+        );
+        hud.set(
+            "velocity_vectors",
+            format!(
+                "Hold {} to show particle velocities",
+                key_name(bindings.velocity_vectors)
+            ),
+        );
+        hud.set(
+            "share",
+            format!(
+                "Press {} to copy the parameters, {} to apply copied ones",
+                key_name(bindings.copy_parameters),
+                key_name(bindings.paste_parameters)
+            ),
+        );
+        hud.set(
+            "annotate",
+            format!(
+                "Hold {} and drag to annotate (shift for straight lines), {} to clear, {} to \
+                 export",
+                key_name(bindings.annotate),
+                key_name(bindings.clear_annotations),
+                key_name(bindings.export_annotations)
+            ),
+        );
+        hud.set(
+            "waveform",
+            format!(
+                "Press {} to save the receiver traces, {} to load them",
+                key_name(bindings.save_capture),
+                key_name(bindings.load_capture)
+            ),
+        );
+        hud.set("palette", palette::palette_label(config.palette, &bindings));
+        hud.set(
+            "scenario",
+            scenario::scenario_label(config.scenario, &bindings),
+        );
+        let alt_action = if config.snap_to_grid {
+            "drag freely"
+        } else {
+            "snap to the grid"
+        };
+        hud.set(
+            "layout",
+            format!(
+                "Drag transmitters and receivers to move them (alt to {alt_action}), \
+                 ctrl+{}/ctrl+{} to undo/redo",
+                key_name(bindings.undo).trim_matches('\''),
+                key_name(bindings.redo).trim_matches('\'')
+            ),
+        );
         commands.spawn((
             TextBundle::from_section(
-                "Press 'r' to restart the simulation",
+                "",
                 TextStyle {
+                    font: font.0.clone(),
                     font_size: 20.,
                     ..default()
                 },
             )
             .with_style(Style {
                 position_type: PositionType::Absolute,
-                top: Val::Px(15.0),
-                left: Val::Px(15.),
+                top: Val::Px(hud::HUD_MARGIN),
+                left: Val::Px(hud::HUD_MARGIN),
                 ..default()
             }),
+            HudText,
+            HIGH_RES_LAYERS,
+        ));
+        // `hud::place_hud` puts it in place and shows it instead of the overlay above.
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 20.,
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::TopLeft,
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HudText,
+            WorldHudText,
             HIGH_RES_LAYERS,
         ));
     }
 
-    commands.insert_resource(ResetTimer {
-        timer: Timer::new(Duration::from_secs(10), TimerMode::Repeating),
+    let reset_timer = ResetTimer {
+        timer: Timer::from_seconds(
+            config.reset_interval.unwrap_or(DEFAULT_RESET_INTERVAL_SECS),
+            TimerMode::Repeating,
+        ),
+        enabled: config.reset_interval.is_some(),
+    };
+    hud.set("auto_reset", auto_reset_label(&reset_timer, &bindings));
+    commands.insert_resource(reset_timer);
+    commands.insert_resource(PersistedTraces {
+        traces: VecDeque::new(),
+        material: materials.add(PERSISTED_TRACE_COLOR),
     });
-    start_simulation(meshes, materials, commands);
+    if config.persist_traces {
+        hud.set(
+            "persist",
+            format!(
+                "Press {} to clear old traces",
+                key_name(bindings.clear_traces)
+            ),
+        );
+    }
+    commands.insert_resource(PlotMarkerMesh(
+        meshes.add(marker::marker_mesh(config.plot_marker)),
+    ));
+    commands.insert_resource(ParticleAppearance::new(
+        &config.particle_shape,
+        &mut meshes,
+        &asset_server,
+    ));
+    start_simulation(meshes, materials, commands, &config, &compare, &font.0);
 }
 
-fn setup_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+fn setup_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>, config: Res<SimConfig>) {
+    if cfg!(feature = "dual_window") {
+        spawn_plot_window(&mut commands);
+    }
+
+    if config.render_pipeline == RenderPipeline::Direct {
+        // A single camera sees both layers and keeps the whole simulation area in view.
+        let mut camera = Camera2dBundle::default();
+        camera.projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: RES_WIDTH as f32,
+            min_height: RES_HEIGHT as f32,
+        };
+        commands.spawn((camera, OuterCamera, RenderLayers::all()));
+        return;
+    }
+
     let canvas_size = Extent3d {
         width: RES_WIDTH,
         height: RES_HEIGHT,
@@ -184,6 +1317,7 @@ fn setup_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
             sample_count: 1,
             usage: TextureUsages::TEXTURE_BINDING
                 | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
                 | TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         },
@@ -222,300 +1356,3216 @@ fn setup_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
 
     // the "outer" camera renders whatever is on `HIGH_RES_LAYERS` to the screen.
     // here, the canvas and one of the sample sprites will be rendered by this camera
-    commands.spawn((Camera2dBundle::default(), OuterCamera, HIGH_RES_LAYERS));
-}
-
-fn propagate_particle(mut query: Query<(&mut Transform, &SignalParticle)>, time: Res<Time>) {
-    for (mut particle_transforms, signal_particle) in query.iter_mut() {
-        let t = time.elapsed().as_millis() as f32 / 1000.;
-
-        let a = -signal_particle.amplitude;
-        let k = 2. * PI * signal_particle.frequency / signal_particle.speed; // v = \omega/k =
-                                                                             // \lambda/T = \lambda * f
-        let x = particle_transforms.translation.x;
-        particle_transforms.translation.x += signal_particle.speed * time.delta_seconds();
-
-        // Classic
-        // propagating wave equation
-        let f = signal_particle.frequency;
-        particle_transforms.translation.y = a * f32::sin(k * x - 2. * PI * f * t);
-    }
+    let [r, g, b] = config.letterbox_color;
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                clear_color: ClearColorConfig::Custom(Color::rgb(r, g, b)),
+                ..default()
+            },
+            ..default()
+        },
+        OuterCamera,
+        HIGH_RES_LAYERS,
+    ));
 }
 
-fn produce_particle(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut query: Query<(Entity, &mut Transmitter)>,
+fn propagate_particle(
+    mut query: Query<(
+        &Parent,
+        &mut Transform,
+        &GlobalTransform,
+        &mut SignalParticle,
+        &mut Visibility,
+        &mut Handle<ColorMaterial>,
+    )>,
+    mut tx_query: Query<&mut Transmitter>,
+    rx_query: Query<(&GlobalTransform, &Receiver)>,
+    config: Res<SimConfig>,
     time: Res<Time>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut faded: ResMut<phosphor::FadedMaterials>,
 ) {
-    for (tx_entity, mut tx) in query.iter_mut() {
-        tx.spawn_rate.tick(time.delta());
+    #[cfg(feature = "debug_profiling")]
+    let _timer = profiling::time("propagate_particle");
+    let receivers: Vec<(Vec2, ReceiverOrientation, ReceiverFace)> = rx_query
+        .iter()
+        .map(|(transform, rx)| (transform.translation().xy(), rx.orientation, rx.face))
+        .collect();
+    let axis = config.propagation_axis();
+    for (
+        parent,
+        mut particle_transforms,
+        global_transform,
+        mut signal_particle,
+        mut visibility,
+        mut material,
+    ) in query.iter_mut()
+    {
+        let t = time.elapsed().as_millis() as f32 / 1000. - signal_particle.delay;
 
-        if tx.spawn_rate.finished() {
-            let new_particle = commands
-                .spawn((
-                    MaterialMesh2dBundle {
-                        mesh: meshes.add(Circle::default()).into(),
-                        material: materials.add(PARTICLE_COLOR),
-                        transform: Transform::from_translation(tx.spawn_point.extend(-1.))
-                            .with_scale(PARTICLE_SIZE),
-                        ..default()
-                    },
-                    SignalParticle {
-                        amplitude: PARTICLE_AMPLITUDE,
-                        speed: PARTICLE_SPEED,
-                        frequency: PARTICLE_FREQUENCY,
-                    },
-                    PIXEL_PERFECT_LAYERS,
-                ))
-                .id();
+        // Particles travel along `direction` from where they were emitted and oscillate across
+        // it. For the default stream from a stationary transmitter `direction` is +x and the
+        // origin is zero, so this is just the x position and y displacement.
+        let origin = signal_particle.origin;
+        let direction = signal_particle.direction;
+        let mut x = (particle_transforms.translation.xy() - origin).dot(direction);
+        // Transmitters only ever translate, so this takes positions to world space.
+        let parent_offset =
+            global_transform.translation().xy() - particle_transforms.translation.xy();
 
-            commands.entity(tx_entity).add_child(new_particle);
+        // With substeps the frame is split up and the particle stops at the first step that
+        // takes it into a receiver, so a long frame can't carry it deep inside before it's
+        // sampled. One already inside, passing through in a wrap-around world, carries on.
+        let substeps = config.particle_substeps.max(1);
+        let dt = time.delta_seconds() / substeps as f32;
+        let in_catch_area = |position: Vec2| {
+            receivers.iter().any(|&(rx, orientation, face)| {
+                distance_to_catch_area(parent_offset + position, rx, orientation, face, axis) == 0.
+            })
+        };
+        let mut was_inside = substeps > 1 && in_catch_area(particle_transforms.translation.xy());
+        let (mut envelope, mut y, mut position) = (0., 0., Vec2::ZERO);
+        for step in 1..=substeps {
+            let t = t - dt * (substeps - step) as f32;
+            envelope = physics::envelope(
+                signal_particle.amplitude,
+                signal_particle.frequency,
+                signal_particle.speed,
+                signal_particle.dispersion,
+                x,
+                t,
+            );
+            y = physics::shaped_displacement(
+                envelope,
+                signal_particle.frequency,
+                signal_particle.speed,
+                &signal_particle.shape,
+                x,
+                t,
+            );
+            x += signal_particle.speed * dt;
+            position = origin + direction * x + direction.perp() * y;
+            if substeps > 1 {
+                let inside = in_catch_area(position);
+                if inside && !was_inside {
+                    break;
+                }
+                was_inside = inside;
+            }
+        }
+        if config.wrap_world {
+            let width = config.world_bounds.width;
+            let world_x = parent_offset.x + position.x;
+            let shift = if world_x < -width / 2. {
+                width
+            } else if world_x > width / 2. {
+                -width
+            } else {
+                0.
+            };
+            if shift != 0. {
+                // Moving the origin along with it leaves the distance travelled, and so the phase,
+                // as it was, so the particle carries on with its own wave.
+                position.x += shift;
+                signal_particle.origin.x += shift;
+                // Receivers hear it again on its next lap.
+                signal_particle.sampled = false;
+                if let Ok(mut tx) = tx_query.get_mut(parent.get()) {
+                    tx.wrapped = true;
+                }
+            }
+        }
+        signal_particle.envelope = envelope;
+        signal_particle.displacement = y;
+        particle_transforms.translation.x = position.x;
+        particle_transforms.translation.y = position.y;
+
+        // In crests-only mode just the particles near a peak or trough are shown, which leaves
+        // the wavefronts moving across the screen.
+        let near_crest = y.abs() >= envelope.abs() * (1. - config.crest_threshold);
+        // Last frame's position is close enough to decide whether it's near a receiver.
+        let thinned = config.thinning.is_some_and(|thinning| {
+            signal_particle.spawn_index % thinning.keep_one_in.max(1) != 0
+                && near_receiver(
+                    global_transform.translation().xy(),
+                    &receivers,
+                    thinning.margin,
+                )
+        });
+        let new_visibility = if (!config.crests_only || near_crest) && !thinned {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(new_visibility);
+
+        let fade_level = if config.age_fade {
+            // `x` and the speed share a sign, so this is how long ago it was emitted.
+            let age = x / signal_particle.speed;
+            let time_left = receivers
+                .iter()
+                .map(|&(rx, orientation, face)| {
+                    distance_to_catch_area(parent_offset + position, rx, orientation, face, axis)
+                })
+                .fold(f32::INFINITY, f32::min)
+                / signal_particle.speed.abs();
+            age_fade_level(age, time_left, config.fade_in_secs, config.fade_out_secs)
+        } else {
+            phosphor::FADE_LEVELS
+        };
+        if fade_level != signal_particle.fade_level {
+            if let Some(handle) = faded.get(&mut materials, &signal_particle.material, fade_level) {
+                *material = handle;
+                signal_particle.fade_level = fade_level;
+            }
         }
     }
 }
 
-fn handle_rx_collision(
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut commands: Commands,
-    sig_query: Query<(&Parent, Entity, &GlobalTransform, &Transform), With<SignalParticle>>,
-    mut rx_query: Query<(Entity, &Transform, &mut Receiver)>,
+/// Opacity level, out of [`phosphor::FADE_LEVELS`], of a particle emitted `age` seconds ago that
+/// reaches a receiver in `time_left` seconds, fading in over the first `fade_in` seconds and out
+/// over the last `fade_out`. A duration of zero or less doesn't fade.
+fn age_fade_level(age: f32, time_left: f32, fade_in: f32, fade_out: f32) -> u8 {
+    let ramp = |elapsed: f32, duration: f32| {
+        if duration > 0. {
+            (elapsed / duration).clamp(0., 1.)
+        } else {
+            1.
+        }
+    };
+    let opacity = ramp(age, fade_in).min(ramp(time_left, fade_out));
+    (opacity * phosphor::FADE_LEVELS as f32).ceil() as u8
+}
+
+fn age_fade_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.age_fade { "stop" } else { "start" };
+    format!(
+        "Press {} to {action} fading particles in and out",
+        key_name(bindings.age_fade)
+    )
+}
+
+/// Counts down the quiet start until the transmitters begin emitting. Left out of `gifcreate`
+/// recordings, which the quiet start is mostly for.
+fn show_start_countdown(
+    config: Res<SimConfig>,
+    tx_query: Query<&Transmitter>,
+    mut hud: ResMut<Hud>,
+) {
+    if config.start_delay_secs <= 0. || cfg!(feature = "gifcreate") {
+        return;
+    }
+    let remaining = tx_query
+        .iter()
+        .next()
+        .map_or(0., |tx| config.start_delay_secs - tx.delay.elapsed_secs());
+    if remaining > 0. {
+        hud.set("start_delay", format!("Emitting in {remaining:.1}s"));
+    } else if hud.get("start_delay").is_some() {
+        hud.remove("start_delay");
+    }
+}
+
+fn wrap_world_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.wrap_world { "stop" } else { "start" };
+    format!(
+        "Press {} to {action} wrapping particles round the world",
+        key_name(bindings.wrap_world)
+    )
+}
+
+/// Switches the wrap-around world on or off. Transmitters fill the world afresh each time it's
+/// switched on, since receivers will have used up particles while it was off.
+fn toggle_wrap_world(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut tx_query: Query<&mut Transmitter>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.wrap_world) {
+        config.wrap_world = !config.wrap_world;
+        for mut tx in tx_query.iter_mut() {
+            tx.wrapped = false;
+        }
+        hud.set("wrap_world", wrap_world_label(&config, &bindings));
+    }
+}
+
+fn propagation_axis_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    format!(
+        "Waves travel at {:.0}° (press {} / {} to turn them)",
+        config.propagation_angle,
+        key_name(bindings.axis_clockwise),
+        key_name(bindings.axis_anticlockwise)
+    )
+}
+
+/// Turns the direction waves travel in. Particles already in flight carry on the way they were
+/// sent, while receivers catch on the new axis straight away.
+fn rotate_propagation_axis(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut turn = 0.;
+    if input.just_pressed(bindings.axis_anticlockwise) {
+        turn += config.propagation_angle_step;
+    }
+    if input.just_pressed(bindings.axis_clockwise) {
+        turn -= config.propagation_angle_step;
+    }
+    if turn != 0. {
+        config.propagation_angle = (config.propagation_angle + turn).rem_euclid(360.);
+        hud.set(
+            "propagation_axis",
+            propagation_axis_label(&config, &bindings),
+        );
+    }
+}
+
+fn toggle_age_fade(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.age_fade) {
+        config.age_fade = !config.age_fade;
+        hud.set("age_fade", age_fade_label(&config, &bindings));
+    }
+}
+
+/// Whether `position` is within `margin` of any of the receivers centred at `receivers`.
+fn near_receiver(
+    position: Vec2,
+    receivers: &[(Vec2, ReceiverOrientation, ReceiverFace)],
+    margin: f32,
+) -> bool {
+    receivers.iter().any(|&(receiver, orientation, _)| {
+        let reach = orientation.size() / 2. + margin;
+        let offset = (position - receiver).abs();
+        offset.x < reach.x && offset.y < reach.y
+    })
+}
+
+/// Debug builds only: circles the spawn point of any transmitter whose newest particle doesn't
+/// continue the wave from the transmitter, i.e. particles visibly jump as they're emitted.
+fn check_emission_continuity(
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    particle_query: Query<(&Parent, &Transform, Ref<SignalParticle>)>,
     time: Res<Time>,
+    mut gizmos: Gizmos,
 ) {
-    for (sig_parent, sig_entity, sig_global_transform, sig_transform) in sig_query.iter() {
-        let particle_pos = sig_global_transform.translation().xy();
-        for (rx_entity, rx_transform, mut rx) in rx_query.iter_mut() {
-            let rx_translation = rx_transform.translation;
-            let rx_right_bound = rx_translation.x + RECEIVER_WIDTH;
-            let rx_top_bound = rx_translation.y + RECEIVER_HEIGHT / 4.;
-            let rx_bottom_bound = rx_translation.y - RECEIVER_HEIGHT / 4.;
-
-            if particle_pos.y < rx_top_bound
-                && particle_pos.y > rx_bottom_bound
-                && particle_pos.x < rx_right_bound
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let mut newest: HashMap<Entity, (&Transform, &SignalParticle)> = HashMap::new();
+    for (parent, transform, particle) in particle_query.iter() {
+        // Only just spawned, so it hasn't been given a displacement yet.
+        if particle.is_added() {
+            continue;
+        }
+        let particle = particle.into_inner();
+        newest
+            .entry(parent.get())
+            .and_modify(|current| {
+                if particle.spawn_index > current.1.spawn_index {
+                    *current = (transform, particle);
+                }
+            })
+            .or_insert((transform, particle));
+    }
+
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    for (tx_entity, (transform, particle)) in newest {
+        let Ok((tx, tx_transform)) = tx_query.get(tx_entity) else {
+            continue;
+        };
+        let distance = (transform.translation.xy() - particle.origin).dot(particle.direction);
+        let jump = physics::emission_discontinuity(
+            particle.displacement,
+            particle.amplitude,
+            particle.frequency,
+            particle.speed,
+            particle.dispersion,
+            &particle.shape,
+            distance,
+            t - particle.delay,
+        );
+        if jump > CONTINUITY_THRESHOLD {
+            let spawn_point = tx_transform.translation().xy() + tx.spawn_point;
+            gizmos.circle_2d(
+                spawn_point,
+                CONTINUITY_MARKER_RADIUS,
+                CONTINUITY_MARKER_COLOR,
+            );
+        }
+    }
+}
+
+fn produce_particle(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut spectrum: ResMut<palette::SpectrumMaterials>,
+    mut emission_hues: ResMut<palette::EmissionHueMaterials>,
+    appearance: Res<ParticleAppearance>,
+    mut query: Query<(Entity, &mut Transmitter)>,
+    particle_query: Query<(Entity, &Parent, &SignalParticle, &Transform)>,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    mut rng: ResMut<EmissionRng>,
+    amplitude: Res<CurrentAmplitude>,
+    mut next_spawn_index: Local<u64>,
+    mut faded: ResMut<phosphor::FadedMaterials>,
+    tempo: Res<tempo::Tempo>,
+) {
+    #[cfg(feature = "debug_profiling")]
+    let _timer = profiling::time("produce_particle");
+    let mut live_particles = particle_query.iter().len();
+    // Particles ordered newest first, only collected once the cap is actually hit.
+    let mut eviction_queue: Option<Vec<(u64, Entity, Entity)>> = None;
+
+    // Synchronised transmitters all get the same jittered interval, so they stay in step.
+    let shared_jitter = (config.sync_emission && config.emission_jitter > 0.).then(|| {
+        let jitter = config.emission_jitter.min(1.);
+        1. + rng.0.gen_range(-jitter..=jitter)
+    });
+
+    // Seconds each transmitter's oldest particle in flight has travelled, and the lowest spawn
+    // index in flight, only measured when the spawn rate adapts to them.
+    let mut oldest_in_flight: HashMap<Entity, (f32, u64)> = HashMap::new();
+    if config.target_in_flight.is_some() {
+        for (_, parent, particle, transform) in particle_query.iter() {
+            if particle.speed == 0. {
+                continue;
+            }
+            let travelled = (transform.translation.xy() - particle.origin).dot(particle.direction);
+            let oldest = oldest_in_flight
+                .entry(parent.get())
+                .or_insert((0., u64::MAX));
+            oldest.0 = oldest.0.max(travelled / particle.speed);
+            oldest.1 = oldest.1.min(particle.spawn_index);
+        }
+    }
+
+    // How far gains move towards their targets this frame.
+    let ramp = if config.amplitude_ramp_secs > 0. {
+        time.delta_seconds() / config.amplitude_ramp_secs
+    } else {
+        f32::INFINITY
+    };
+    for (tx_entity, mut tx) in query.iter_mut() {
+        if tx.gain != tx.target_gain {
+            tx.gain += (tx.target_gain - tx.gain).clamp(-ramp, ramp);
+        }
+        if !tx.delay.tick(time.delta()).finished() {
+            continue;
+        }
+        tx.spawn_rate.tick(time.delta());
+        // The spawn timer keeps running through the silence so bursts stay on the same beat.
+        if tx.burst.silent(time.delta_seconds()) {
+            continue;
+        }
+
+        let fired = tx.pattern == EmissionPattern::Fired;
+        let due = if fired {
+            tx.pending_pulses > 0
+        } else {
+            tx.spawn_rate.finished()
+        };
+        if !tx.emitting || !due || config.max_particles == 0 || (config.wrap_world && tx.wrapped) {
+            continue;
+        }
+        let pattern = tx.pattern;
+        tx.burst.record_emission(pattern);
+        if fired {
+            tx.pending_pulses -= 1;
+        }
+        let hues = config.emission_hues.or(fired.then_some(FIRED_HUES));
+        let now = time.elapsed().as_millis() as f32 / 1000.;
+        tx.first_emission.get_or_insert(now);
+        tx.first_spawn_index.get_or_insert(*next_spawn_index);
+        tx.last_emission = Some(now);
+        let lanes = emission_lanes(&config);
+        let elements = emission_elements(&config, tx.frequency);
+        let rays = emission_rays(&config, &tx);
+        let mut interval = config.spawn_interval().as_secs_f32();
+        if pattern == EmissionPattern::OnBeat {
+            // Exactly on the beat, whatever would otherwise tune or jitter the interval.
+            tx.spawn_rate.set_duration(tempo.beat());
+        } else if let Some(target) = config.target_in_flight {
+            // Particles stay in flight about as long as the oldest one has, so spreading the
+            // target over that long settles on it rather than chasing the count, which only
+            // answers a change once the particles emitted since have crossed the world.
+            let per_emission = (lanes.len() * elements.len() * rays.len()) as f32;
+            let (lifetime, oldest_index) = oldest_in_flight
+                .get(&tx_entity)
+                .copied()
+                .unwrap_or((0., u64::MAX));
+            let (shortest, longest) = ADAPTIVE_INTERVAL_RANGE;
+            let tuned = (lifetime * per_emission / target.max(1) as f32).clamp(shortest, longest);
+            // Until its first particle has reached a receiver or been evicted, the oldest is
+            // still ageing and only gives a lower bound on the time in flight, so it never emits
+            // faster than configured before then.
+            let first_in_flight = tx.emissions == 0
+                || tx
+                    .first_spawn_index
+                    .is_some_and(|first| oldest_index <= first);
+            interval = if first_in_flight {
+                tuned.max(interval)
+            } else {
+                tuned
+            };
+            tx.adaptive_interval = Some(interval);
+            tx.spawn_rate
+                .set_duration(Duration::from_secs_f32(interval));
+        }
+        if config.emission_jitter > 0. && pattern != EmissionPattern::OnBeat {
+            let jitter = config.emission_jitter.min(1.);
+            let scale = shared_jitter.unwrap_or_else(|| 1. + rng.0.gen_range(-jitter..=jitter));
+            tx.spawn_rate
+                .set_duration(Duration::from_secs_f32(interval * scale));
+        }
+
+        let output = amplitude.0 * tx.gain;
+        for (direction, speed, gain) in rays {
+            for (&lane, &(element, lead)) in lanes
+                .iter()
+                .flat_map(|lane| elements.iter().map(move |element| (lane, element)))
             {
-                let t = time.elapsed().as_millis() as f32 / 1000.;
-                let y = sig_transform.translation.y;
+                let origin = tx.spawn_point
+                    + element
+                    + direction.perp() * lane as f32 * config.plane_wave_spacing;
+                if live_particles >= config.max_particles {
+                    let queue = eviction_queue.get_or_insert_with(|| {
+                        let mut particles: Vec<_> = particle_query
+                            .iter()
+                            .map(|(entity, parent, particle, _)| {
+                                (particle.spawn_index, entity, parent.get())
+                            })
+                            .collect();
+                        particles.sort_unstable_by_key(|&(spawn_index, _, _)| {
+                            std::cmp::Reverse(spawn_index)
+                        });
+                        particles
+                    });
+                    if let Some((_, oldest, parent)) = queue.pop() {
+                        commands.entity(parent).remove_children(&[oldest]);
+                        commands.entity(oldest).despawn();
+                        live_particles -= 1;
+                    }
+                }
+
+                // Particles are children of the transmitter, so offset from its depth.
+                let particle_z = config.z_layers.particles - config.z_layers.transmitters;
+                let full_material = if let Some(hues) = hues {
+                    emission_hues.get(&mut materials, &appearance, hues, tx.emissions)
+                } else if config.spectrum_colors {
+                    spectrum.get(&mut materials, &appearance, &config, tx.frequency)
+                } else {
+                    tx.particle_material.clone()
+                };
+                // Starts out faded so it doesn't flash at full opacity before it's first moved.
+                let fade_level = if config.age_fade {
+                    age_fade_level(0., f32::INFINITY, config.fade_in_secs, config.fade_out_secs)
+                } else {
+                    phosphor::FADE_LEVELS
+                };
+                let material = faded
+                    .get(&mut materials, &full_material, fade_level)
+                    .unwrap_or_else(|| full_material.clone());
+                let new_particle = commands
+                    .spawn((
+                        MaterialMesh2dBundle {
+                            mesh: appearance.mesh(config.particle_fill).into(),
+                            material,
+                            // An element's wave that leads starts out as far ahead as it's led.
+                            transform: Transform::from_translation(
+                                (origin + direction * speed * lead).extend(particle_z),
+                            )
+                            .with_scale(config.particle_size()),
+                            ..default()
+                        },
+                        SignalParticle {
+                            amplitude: output * gain,
+                            speed,
+                            frequency: tx.frequency,
+                            spawn_index: *next_spawn_index,
+                            origin,
+                            lane,
+                            direction,
+                            displacement: 0.,
+                            dispersion: tx.dispersion,
+                            delay: tx.delay.duration().as_secs_f32() - lead,
+                            envelope: output * gain,
+                            shape: tx.shape.clone(),
+                            material: full_material,
+                            fade_level,
+                            sampled: false,
+                            hue: hues.map(|hues| (hues, tx.emissions)),
+                        },
+                        PIXEL_PERFECT_LAYERS,
+                    ))
+                    .id();
+                live_particles += 1;
+
+                commands.entity(tx_entity).add_child(new_particle);
+            }
+            *next_spawn_index += 1;
+        }
+        tx.emissions = tx.emissions.wrapping_add(1);
+    }
+}
+
+/// Offset from the spawn point of each element of a transmitter emitting at `frequency`, and
+/// seconds its wave leads the element lagging most: a single element at the spawn point, or
+/// `array_elements` in a line across the propagation axis, `array_spacing` apart, each lagging
+/// `array_phase_step` degrees of the period behind the one before.
+fn emission_elements(config: &SimConfig, frequency: f32) -> Vec<(Vec2, f32)> {
+    let elements = config.array_elements();
+    let across = config.propagation_axis().perp() * config.array_spacing;
+    let middle = (elements - 1) as f32 / 2.;
+    let lag = if frequency > 0. {
+        config.array_phase_step() / 360. / frequency
+    } else {
+        0.
+    };
+    // The first element lags most if the lag steps are negative, otherwise the last.
+    let most_lag = ((elements - 1) as f32 * lag).max(0.);
+    (0..elements)
+        .map(|i| {
+            let offset = across * (i as f32 - middle);
+            (offset, most_lag - i as f32 * lag)
+        })
+        .collect()
+}
+
+/// Lanes a transmitter emits a particle in along each ray: a plane wave's column, spaced
+/// `plane_wave_spacing` apart across `world_bounds.height`, or just the one through the
+/// transmitter.
+fn emission_lanes(config: &SimConfig) -> Vec<i32> {
+    if config.emission_mode() != EmissionMode::PlaneWave {
+        return vec![0];
+    }
+    let spacing = config.plane_wave_spacing.max(1.);
+    let reach = (config.world_bounds.height / 2. / spacing) as i32;
+    (-reach..=reach).collect()
+}
+
+/// Direction, signed speed and amplitude gain of each particle a transmitter emits at once.
+fn emission_rays(config: &SimConfig, tx: &Transmitter) -> Vec<(Vec2, f32, f32)> {
+    let axis = config.propagation_axis();
+    match config.emission_mode() {
+        EmissionMode::Stream if tx.bidirectional => vec![
+            (axis, config.wave_speed, 1.),
+            // Negating both the speed and the distance travelled leaves the phase unchanged, so
+            // the two streams are in step at the source.
+            (axis, -config.wave_speed, 1.),
+        ],
+        EmissionMode::Stream | EmissionMode::PlaneWave => vec![(axis, config.wave_speed, 1.)],
+        EmissionMode::PointSource => {
+            let rays = config.point_source_rays.max(1);
+            let omnidirectional = tx.beam_width >= TAU;
+            let half_width = tx.beam_width.clamp(0., TAU) / 2.;
+            (0..rays)
+                .map(|i| {
+                    let offset = if omnidirectional {
+                        i as f32 / rays as f32 * TAU
+                    } else if rays == 1 {
+                        0.
+                    } else {
+                        (i as f32 / (rays - 1) as f32 * 2. - 1.) * half_width
+                    };
+                    (
+                        Vec2::from_angle(tx.beam_direction + offset).rotate(axis),
+                        config.wave_speed.abs(),
+                        beam_gain(offset, half_width, omnidirectional),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// Amplitude gain for a ray `offset` radians from the beam center, tapering from 1 at the center
+/// to 0 at the edge of the beam.
+fn beam_gain(offset: f32, half_width: f32, omnidirectional: bool) -> f32 {
+    if omnidirectional {
+        return 1.;
+    }
+    if half_width <= 0. {
+        return if offset == 0. { 1. } else { 0. };
+    }
+    (offset / half_width * PI / 2.).cos().max(0.)
+}
+
+/// Outlines the beam of each directional point-source transmitter.
+fn draw_beam_cones(
+    config: Res<SimConfig>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if config.emission_mode() != EmissionMode::PointSource {
+        return;
+    }
+    for (tx, transform) in tx_query.iter() {
+        if tx.beam_width >= TAU {
+            continue;
+        }
+        let origin = transform.translation().xy() + tx.spawn_point;
+        for edge in [-tx.beam_width / 2., tx.beam_width / 2.] {
+            let direction =
+                Vec2::from_angle(tx.beam_direction + edge).rotate(config.propagation_axis());
+            let end = origin + direction * BEAM_CONE_LENGTH;
+            gizmos.line_2d(origin, end, BEAM_CONE_COLOR);
+        }
+    }
+}
+
+/// Draws the Mach cone behind each transmitter moving faster than the waves: the two lines from
+/// the transmitter tangent to every wavefront it has emitted since it started, where they pile
+/// up into a shock. Wavefronts emitted `τ` seconds ago are circles of radius `cτ` centred `vτ`
+/// behind it, so the tangents meet them `τ sqrt(v² - c²)` away at the Mach angle. The cone is
+/// exact for point-source waves; a stream only shows the part of it along its line.
+fn draw_mach_cones(
+    config: Res<SimConfig>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<(&GlobalTransform, &Mover), With<TransmitterBody>>,
+    time: Res<Time>,
+    mut hud: ResMut<Hud>,
+    mut gizmos: Gizmos,
+) {
+    let now = time.elapsed().as_millis() as f32 / 1000.;
+    let mut fastest: Option<f32> = None;
+    for (tx, children) in tx_query.iter() {
+        let Some(first_emission) = tx.first_emission else {
+            continue;
+        };
+        for (transform, mover) in body_query.iter_many(children) {
+            let direction = mover.0.direction();
+            let Some(angle) = physics::mach_angle(direction * tx.speed, config.wave_speed) else {
+                continue;
+            };
+            fastest = Some(fastest.map_or(tx.speed, |speed| speed.max(tx.speed)));
+
+            let apex = transform.translation().xy();
+            let behind = Vec2::X * -direction;
+            let age = now - first_emission;
+            let length = age * (tx.speed.powi(2) - config.wave_speed.powi(2)).sqrt();
+            for side in [-angle, angle] {
+                let end = apex + Vec2::from_angle(side).rotate(behind) * length;
+                gizmos.line_2d(apex, end, MACH_CONE_COLOR);
+            }
+        }
+    }
+
+    match fastest {
+        Some(speed) => {
+            let mach = speed / config.wave_speed.abs();
+            let angle = physics::mach_angle(speed, config.wave_speed).unwrap_or_default();
+            hud.set(
+                "mach_cone",
+                format!(
+                    "Boom! A transmitter is supersonic at Mach {mach:.2}, trailing a Mach cone \
+                     with a {:.1}° half-angle",
+                    angle.to_degrees()
+                ),
+            );
+        }
+        None => hud.remove("mach_cone"),
+    }
+}
+
+/// Draws an arrow above each moving receiver showing its velocity, so the motion causing the
+/// shift sits next to the plot showing it.
+fn draw_speedometers(rx_query: Query<(&GlobalTransform, &Mover, &Receiver)>, mut gizmos: Gizmos) {
+    for (transform, mover, rx) in rx_query.iter() {
+        let velocity = mover.0.direction() * rx.speed;
+        if velocity == 0. {
+            continue;
+        }
+        let start = transform.translation().xy()
+            + Vec2::Y * (rx.orientation.size().y / 2. + SPEEDOMETER_OFFSET);
+        let end = start + Vec2::X * velocity * SPEEDOMETER_SCALE;
+        gizmos.arrow_2d(start, end, SPEEDOMETER_COLOR);
+    }
+}
+
+/// While the velocity key is held, draws an arrow from each visible particle: its speed along the
+/// line of travel plus the transverse velocity of the medium where it is. Particles ride the
+/// phase, so the part across the line is the medium's motion rather than the particle's own.
+fn draw_velocity_vectors(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    particle_query: Query<(
+        &Transform,
+        &GlobalTransform,
+        &SignalParticle,
+        &ViewVisibility,
+    )>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+) {
+    if !input.pressed(bindings.velocity_vectors) {
+        return;
+    }
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    for (transform, global_transform, particle, visibility) in particle_query.iter() {
+        if !visibility.get() {
+            continue;
+        }
+        let x = (transform.translation.xy() - particle.origin).dot(particle.direction);
+        let dy_dt = physics::wave_velocity(
+            particle.amplitude,
+            particle.frequency,
+            particle.speed,
+            particle.dispersion,
+            x,
+            t - particle.delay,
+        );
+        let velocity = particle.direction * particle.speed + particle.direction.perp() * dy_dt;
+        let start = global_transform.translation().xy();
+        gizmos.arrow_2d(
+            start,
+            start + velocity * VELOCITY_VECTOR_SCALE,
+            VELOCITY_VECTOR_COLOR,
+        );
+    }
+}
+
+fn toggle_ghost_preview(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.ghost_preview) {
+        config.ghost_preview = !config.ghost_preview;
+        hud.set("ghost_preview", ghost_preview_label(&config, &bindings));
+    }
+}
+
+fn ghost_preview_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.ghost_preview { "hide" } else { "show" };
+    format!(
+        "Press {} to {action} where the wave will be in {}s",
+        key_name(bindings.ghost_preview),
+        config.ghost_lookahead_secs
+    )
+}
+
+/// Draws faint circles where particles will be `ghost_lookahead_secs` from now, following the
+/// same wave equation as [`propagate_particle`], and a line across each wavefront's future
+/// position so the time it takes to reach a receiver can be read off. Only a preview: the
+/// particles themselves are left alone, and receivers will have caught some of them by then.
+fn draw_ghost_preview(
+    config: Res<SimConfig>,
+    particle_query: Query<(&Transform, &GlobalTransform, &Parent, &SignalParticle)>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+) {
+    if !config.ghost_preview {
+        return;
+    }
+    let lookahead = config.ghost_lookahead_secs.max(0.);
+    let t = time.elapsed().as_millis() as f32 / 1000. + lookahead;
+    // The furthest travelled particle along each ray of each transmitter, i.e. its wavefront.
+    let mut fronts: HashMap<(Entity, [u32; 2]), (u64, Vec2, Vec2, f32)> = HashMap::new();
+    for (transform, global_transform, parent, particle) in particle_query.iter() {
+        // Particles are positioned relative to their transmitter, which doesn't move.
+        let tx_position = global_transform.translation().xy() - transform.translation.xy();
+        let x = (transform.translation.xy() - particle.origin).dot(particle.direction)
+            + particle.speed * lookahead;
+        let envelope = physics::envelope(
+            particle.amplitude,
+            particle.frequency,
+            particle.speed,
+            particle.dispersion,
+            x,
+            t - particle.delay,
+        );
+        let y = physics::shaped_displacement(
+            envelope,
+            particle.frequency,
+            particle.speed,
+            &particle.shape,
+            x,
+            t - particle.delay,
+        );
+        let center = tx_position + particle.origin + particle.direction * x;
+        if particle.spawn_index % GHOST_STRIDE == 0 {
+            gizmos.circle_2d(
+                center + particle.direction.perp() * y,
+                config.particle_radius,
+                GHOST_COLOR,
+            );
+        }
+
+        let ray = (
+            parent.get(),
+            [
+                particle.direction.x.to_bits(),
+                particle.direction.y.to_bits(),
+            ],
+        );
+        let front = (
+            particle.spawn_index,
+            center,
+            particle.direction,
+            particle.amplitude,
+        );
+        fronts
+            .entry(ray)
+            .and_modify(|oldest| {
+                if front.0 < oldest.0 {
+                    *oldest = front;
+                }
+            })
+            .or_insert(front);
+    }
+    for (_, center, direction, amplitude) in fronts.into_values() {
+        let across = direction.perp() * amplitude;
+        gizmos.line_2d(center - across, center + across, GHOST_COLOR);
+    }
+}
+
+/// A received particle shrinking away before it's despawned.
+#[derive(Component)]
+struct Despawning(Timer);
+
+fn animate_despawning(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Despawning)>,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut despawning) in query.iter_mut() {
+        if despawning.0.tick(time.delta()).finished() {
+            commands
+                .entity(entity)
+                .remove::<Despawning>()
+                .insert(rewind::Retired::bundle());
+        } else {
+            transform.scale = config.particle_size() * despawning.0.fraction_remaining();
+        }
+    }
+}
+
+/// A particle that reached a receiver this frame.
+struct RxHit {
+    transmitter: Entity,
+    spawn_index: u64,
+    value: f32,
+    /// When it crossed into the receiver's catch area, in seconds since the app started. Worked
+    /// out from how far inside it got, so it can be earlier than this frame.
+    crossed_at: f32,
+    /// How fast it was moving across its path, as a fraction of the wave's peak speed across it,
+    /// positive in the direction of positive displacement.
+    velocity: f32,
+    /// The particle's [`SignalParticle::hue`].
+    hue: Option<(u32, u32)>,
+    /// Phase it arrived with against the source's, from how long it was travelling.
+    phase: f32,
+}
+
+/// Pixels the plot advances before each of `hits`, in order, and after the last, sharing out the
+/// distance swept between `prev_collision_time` and `t`. Evenly spaced by default; with
+/// [`OverlapHandling::CrossingTime`] each hit goes where the plot was when it crossed.
+fn plot_gaps(
+    hits: &[RxHit],
+    handling: OverlapHandling,
+    prev_collision_time: f32,
+    t: f32,
+) -> (Vec<f32>, f32) {
+    let swept = (t - prev_collision_time).max(0.);
+    let offsets: Vec<f32> = match handling {
+        OverlapHandling::Even => {
+            let step = swept / hits.len() as f32;
+            (0..hits.len()).map(|i| i as f32 * step).collect()
+        }
+        // Clamped so a particle that seems to have crossed before the last frame, or a rounding
+        // error past this one, can't move the plot backwards or past the time swept.
+        OverlapHandling::CrossingTime => hits
+            .iter()
+            .map(|hit| (hit.crossed_at - prev_collision_time).clamp(0., swept))
+            .collect(),
+    };
+    let mut previous = 0.;
+    let gaps = offsets
+        .iter()
+        .map(|&offset| {
+            let gap = (offset - previous).max(0.);
+            previous = previous.max(offset);
+            gap * RECEIVER_DELTA_X_PER_SECOND
+        })
+        .collect();
+    let trailing = match handling {
+        OverlapHandling::Even => swept / hits.len() as f32,
+        OverlapHandling::CrossingTime => swept - previous,
+    };
+    (gaps, trailing * RECEIVER_DELTA_X_PER_SECOND)
+}
+
+/// Distance from `point` to the area a receiver at `rx_position` picks particles up in: level
+/// with the receiver and anywhere behind the edge it faces, e.g. left of its right edge. Zero
+/// inside it. The area is laid out along the propagation `axis`, so with the default +x "level"
+/// means at the same height, and otherwise its edge is a plane across the axis that the waves
+/// meet head on.
+fn distance_to_catch_area(
+    point: Vec2,
+    rx_position: Vec2,
+    orientation: ReceiverOrientation,
+    face: ReceiverFace,
+    axis: Vec2,
+) -> f32 {
+    let half_size = orientation.size() / 2.;
+    // Rotating by the axis's conjugate undoes its rotation.
+    let local = Vec2::new(axis.x, -axis.y).rotate(point - rx_position);
+    let edge = half_size.x * face.sign();
+    let dx = ((local.x - edge) * face.sign()).max(0.);
+    let dy = (local.y.abs() - half_size.y).max(0.);
+    Vec2::new(dx, dy).length()
+}
+
+/// Seconds since a particle now at `point`, moving at `velocity`, crossed the edge of the catch
+/// area of [`distance_to_catch_area`] that faces its transmitter. Zero if it hasn't crossed it or
+/// isn't moving into the area.
+fn time_since_crossing(
+    point: Vec2,
+    velocity: Vec2,
+    rx_position: Vec2,
+    orientation: ReceiverOrientation,
+    face: ReceiverFace,
+    axis: Vec2,
+) -> f32 {
+    let undo_axis = Vec2::new(axis.x, -axis.y);
+    let local = undo_axis.rotate(point - rx_position);
+    let edge = orientation.size().x / 2. * face.sign();
+    let depth = (edge - local.x) * face.sign();
+    let closing_speed = -undo_axis.rotate(velocity).x * face.sign();
+    if depth > 0. && closing_speed > 0. {
+        depth / closing_speed
+    } else {
+        0.
+    }
+}
+
+fn handle_rx_collision(
+    config: Res<SimConfig>,
+    marker: Res<PlotMarkerMesh>,
+    mut commands: Commands,
+    mut sig_query: Query<
+        (
+            &Parent,
+            Entity,
+            &Transform,
+            &GlobalTransform,
+            &mut SignalParticle,
+        ),
+        Without<Receiver>,
+    >,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut velocity_materials: ResMut<palette::VelocityMaterials>,
+    mut emission_hues: ResMut<palette::EmissionHueMaterials>,
+    mut rx_query: Query<(Entity, &mut Transform, &mut Receiver, Option<&Children>)>,
+    plot_query: Query<(), With<PlotPoint>>,
+    time: Res<Time>,
+) {
+    #[cfg(feature = "debug_profiling")]
+    let _timer = profiling::time("handle_rx_collision");
+    let mut hits: HashMap<Entity, Vec<RxHit>> = HashMap::new();
+    let axis = config.propagation_axis();
+    let t = time.elapsed().as_millis() as f32 / 1000.;
+    for (sig_parent, sig_entity, sig_transform, sig_global_transform, mut signal_particle) in
+        sig_query.iter_mut()
+    {
+        let particle_pos = sig_global_transform.translation().xy();
+        for (rx_entity, rx_transform, rx, _) in rx_query.iter() {
+            let rx_position = rx_transform.translation.xy();
+            let distance =
+                distance_to_catch_area(particle_pos, rx_position, rx.orientation, rx.face, axis);
+            let caught = distance == 0.;
+            // Within the capture band a particle is sampled once, the first frame it's in there,
+            // then carries on until it's caught or passes by.
+            if !caught && (signal_particle.sampled || distance > config.capture_band) {
+                continue;
+            }
+            // In a wrap-around world particles pass through, so they keep going round.
+            if caught && !config.wrap_world {
+                if config.despawn_animation_secs > 0. {
+                    // No longer a SignalParticle, so it stops moving and can't be received again.
+                    commands
+                        .entity(sig_entity)
+                        .remove::<SignalParticle>()
+                        .insert(Despawning(Timer::from_seconds(
+                            config.despawn_animation_secs,
+                            TimerMode::Once,
+                        )));
+                } else {
+                    // No longer a SignalParticle either, but kept for the rewind.
+                    commands
+                        .entity(sig_entity)
+                        .remove::<SignalParticle>()
+                        .insert(rewind::Retired::bundle());
+                }
+            }
+            if !signal_particle.sampled {
+                // Particles sampled from the capture band haven't crossed yet, so count as now.
+                let crossed_at = t - time_since_crossing(
+                    particle_pos,
+                    signal_particle.direction * signal_particle.speed,
+                    rx_position,
+                    rx.orientation,
+                    rx.face,
+                    axis,
+                );
+                let x = (sig_transform.translation.xy() - signal_particle.origin)
+                    .dot(signal_particle.direction);
+                let velocity = physics::wave_velocity(
+                    signal_particle.amplitude,
+                    signal_particle.frequency,
+                    signal_particle.speed,
+                    signal_particle.dispersion,
+                    x,
+                    t - signal_particle.delay,
+                );
+                let peak_velocity = TAU * signal_particle.frequency * signal_particle.amplitude;
+                // It's been travelling for `x / speed` by now, which is `t - crossed_at` longer
+                // than it had when it crossed.
+                let travel_secs =
+                    x / signal_particle.speed.abs().max(f32::EPSILON) - (t - crossed_at);
+                hits.entry(rx_entity).or_default().push(RxHit {
+                    crossed_at,
+                    velocity: if peak_velocity > 0. {
+                        velocity / peak_velocity
+                    } else {
+                        0.
+                    },
+                    transmitter: sig_parent.get(),
+                    spawn_index: signal_particle.spawn_index,
+                    phase: physics::relative_phase(signal_particle.frequency, travel_secs),
+                    hue: signal_particle.hue,
+                    value: physics::clip(
+                        signal_particle.displacement,
+                        config.clip_level,
+                        config.clip_shape,
+                    ),
+                });
+                signal_particle.sampled = !caught || config.wrap_world;
+            }
+        }
+    }
+
+    for (rx_entity, mut rx_hits) in hits {
+        let Ok((_, mut rx_transform, mut rx, children)) = rx_query.get_mut(rx_entity) else {
+            continue;
+        };
+        rx.first_collision.get_or_insert(t);
+        // A plane wave's column reaches a receiver all at once and counts as one sample.
+        let mut emissions = HashSet::new();
+        rx_hits.retain(|hit| emissions.insert(hit.spawn_index));
+        match config.overlap_handling {
+            // Older particles were emitted first so they arrived first.
+            OverlapHandling::Even => rx_hits.sort_unstable_by_key(|hit| hit.spawn_index),
+            OverlapHandling::CrossingTime => rx_hits.sort_unstable_by(|a, b| {
+                a.crossed_at
+                    .total_cmp(&b.crossed_at)
+                    .then(a.spawn_index.cmp(&b.spawn_index))
+            }),
+        }
+
+        if config.dead_time_secs > 0. {
+            // A recovering detector doesn't see the trigger either, so this goes first.
+            rx_hits.retain(|hit| {
+                let live = rx.dead_until.is_none_or(|until| hit.crossed_at >= until);
+                if live {
+                    rx.dead_until = Some(hit.crossed_at + config.dead_time_secs);
+                }
+                live
+            });
+            if rx_hits.is_empty() {
+                continue;
+            }
+        }
+
+        if let (Some(trigger), false) = (rx.trigger, rx.triggered) {
+            // Drop everything that arrived before the trigger fired. The particle that fired it
+            // is the first one plotted.
+            let fired_at = rx_hits.iter().position(|hit| {
+                let fired = rx
+                    .last_value
+                    .is_some_and(|prev| trigger.fires(prev, hit.value));
+                rx.last_value = Some(hit.value);
+                fired
+            });
+            let Some(fired_at) = fired_at else {
+                continue;
+            };
+            rx.triggered = true;
+            rx_hits.drain(..fired_at);
+        }
+
+        let decimation = config.plot_decimation.max(1);
+        rx_hits.retain(|_| {
+            let keep = rx.received % decimation == 0;
+            rx.received = rx.received.wrapping_add(1);
+            keep
+        });
+        if rx_hits.is_empty() {
+            // Leave the previous collision time alone so the next sample plotted covers the
+            // distance swept while these were skipped.
+            continue;
+        }
+
+        // Everything that arrived since the last collision is spread over the distance swept in
+        // that time, rather than stacked at one point when a slow frame lets several particles
+        // through at once.
+        let prev_collision_time = rx.prev_collision_time.unwrap_or(t);
+        let (gaps, trailing_gap) =
+            plot_gaps(&rx_hits, config.overlap_handling, prev_collision_time, t);
+        let mut plotted_all = true;
+        for (hit, gap) in rx_hits.into_iter().zip(gaps) {
+            rx.current_draw_position += gap;
+            if rx.current_draw_position > 2. * RECEIVER_WIDTH {
+                let SweepMode::Continuous { return_to_start } = rx.sweep else {
+                    // If we have already plotted over the entire width of the receiver then just
+                    // don't do anything, until a ping-pong receiver turns round and starts over
+                    if !rx.movement.oscillates() {
+                        commands.entity(rx_entity).remove::<Mover>();
+                    }
+                    plotted_all = false;
+                    break;
+                };
+                clear_plot(
+                    &mut commands,
+                    &config,
+                    rx_entity,
+                    &mut rx,
+                    children,
+                    &plot_query,
+                );
+                if return_to_start {
+                    rx_transform.translation.x = rx.start_x;
+                    commands.entity(rx_entity).insert(Mover(rx.movement));
+                } else if !rx.movement.oscillates() {
+                    commands.entity(rx_entity).remove::<Mover>();
+                }
+                if rx.trigger.is_some() {
+                    // The rest arrived before the trigger could fire again.
+                    rx.triggered = false;
+                    plotted_all = false;
+                    break;
+                }
+            }
+
+            let Ok((tx, tx_transform)) = tx_query.get(hit.transmitter) else {
+                continue;
+            };
+            let spawn_point = tx_transform.translation().xy() + tx.spawn_point;
+            let sample = RxSample {
+                value: hit.value,
+                plot_x: RECEIVER_WIDTH - rx.current_draw_position,
+                distance: Some(rx_transform.translation.xy().distance(spawn_point)),
+                phase: Some(hit.phase),
+            };
+            let plot_material = if config.velocity_colored_plot {
+                velocity_materials.get(&mut materials, hit.velocity)
+            } else if let Some((hues, emission)) = hit.hue {
+                emission_hues.plot_point(&mut materials, hues, emission)
+            } else {
+                tx.plot_material.clone()
+            };
+            let plot_point = spawn_plot_point(
+                &mut commands,
+                marker.0.clone(),
+                plot_material.clone(),
+                &rx,
+                &sample,
+                rx.next_sample_index(),
+                config
+                    .z_layers
+                    .relative_to_receiver(config.z_layers.plot_points),
+            );
+            commands.entity(rx_entity).add_child(plot_point);
+            if config.phosphor_decay_secs.is_some() {
                 commands
-                    .entity(sig_parent.get())
-                    .remove_children(&[sig_entity]);
-                commands.entity(sig_entity).despawn();
+                    .entity(plot_point)
+                    .insert(phosphor::Phosphor::new(t, plot_material));
+            }
+            rx.push_sample(sample, config.receiver_buffer_size);
+            rx.sample_times.push_back(t);
+            rx.smoothed_frequency = stats::smooth_frequency(
+                rx.smoothed_frequency,
+                stats::measured_frequency(&rx, config.crossing_hysteresis),
+                config.frequency_smoothing,
+            );
+        }
+        if plotted_all {
+            rx.current_draw_position += trailing_gap;
+        }
+
+        rx.prev_collision_time = Some(t);
+        while rx
+            .sample_times
+            .front()
+            .is_some_and(|&time| time < t - SAMPLE_RATE_WINDOW_SECS)
+        {
+            rx.sample_times.pop_front();
+        }
+    }
+}
+
+/// Empties `rx`'s plot so it starts again from the beginning.
+fn clear_plot(
+    commands: &mut Commands,
+    config: &SimConfig,
+    rx_entity: Entity,
+    rx: &mut Receiver,
+    children: Option<&Children>,
+    plot_query: &Query<(), With<PlotPoint>>,
+) {
+    let points: Vec<Entity> = children
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&child| plot_query.contains(child))
+        .collect();
+    if config.phosphor_decay_secs.is_some() {
+        // Left to fade out, but no longer part of the plot the samples describe.
+        for point in points {
+            commands.entity(point).remove::<PlotPoint>();
+        }
+    } else {
+        // Kept for the rewind, as part of the plot it was.
+        for point in points {
+            commands
+                .entity(point)
+                .remove::<PlotPoint>()
+                .insert(rewind::Retired::bundle());
+        }
+    }
+    rx.samples.clear();
+    rx.dropped_samples = 0;
+    rx.current_draw_position = 0.;
+    rx.smoothed_frequency = None;
+}
+
+/// Spawns the point for `sample`, the `index`th of `rx`'s plot, at depth `z` relative to the
+/// receiver. It still needs adding as a child of the receiver.
+fn spawn_plot_point(
+    commands: &mut Commands,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+    rx: &Receiver,
+    sample: &RxSample,
+    index: usize,
+    z: f32,
+) -> Entity {
+    let visibility = if rx.view == ReceiverView::Raw {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: mesh.into(),
+                material,
+                transform: Transform::from_xyz(sample.plot_x, rx.plot_y(sample.value), z)
+                    .with_scale(RECEIVER_PLOT_SIZE),
+                visibility,
+                ..default()
+            },
+            PlotPoint { index },
+            PLOT_POINT_LAYERS,
+        ))
+        .id()
+}
+
+fn move_rx(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    mut rx_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Mover,
+        &mut Receiver,
+        Option<&Children>,
+    )>,
+    plot_query: Query<(), With<PlotPoint>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (rx_entity, mut transform, mut mover, mut rx, children) in rx_query.iter_mut() {
+        let velocity = mover.0.direction() * rx.speed;
+        if !config.drift_free_motion {
+            transform.translation.x += velocity * time.delta_seconds();
+        } else {
+            // Start again from wherever it is if something else moved it or its velocity
+            // changed, e.g. a drag, a rewind, a sweep starting over or a ping-pong turning round.
+            let x = transform.translation.x;
+            let anchor = match rx.anchor {
+                Some(anchor) if anchor.placed_x == x && anchor.velocity == velocity => anchor,
+                _ => MotionAnchor {
+                    x,
+                    since: now - time.delta_seconds_f64(),
+                    velocity,
+                    placed_x: x,
+                },
+            };
+            let new_x = anchor.position(now);
+            transform.translation.x = new_x;
+            rx.anchor = Some(MotionAnchor {
+                placed_x: new_x,
+                ..anchor
+            });
+        }
+
+        if let Some(bound) = mover.0.bounce(transform.translation.x) {
+            transform.translation.x = bound;
+            // Each leg gets a fresh plot, so the traces alternate between the two shifts.
+            clear_plot(
+                &mut commands,
+                &config,
+                rx_entity,
+                &mut rx,
+                children,
+                &plot_query,
+            );
+            rx.triggered = false;
+            // A single capture cut short by the turn waits for the trigger again.
+            if let Some(capture) = rx.single_capture.as_mut() {
+                if capture.stage == single_capture::CaptureStage::Capturing {
+                    capture.stage = single_capture::CaptureStage::Armed;
+                }
+            }
+        }
+    }
+}
+
+/// Moves transmitter bodies and emits from wherever they now are.
+fn move_tx(
+    mut tx_query: Query<(&mut Transmitter, &Children)>,
+    mut body_query: Query<(&mut Transform, Option<&Mover>), With<TransmitterBody>>,
+    time: Res<Time>,
+) {
+    for (mut tx, children) in tx_query.iter_mut() {
+        let mut bodies = body_query.iter_many_mut(children);
+        while let Some((mut transform, mover)) = bodies.fetch_next() {
+            if let Some(mover) = mover {
+                transform.translation.x += mover.0.direction() * tx.speed * time.delta_seconds();
+            }
+            tx.spawn_point = transform.translation.xy();
+        }
+    }
+}
+
+/// Frequency `rx` should observe from `tx` according to the configured Doppler formula.
+fn expected_frequency(
+    config: &SimConfig,
+    tx: &Transmitter,
+    tx_mover: Option<&Mover>,
+    rx: &Receiver,
+    rx_mover: Option<&Mover>,
+) -> f32 {
+    // Moving in the direction the waves travel means moving towards the receivers, and moving
+    // against it means moving towards the transmitters. Receivers facing left hear the waves a
+    // bidirectional transmitter sends the other way. Everything moves along x, so with the waves at
+    // an angle only the part of each velocity along the axis, cos θ of it, shifts the frequency.
+    let wave_direction = physics::direction_of_travel(config.wave_speed)
+        .rotate(config.propagation_axis())
+        .x
+        * rx.face.sign();
+    let source_direction = tx_mover.map_or(0., |mover| mover.0.direction());
+    let observer_direction = rx_mover.map_or(0., |mover| mover.0.direction());
+    physics::doppler_shift(
+        config.doppler_formula,
+        tx.frequency,
+        config.wave_speed,
+        source_direction * tx.speed * wave_direction,
+        -observer_direction * rx.speed * wave_direction,
+    )
+}
+
+fn period_markers_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.period_markers {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press {} to {action} source and observed periods on the plots",
+        key_name(bindings.period_markers)
+    )
+}
+
+fn toggle_period_markers(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.period_markers) {
+        config.period_markers = !config.period_markers;
+        hud.set("period_markers", period_markers_label(&config, &bindings));
+    }
+}
+
+/// Draws ticks along each receiver's plot from its first sample onwards: one source period apart
+/// in the top half and one expected observed period apart in the bottom half. Where the bottom
+/// ticks are closer together the receiver hears the waves compressed, and where they're further
+/// apart, stretched.
+fn draw_period_markers(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &GlobalTransform, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut gizmos: Gizmos,
+) {
+    if !config.period_markers {
+        return;
+    }
+    let half_height = RECEIVER_SIZE.y / 2.;
+    for (rx, rx_transform, rx_mover) in rx_query.iter() {
+        let (Some(first), Some(last)) = (rx.samples.front(), rx.samples.back()) else {
+            continue;
+        };
+        let Some((tx, children)) = tx_query.iter().find(|(tx, _)| tx.index == rx.index) else {
+            continue;
+        };
+        let tx_mover = body_query.iter_many(children).next();
+        let observed = expected_frequency(&config, tx, tx_mover, rx, rx_mover);
+
+        let to_world = |x: f32, y: f32| rx_transform.transform_point(Vec3::new(x, y, 0.)).xy();
+        for (frequency, direction, color) in [
+            (tx.frequency, 1., SOURCE_PERIOD_COLOR),
+            (observed, -1., OBSERVED_PERIOD_COLOR),
+        ] {
+            let Some(period) = physics::period(frequency) else {
+                continue;
+            };
+            let spacing = period * RECEIVER_DELTA_X_PER_SECOND;
+            if spacing < MIN_PERIOD_TICK_SPACING {
+                continue;
+            }
+            // Plots grow towards -x as time goes on.
+            let mut x = first.plot_x;
+            while x >= last.plot_x {
+                gizmos.line_2d(to_world(x, 0.), to_world(x, direction * half_height), color);
+                x -= spacing;
+            }
+        }
+    }
+}
+
+/// Shows, for each receiver, how long the first particle should take to reach it in a straight
+/// line from its transmitter and how long it actually took, listed from the top of the screen
+/// down. The measured time is rounded up to whole frames, so a difference of about a frame is
+/// expected; anything more means particles are being emitted or moved at the wrong time. The
+/// prediction assumes neither end moves.
+fn show_time_of_flight(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &GlobalTransform)>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by(|(_, a), (_, b)| b.translation().y.total_cmp(&a.translation().y));
+
+    let times: Vec<_> = receivers
+        .iter()
+        .filter_map(|(rx, rx_transform)| {
+            let (tx, tx_transform) = tx_query.iter().find(|(tx, _)| tx.index == rx.index)?;
+            let spawn_point = tx_transform.translation().xy() + tx.spawn_point;
+            let distance = distance_to_catch_area(
+                spawn_point,
+                rx_transform.translation().xy(),
+                rx.orientation,
+                rx.face,
+                config.propagation_axis(),
+            );
+            let expected = physics::time_of_flight(distance, config.wave_speed)
+                .map_or("never".to_string(), |secs| format!("{secs:.2}s"));
+            let measured = match (tx.first_emission, rx.first_collision) {
+                (Some(emitted), Some(received)) => format!("{:.2}s", received - emitted),
+                _ => "-".to_string(),
+            };
+            Some(format!("{expected} / {measured}"))
+        })
+        .collect();
+
+    hud.set(
+        "time_of_flight",
+        format!("Time of flight (expected / measured): {}", times.join(", ")),
+    );
+}
+
+/// Shows the signal-to-noise ratio of what each receiver is plotting, listed from the top of the
+/// screen down, taking the signal to be the frequency it should observe. Only the last
+/// [`SAMPLE_RATE_WINDOW_SECS`] count, since the frequency changes when a receiver stops.
+fn show_snr(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &Transform, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by(|(_, a, _), (_, b, _)| b.translation.y.total_cmp(&a.translation.y));
+
+    let ratios: Vec<_> = receivers
+        .iter()
+        .filter_map(|(rx, _, mover)| {
+            let (tx, children) = tx_query.iter().find(|(tx, _)| tx.index == rx.index)?;
+            let tx_mover = body_query.iter_many(children).next();
+            let plotting = rx.current_draw_position <= 2. * RECEIVER_WIDTH;
+            let count = rx.sample_times.len().min(rx.samples.len());
+            let snr = plotting
+                .then(|| {
+                    let f = expected_frequency(&config, tx, tx_mover, rx, *mover);
+                    // Plots sweep at a constant rate, so position along them is time.
+                    let samples: Vec<(f32, f32)> = rx
+                        .samples
+                        .range(rx.samples.len() - count..)
+                        .map(|sample| (-sample.plot_x / RECEIVER_DELTA_X_PER_SECOND, sample.value))
+                        .collect();
+                    physics::estimate_snr(&samples, f)
+                })
+                .flatten();
+            Some(snr.map_or("-".to_string(), |snr| format!("{snr:.1} dB")))
+        })
+        .collect();
+
+    hud.set("snr", format!("Signal to noise: {}", ratios.join(", ")));
+}
+
+fn particle_fill_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    format!(
+        "Particles: {:?} (press {} to change)",
+        config.particle_fill,
+        key_name(bindings.particle_fill)
+    )
+}
+
+/// Switches particles between filled and outlined, including those already in flight.
+fn cycle_particle_fill(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    appearance: Res<ParticleAppearance>,
+    mut config: ResMut<SimConfig>,
+    mut particle_query: Query<&mut Mesh2dHandle, With<SignalParticle>>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.particle_fill) {
+        return;
+    }
+    config.particle_fill = config.particle_fill.next();
+    let mesh = appearance.mesh(config.particle_fill);
+    for mut handle in particle_query.iter_mut() {
+        handle.0 = mesh.clone();
+    }
+    hud.set("particle_fill", particle_fill_label(&config, &bindings));
+}
+
+/// Puts the particle sprite, if there is one, on the particle material of each new transmitter.
+fn texture_particle_materials(
+    appearance: Res<ParticleAppearance>,
+    tx_query: Query<&Transmitter, Added<Transmitter>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if appearance.texture.is_none() {
+        return;
+    }
+    for tx in tx_query.iter() {
+        if let Some(material) = materials.get_mut(&tx.particle_material) {
+            material.texture = appearance.texture.clone();
+        }
+    }
+}
+
+/// Shows the frequency each receiver should observe from its transmitter according to the
+/// configured Doppler formula, listed from the top of the screen down.
+fn show_expected_frequencies(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &Transform, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by(|(_, a, _), (_, b, _)| b.translation.y.total_cmp(&a.translation.y));
+
+    let frequencies: Vec<_> = receivers
+        .iter()
+        .filter_map(|(rx, _, mover)| {
+            let (tx, children) = tx_query.iter().find(|(tx, _)| tx.index == rx.index)?;
+            let tx_mover = body_query.iter_many(children).next();
+            let f = expected_frequency(&config, tx, tx_mover, rx, *mover);
+            Some(format!("{:.2} -> {f:.2} Hz", tx.frequency))
+        })
+        .collect();
+
+    hud.set(
+        "expected_frequencies",
+        format!(
+            "Expected frequencies ({:?}): {}",
+            config.doppler_formula,
+            frequencies.join(", ")
+        ),
+    );
+}
+
+/// Shows how fast the crests and the wave packets of each dispersive transmitter's waves travel,
+/// so they can be compared with what's on screen.
+fn show_group_speeds(config: Res<SimConfig>, tx_query: Query<&Transmitter>, mut hud: ResMut<Hud>) {
+    let mut transmitters: Vec<_> = tx_query
+        .iter()
+        .filter(|tx| tx.dispersion != Dispersion::None)
+        .collect();
+    if transmitters.is_empty() {
+        hud.remove("group_speed");
+        return;
+    }
+    transmitters.sort_by_key(|tx| tx.index);
+
+    let speeds: Vec<_> = transmitters
+        .iter()
+        .map(|tx| {
+            let group_speed = physics::group_speed(tx.frequency, config.wave_speed, tx.dispersion);
+            format!("{:.0} / {:.0}", config.wave_speed.abs(), group_speed.abs())
+        })
+        .collect();
+    hud.set(
+        "group_speed",
+        format!("Phase / group speed: {} px/s", speeds.join(", ")),
+    );
+}
+
+/// Shows how often the selected receiver is sampling the wave and whether that's fast enough for
+/// the frequency it should observe. Below the Nyquist rate the plotted trace shows the alias
+/// frequency instead.
+fn show_sample_rate(
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    rx_query: Query<(&Receiver, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut hud: ResMut<Hud>,
+) {
+    let Some((rx, rx_mover)) = rx_query.iter().find(|(rx, _)| rx.index == selected.0) else {
+        hud.remove("sample_rate");
+        return;
+    };
+    let sample_rate = rx.sample_rate();
+
+    let key = key_name(bindings.decimation);
+    let mut label = format!(
+        "Receiver {} samples at {sample_rate:.1} Hz, plotting 1 in {} particles (press {key} for \
+         fewer, shift+{} for more)",
+        rx.index + 1,
+        config.plot_decimation.max(1),
+        key.trim_matches('\'')
+    );
+    let tx = tx_query.iter().find(|(tx, _)| tx.index == rx.index);
+    if let (Some((tx, children)), true) = (tx, sample_rate > 0.) {
+        let tx_mover = body_query.iter_many(children).next();
+        let f = expected_frequency(&config, tx, tx_mover, rx, rx_mover);
+        if physics::is_aliased(f, sample_rate) {
+            label.push_str(&format!(
+                "\nAliasing: {f:.2} Hz is above the Nyquist limit of {:.2} Hz and plots as \
+                 {:.2} Hz",
+                sample_rate / 2.,
+                physics::alias_frequency(f, sample_rate)
+            ));
+        }
+    }
+    hud.set("sample_rate", label);
+}
+
+fn change_plot_decimation(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+) {
+    if !input.just_pressed(bindings.decimation) {
+        return;
+    }
+    let decimation = config.plot_decimation.max(1);
+    config.plot_decimation = if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        decimation / 2
+    } else {
+        decimation * 2
+    }
+    .clamp(1, MAX_PLOT_DECIMATION);
+}
+
+/// Removes the oldest receivers, and the transmitters paired with them, once there are more than
+/// `max_receivers`. Receivers are ordered by when they were spawned, and by index among those
+/// spawned together.
+fn limit_receivers(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    added_query: Query<(Entity, &Receiver), Added<Receiver>>,
+    rx_query: Query<&Receiver>,
+    tx_query: Query<(Entity, &Transmitter)>,
+    mut spawn_order: Local<VecDeque<Entity>>,
+) {
+    let mut added: Vec<_> = added_query.iter().collect();
+    added.sort_unstable_by_key(|(_, rx)| rx.index);
+    spawn_order.extend(added.into_iter().map(|(entity, _)| entity));
+    spawn_order.retain(|entity| rx_query.contains(*entity));
+
+    for oldest in evict_oldest(&mut spawn_order, config.max_receivers) {
+        let index = rx_query.get(oldest).unwrap().index;
+        for (tx_entity, _) in tx_query.iter().filter(|(_, tx)| tx.index == index) {
+            commands.entity(tx_entity).despawn_recursive();
+        }
+        commands.entity(oldest).despawn_recursive();
+    }
+}
+
+/// Takes the oldest entries off the front of `spawn_order` until there are at most `max` left,
+/// returning them oldest first. Nothing is taken without a limit.
+fn evict_oldest<T>(spawn_order: &mut VecDeque<T>, max: Option<usize>) -> Vec<T> {
+    let excess = max.map_or(0, |max| spawn_order.len().saturating_sub(max));
+    spawn_order.drain(..excess).collect()
+}
+
+/// Warns when any transmitter or receiver moves close to or faster than the configured speed
+/// limit, since the predicted frequencies stop making sense there.
+fn check_speed_limit(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Mover, &Receiver)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut hud: ResMut<Hud>,
+    mut last_check: Local<Option<SpeedCheck>>,
+) {
+    let limit = config.speed_limit.unwrap_or(config.wave_speed);
+    let speeds = rx_query
+        .iter()
+        .map(|(mover, rx)| mover.0.direction() * rx.speed)
+        .chain(tx_query.iter().flat_map(|(tx, children)| {
+            body_query
+                .iter_many(children)
+                .map(|mover| mover.0.direction() * tx.speed)
+        }));
+    let check = speeds
+        .map(|speed| physics::check_speed(speed, limit))
+        .max()
+        .unwrap_or(SpeedCheck::Safe);
+    if *last_check == Some(check) {
+        return;
+    }
+    *last_check = Some(check);
+
+    let suggestion = match config.doppler_formula {
+        DopplerFormula::Classical => "; consider setting doppler_formula: Relativistic",
+        DopplerFormula::Relativistic => "",
+    };
+    let message = match check {
+        SpeedCheck::Safe => {
+            hud.remove("speed_limit");
+            return;
+        }
+        SpeedCheck::Approaching => {
+            format!("Warning: a mover is approaching the speed limit of {limit:.0}{suggestion}")
+        }
+        SpeedCheck::Exceeded => format!(
+            "Warning: a mover is at or beyond the speed limit of {limit:.0}, expected frequencies \
+             are meaningless"
+        ),
+    };
+    warn!("{message}");
+    hud.set("speed_limit", message);
+}
+
+fn start_simulation(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+    config: &SimConfig,
+    compare: &CompareMode,
+    font: &Handle<Font>,
+) {
+    let specs = if cfg!(feature = "stress") {
+        stress::grid_simulations()
+    } else {
+        let mut specs = config.scenario.simulations();
+        if config.placement == Placement::Auto {
+            placement::auto_place(&mut specs, &config.world_bounds);
+        }
+        if compare.is_active() {
+            compare.split(specs)
+        } else {
+            specs
+        }
+    };
+    for (index, spec) in specs.iter().enumerate() {
+        create_simulation(
+            &mut meshes,
+            &mut materials,
+            &mut commands,
+            config,
+            index,
+            spec,
+            font,
+        );
+    }
+}
+
+/// Layout and motion of one transmitter/receiver pair.
+struct SimulationSpec {
+    /// Title drawn above the receiver.
+    label: Option<&'static str>,
+    rx_start_x: f32,
+    tx_x: f32,
+    y_pos: f32,
+    /// Frequency the transmitter emits at.
+    frequency: f32,
+    /// Speed of the receiver when it moves.
+    rx_speed: f32,
+    /// Speed of the transmitter when it moves.
+    tx_speed: f32,
+    /// Orientation of the receiver, unless a vertical orientation is configured.
+    rx_orientation: Option<ReceiverOrientation>,
+    /// Samples of one period of the signal the transmitter sends, unless a waveform is
+    /// configured. See [`Waveform::Samples`].
+    waveform: Option<&'static [f32]>,
+    /// Half of the canvas the pair is confined to in compare mode.
+    side: Option<usize>,
+    /// Emission pattern to use unless one other than continuous is configured, so a configured
+    /// burst's count and gap take precedence.
+    emission_pattern: Option<EmissionPattern>,
+    /// Dispersion of the transmitter's waves, unless dispersion is configured.
+    dispersion: Option<Dispersion>,
+    /// Whether the transmitter streams particles both ways, as well as when it's configured.
+    bidirectional: bool,
+    /// Movement of a second receiver the same distance away on the other side of the
+    /// transmitter, if there is one. It only hears a bidirectional transmitter.
+    mirrored_rx: Option<Movement>,
+    /// Whether its receivers are stationary on the configured `path_arc` around the transmitter,
+    /// all the same distance from it, instead of level with it at `rx_start_x`.
+    on_arc: bool,
+    rx_movement: Movement,
+    tx_movement: Movement,
+}
+
+impl Default for SimulationSpec {
+    fn default() -> Self {
+        Self {
+            label: None,
+            rx_start_x: -300.,
+            tx_x: 400.,
+            frequency: PARTICLE_FREQUENCY,
+            rx_speed: RECEIVER_SPEED,
+            tx_speed: TRANSMITTER_SPEED,
+            rx_orientation: None,
+            waveform: None,
+            side: None,
+            emission_pattern: None,
+            dispersion: None,
+            bidirectional: false,
+            mirrored_rx: None,
+            on_arc: false,
+            y_pos: 0.,
+            rx_movement: Movement::Stationary,
+            tx_movement: Movement::Stationary,
+        }
+    }
+}
+
+fn create_simulation(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    commands: &mut Commands,
+    config: &SimConfig,
+    index: usize,
+    spec: &SimulationSpec,
+    font: &Handle<Font>,
+) {
+    let colors = config.palette.colors();
+    let (particle_color, plot_color) = palette::source_colors(config, index);
+
+    let half_tri_size = TRANSMITTER_SIZE / 2.;
+    let pta = Vec2::new(half_tri_size, half_tri_size);
+    let ptb = Vec2::new(0., -half_tri_size);
+    let ptc = Vec2::new(-half_tri_size, half_tri_size);
+    let mut body = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(Triangle2d::new(pta, ptb, ptc)).into(),
+            material: materials.add(colors.transmitter),
+            ..default()
+        },
+        TransmitterBody,
+        Draggable {
+            half_size: Vec2::splat(half_tri_size),
+        },
+        PIXEL_PERFECT_LAYERS,
+    ));
+    if !matches!(spec.tx_movement, Movement::Stationary) {
+        body.insert(Mover(spec.tx_movement));
+    }
+    let body = body.id();
+
+    let mut transmitter = commands.spawn((
+        Transmitter {
+            spawn_rate: Timer::new(config.spawn_interval(), TimerMode::Repeating),
+            beam_width: config.beam_width.to_radians(),
+            beam_direction: config.beam_direction.to_radians(),
+            emitting: true,
+            pattern: match spec.emission_pattern {
+                Some(pattern) if config.emission_pattern == EmissionPattern::Continuous => pattern,
+                _ => config.emission_pattern,
+            },
+            delay: Timer::from_seconds(
+                config.start_delay_secs
+                    + index as f32 * config.group_stagger_secs
+                    + if config.sync_emission {
+                        config
+                            .emission_delays
+                            .get(index)
+                            .copied()
+                            .unwrap_or(0.)
+                            .max(0.)
+                    } else {
+                        0.
+                    },
+                TimerMode::Once,
+            ),
+            dispersion: match spec.dispersion {
+                Some(dispersion) if config.dispersion == Dispersion::None => dispersion,
+                _ => config.dispersion,
+            },
+            frequency: spec.frequency,
+            speed: spec.tx_speed,
+            bidirectional: spec.bidirectional || config.bidirectional,
+            gain: 1.,
+            target_gain: 1.,
+            shape: match (config.transmitter_waveforms.get(index), spec.waveform) {
+                (Some(waveform), _) => waveform.shape(),
+                (None, Some(samples)) if config.waveform == Waveform::Sine => {
+                    WaveShape::from_samples(samples).unwrap_or_default()
+                }
+                (None, _) => config.waveform.shape(),
+            },
+            index,
+            particle_material: materials.add(particle_color),
+            plot_material: materials.add(plot_color),
+            ..Default::default()
+        },
+        SpatialBundle::from_transform(Transform::from_xyz(
+            spec.tx_x,
+            spec.y_pos,
+            config.z_layers.transmitters,
+        )),
+        PIXEL_PERFECT_LAYERS,
+    ));
+    transmitter.add_child(body);
+    if let Some(side) = spec.side {
+        transmitter.insert(Side(side));
+    }
+
+    let rx_movement = match (spec.rx_movement, config.ping_pong_bounds) {
+        (Movement::PingPong { rightward, .. }, Some((left, right))) => Movement::PingPong {
+            left,
+            right,
+            rightward,
+        },
+        (movement, _) => movement,
+    };
+    let mut placements = if spec.on_arc {
+        let positions = config
+            .path_arc
+            .receiver_positions(Vec2::new(spec.tx_x, spec.y_pos));
+        positions
+            .into_iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let label = if i == 0 { spec.label } else { None };
+                (position, Movement::Stationary, label)
+            })
+            .collect()
+    } else {
+        vec![(
+            Vec2::new(spec.rx_start_x, spec.y_pos),
+            rx_movement,
+            spec.label,
+        )]
+    };
+    if let Some(movement) = spec.mirrored_rx {
+        placements.push((
+            Vec2::new(2. * spec.tx_x - spec.rx_start_x, spec.y_pos),
+            movement,
+            None,
+        ));
+    }
+    for (Vec2 { x, y }, movement, label) in placements {
+        // Particles reach it from the transmitter's side.
+        let face = if x > spec.tx_x {
+            ReceiverFace::Left
+        } else {
+            ReceiverFace::Right
+        };
+        let orientation = match spec.rx_orientation {
+            Some(orientation) if config.receiver_orientation == ReceiverOrientation::Horizontal => {
+                orientation
+            }
+            _ => config.receiver_orientation,
+        };
+        let mb = MaterialMesh2dBundle {
+            mesh: meshes.add(Rectangle::from_size(RECEIVER_SIZE)).into(),
+            material: materials.add(colors.receiver),
+            transform: Transform::from_xyz(x, y, config.z_layers.receivers)
+                .with_rotation(orientation.rotation()),
+            ..default()
+        };
+        let single_capture = config
+            .single_captures
+            .iter()
+            .find(|capture| capture.receiver == index);
+        let receiver = (
+            Receiver {
+                index,
+                view: config.receiver_view,
+                trigger: single_capture
+                    .and_then(|capture| capture.trigger)
+                    .or(config.trigger),
+                speed: spec.rx_speed,
+                // It only ever captures the one sweep.
+                sweep: if single_capture.is_some() {
+                    SweepMode::OneShot
+                } else {
+                    config.sweep_mode
+                },
+                single_capture: single_capture.map(|capture| single_capture::SingleCaptureState {
+                    name: capture.name.clone(),
+                    ..default()
+                }),
+                start_x: x,
+                movement,
+                orientation,
+                face,
+                ..default()
+            },
+            Draggable {
+                half_size: orientation.size() / 2.,
+            },
+        );
+        let mut receiver = match movement {
+            Movement::Stationary => commands.spawn((mb, receiver, PIXEL_PERFECT_LAYERS)),
+            movement => commands.spawn((mb, receiver, Mover(movement), PIXEL_PERFECT_LAYERS)),
+        };
+        if let Some(side) = spec.side {
+            receiver.insert(Side(side));
+        }
+        if let Some([r, g, b, a]) = config.plot_panel {
+            // Inset so the edge of the receiver still shows around it as an outline.
+            let panel = MaterialMesh2dBundle {
+                mesh: meshes
+                    .add(Rectangle::from_size(RECEIVER_SIZE - 2. * PLOT_PANEL_INSET))
+                    .into(),
+                material: materials.add(Color::rgba(r, g, b, a)),
+                transform: Transform::from_xyz(
+                    0.,
+                    0.,
+                    config
+                        .z_layers
+                        .relative_to_receiver(config.z_layers.plot_panel),
+                ),
+                ..default()
+            };
+            receiver.with_children(|parent| {
+                parent.spawn((panel, PIXEL_PERFECT_LAYERS));
+            });
+        }
+        let [r, g, b, a] = config.window_band_color;
+        let band = MaterialMesh2dBundle {
+            // Stretched along x to the window each frame.
+            mesh: meshes
+                .add(Rectangle::new(1., RECEIVER_SIZE.y - 2. * PLOT_PANEL_INSET))
+                .into(),
+            material: materials.add(Color::rgba(r, g, b, a)),
+            transform: Transform::from_xyz(
+                0.,
+                0.,
+                config
+                    .z_layers
+                    .relative_to_receiver(config.z_layers.window_band),
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        };
+        receiver.with_children(|parent| {
+            parent.spawn((band, receiver_view::WindowBand, PIXEL_PERFECT_LAYERS));
+        });
+        if let Some(label) = label {
+            // A child of the receiver so it follows it around and goes away with it on reset.
+            // Turned back so it's level and above the receiver whichever way the receiver lies.
+            let unrotate = orientation.rotation().inverse();
+            let offset = Vec3::Y * (orientation.size().y / 2. + GROUP_LABEL_GAP);
+            receiver.with_children(|parent| {
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            label,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: GROUP_LABEL_FONT_SIZE,
+                                color: GROUP_LABEL_COLOR,
+                            },
+                        ),
+                        transform: Transform::from_translation(
+                            (unrotate * offset).truncate().extend(
+                                config.z_layers.relative_to_receiver(config.z_layers.labels),
+                            ),
+                        )
+                        .with_rotation(unrotate),
+                        ..default()
+                    },
+                    follow::WorldLabel,
+                    HIGH_RES_LAYERS,
+                ));
+            });
+        }
+    }
+}
+
+fn seed_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    format!(
+        "Seed {} (press {} to replay it, {} for a new one)",
+        config.seed,
+        key_name(bindings.restart),
+        key_name(bindings.new_seed)
+    )
+}
+
+/// Restarts with a new random seed, shown so the run can be reproduced later.
+fn roll_seed(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.new_seed) {
+        config.seed = rand::random();
+        info!("New seed {}", config.seed);
+        hud.set("seed", seed_label(&config, &bindings));
+        reset_events.send(SimulationReset);
+    }
+}
+
+fn reset_simulation(
+    mut reset_timer: ResMut<ResetTimer>,
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut reset_events: EventWriter<SimulationReset>,
+) {
+    if input.pressed(bindings.restart) {
+        reset_timer.timer.reset();
+        reset_events.send(SimulationReset);
+    }
+}
+
+fn reset_simulation_timer(
+    mut reset_timer: ResMut<ResetTimer>,
+    time: Res<Time>,
+    mut reset_events: EventWriter<SimulationReset>,
+) {
+    if !reset_timer.enabled {
+        return;
+    }
+
+    reset_timer.timer.tick(time.delta());
+    if reset_timer.timer.finished() {
+        reset_events.send(SimulationReset);
+    }
+}
+
+/// Tears down every transmitter and receiver and builds the simulation again.
+fn restart_simulation(
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    mut reset_events: EventReader<SimulationReset>,
+    mut persisted: ResMut<PersistedTraces>,
+    mut rng: ResMut<EmissionRng>,
+    compare: Res<CompareMode>,
+    font: Res<OverlayFont>,
+    tx_query: Query<Entity, With<Transmitter>>,
+    rx_query: Query<Entity, With<Receiver>>,
+    plot_query: Query<(Entity, &GlobalTransform), With<PlotPoint>>,
+) {
+    if reset_events.read().count() == 0 {
+        return;
+    }
+
+    if config.persist_traces {
+        persist_traces(&mut commands, &config, &mut persisted, &plot_query);
+    }
 
-                if rx.current_draw_position > 2. * RECEIVER_WIDTH {
-                    // If we have already plotted over the entire width of the receiver then just
-                    // don't do anything
-                    commands.entity(rx_entity).remove::<Mover>();
-                    continue;
-                }
+    for tx in tx_query.iter() {
+        commands.entity(tx).despawn_recursive();
+    }
 
-                let plot_point = commands
-                    .spawn(MaterialMesh2dBundle {
-                        mesh: meshes.add(Circle::default()).into(),
-                        material: materials.add(RECEIVER_PLOT_COLOR),
-                        transform: Transform::from_xyz(
-                            (RECEIVER_WIDTH) - rx.current_draw_position,
-                            y,
-                            2.,
-                        )
-                        .with_scale(RECEIVER_PLOT_SIZE),
-                        ..default()
-                    })
-                    .id();
+    for rx in rx_query.iter() {
+        commands.entity(rx).despawn_recursive();
+    }
 
-                commands.entity(rx_entity).add_child(plot_point);
+    *rng = EmissionRng::new(&config);
+    start_simulation(meshes, materials, commands, &config, &compare, &font.0);
+}
 
-                if rx.prev_collision_time.is_none() {
-                    rx.prev_collision_time = Some(t);
-                }
-                rx.current_draw_position +=
-                    RECEIVER_DELTA_X_PER_SECOND * (t - rx.prev_collision_time.unwrap());
+/// Moves the current plot points out of their receivers into a greyed out trace that survives the
+/// reset, dropping the oldest traces beyond `max_persisted_traces`.
+fn persist_traces(
+    commands: &mut Commands,
+    config: &SimConfig,
+    persisted: &mut PersistedTraces,
+    plot_query: &Query<(Entity, &GlobalTransform), With<PlotPoint>>,
+) {
+    if plot_query.is_empty() {
+        return;
+    }
 
-                rx.prev_collision_time = Some(t);
-            }
+    let trace = commands
+        .spawn((SpatialBundle::default(), PIXEL_PERFECT_LAYERS))
+        .id();
+    for (point, global_transform) in plot_query.iter() {
+        let mut transform = global_transform.compute_transform();
+        transform.translation.z = config.z_layers.persisted_traces;
+        commands
+            .entity(point)
+            .remove::<(PlotPoint, phosphor::Phosphor)>()
+            .insert((transform, persisted.material.clone()))
+            .set_parent(trace);
+    }
+
+    persisted.traces.push_back(trace);
+    while persisted.traces.len() > config.max_persisted_traces {
+        if let Some(oldest) = persisted.traces.pop_front() {
+            commands.entity(oldest).despawn_recursive();
         }
     }
 }
 
-fn move_rx(mut rx_query: Query<(&mut Transform, &Mover), With<Receiver>>, time: Res<Time>) {
-    for (mut transform, movement) in rx_query.iter_mut() {
-        let direction = match movement.0 {
-            Movement::Left => -1.,
-            Movement::Right => 1.0,
-            Movement::Stationary => 0.,
-        };
-        transform.translation.x += direction * RECEIVER_SPEED * time.delta_seconds();
+fn clear_persisted_traces(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut commands: Commands,
+    mut persisted: ResMut<PersistedTraces>,
+) {
+    if input.just_pressed(bindings.clear_traces) {
+        for trace in persisted.traces.drain(..) {
+            commands.entity(trace).despawn_recursive();
+        }
     }
 }
 
-fn start_simulation(
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut commands: Commands,
+/// Switches the transmitter paired with the selected receiver off or back on.
+fn toggle_transmitter(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    mut tx_query: Query<&mut Transmitter>,
 ) {
-    let start_x = -300.;
-    let y_pos = 200.;
+    if !input.just_pressed(bindings.toggle_transmitter) {
+        return;
+    }
+    for mut tx in tx_query.iter_mut() {
+        if tx.index == selected.0 {
+            tx.emitting = !tx.emitting;
+            let state = if tx.emitting { "on" } else { "off" };
+            info!("Transmitter {} switched {state}", tx.index);
+        }
+    }
+}
 
-    create_simulation(
-        &mut meshes,
-        &mut materials,
-        &mut commands,
-        start_x,
-        y_pos,
-        Movement::Stationary,
-    );
+/// Fires a pulse from the selected receiver's transmitter, if it has a
+/// [`EmissionPattern::Fired`] pattern. It's emitted once its start delay is over.
+fn fire_pulse(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    mut tx_query: Query<&mut Transmitter>,
+) {
+    if !input.just_pressed(bindings.fire_pulse) {
+        return;
+    }
+    for mut tx in tx_query.iter_mut() {
+        if tx.index == selected.0 && tx.pattern == EmissionPattern::Fired {
+            tx.pending_pulses += 1;
+        }
+    }
+}
 
-    create_simulation(
-        &mut meshes,
-        &mut materials,
-        &mut commands,
-        start_x,
-        0.,
-        Movement::Right,
-    );
+/// Sets the gain the selected receiver's transmitter ramps towards, a step up or down per press.
+/// Its output follows over `amplitude_ramp_secs` rather than jumping.
+fn ramp_transmitter_gain(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    mut tx_query: Query<&mut Transmitter>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut step = 0.;
+    if input.just_pressed(bindings.gain_up) {
+        step += GAIN_STEP;
+    }
+    if input.just_pressed(bindings.gain_down) {
+        step -= GAIN_STEP;
+    }
+    if step == 0. {
+        return;
+    }
+    for mut tx in tx_query.iter_mut() {
+        if tx.index == selected.0 {
+            tx.target_gain = (tx.target_gain + step).clamp(0., 1.);
+            info!(
+                "Transmitter {} ramping to {:.0}% output",
+                tx.index,
+                tx.target_gain * 100.
+            );
+            hud.set(
+                "transmitter_gain",
+                transmitter_gain_label(Some(tx.target_gain), &bindings),
+            );
+        }
+    }
+}
 
-    create_simulation(
-        &mut meshes,
-        &mut materials,
-        &mut commands,
-        100.,
-        -y_pos,
-        Movement::Left,
+fn transmitter_gain_label(target: Option<f32>, bindings: &KeyBindings) -> String {
+    let keys = format!(
+        "{} / {}",
+        key_name(bindings.gain_up),
+        key_name(bindings.gain_down)
     );
+    match target {
+        Some(target) => format!(
+            "Transmitter ramping to {:.0}% output (press {keys} to ramp it up or down)",
+            target * 100.
+        ),
+        None => format!("Press {keys} to ramp the selected receiver's transmitter up or down"),
+    }
 }
 
-fn create_simulation(
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    commands: &mut Commands,
-    rx_start_x: f32,
-    y_pos: f32,
-    movement: Movement,
+fn adjust_amplitude(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut amplitude: ResMut<CurrentAmplitude>,
+    mut hud: ResMut<Hud>,
 ) {
-    let transmitter_x = 400.;
-    let half_tri_size = TRANSMITTER_SIZE / 2.;
-    let pta = Vec2::new(half_tri_size, half_tri_size);
-    let ptb = Vec2::new(0., -half_tri_size);
-    let ptc = Vec2::new(-half_tri_size, half_tri_size);
-    commands.spawn((
-        Transmitter {
-            spawn_rate: Timer::new(
-                Duration::from_millis(PARTICLE_SPAWN_RATE_MS),
-                TimerMode::Repeating,
-            ),
-            ..Default::default()
-        },
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Triangle2d::new(pta, ptb, ptc)).into(),
-            material: materials.add(TRANSMITTER_COLOR),
-            transform: Transform::from_xyz(transmitter_x, y_pos, 1.),
-            ..default()
-        },
-        PIXEL_PERFECT_LAYERS,
-    ));
-
-    let mb = MaterialMesh2dBundle {
-        mesh: meshes.add(Rectangle::from_size(RECEIVER_SIZE)).into(),
-        material: materials.add(RECEIVER_COLOR),
-        transform: Transform::from_xyz(rx_start_x, y_pos, 1.),
-        ..default()
+    if !input.just_pressed(bindings.amplitude) {
+        return;
+    }
+    let step = if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        -AMPLITUDE_STEP
+    } else {
+        AMPLITUDE_STEP
     };
-    match movement {
-        Movement::Left => commands.spawn((
-            mb,
-            Receiver::default(),
-            Mover(Movement::Left),
-            PIXEL_PERFECT_LAYERS,
-        )),
+    let new_amplitude = (amplitude.0 + step).clamp(AMPLITUDE_STEP, PARTICLE_AMPLITUDE);
+    if new_amplitude != amplitude.0 {
+        amplitude.0 = new_amplitude;
+        info!("Amplitude set to {new_amplitude}");
+    }
+    hud.set("amplitude", amplitude_label(amplitude.0, &bindings));
+}
 
-        Movement::Right => commands.spawn((
-            mb,
-            Receiver::default(),
-            Mover(Movement::Right),
-            PIXEL_PERFECT_LAYERS,
-        )),
-        Movement::Stationary => commands.spawn((mb, Receiver::default(), PIXEL_PERFECT_LAYERS)),
+fn amplitude_label(amplitude: f32, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.amplitude);
+    let shifted = format!("shift+{}", key.trim_matches('\''));
+    if amplitude >= PARTICLE_AMPLITUDE {
+        format!("Amplitude: {amplitude:.0}, the maximum (press {shifted} to decrease)")
+    } else if amplitude <= AMPLITUDE_STEP {
+        format!("Amplitude: {amplitude:.0}, the minimum (press {key} to increase)")
+    } else {
+        format!("Amplitude: {amplitude:.0} (press {key} to increase, {shifted} to decrease)")
+    }
+}
+
+fn toggle_crests_only(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.crests_only) {
+        config.crests_only = !config.crests_only;
+        hud.set(
+            "crests_only",
+            crests_only_label(config.crests_only, &bindings),
+        );
+    }
+}
+
+fn crests_only_label(crests_only: bool, bindings: &KeyBindings) -> String {
+    let action = if crests_only {
+        "show all particles"
+    } else {
+        "show only crests"
     };
+    format!("Press {} to {action}", key_name(bindings.crests_only))
 }
 
-fn reset_simulation(
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
-    mut commands: Commands,
-    mut reset_timer: ResMut<ResetTimer>,
+fn select_receiver(
     input: Res<ButtonInput<KeyCode>>,
-    tx_query: Query<Entity, With<Transmitter>>,
-    rx_query: Query<Entity, With<Receiver>>,
+    bindings: Res<KeyBindings>,
+    mut selected: ResMut<SelectedReceiver>,
+    rx_query: Query<&Receiver>,
 ) {
-    if input.pressed(KeyCode::KeyR) {
-        reset_timer.timer.reset();
-        for tx in tx_query.iter() {
-            commands.entity(tx).despawn_recursive();
+    if input.just_pressed(bindings.select_receiver) {
+        let count = rx_query.iter().len().max(1);
+        selected.0 = (selected.0 + 1) % count;
+    }
+}
+
+/// Switches the selected receiver between a one-shot sweep and repeating ones.
+fn cycle_sweep_mode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    mut rx_query: Query<&mut Receiver>,
+    mut hud: ResMut<Hud>,
+) {
+    for mut rx in rx_query.iter_mut() {
+        if rx.index != selected.0 {
+            continue;
+        }
+        if input.just_pressed(bindings.sweep_mode) {
+            rx.sweep = rx.sweep.next();
         }
+        hud.set(
+            "sweep_mode",
+            format!(
+                "Receiver {} sweep: {} (press {} to change)",
+                rx.index + 1,
+                rx.sweep.label(),
+                key_name(bindings.sweep_mode)
+            ),
+        );
+    }
+}
 
-        for rx in rx_query.iter() {
-            commands.entity(rx).despawn_recursive();
+fn toggle_pause(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.pause) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
         }
+        hud.set("pause", pause_label(time.is_paused(), &bindings));
+    }
+}
 
-        start_simulation(meshes, materials, commands);
+fn pause_label(paused: bool, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.pause);
+    if paused {
+        format!("Paused (press {key} to resume)")
+    } else {
+        format!("Press {key} to pause")
     }
 }
 
-fn reset_simulation_timer(
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
-    mut commands: Commands,
+fn toggle_auto_reset(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
     mut reset_timer: ResMut<ResetTimer>,
-    time: Res<Time>,
-    tx_query: Query<Entity, With<Transmitter>>,
-    rx_query: Query<Entity, With<Receiver>>,
+    mut hud: ResMut<Hud>,
 ) {
-    reset_timer.timer.tick(time.delta());
-    if reset_timer.timer.finished() {
-        for tx in tx_query.iter() {
-            commands.entity(tx).despawn_recursive();
-        }
-
-        for rx in rx_query.iter() {
-            commands.entity(rx).despawn_recursive();
-        }
+    if input.just_pressed(bindings.auto_reset) {
+        reset_timer.enabled = !reset_timer.enabled;
+        reset_timer.timer.reset();
+        hud.set("auto_reset", auto_reset_label(&reset_timer, &bindings));
+    }
+}
 
-        start_simulation(meshes, materials, commands);
+fn auto_reset_label(reset_timer: &ResetTimer, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.auto_reset);
+    if reset_timer.enabled {
+        format!(
+            "Auto-reset every {}s (press {key} to turn off)",
+            reset_timer.timer.duration().as_secs_f32()
+        )
+    } else {
+        format!("Auto-reset off (press {key} to turn on)")
     }
 }
 
-/// Scales camera projection to fit the window (integer multiples only).
+/// Opens a second window that shows only the receiver plots, so they can be put full screen on
+/// another display.
+fn spawn_plot_window(commands: &mut Commands) {
+    let window = commands
+        .spawn(Window {
+            title: "doppl-rs plots".into(),
+            ..default()
+        })
+        .id();
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Window(WindowRef::Entity(window)),
+            ..default()
+        },
+        ..default()
+    };
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: RES_WIDTH as f32,
+        min_height: RES_HEIGHT as f32,
+    };
+    commands.spawn((camera, RenderLayers::layer(PLOT_WINDOW_LAYER)));
+}
+
+/// Scales the camera projection so the canvas fits the window. The camera stays centered on the
+/// canvas, so any space left over becomes letterbox bars split evenly either side.
+///
+/// The scale is worked out in physical pixels, then converted to the logical pixels the
+/// projection is sized in. For example a 1280x720 logical window on a 2x display is 2560x1440
+/// physical pixels, so each canvas pixel covers 2x2 of them and the projection scale is
+/// `scale_factor / 2 = 1`. Scaling by logical pixels alone would let integer scaling pick 1x
+/// there, leaving the canvas at a quarter of the window, and would allow 1.5x logical scales that
+/// aren't whole physical pixels on a 1.5x display.
 fn fit_canvas(
+    config: Res<SimConfig>,
     mut resize_events: EventReader<WindowResized>,
+    mut scale_factor_events: EventReader<WindowScaleFactorChanged>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     mut projections: Query<&mut OrthographicProjection, With<OuterCamera>>,
+    mut fitted: Local<bool>,
 ) {
-    for event in resize_events.read() {
-        let h_scale = event.width / RES_WIDTH as f32;
-        let v_scale = event.height / RES_HEIGHT as f32;
-        let mut projection = projections.single_mut();
-        projection.scale = 1. / h_scale.min(v_scale);
+    // The window may not send a resize event before the first frame, so fit to its initial size.
+    let resized = resize_events.read().count() > 0;
+    let rescaled = scale_factor_events.read().count() > 0;
+    if *fitted && !resized && !rescaled {
+        return;
+    }
+    let (Ok(window), Ok(mut projection)) = (windows.get_single(), projections.get_single_mut())
+    else {
+        return;
+    };
+    *fitted = true;
+    let size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    projection.scale = window.scale_factor() / canvas_scale(config.canvas_scaling, size);
+}
+
+/// How many physical window pixels each canvas pixel covers in a window of `size` physical
+/// pixels.
+fn canvas_scale(scaling: CanvasScaling, size: Vec2) -> f32 {
+    let scale = (size.x / RES_WIDTH as f32).min(size.y / RES_HEIGHT as f32);
+    match scaling {
+        CanvasScaling::Fit => scale,
+        CanvasScaling::Integer => scale.floor().max(1.),
     }
 }
 
+fn uses_canvas(config: Res<SimConfig>) -> bool {
+    config.render_pipeline == RenderPipeline::Canvas
+}
+
+/// Converts the cursor position to world coordinates.
+///
+/// The `OuterCamera` projection already includes the scaling applied by `fit_canvas`, and the
+/// canvas is drawn 1:1 at the origin of the high-res world, so the result is in canvas pixels.
+/// Those only need taking through wherever the [`InGameCamera`] has panned and zoomed to (e.g. to
+/// follow a receiver) to get the coordinates of the pixel-perfect simulation.
+fn cursor_world_position(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: &Query<&Transform, With<InGameCamera>>,
+) -> Option<Vec2> {
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let canvas_position = camera.viewport_to_world_2d(camera_transform, cursor)?;
+    Some(
+        in_game_camera_query
+            .get_single()
+            .map_or(canvas_position, |transform| {
+                transform.transform_point(canvas_position.extend(0.)).xy()
+            }),
+    )
+}
+
 fn screenshot_window(
+    config: Res<SimConfig>,
     input: Res<ButtonInput<KeyCode>>,
-    main_window: Query<Entity, With<PrimaryWindow>>,
+    bindings: Res<KeyBindings>,
+    main_window: Query<(Entity, &Window), With<PrimaryWindow>>,
     mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut reset_events: EventWriter<SimulationReset>,
+    frame_count: Res<FrameCount>,
     mut counter: Local<u32>,
+    mut frames: Local<u32>,
     mut start_screenshot: Local<bool>,
+    // Update the sequence started on, to number frames by the frame clock.
+    mut start_frame: Local<u32>,
 ) {
     if cfg!(feature = "gifcreate") {
-        let path = format!("./screenshots/screenshot-{num:0>3}.png", num = *counter);
-        if input.just_pressed(KeyCode::Space) {
+        if input.just_pressed(bindings.gif_capture) {
             *start_screenshot = true;
+            *start_frame = frame_count.0;
+            // Start over so the sequence opens on the empty scene and catches the wave turning on.
+            if config.start_delay_secs > 0. {
+                reset_events.send(SimulationReset);
+            }
         }
+        // Numbered by the updates since the sequence started, under the frame clock the names
+        // follow the simulation, so a rerun regenerates the same frames under the same names,
+        // and pausing or changing the speed still gives every frame its own. The real-time
+        // clock's frames aren't evenly spaced, so those are numbered as they're saved.
+        let path = if config.gif_sim_frame_names && config.clock_mode == clock::ClockMode::Frames {
+            let frame = frame_count.0.wrapping_sub(*start_frame);
+            capture::sequence_frame_path(&config, frame)
+        } else {
+            capture::screenshot_path(&config, capture::ScreenshotKind::Sequence, *counter)
+        };
 
         if *counter < 500 && *start_screenshot {
+            // Only frames that are saved count towards the 500, so skipping makes the GIF longer
+            // rather than shorter.
+            let skipped = !frames.is_multiple_of(config.gif_frame_skip.max(1));
+            *frames += 1;
+            if skipped {
+                return;
+            }
             *counter += 1;
-            screenshot_manager
-                .save_screenshot_to_disk(main_window.single(), path)
-                .unwrap();
+            let (window_entity, window) = main_window.single();
+            let size = UVec2::new(window.physical_width(), window.physical_height());
+            let crop = capture::fit_within(size, config.gif_max_size);
+            capture::save_window(
+                &mut screenshot_manager,
+                window_entity,
+                path,
+                crop,
+                config.export_contrast,
+            );
+        }
+    }
+}
+
+/// A headless app running just the systems that emit, move and catch particles, on a clock that
+/// only moves by what its `TimeUpdateStrategy` is set to before each update.
+fn headless_app(config: SimConfig) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<Image>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+    .insert_resource(EmissionRng::new(&config))
+    .insert_resource(tempo::Tempo(config.tempo_bpm))
+    .init_resource::<CurrentAmplitude>()
+    .init_resource::<palette::SpectrumMaterials>()
+    .init_resource::<palette::EmissionHueMaterials>()
+    .init_resource::<palette::VelocityMaterials>()
+    .init_resource::<phosphor::FadedMaterials>()
+    .insert_resource(config)
+    .add_systems(Update, (produce_particle, propagate_particle).chain())
+    .add_systems(
+        PostUpdate,
+        handle_rx_collision.after(TransformSystem::TransformPropagate),
+    );
+    let world = &mut app.world;
+    world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+        let config = world.resource::<SimConfig>();
+        let appearance = ParticleAppearance::new(
+            &config.particle_shape,
+            &mut meshes,
+            world.resource::<AssetServer>(),
+        );
+        let marker = PlotMarkerMesh(meshes.add(Circle::default()));
+        world.insert_resource(appearance);
+        world.insert_resource(marker);
+    });
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs one frame `delta` after the last.
+    fn advance(app: &mut App, delta: Duration) {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+        app.update();
+    }
+
+    /// A transmitter at `x` emitting at 1 Hz, with `pattern`, on the x axis.
+    fn spawn_transmitter(app: &mut App, x: f32, pattern: EmissionPattern) -> Entity {
+        let interval = app.world.resource::<SimConfig>().spawn_interval();
+        app.world
+            .spawn((
+                Transmitter {
+                    spawn_rate: Timer::new(interval, TimerMode::Repeating),
+                    emitting: true,
+                    pattern,
+                    frequency: 1.,
+                    gain: 1.,
+                    target_gain: 1.,
+                    ..default()
+                },
+                SpatialBundle::from_transform(Transform::from_xyz(x, 0., 0.)),
+            ))
+            .id()
+    }
+
+    /// A stationary receiver at `x` on the x axis, facing transmitters to its right.
+    fn spawn_receiver(app: &mut App, x: f32) -> Entity {
+        app.world
+            .spawn((
+                Receiver {
+                    start_x: x,
+                    ..default()
+                },
+                SpatialBundle::from_transform(Transform::from_xyz(x, 0., 0.)),
+            ))
+            .id()
+    }
+
+    fn particles(app: &mut App) -> usize {
+        app.world
+            .query::<&SignalParticle>()
+            .iter(&app.world)
+            .count()
+    }
+
+    /// Emits `count` particles from 700 pixels away, one every 20 ms on a steady frame rate,
+    /// then lets them arrive over frames of `deltas` in turn, returning the gaps between where
+    /// the receiver plotted the ones it sampled.
+    fn steady_stream_gaps(config: SimConfig, count: usize, deltas: &[u64]) -> Vec<f32> {
+        let mut app = headless_app(SimConfig {
+            spawn_rate_ms: 20,
+            ..config
+        });
+        let tx = spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        let rx = spawn_receiver(&mut app, -300.);
+        advance(&mut app, Duration::ZERO);
+        for _ in 0..count {
+            advance(&mut app, Duration::from_millis(20));
+        }
+        app.world.get_mut::<Transmitter>(tx).unwrap().emitting = false;
+        for &delta in deltas.iter().cycle().take(400) {
+            advance(&mut app, Duration::from_millis(delta));
+        }
+        let samples = &app.world.get::<Receiver>(rx).unwrap().samples;
+        samples
+            .iter()
+            .zip(samples.iter().skip(1))
+            .map(|(a, b)| a.plot_x - b.plot_x)
+            .collect()
+    }
+
+    #[test]
+    fn live_particles_never_exceed_the_cap() {
+        let mut app = headless_app(SimConfig {
+            emission_mode: EmissionMode::PointSource,
+            point_source_rays: 36,
+            point_source_spawn_rate_ms: 5,
+            max_particles: 100,
+            ..default()
+        });
+        let tx = spawn_transmitter(&mut app, 0., EmissionPattern::Continuous);
+        app.world.get_mut::<Transmitter>(tx).unwrap().beam_width = TAU;
+        for _ in 0..60 {
+            advance(&mut app, Duration::from_millis(16));
+            assert!(particles(&mut app) <= 100);
+        }
+        assert_eq!(particles(&mut app), 100);
+
+        // The oldest went first, leaving the newest hundred.
+        let spawn_indices: HashSet<u64> = app
+            .world
+            .query::<&SignalParticle>()
+            .iter(&app.world)
+            .map(|particle| particle.spawn_index)
+            .collect();
+        let newest = *spawn_indices.iter().max().unwrap();
+        assert_eq!(spawn_indices, (newest - 99..=newest).collect());
+    }
+
+    fn hit_crossing_at(crossed_at: f32) -> RxHit {
+        RxHit {
+            transmitter: Entity::PLACEHOLDER,
+            spawn_index: 0,
+            value: 0.,
+            crossed_at,
+            velocity: 0.,
+            hue: None,
+            phase: 0.,
+        }
+    }
+
+    #[test]
+    fn simultaneous_crossings_are_plotted_where_they_crossed() {
+        // Four particles through in one frame from 1 s to 1.04 s, in crossing order, one of
+        // them seeming to cross just before the last frame.
+        let hits = [0.995, 1.005, 1.01, 1.025].map(hit_crossing_at);
+        let (gaps, trailing) = plot_gaps(&hits, OverlapHandling::CrossingTime, 1., 1.04);
+
+        let mut position = 0.;
+        let positions: Vec<f32> = gaps
+            .iter()
+            .map(|gap| {
+                position += gap;
+                position
+            })
+            .collect();
+        let expected = [0., 0.005, 0.01, 0.025].map(|offset| offset * RECEIVER_DELTA_X_PER_SECOND);
+        for (position, expected) in positions.iter().zip(expected) {
+            assert!((position - expected).abs() < 1e-3, "{positions:?}");
+        }
+        assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+        // The frame's whole sweep is used, with the rest after the last hit.
+        let swept = position + trailing;
+        assert!((swept - 0.04 * RECEIVER_DELTA_X_PER_SECOND).abs() < 1e-3);
+    }
+
+    #[test]
+    fn simultaneous_crossings_are_spread_evenly_by_default() {
+        let hits = [1.03, 1.005, 1.01].map(hit_crossing_at);
+        let (gaps, trailing) = plot_gaps(&hits, OverlapHandling::Even, 1., 1.03);
+        let step = 0.01 * RECEIVER_DELTA_X_PER_SECOND;
+        for (gap, expected) in gaps.iter().zip([0., step, step]) {
+            assert!((gap - expected).abs() < 1e-3, "{gaps:?}");
+        }
+        assert!((trailing - step).abs() < 1e-3);
+    }
+
+    #[test]
+    fn adaptive_spawn_rate_settles_on_the_target() {
+        // 700 pixels at 200 pixels a second is 3.5 s in flight, so the target needs an emission
+        // every 70 ms, far from the configured 10 ms.
+        let mut app = headless_app(SimConfig {
+            target_in_flight: Some(50),
+            ..default()
+        });
+        spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        spawn_receiver(&mut app, -300.);
+        let mut counts = Vec::new();
+        for _ in 0..3000 {
+            advance(&mut app, Duration::from_millis(10));
+            counts.push(particles(&mut app));
+        }
+        // Over the last 10 seconds it stays close to the target.
+        for &count in &counts[2000..] {
+            assert!((45..=55).contains(&count), "{count} in flight");
+        }
+    }
+
+    #[test]
+    fn adaptive_spawn_rate_starts_no_faster_than_configured() {
+        let mut app = headless_app(SimConfig {
+            target_in_flight: Some(50),
+            ..default()
+        });
+        spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        spawn_receiver(&mut app, -300.);
+        // Frames shorter than the configured 10 ms would let a shorter interval show. Nothing
+        // arrives in the first 3 s, so every particle emitted is still in flight.
+        for frame in 1..=600 {
+            advance(&mut app, Duration::from_millis(5));
+            let count = particles(&mut app);
+            assert!(
+                count <= frame / 2 + 1,
+                "{count} in flight after {frame} frames"
+            );
+        }
+    }
+
+    /// Pixels the plot sweeps between particles emitted 20 ms apart.
+    const STEADY_GAP: f32 = 0.02 * RECEIVER_DELTA_X_PER_SECOND;
+
+    #[test]
+    fn uneven_frames_spread_samples_out() {
+        // Frames from 5 to 90 ms long let several particles through at once.
+        let gaps = steady_stream_gaps(default(), 40, &[5, 47, 16, 90, 33]);
+        assert_eq!(gaps.len(), 39);
+        // The first frame anything arrives in has no earlier collision to spread them back to.
+        for &gap in &gaps[1..] {
+            assert!(
+                (STEADY_GAP / 5. ..STEADY_GAP * 2.).contains(&gap),
+                "gap of {gap} in {gaps:?}"
+            );
+        }
+        let span: f32 = gaps.iter().sum();
+        let steady_span = 39. * STEADY_GAP;
+        assert!(
+            (span - steady_span).abs() < 0.1 * steady_span,
+            "spans {span}, not {steady_span}"
+        );
+    }
+
+    #[test]
+    fn dead_time_ignores_particles_until_it_is_over() {
+        // Particles 20 ms apart against a 50 ms dead time: each sample blocks the next two.
+        let config = SimConfig {
+            dead_time_secs: 0.05,
+            overlap_handling: OverlapHandling::CrossingTime,
+            ..default()
+        };
+        let gaps = steady_stream_gaps(config, 30, &[20]);
+        assert_eq!(gaps.len(), 9);
+        for &gap in &gaps[1..] {
+            assert!((gap - 3. * STEADY_GAP).abs() < 0.01, "{gaps:?}");
+        }
+
+        let gaps = steady_stream_gaps(default(), 30, &[20]);
+        assert_eq!(gaps.len(), 29);
+    }
+
+    #[test]
+    fn huge_frame_is_caught_once_at_the_receiver_edge() {
+        let config = SimConfig {
+            particle_substeps: 1000,
+            // Caught particles linger, so where they stopped can be checked.
+            despawn_animation_secs: 1.,
+            ..default()
+        };
+        let speed = config.wave_speed;
+        let mut app = headless_app(config);
+        let tx = spawn_transmitter(&mut app, 400., EmissionPattern::Fired);
+        let rx = spawn_receiver(&mut app, -300.);
+        app.world.get_mut::<Transmitter>(tx).unwrap().pending_pulses = 1;
+        advance(&mut app, Duration::ZERO);
+        assert_eq!(particles(&mut app), 1);
+
+        // Ten seconds carries it 2000 pixels, right through the receiver and far beyond.
+        app.world
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(10));
+        advance(&mut app, Duration::from_secs(10));
+        for _ in 0..10 {
+            advance(&mut app, Duration::from_millis(16));
+        }
+        assert_eq!(particles(&mut app), 0);
+        assert_eq!(app.world.get::<Receiver>(rx).unwrap().samples.len(), 1);
+
+        // It stopped within a substep, 2 pixels, of the edge it crossed, rather than 1300 pixels
+        // past it.
+        let edge = -300. + RECEIVER_HEIGHT / 2.;
+        let stopped = app
+            .world
+            .query_filtered::<&GlobalTransform, With<Despawning>>()
+            .single(&app.world)
+            .translation()
+            .x;
+        let substep = speed.abs() * 10. / 1000.;
+        assert!(
+            (edge - substep..=edge).contains(&stopped),
+            "stopped at {stopped}, edge at {edge}"
+        );
+    }
+
+    #[test]
+    fn particle_inside_a_receiver_keeps_its_speed_in_a_wrapped_world() {
+        let config = SimConfig {
+            particle_substeps: 10,
+            wrap_world: true,
+            ..default()
+        };
+        let speed = config.wave_speed;
+        let mut app = headless_app(config);
+        let tx = spawn_transmitter(&mut app, 200., EmissionPattern::Fired);
+        spawn_receiver(&mut app, -100.);
+        app.world.get_mut::<Transmitter>(tx).unwrap().pending_pulses = 1;
+        advance(&mut app, Duration::ZERO);
+
+        let particle_x = |app: &mut App| {
+            200. + app
+                .world
+                .query_filtered::<&Transform, With<SignalParticle>>()
+                .single(&app.world)
+                .translation
+                .x
+        };
+        // Into the catch area, which goes on behind the receiver's face.
+        let edge = -100. + RECEIVER_HEIGHT / 2.;
+        for _ in 0..1000 {
+            if particle_x(&mut app) < edge - 10. {
+                break;
+            }
+            advance(&mut app, Duration::from_millis(16));
+        }
+        let before = particle_x(&mut app);
+        assert!(before < edge, "never reached the receiver");
+
+        advance(&mut app, Duration::from_millis(100));
+        let moved = (particle_x(&mut app) - before).abs();
+        assert!(
+            (moved - speed.abs() * 0.1).abs() < 1.,
+            "moved {moved} in 100 ms"
+        );
+    }
+
+    #[test]
+    fn long_gap_only_catches_up_to_the_cap() {
+        let config = SimConfig {
+            max_frame_delta_ms: 100,
+            ..default()
+        };
+        let speed = config.wave_speed;
+        let mut app = headless_app(config);
+        app.add_systems(First, focus::limit_frame_delta.before(TimeSystem));
+        spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        advance(&mut app, Duration::ZERO);
+        let before = particles(&mut app);
+
+        // The window comes back after 10 s in the background.
+        advance(&mut app, Duration::from_secs(10));
+        assert_eq!(
+            app.world.resource::<Time<Virtual>>().delta(),
+            Duration::from_millis(100)
+        );
+        // No more than 100 ms of emissions 10 ms apart, rather than 10 s of them.
+        let spawned = particles(&mut app) - before;
+        assert!((1..=10).contains(&spawned), "{spawned} particles spawned");
+        let furthest = app
+            .world
+            .query_filtered::<&Transform, With<SignalParticle>>()
+            .iter(&app.world)
+            .map(|transform| transform.translation.x.abs())
+            .fold(0., f32::max);
+        assert!(
+            furthest <= speed.abs() * 0.1 + 1.,
+            "a particle travelled {furthest} px"
+        );
+    }
+
+    fn sample_of(value: f32) -> RxSample {
+        RxSample {
+            value,
+            plot_x: -value,
+            distance: None,
+            phase: None,
+        }
+    }
+
+    #[test]
+    fn receiver_buffer_wraps_around() {
+        let mut rx = Receiver::default();
+        for value in 0..7 {
+            rx.push_sample(sample_of(value as f32), Some(3));
+        }
+        let values: Vec<f32> = rx.samples.iter().map(|sample| sample.value).collect();
+        assert_eq!(values, [4., 5., 6.]);
+        assert_eq!(rx.dropped_samples, 4);
+        assert_eq!(rx.next_sample_index(), 7);
+        // Samples keep the index they were taken at, and dropped ones are gone.
+        assert!(rx.sample(3).is_none());
+        assert_eq!(rx.sample(4).map(|sample| sample.value), Some(4.));
+        assert_eq!(rx.sample(6).map(|sample| sample.value), Some(6.));
+        assert!(rx.sample(7).is_none());
+
+        // Without a capacity nothing is dropped, and a capacity of 0 still keeps the latest.
+        let mut unbounded = Receiver::default();
+        let mut single = Receiver::default();
+        for value in 0..5 {
+            unbounded.push_sample(sample_of(value as f32), None);
+            single.push_sample(sample_of(value as f32), Some(0));
+        }
+        assert_eq!(unbounded.samples.len(), 5);
+        assert_eq!(single.samples.len(), 1);
+        assert_eq!(single.samples[0].value, 4.);
+    }
+
+    #[test]
+    fn drift_free_receiver_stays_exact_over_ten_minutes() {
+        let mut app = headless_app(SimConfig {
+            drift_free_motion: true,
+            ..default()
+        });
+        app.add_systems(Update, move_rx);
+        let rx = spawn_receiver(&mut app, -300.);
+        app.world.entity_mut(rx).insert(Mover(Movement::Right));
+        app.world.get_mut::<Receiver>(rx).unwrap().speed = 0.37;
+        advance(&mut app, Duration::ZERO);
+
+        // Ten minutes of 60 fps frames, long enough for adding up each frame to drift.
+        let frame = Duration::from_secs(1) / 60;
+        for _ in 0..10 * 60 * 60 {
+            advance(&mut app, frame);
+        }
+        let position = app.world.get::<Transform>(rx).unwrap().translation.x;
+        let analytic = -300. + 0.37 * 600.;
+        assert!(
+            (position - analytic).abs() < 1e-3,
+            "{position} vs {analytic}"
+        );
+    }
+
+    #[test]
+    fn amplitude_label_says_which_way_it_can_still_go() {
+        let bindings = KeyBindings::default();
+        let at_max = amplitude_label(PARTICLE_AMPLITUDE, &bindings);
+        assert!(
+            at_max.contains("maximum") && !at_max.contains("increase"),
+            "{at_max}"
+        );
+        let at_min = amplitude_label(AMPLITUDE_STEP, &bindings);
+        assert!(
+            at_min.contains("minimum") && !at_min.contains("decrease"),
+            "{at_min}"
+        );
+        let between = amplitude_label(PARTICLE_AMPLITUDE / 2., &bindings);
+        assert!(
+            between.contains("increase") && between.contains("decrease"),
+            "{between}"
+        );
+    }
+
+    #[test]
+    fn spawning_past_the_receiver_cap_evicts_the_oldest() {
+        let mut spawn_order: VecDeque<u32> = (0..3).collect();
+        for newest in 3..6 {
+            spawn_order.push_back(newest);
+            assert_eq!(evict_oldest(&mut spawn_order, Some(3)), [newest - 3]);
+            assert_eq!(spawn_order, [newest - 2, newest - 1, newest]);
         }
     }
+
+    #[test]
+    fn evicting_a_receiver_removes_its_transmitter() {
+        let mut app = headless_app(SimConfig {
+            max_receivers: Some(1),
+            ..default()
+        });
+        app.add_systems(Update, limit_receivers);
+        let old_tx = spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        let old_rx = spawn_receiver(&mut app, -300.);
+        advance(&mut app, Duration::ZERO);
+        let new_tx = spawn_transmitter(&mut app, 400., EmissionPattern::Continuous);
+        let new_rx = spawn_receiver(&mut app, -300.);
+        app.world.get_mut::<Transmitter>(new_tx).unwrap().index = 1;
+        app.world.get_mut::<Receiver>(new_rx).unwrap().index = 1;
+        advance(&mut app, Duration::from_millis(16));
+        assert!(app.world.get_entity(old_rx).is_none());
+        assert!(app.world.get_entity(old_tx).is_none());
+        assert!(app.world.get_entity(new_rx).is_some());
+        assert!(app.world.get_entity(new_tx).is_some());
+    }
+
+    #[test]
+    fn receivers_are_kept_without_a_cap() {
+        let mut spawn_order: VecDeque<u32> = (0..10).collect();
+        assert!(evict_oldest(&mut spawn_order, None).is_empty());
+        assert_eq!(spawn_order.len(), 10);
+    }
 }
@@ -0,0 +1,208 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology},
+};
+use serde::{Deserialize, Serialize};
+
+/// Width of the arms of the cross and plus markers, as a fraction of the marker size.
+const ARM_WIDTH: f32 = 0.25;
+/// Thickness of outlined particles, as a fraction of the particle size.
+const OUTLINE_WIDTH: f32 = 0.15;
+/// Sides of the polygon standing in for a circle in an outline.
+const RING_SEGMENTS: usize = 32;
+
+/// Shape drawn for each point of a receiver's plot.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlotMarker {
+    #[default]
+    Dot,
+    Cross,
+    Square,
+    Plus,
+}
+
+/// Shape of the particles. Sizes are still set by `particle_radius`, and receivers pick particles
+/// up by position alone, so the shape is purely visual.
+#[derive(Clone, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParticleShape {
+    #[default]
+    Circle,
+    Square,
+    Triangle,
+    /// An image from the assets folder, tinted with the particle color.
+    Sprite(String),
+}
+
+/// Whether particles are drawn solid or as just their outline. Receivers still pick particles up
+/// by their position alone, so hollow particles are caught exactly like filled ones.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParticleFill {
+    #[default]
+    Filled,
+    /// Hollow rings, or the outlines of the other shapes, which read as cleaner wavefronts when
+    /// particles crowd together. Sprites are always drawn whole.
+    Outline,
+}
+
+impl ParticleFill {
+    pub fn next(self) -> Self {
+        match self {
+            ParticleFill::Filled => ParticleFill::Outline,
+            ParticleFill::Outline => ParticleFill::Filled,
+        }
+    }
+}
+
+/// Meshes and texture shared by every particle, built once for the configured [`ParticleShape`].
+#[derive(Resource)]
+pub struct ParticleAppearance {
+    filled_mesh: Handle<Mesh>,
+    outline_mesh: Handle<Mesh>,
+    pub texture: Option<Handle<Image>>,
+}
+
+impl ParticleAppearance {
+    pub fn new(
+        shape: &ParticleShape,
+        meshes: &mut Assets<Mesh>,
+        asset_server: &AssetServer,
+    ) -> Self {
+        let mesh: Mesh = match shape {
+            ParticleShape::Circle => Circle::default().into(),
+            ParticleShape::Square | ParticleShape::Sprite(_) => Rectangle::new(1., 1.).into(),
+            ParticleShape::Triangle => {
+                // Fits in the same unit circle as the other shapes.
+                let (x, y) = (0.75_f32.sqrt() / 2., 0.25);
+                Triangle2d::new(Vec2::new(0., 0.5), Vec2::new(-x, -y), Vec2::new(x, -y)).into()
+            }
+        };
+        let outline = match shape {
+            ParticleShape::Circle => Some(ring_mesh(
+                &(0..RING_SEGMENTS)
+                    .map(|i| Vec2::from_angle(i as f32 / RING_SEGMENTS as f32 * TAU) * 0.5)
+                    .collect::<Vec<_>>(),
+            )),
+            ParticleShape::Square => Some(ring_mesh(&[
+                Vec2::new(-0.5, -0.5),
+                Vec2::new(0.5, -0.5),
+                Vec2::new(0.5, 0.5),
+                Vec2::new(-0.5, 0.5),
+            ])),
+            ParticleShape::Triangle => {
+                let (x, y) = (0.75_f32.sqrt() / 2., 0.25);
+                Some(ring_mesh(&[
+                    Vec2::new(0., 0.5),
+                    Vec2::new(-x, -y),
+                    Vec2::new(x, -y),
+                ]))
+            }
+            ParticleShape::Sprite(_) => None,
+        };
+        let texture = match shape {
+            ParticleShape::Sprite(path) => Some(asset_server.load(path.clone())),
+            _ => None,
+        };
+        let filled_mesh = meshes.add(mesh);
+        Self {
+            outline_mesh: outline.map_or_else(|| filled_mesh.clone(), |mesh| meshes.add(mesh)),
+            filled_mesh,
+            texture,
+        }
+    }
+
+    /// Mesh for particles drawn with `fill`.
+    pub fn mesh(&self, fill: ParticleFill) -> Handle<Mesh> {
+        match fill {
+            ParticleFill::Filled => self.filled_mesh.clone(),
+            ParticleFill::Outline => self.outline_mesh.clone(),
+        }
+    }
+
+    /// Material for particles of `color`, textured for sprites.
+    pub fn material(&self, color: Color) -> ColorMaterial {
+        ColorMaterial {
+            color,
+            texture: self.texture.clone(),
+        }
+    }
+}
+
+/// Mesh shared by every plot point, built once for the configured [`PlotMarker`].
+#[derive(Resource)]
+pub struct PlotMarkerMesh(pub Handle<Mesh>);
+
+/// Builds a unit sized mesh for `marker`, to be scaled up like the other plot point meshes.
+pub fn marker_mesh(marker: PlotMarker) -> Mesh {
+    match marker {
+        PlotMarker::Dot => Circle::default().into(),
+        PlotMarker::Square => Rectangle::new(1., 1.).into(),
+        PlotMarker::Plus => plus_mesh(0.),
+        PlotMarker::Cross => plus_mesh(std::f32::consts::FRAC_PI_4),
+    }
+}
+
+/// A band [`OUTLINE_WIDTH`] thick just inside the polygon with corners `outer`, which must be
+/// centred on the origin.
+fn ring_mesh(outer: &[Vec2]) -> Mesh {
+    let inner_scale = 1. - 2. * OUTLINE_WIDTH;
+    let mut positions = Vec::with_capacity(outer.len() * 2);
+    for &corner in outer {
+        positions.push(corner.extend(0.).to_array());
+        positions.push((corner * inner_scale).extend(0.).to_array());
+    }
+    let count = outer.len() as u32;
+    let mut indices = Vec::with_capacity(outer.len() * 6);
+    for i in 0..count {
+        let next = (i + 1) % count;
+        let (outer_a, inner_a, outer_b, inner_b) = (2 * i, 2 * i + 1, 2 * next, 2 * next + 1);
+        indices.extend([outer_a, outer_b, inner_b, outer_a, inner_b, inner_a]);
+    }
+    let uvs: Vec<[f32; 2]> = positions.iter().map(|p| [p[0] + 0.5, 0.5 - p[1]]).collect();
+    let normals = vec![[0., 0., 1.]; positions.len()];
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Two crossing bars, rotated anticlockwise by `angle`.
+fn plus_mesh(angle: f32) -> Mesh {
+    let (half_len, half_width) = (0.5, ARM_WIDTH / 2.);
+    let rotation = Vec2::from_angle(angle);
+    let bars = [
+        Vec2::new(half_len, half_width),
+        Vec2::new(half_width, half_len),
+    ];
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    for half_size in bars {
+        let start = positions.len() as u32;
+        for corner in [
+            Vec2::new(-half_size.x, -half_size.y),
+            Vec2::new(half_size.x, -half_size.y),
+            Vec2::new(half_size.x, half_size.y),
+            Vec2::new(-half_size.x, half_size.y),
+        ] {
+            positions.push(rotation.rotate(corner).extend(0.).to_array());
+        }
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+    let uvs: Vec<[f32; 2]> = positions.iter().map(|p| [p[0] + 0.5, 0.5 - p[1]]).collect();
+    let normals = vec![[0., 0., 1.]; positions.len()];
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
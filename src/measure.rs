@@ -0,0 +1,88 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    cursor_world_position, hud::Hud, keybindings::KeyBindings, settings::PointerOverUi,
+    InGameCamera, OuterCamera,
+};
+
+const CROSSHAIR_COLOR: Color = Color::WHITE;
+const CROSSHAIR_HALF_SIZE: f32 = 12.;
+
+/// Crosshair that reads off world coordinates under the cursor. Clicking sets a reference point
+/// so distances can be measured from it.
+#[derive(Resource, Default)]
+pub struct MeasureMode {
+    active: bool,
+    reference: Option<Vec2>,
+}
+
+impl MeasureMode {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+pub fn toggle_measure_mode(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut measure: ResMut<MeasureMode>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.measure) {
+        measure.active = !measure.active;
+        measure.reference = None;
+        if !measure.active {
+            hud.remove("measure");
+        }
+    }
+}
+
+pub fn measure(
+    mouse: Res<ButtonInput<MouseButton>>,
+    pointer: Res<PointerOverUi>,
+    mut measure: ResMut<MeasureMode>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    mut gizmos: Gizmos,
+    mut hud: ResMut<Hud>,
+) {
+    if !measure.active {
+        return;
+    }
+    let Some(cursor) = cursor_world_position(&windows, &camera_query, &in_game_camera_query) else {
+        return;
+    };
+
+    if mouse.just_pressed(MouseButton::Left) && !pointer.0 {
+        measure.reference = Some(cursor);
+    } else if mouse.just_pressed(MouseButton::Right) {
+        measure.reference = None;
+    }
+
+    gizmos.line_2d(
+        cursor - Vec2::X * CROSSHAIR_HALF_SIZE,
+        cursor + Vec2::X * CROSSHAIR_HALF_SIZE,
+        CROSSHAIR_COLOR,
+    );
+    gizmos.line_2d(
+        cursor - Vec2::Y * CROSSHAIR_HALF_SIZE,
+        cursor + Vec2::Y * CROSSHAIR_HALF_SIZE,
+        CROSSHAIR_COLOR,
+    );
+
+    let mut label = format!("Cursor ({:.1}, {:.1})", cursor.x, cursor.y);
+    if let Some(reference) = measure.reference {
+        let delta = cursor - reference;
+        gizmos.line_2d(reference, cursor, CROSSHAIR_COLOR);
+        label += &format!(
+            "  dx {:.1}  dy {:.1}  distance {:.1}",
+            delta.x,
+            delta.y,
+            delta.length()
+        );
+    } else {
+        label += "  (click to set a reference point)";
+    }
+    hud.set("measure", label);
+}
@@ -0,0 +1,117 @@
+//! Playing the selected receiver's observed frequency on a MIDI synth, only built with the `midi`
+//! feature: the frequency is scaled up into the audible range, rounded to the nearest note and
+//! sent as note on and off events, so the Doppler shift is heard on an external instrument.
+
+use bevy::prelude::*;
+use midir::{MidiOutput, MidiOutputConnection};
+
+use crate::{config::SimConfig, Receiver, SelectedReceiver};
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+const VELOCITY: u8 = 100;
+
+/// MIDI note nearest `frequency` in Hz, with A4 at 440 Hz as note 69, or `None` if it's outside
+/// the notes MIDI has.
+pub fn nearest_note(frequency: f32) -> Option<u8> {
+    if !frequency.is_finite() || frequency <= 0. {
+        return None;
+    }
+    let note = (69. + 12. * (frequency / 440.).log2()).round();
+    (0. ..=127.).contains(&note).then_some(note as u8)
+}
+
+/// The connection to the synth, if one could be made, and the note it's playing.
+pub struct MidiOut {
+    connection: Option<MidiOutputConnection>,
+    channel: u8,
+    playing: Option<u8>,
+}
+
+impl MidiOut {
+    /// Connects to the first output port whose name contains `midi_port`, or the first port if
+    /// that isn't set. Without one, a warning is logged and nothing is played.
+    pub fn connect(config: &SimConfig) -> Self {
+        let connection = MidiOutput::new("doppl-rs")
+            .map_err(|err| err.to_string())
+            .and_then(|output| {
+                let port = output
+                    .ports()
+                    .into_iter()
+                    .find(|port| {
+                        config.midi_port.as_ref().is_none_or(|name| {
+                            output
+                                .port_name(port)
+                                .is_ok_and(|port_name| port_name.contains(name.as_str()))
+                        })
+                    })
+                    .ok_or_else(|| match &config.midi_port {
+                        Some(name) => format!("no output port matching {name:?}"),
+                        None => "no output ports".to_string(),
+                    })?;
+                output
+                    .connect(&port, "doppl-rs")
+                    .map_err(|err| err.to_string())
+            });
+        let connection = match connection {
+            Ok(connection) => Some(connection),
+            Err(err) => {
+                warn!("Not playing the observed frequency over MIDI: {err}");
+                None
+            }
+        };
+        MidiOut {
+            connection,
+            channel: config.midi_channel,
+            playing: None,
+        }
+    }
+
+    /// Stops the note playing, if any, and starts `note`.
+    fn play(&mut self, note: Option<u8>) {
+        if note == self.playing {
+            return;
+        }
+        let Some(connection) = &mut self.connection else {
+            return;
+        };
+        let mut send = |message: &[u8]| {
+            if let Err(err) = connection.send(message) {
+                warn!("MIDI message failed to send: {err}");
+            }
+        };
+        if let Some(playing) = self.playing {
+            send(&[NOTE_OFF | self.channel, playing, 0]);
+        }
+        if let Some(note) = note {
+            send(&[NOTE_ON | self.channel, note, VELOCITY]);
+        }
+        self.playing = note;
+    }
+}
+
+impl Drop for MidiOut {
+    /// Leaves the synth silent rather than holding the last note after the app closes.
+    fn drop(&mut self) {
+        self.play(None);
+    }
+}
+
+/// Plays the note nearest the selected receiver's observed frequency times `midi_pitch_scale`,
+/// changing note as the frequency does, and stops while paused or before the receiver has
+/// measured anything.
+pub fn play_observed_note(
+    mut midi: NonSendMut<MidiOut>,
+    config: Res<SimConfig>,
+    selected: Res<SelectedReceiver>,
+    time: Res<Time<Virtual>>,
+    rx_query: Query<&Receiver>,
+) {
+    let note = rx_query
+        .iter()
+        .find(|rx| rx.index == selected.0)
+        .and_then(|rx| rx.smoothed_frequency)
+        .filter(|_| !time.is_paused())
+        .and_then(|frequency| nearest_note(frequency * config.midi_pitch_scale));
+    midi.play(note);
+}
@@ -0,0 +1,143 @@
+//! A line under each receiver saying whether it samples fast enough for the frequency it should
+//! observe, green while it's under the Nyquist limit and red once it's aliasing, so it's clear
+//! which plots can be trusted without selecting each receiver in turn.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    expected_frequency,
+    follow::WorldLabel,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics, Mover, OverlayFont, Receiver, Transmitter, TransmitterBody, HIGH_RES_LAYERS,
+};
+
+const NYQUIST_LABEL_FONT_SIZE: f32 = 16.;
+/// Gap between the bottom of a receiver and its indicator, leaving room for the distance label.
+const NYQUIST_LABEL_GAP: f32 = 42.;
+const SAMPLED_COLOR: Color = Color::LIME_GREEN;
+const ALIASED_COLOR: Color = Color::RED;
+const UNKNOWN_COLOR: Color = Color::GRAY;
+
+#[derive(Component)]
+pub struct NyquistLabel;
+
+pub fn nyquist_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.nyquist_indicator {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press {} to {action} each receiver's Nyquist indicator",
+        key_name(bindings.nyquist)
+    )
+}
+
+pub fn toggle_nyquist_indicator(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.nyquist) {
+        return;
+    }
+    config.nyquist_indicator = !config.nyquist_indicator;
+    hud.set("nyquist", nyquist_label(&config, &bindings));
+}
+
+/// Gives each new receiver a hidden indicator under its distance label, turned back so it's level
+/// whichever way the receiver lies.
+pub fn spawn_nyquist_labels(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    rx_query: Query<(Entity, &Receiver), Added<Receiver>>,
+) {
+    for (rx_entity, rx) in rx_query.iter() {
+        let unrotate = rx.orientation.rotation().inverse();
+        let offset = -Vec3::Y * (rx.orientation.size().y / 2. + NYQUIST_LABEL_GAP);
+        let label = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: NYQUIST_LABEL_FONT_SIZE,
+                            color: UNKNOWN_COLOR,
+                        },
+                    ),
+                    transform: Transform::from_translation(
+                        (unrotate * offset)
+                            .truncate()
+                            .extend(config.z_layers.relative_to_receiver(config.z_layers.labels)),
+                    )
+                    .with_rotation(unrotate),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                NyquistLabel,
+                WorldLabel,
+                HIGH_RES_LAYERS,
+            ))
+            .id();
+        commands.entity(rx_entity).add_child(label);
+    }
+}
+
+/// Compares the frequency each receiver should observe from its transmitter with half the rate
+/// it's actually sampling at, and colors its indicator to match.
+pub fn update_nyquist_labels(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, Option<&Mover>, &Children)>,
+    tx_query: Query<(&Transmitter, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+    mut label_query: Query<(&mut Text, &mut Visibility), With<NyquistLabel>>,
+) {
+    for (rx, rx_mover, children) in rx_query.iter() {
+        let mut labels = label_query.iter_many_mut(children);
+        let Some((mut text, mut visibility)) = labels.fetch_next() else {
+            continue;
+        };
+        if !config.nyquist_indicator {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let sample_rate = rx.sample_rate();
+        let tx = tx_query.iter().find(|(tx, _)| tx.index == rx.index);
+        let (value, color) = match (tx, sample_rate > 0.) {
+            (Some((tx, tx_children)), true) => {
+                let tx_mover = body_query.iter_many(tx_children).next();
+                let f = expected_frequency(&config, tx, tx_mover, rx, rx_mover);
+                if physics::is_aliased(f, sample_rate) {
+                    (
+                        format!(
+                            "Aliasing: {f:.2} Hz > {:.2} Hz, plots as {:.2} Hz",
+                            sample_rate / 2.,
+                            physics::alias_frequency(f, sample_rate)
+                        ),
+                        ALIASED_COLOR,
+                    )
+                } else {
+                    (
+                        format!("Nyquist OK: {f:.2} Hz < {:.2} Hz", sample_rate / 2.),
+                        SAMPLED_COLOR,
+                    )
+                }
+            }
+            _ => ("Nyquist: waiting for samples".to_string(), UNKNOWN_COLOR),
+        };
+        let section = &mut text.sections[0];
+        if section.value != value {
+            section.value = value;
+        }
+        if section.style.color != color {
+            section.style.color = color;
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    marker::ParticleAppearance,
+    Receiver, Transmitter, TransmitterBody, PARTICLE_COLOR, RECEIVER_COLOR, RECEIVER_PLOT_COLOR,
+    TRANSMITTER_COLOR,
+};
+
+/// Hue of the first transmitter when each transmitter gets its own color. Successive transmitters
+/// step around the color wheel by the golden angle so neighbouring sources never look alike.
+const TRANSMITTER_HUE_START: f32 = 120.;
+const TRANSMITTER_HUE_STEP: f32 = 137.5;
+
+/// Hue of violet, the high-frequency end of the visible spectrum. Red, the low end, is hue 0.
+const SPECTRUM_VIOLET_HUE: f32 = 270.;
+/// Number of distinct colors the spectrum is split into, so particles share a few materials
+/// instead of each frequency getting its own.
+const SPECTRUM_STEPS: u32 = 64;
+/// Number of distinct colors velocity-colored plot points are split into.
+const VELOCITY_STEPS: u32 = 17;
+
+// Colors from the Okabe-Ito palette, which stay distinguishable under the common forms of color
+// blindness.
+const OKABE_ITO_ORANGE: Color = Color::rgb(0.9, 0.62, 0.);
+const OKABE_ITO_SKY_BLUE: Color = Color::rgb(0.34, 0.71, 0.91);
+const OKABE_ITO_BLUISH_GREEN: Color = Color::rgb(0., 0.62, 0.45);
+const OKABE_ITO_YELLOW: Color = Color::rgb(0.94, 0.89, 0.26);
+const OKABE_ITO_BLUE: Color = Color::rgb(0., 0.45, 0.7);
+const OKABE_ITO_VERMILLION: Color = Color::rgb(0.84, 0.37, 0.);
+const OKABE_ITO_REDDISH_PURPLE: Color = Color::rgb(0.8, 0.47, 0.65);
+
+/// Per-transmitter colors for the color-blind-safe palette, excluding the transmitter and receiver
+/// colors themselves.
+const COLOR_BLIND_SAFE_SOURCES: [Color; 5] = [
+    OKABE_ITO_SKY_BLUE,
+    OKABE_ITO_YELLOW,
+    OKABE_ITO_REDDISH_PURPLE,
+    OKABE_ITO_BLUISH_GREEN,
+    OKABE_ITO_VERMILLION,
+];
+
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// Hues that stay distinct with color blindness. The light particles, mid-brightness
+    /// transmitters and dark receivers also differ in brightness, not just hue.
+    ColorBlindSafe,
+}
+
+pub struct PaletteColors {
+    pub particle: Color,
+    pub transmitter: Color,
+    pub receiver: Color,
+    pub plot: Color,
+}
+
+impl Palette {
+    pub fn colors(self) -> PaletteColors {
+        match self {
+            Palette::Standard => PaletteColors {
+                particle: PARTICLE_COLOR,
+                transmitter: TRANSMITTER_COLOR,
+                receiver: RECEIVER_COLOR,
+                plot: RECEIVER_PLOT_COLOR,
+            },
+            Palette::ColorBlindSafe => PaletteColors {
+                particle: OKABE_ITO_SKY_BLUE,
+                transmitter: OKABE_ITO_ORANGE,
+                receiver: OKABE_ITO_BLUE,
+                plot: RECEIVER_PLOT_COLOR,
+            },
+        }
+    }
+
+    /// Picks a distinct color for the transmitter at `index`.
+    fn transmitter_color(self, index: usize) -> Color {
+        match self {
+            Palette::Standard => {
+                let hue = (TRANSMITTER_HUE_START + index as f32 * TRANSMITTER_HUE_STEP) % 360.;
+                Color::hsl(hue, 0.8, 0.5)
+            }
+            Palette::ColorBlindSafe => {
+                COLOR_BLIND_SAFE_SOURCES[index % COLOR_BLIND_SAFE_SOURCES.len()]
+            }
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Palette::Standard => Palette::ColorBlindSafe,
+            Palette::ColorBlindSafe => Palette::Standard,
+        }
+    }
+}
+
+/// Colors of the particles and plot points coming from the transmitter at `index`.
+pub fn source_colors(config: &SimConfig, index: usize) -> (Color, Color) {
+    let (particle, plot) = if config.color_per_transmitter {
+        let color = config.palette.transmitter_color(index);
+        (color, color)
+    } else {
+        let colors = config.palette.colors();
+        (colors.particle, colors.plot)
+    };
+    match config.particle_color {
+        Some([r, g, b]) => (Color::rgb(r, g, b), plot),
+        None => (particle, plot),
+    }
+}
+
+/// Color of visible light with `f` mapped linearly onto the spectrum: red at `f_min`, violet at
+/// `f_max`, and clamped to those ends outside the range.
+pub fn frequency_to_rgb(f: f32, f_min: f32, f_max: f32) -> Color {
+    Color::hsl(
+        spectrum_position(f, f_min, f_max) * SPECTRUM_VIOLET_HUE,
+        1.,
+        0.5,
+    )
+}
+
+/// How far `f` is through `f_min..=f_max`, from 0 to 1.
+fn spectrum_position(f: f32, f_min: f32, f_max: f32) -> f32 {
+    if f_max > f_min {
+        ((f - f_min) / (f_max - f_min)).clamp(0., 1.)
+    } else {
+        0.
+    }
+}
+
+/// Shared particle materials for [`SimConfig::spectrum_colors`], keyed by the frequency quantized
+/// to one of [`SPECTRUM_STEPS`] steps across `spectrum_range`.
+#[derive(Resource, Default)]
+pub struct SpectrumMaterials(HashMap<u32, Handle<ColorMaterial>>);
+
+impl SpectrumMaterials {
+    pub fn get(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        appearance: &ParticleAppearance,
+        config: &SimConfig,
+        frequency: f32,
+    ) -> Handle<ColorMaterial> {
+        let [f_min, f_max] = config.spectrum_range;
+        let t = spectrum_position(frequency, f_min, f_max);
+        let step = (t * (SPECTRUM_STEPS - 1) as f32).round() as u32;
+        self.0
+            .entry(step)
+            .or_insert_with(|| {
+                let quantized = f_min + step as f32 / (SPECTRUM_STEPS - 1) as f32 * (f_max - f_min);
+                materials.add(appearance.material(frequency_to_rgb(quantized, f_min, f_max)))
+            })
+            .clone()
+    }
+}
+
+/// Shared particle and plot point materials for [`SimConfig::emission_hues`], keyed by the number
+/// of hues in the cycle and which of them it is.
+#[derive(Resource, Default)]
+pub struct EmissionHueMaterials {
+    particles: HashMap<(u32, u32), Handle<ColorMaterial>>,
+    plot_points: HashMap<(u32, u32), Handle<ColorMaterial>>,
+}
+
+/// Which of `hues` steps round the color wheel a transmitter's `emission`th emission gets, and
+/// that color.
+fn emission_hue(hues: u32, emission: u32) -> (u32, Color) {
+    let hues = hues.max(1);
+    let step = emission % hues;
+    (step, Color::hsl(step as f32 / hues as f32 * 360., 1., 0.5))
+}
+
+impl EmissionHueMaterials {
+    /// Material for a transmitter's `emission`th emission, going round the color wheel in `hues`
+    /// steps.
+    pub fn get(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        appearance: &ParticleAppearance,
+        hues: u32,
+        emission: u32,
+    ) -> Handle<ColorMaterial> {
+        let (step, color) = emission_hue(hues, emission);
+        self.particles
+            .entry((hues, step))
+            .or_insert_with(|| materials.add(appearance.material(color)))
+            .clone()
+    }
+
+    /// Material for the plot points of samples from a transmitter's `emission`th emission, the
+    /// same color as its particles.
+    pub fn plot_point(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        hues: u32,
+        emission: u32,
+    ) -> Handle<ColorMaterial> {
+        let (step, color) = emission_hue(hues, emission);
+        self.plot_points
+            .entry((hues, step))
+            .or_insert_with(|| materials.add(color))
+            .clone()
+    }
+}
+
+/// Shared plot point materials for [`SimConfig::velocity_colored_plot`], keyed by the particle's
+/// velocity across its path quantized to one of [`VELOCITY_STEPS`] steps.
+#[derive(Resource, Default)]
+pub struct VelocityMaterials(HashMap<u32, Handle<ColorMaterial>>);
+
+impl VelocityMaterials {
+    /// Material for a particle moving across its path at `fraction` of the wave's peak speed
+    /// across it: blue falling, white at a crest or trough and red rising.
+    pub fn get(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        fraction: f32,
+    ) -> Handle<ColorMaterial> {
+        let position = (fraction.clamp(-1., 1.) + 1.) / 2.;
+        let step = (position * (VELOCITY_STEPS - 1) as f32).round() as u32;
+        self.0
+            .entry(step)
+            .or_insert_with(|| {
+                let quantized = step as f32 / (VELOCITY_STEPS - 1) as f32 * 2. - 1.;
+                materials.add(velocity_color(quantized))
+            })
+            .clone()
+    }
+}
+
+/// Diverging blue-white-red color for a velocity `fraction` from -1 to 1.
+fn velocity_color(fraction: f32) -> Color {
+    let fade = 1. - fraction.abs();
+    if fraction < 0. {
+        Color::rgb(fade, fade, 1.)
+    } else {
+        Color::rgb(1., fade, fade)
+    }
+}
+
+/// Switches palette and recolors everything already on screen.
+pub fn switch_palette(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    tx_query: Query<&Transmitter>,
+    body_query: Query<&Handle<ColorMaterial>, With<TransmitterBody>>,
+    rx_query: Query<&Handle<ColorMaterial>, With<Receiver>>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.palette) {
+        return;
+    }
+    config.palette = config.palette.next();
+    hud.set("palette", palette_label(config.palette, &bindings));
+
+    let colors = config.palette.colors();
+    let mut recolor = |handle: &Handle<ColorMaterial>, color: Color| {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color;
+        }
+    };
+    for tx in tx_query.iter() {
+        let (particle, plot) = source_colors(&config, tx.index);
+        recolor(&tx.particle_material, particle);
+        recolor(&tx.plot_material, plot);
+    }
+    for body_material in body_query.iter() {
+        recolor(body_material, colors.transmitter);
+    }
+    for rx_material in rx_query.iter() {
+        recolor(rx_material, colors.receiver);
+    }
+}
+
+pub fn palette_label(palette: Palette, bindings: &KeyBindings) -> String {
+    let key = key_name(bindings.palette);
+    format!("Palette: {palette:?} (press {key} to switch)")
+}
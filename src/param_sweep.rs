@@ -0,0 +1,153 @@
+//! Parameter sweeps: one of the settings panel's settings is moved smoothly from one value to
+//! another while every frame is saved, so the frames can be made into a GIF of the wave changing
+//! with it.
+
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capture::{self, ScreenshotKind},
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    settings::{apply_setting, SliderSetting},
+    CurrentAmplitude, SelectedReceiver, Transmitter,
+};
+
+/// A setting to sweep and the values to sweep it between.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParameterSweep {
+    /// The setting to sweep. The frequency is the selected receiver's transmitter's.
+    pub param: SliderSetting,
+    /// Value at the start of the sweep.
+    pub start: f32,
+    /// Value at the end of the sweep.
+    pub end: f32,
+    /// Seconds of simulation time the sweep takes.
+    pub duration_secs: f32,
+}
+
+impl Default for ParameterSweep {
+    fn default() -> Self {
+        Self {
+            param: SliderSetting::Frequency,
+            start: 1.,
+            end: 5.,
+            duration_secs: 10.,
+        }
+    }
+}
+
+impl ParameterSweep {
+    /// Value `elapsed` seconds into the sweep, held at the end value once it's over.
+    fn value_at(&self, elapsed: f32) -> f32 {
+        let fraction = if self.duration_secs > 0. {
+            (elapsed / self.duration_secs).clamp(0., 1.)
+        } else {
+            1.
+        };
+        self.start + (self.end - self.start) * fraction
+    }
+}
+
+/// The sweep in progress, if any.
+#[derive(Resource, Default)]
+pub struct RunningSweep(Option<SweepProgress>);
+
+pub struct SweepProgress {
+    sweep: ParameterSweep,
+    /// Seconds since the sweep started.
+    elapsed: f32,
+    /// Frames saved so far.
+    frames: u32,
+}
+
+pub fn sweep_help(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let sweep = &config.parameter_sweep;
+    format!(
+        "Press {} to sweep {} from {} to {} over {}s, saving every frame",
+        key_name(bindings.parameter_sweep),
+        sweep.param.label(),
+        sweep.param.format(sweep.start),
+        sweep.param.format(sweep.end),
+        sweep.duration_secs
+    )
+}
+
+/// Starts the configured sweep when its key is pressed, or stops one in progress. While it runs,
+/// sets the swept value for the time since it started and saves a frame, then stops once the
+/// end value's frame has been saved.
+pub fn run_parameter_sweep(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    time: Res<Time>,
+    selected: Res<SelectedReceiver>,
+    main_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut running: ResMut<RunningSweep>,
+    mut config: ResMut<SimConfig>,
+    mut amplitude: ResMut<CurrentAmplitude>,
+    mut tx_query: Query<&mut Transmitter>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.parameter_sweep) {
+        running.0 = match running.0 {
+            Some(ref progress) => {
+                info!("Parameter sweep stopped after {} frames", progress.frames);
+                None
+            }
+            None => Some(SweepProgress {
+                sweep: config.parameter_sweep,
+                elapsed: 0.,
+                frames: 0,
+            }),
+        };
+        hud.set("parameter_sweep", sweep_help(&config, &bindings));
+    }
+    let Some(progress) = running.0.as_mut() else {
+        return;
+    };
+
+    let sweep = progress.sweep;
+    let value = sweep.value_at(progress.elapsed);
+    apply_setting(
+        sweep.param,
+        value,
+        &mut config,
+        &mut amplitude,
+        selected.0,
+        &mut tx_query,
+        &bindings,
+        &mut hud,
+    );
+    let path = capture::screenshot_path(&config, ScreenshotKind::Sweep, progress.frames);
+    if let Ok(window) = main_window.get_single() {
+        capture::save_window(
+            &mut screenshot_manager,
+            window,
+            path,
+            None,
+            config.export_contrast,
+        );
+    }
+    progress.frames += 1;
+    hud.set(
+        "parameter_sweep",
+        format!(
+            "Sweeping {}: {} (frame {}, press {} to stop)",
+            sweep.param.label(),
+            sweep.param.format(value),
+            progress.frames,
+            key_name(bindings.parameter_sweep)
+        ),
+    );
+
+    if progress.elapsed >= sweep.duration_secs {
+        info!("Parameter sweep saved {} frames", progress.frames);
+        running.0 = None;
+        hud.set("parameter_sweep", sweep_help(&config, &bindings));
+    } else {
+        progress.elapsed += time.delta_seconds();
+    }
+}
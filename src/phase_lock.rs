@@ -0,0 +1,76 @@
+//! Comparison figures at the same point in the wave: with `phase_lock` set, the simulation pauses
+//! and snapshots the canvas the moment a transmitter's leading wavefront reaches a marked line,
+//! so runs with different parameters are all captured with the wave in the same place.
+
+use bevy::prelude::*;
+
+use crate::{
+    capture::{screenshot_path, ScreenshotKind, SnapshotCanvas},
+    config::SimConfig,
+    hud::Hud,
+    keybindings::KeyBindings,
+    pause_label,
+    pin::Pinned,
+    SignalParticle, SimulationReset, Transmitter, RES_HEIGHT,
+};
+
+const MARKER_COLOR: Color = Color::rgba(1., 1., 0., 0.4);
+
+/// Pauses and snapshots the canvas, once a run, the first frame a particle from the
+/// `phase_lock` transmitter is past the marked line on the far side from where it was emitted.
+/// Only a reset arms it again, so later bursts or a front eaten by a receiver aren't captured
+/// too.
+pub fn lock_phase(
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    tx_query: Query<(&Transmitter, &GlobalTransform, &Children)>,
+    particle_query: Query<(&SignalParticle, &GlobalTransform), Without<Pinned>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut snapshots: EventWriter<SnapshotCanvas>,
+    mut hud: ResMut<Hud>,
+    mut reset_events: EventReader<SimulationReset>,
+    mut captured: Local<bool>,
+    mut counter: Local<u32>,
+) {
+    if reset_events.read().count() > 0 {
+        *captured = false;
+    }
+    let Some(lock) = config.phase_lock else {
+        return;
+    };
+    if *captured {
+        return;
+    }
+    let reached = tx_query
+        .iter()
+        .filter(|(tx, ..)| tx.index == lock.transmitter)
+        .any(|(_, tx_transform, children)| {
+            particle_query
+                .iter_many(children)
+                .any(|(particle, transform)| {
+                    let emitted = tx_transform.translation().x + particle.origin.x;
+                    (transform.translation().x - lock.x) * (emitted - lock.x) <= 0.
+                })
+        });
+    if reached {
+        time.pause();
+        hud.set("pause", pause_label(true, &bindings));
+        let path = screenshot_path(&config, ScreenshotKind::PhaseLock, *counter);
+        snapshots.send(SnapshotCanvas(path));
+        *counter += 1;
+        *captured = true;
+    }
+}
+
+/// Draws the line the `phase_lock` waits for the wavefront to reach.
+pub fn draw_phase_lock_marker(config: Res<SimConfig>, mut gizmos: Gizmos) {
+    let Some(lock) = config.phase_lock else {
+        return;
+    };
+    let half_height = RES_HEIGHT as f32 / 2.;
+    gizmos.line_2d(
+        Vec2::new(lock.x, -half_height),
+        Vec2::new(lock.x, half_height),
+        MARKER_COLOR,
+    );
+}
@@ -0,0 +1,192 @@
+//! A long exposure of the waves: each particle's displacement is added into a low resolution
+//! map wherever it passes, fading away over time, so the wave's shape in space builds up behind
+//! the particles like a photo taken with the shutter held open. Unlike the heatmap, which shows
+//! the field at one instant, it shows where the waves have been.
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::{
+    capture::ScreenshotKind,
+    config::SimConfig,
+    field_export::{self, FieldExtents},
+    heatmap,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    pin::Pinned,
+    SignalParticle, PIXEL_PERFECT_LAYERS, RES_HEIGHT, RES_WIDTH,
+};
+
+#[derive(Resource)]
+pub struct PhaseMap {
+    image: Handle<Image>,
+    visible: bool,
+    /// Seconds of displacement, as a fraction of the amplitude, built up in each cell, a row at a
+    /// time from the top left.
+    exposure: Vec<f32>,
+    columns: u32,
+    rows: u32,
+}
+
+impl PhaseMap {
+    /// Index into `exposure` of the cell over `point`, if it's on the canvas.
+    fn cell(&self, point: Vec2) -> Option<usize> {
+        let size = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+        let offset = Vec2::new(point.x + size.x / 2., size.y / 2. - point.y) / size;
+        if !(0. ..1.).contains(&offset.x) || !(0. ..1.).contains(&offset.y) {
+            return None;
+        }
+        let column = (offset.x * self.columns as f32) as u32;
+        let row = (offset.y * self.rows as f32) as u32;
+        Some((row * self.columns + column) as usize)
+    }
+}
+
+#[derive(Component)]
+pub struct PhaseMapSprite;
+
+pub fn phase_map_label(visible: bool, bindings: &KeyBindings) -> String {
+    let action = if visible { "hide" } else { "show" };
+    let key = key_name(bindings.phase_map);
+    format!("Press {key} to {action} the long exposure phase map, shift+{key} to save it")
+}
+
+pub fn setup_phase_map(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<SimConfig>,
+) {
+    let [columns, rows] = config.phase_map_cells.map(|cells| cells.max(1));
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width: columns,
+            height: rows,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        SpriteBundle {
+            texture: image.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., config.z_layers.phase_map),
+            visibility: if config.phase_map {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+        PhaseMapSprite,
+        PIXEL_PERFECT_LAYERS,
+    ));
+    commands.insert_resource(PhaseMap {
+        image,
+        visible: config.phase_map,
+        exposure: vec![0.; (columns * rows) as usize],
+        columns,
+        rows,
+    });
+}
+
+/// Shows or hides the phase map, starting a fresh exposure each time it's shown. With shift it
+/// saves the exposure so far instead.
+pub fn toggle_phase_map(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    mut phase_map: ResMut<PhaseMap>,
+    mut sprite_query: Query<&mut Visibility, With<PhaseMapSprite>>,
+    mut hud: ResMut<Hud>,
+    mut counter: Local<u32>,
+) {
+    if !input.just_pressed(bindings.phase_map) {
+        return;
+    }
+    if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        let extents = FieldExtents::canvas(
+            phase_map.columns,
+            phase_map.rows,
+            time.elapsed().as_millis() as f32 / 1000.,
+        );
+        let saved = field_export::save_grid(
+            &config,
+            ScreenshotKind::PhaseMap,
+            *counter,
+            &phase_map.exposure,
+            &extents,
+        );
+        *counter += 1;
+        match saved {
+            Ok(path) => info!("Saved the phase map to {}", path.display()),
+            Err(err) => error!("Cannot save the phase map: {err}"),
+        }
+        return;
+    }
+    phase_map.visible = !phase_map.visible;
+    phase_map.exposure.fill(0.);
+    for mut visibility in sprite_query.iter_mut() {
+        *visibility = if phase_map.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    hud.set("phase_map", phase_map_label(phase_map.visible, &bindings));
+}
+
+/// Fades the exposure by `phase_map_half_life`, then adds each particle's displacement, as a
+/// fraction of its amplitude, to the cell it's in for as long as it's there, and redraws the map
+/// scaled to the strongest cell. Stands still while paused.
+pub fn expose_phase_map(
+    mut phase_map: ResMut<PhaseMap>,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    particle_query: Query<(&SignalParticle, &GlobalTransform), Without<Pinned>>,
+) {
+    let dt = time.delta_seconds();
+    if !phase_map.visible || dt == 0. {
+        return;
+    }
+    let fade = 0.5f32.powf(dt / config.phase_map_half_life);
+    for value in phase_map.exposure.iter_mut() {
+        *value *= fade;
+    }
+    for (particle, transform) in particle_query.iter() {
+        if particle.amplitude == 0. {
+            continue;
+        }
+        if let Some(cell) = phase_map.cell(transform.translation().xy()) {
+            phase_map.exposure[cell] += particle.displacement / particle.amplitude * dt;
+        }
+    }
+
+    let Some(image) = images.get_mut(&phase_map.image) else {
+        return;
+    };
+    let brightest = phase_map
+        .exposure
+        .iter()
+        .fold(0., |brightest: f32, value| brightest.max(value.abs()));
+    for (pixel, value) in image.data.chunks_exact_mut(4).zip(&phase_map.exposure) {
+        let value = if brightest > 0. {
+            value / brightest
+        } else {
+            0.
+        };
+        pixel.copy_from_slice(&heatmap::field_color(value));
+    }
+}
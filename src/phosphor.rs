@@ -0,0 +1,99 @@
+//! Phosphor persistence: plot points fade out over `phosphor_decay_secs` like the glow on an
+//! analog oscilloscope's screen, instead of staying until the plot is cleared.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{config::SimConfig, SimulationReset};
+
+/// Distinct brightness levels a point fades through. Points on the same level share a material,
+/// so this bounds how many materials fading needs.
+pub const FADE_LEVELS: u8 = 16;
+
+/// A plot point that fades out and is then removed.
+#[derive(Component)]
+pub struct Phosphor {
+    /// When it was plotted, in seconds of simulation time.
+    plotted_at: f32,
+    /// Its material at full brightness.
+    material: Handle<ColorMaterial>,
+    level: u8,
+}
+
+impl Phosphor {
+    pub fn new(plotted_at: f32, material: Handle<ColorMaterial>) -> Self {
+        Self {
+            plotted_at,
+            material,
+            level: FADE_LEVELS,
+        }
+    }
+}
+
+/// Faded copies of the plot and particle materials, by full brightness material and level.
+#[derive(Resource, Default)]
+pub struct FadedMaterials(HashMap<(AssetId<ColorMaterial>, u8), Handle<ColorMaterial>>);
+
+impl FadedMaterials {
+    /// `full` with its alpha scaled down to `level` out of [`FADE_LEVELS`], made the first time
+    /// it's asked for. `None` if `full` has gone.
+    pub fn get(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        full: &Handle<ColorMaterial>,
+        level: u8,
+    ) -> Option<Handle<ColorMaterial>> {
+        if level >= FADE_LEVELS {
+            return Some(full.clone());
+        }
+        let key = (full.id(), level);
+        if let Some(handle) = self.0.get(&key) {
+            return Some(handle.clone());
+        }
+        let mut dimmed = materials.get(full)?.clone();
+        let alpha = dimmed.color.a() * level as f32 / FADE_LEVELS as f32;
+        dimmed.color.set_a(alpha);
+        let handle = materials.add(dimmed);
+        self.0.insert(key, handle.clone());
+        Some(handle)
+    }
+}
+
+/// Brightness level, out of [`FADE_LEVELS`], of a point `age` seconds old that fades out over
+/// `decay` seconds. Zero once it has faded out completely.
+fn fade_level(age: f32, decay: f32) -> u8 {
+    let remaining = (1. - age / decay).clamp(0., 1.);
+    (remaining * FADE_LEVELS as f32).ceil() as u8
+}
+
+pub fn fade_phosphor(
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut faded: ResMut<FadedMaterials>,
+    mut point_query: Query<(Entity, &mut Phosphor, &mut Handle<ColorMaterial>)>,
+    mut reset_events: EventReader<SimulationReset>,
+) {
+    // Transmitters get new materials when they're rebuilt, so the old faded copies are unused.
+    if reset_events.read().count() > 0 {
+        faded.0.clear();
+    }
+    let Some(decay) = config.phosphor_decay_secs else {
+        return;
+    };
+    let now = time.elapsed().as_millis() as f32 / 1000.;
+    for (entity, mut phosphor, mut material) in point_query.iter_mut() {
+        let level = fade_level(now - phosphor.plotted_at, decay);
+        if level == phosphor.level {
+            continue;
+        }
+        phosphor.level = level;
+        if level == 0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if let Some(handle) = faded.get(&mut materials, &phosphor.material, level) {
+            *material = handle;
+        }
+    }
+}
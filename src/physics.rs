@@ -0,0 +1,738 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use bevy::{
+    math::{DMat3, DVec3, Vec2},
+    reflect::Reflect,
+};
+use serde::{Deserialize, Serialize};
+
+/// Which Doppler formula to use when predicting the frequency a receiver observes.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DopplerFormula {
+    #[default]
+    Classical,
+    /// Treats the wave speed as the speed of light.
+    Relativistic,
+}
+
+/// How a wave's angular frequency depends on its wave number, `ω(k)`. With dispersion, waves are
+/// drawn as packets so the group velocity `dω/dk` can be seen apart from the phase velocity
+/// `ω/k` the particles travel at.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Serialize, Deserialize)]
+pub enum Dispersion {
+    /// `ω = speed * k`: every wavelength travels at the wave speed, so there's nothing to tell
+    /// the groups and crests apart.
+    #[default]
+    None,
+    /// `ω ∝ k^exponent`, scaled so the carrier still travels at the wave speed. Groups travel at
+    /// `exponent` times the phase speed: 0.5 for deep water waves, where crests run forwards
+    /// through each group, or 2 for a free quantum particle, where groups outrun their crests.
+    PowerLaw { exponent: f32 },
+}
+
+/// How a saturating detector limits the signal to its clip level.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipShape {
+    /// Cuts the signal off flat at the clip level, adding odd harmonics at every crest.
+    #[default]
+    Hard,
+    /// `level * tanh(value / level)`: linear for small signals and rounding off smoothly into the
+    /// clip level, so the harmonics are weaker.
+    Soft,
+}
+
+/// Carrier wavelengths in each wave packet of a dispersive wave.
+const PACKET_WAVELENGTHS: f32 = 4.;
+
+/// Wave number and angular frequency of the envelope a dispersive wave is modulated by, or `None`
+/// without dispersion or for a standing wave.
+///
+/// The wave is treated as the sum of two waves with wave numbers `k ± dk` either side of the
+/// carrier's `k = 2πf / speed`, and frequencies taken from the dispersion relation at those wave
+/// numbers. Their beats form an envelope `cos(dk x - dω t)` that travels at `dω / dk`, which
+/// tends to the group velocity as the packets get longer.
+pub fn modulation(frequency: f32, speed: f32, dispersion: Dispersion) -> Option<(f32, f32)> {
+    let Dispersion::PowerLaw { exponent } = dispersion else {
+        return None;
+    };
+    if speed == 0. {
+        return None;
+    }
+    let omega = TAU * frequency;
+    let k = omega / speed;
+    let dk = k / (2. * PACKET_WAVELENGTHS);
+    // `ω(k')` normalised so the carrier's own frequency comes out as `omega`.
+    let omega_at = |k_prime: f32| omega * (k_prime / k).powf(exponent);
+    Some((dk, (omega_at(k + dk) - omega_at(k - dk)) / 2.))
+}
+
+/// Speed the wave packets of a dispersive wave travel at, signed like `speed`. Without
+/// dispersion that's just `speed`.
+pub fn group_speed(frequency: f32, speed: f32, dispersion: Dispersion) -> f32 {
+    match modulation(frequency, speed, dispersion) {
+        Some((dk, d_omega)) => d_omega / dk,
+        None => speed,
+    }
+}
+
+/// Local amplitude of a wave `x` along its direction of travel at time `t`: `amplitude` without
+/// dispersion, otherwise `amplitude * cos(dk x - dω t)` (see [`modulation`]). Particles keep
+/// riding the carrier at the wave speed while this envelope slides past them at the group speed.
+pub fn envelope(
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+    dispersion: Dispersion,
+    x: f32,
+    t: f32,
+) -> f32 {
+    match modulation(frequency, speed, dispersion) {
+        Some((dk, d_omega)) => amplitude * (dk * x - d_omega * t).cos(),
+        None => amplitude,
+    }
+}
+
+/// Classical Doppler shift for a source and observer moving along the line between them.
+///
+/// Both velocities are positive when moving towards the other party. When the source catches up
+/// with its own waves (`source_velocity >= wave_speed`) the observed frequency is infinite.
+pub fn observed_frequency(
+    source_freq: f32,
+    wave_speed: f32,
+    source_velocity: f32,
+    observer_velocity: f32,
+) -> f32 {
+    let wave_speed = wave_speed.abs();
+    if source_velocity >= wave_speed {
+        return f32::INFINITY;
+    }
+    (source_freq * (wave_speed + observer_velocity) / (wave_speed - source_velocity)).max(0.)
+}
+
+/// Relativistic (longitudinal) Doppler shift, where `beta` is the closing speed as a fraction of
+/// the speed of light: positive when approaching, negative when receding.
+///
+/// `beta` is only meaningful in `(-1, 1)`; at or beyond the limits the result saturates to
+/// infinity (approaching) or zero (receding).
+pub fn relativistic_observed_frequency(source_freq: f32, beta: f32) -> f32 {
+    if beta >= 1. {
+        return f32::INFINITY;
+    }
+    if beta <= -1. {
+        return 0.;
+    }
+    source_freq * ((1. + beta) / (1. - beta)).sqrt()
+}
+
+/// Frequency a receiver observes from a source using the chosen formula. Velocities are the same
+/// as for [`observed_frequency`].
+///
+/// For the relativistic formula the two velocities are combined with relativistic velocity
+/// addition, so only their relative speed matters. For small speeds both formulas agree to first
+/// order in `v / wave_speed`.
+///
+/// A standing wave (zero wave speed) oscillates in place at the source frequency, which is what
+/// either formula gives for it.
+pub fn doppler_shift(
+    formula: DopplerFormula,
+    source_freq: f32,
+    wave_speed: f32,
+    source_velocity: f32,
+    observer_velocity: f32,
+) -> f32 {
+    if wave_speed == 0. {
+        return source_freq;
+    }
+    match formula {
+        DopplerFormula::Classical => {
+            observed_frequency(source_freq, wave_speed, source_velocity, observer_velocity)
+        }
+        DopplerFormula::Relativistic => {
+            let c = wave_speed.abs();
+            let (bs, bo) = (source_velocity / c, observer_velocity / c);
+            relativistic_observed_frequency(source_freq, (bs + bo) / (1. + bs * bo))
+        }
+    }
+}
+
+/// Seconds a wave travelling at `speed` takes to cover `distance`, or `None` for a standing wave,
+/// which never arrives.
+pub fn time_of_flight(distance: f32, speed: f32) -> Option<f32> {
+    (speed != 0.).then(|| distance / speed.abs())
+}
+
+/// What a detector that saturates at `level` reads for `value`. An infinite level never clips.
+pub fn clip(value: f32, level: f32, shape: ClipShape) -> f32 {
+    if level.is_infinite() {
+        return value;
+    }
+    match shape {
+        ClipShape::Hard => value.clamp(-level, level),
+        ClipShape::Soft => level * (value / level).tanh(),
+    }
+}
+
+/// Half-angle of the Mach cone, `asin(wave_speed / source_speed)` in radians, trailing a source
+/// that outruns its own waves, or `None` while it's slower than them and no cone forms. A
+/// standing wave, with a wave speed of 0, doesn't travel, so there's nothing to outrun.
+pub fn mach_angle(source_speed: f32, wave_speed: f32) -> Option<f32> {
+    let (source_speed, wave_speed) = (source_speed.abs(), wave_speed.abs());
+    (wave_speed > 0. && source_speed > wave_speed).then(|| (wave_speed / source_speed).asin())
+}
+
+/// Signal-to-noise ratio in decibels of `samples`, `(time, value)` pairs, taking the signal to
+/// be a sine at `frequency`.
+///
+/// The sine, with whatever amplitude, phase and offset fit the samples best in the least squares
+/// sense, is the signal and everything left over is noise. A clean sine sampled anywhere comes out
+/// at `inf`; adding independent noise with an RMS of a tenth of its amplitude gives about 17 dB,
+/// `10 log10((1/2) / 0.1²)`. Harmonics from distortion count as noise too. `None` if there are too
+/// few samples, or too few distinct times, to fit the sine.
+pub fn estimate_snr(samples: &[(f32, f32)], frequency: f32) -> Option<f32> {
+    if samples.len() < 4 {
+        return None;
+    }
+    let omega = TAU as f64 * frequency as f64;
+    let basis = |t: f32| {
+        let phase = omega * t as f64;
+        DVec3::new(phase.cos(), phase.sin(), 1.)
+    };
+    // Normal equations for the coefficients of cos, sin and the offset.
+    let (mut normal, mut rhs) = (DMat3::ZERO, DVec3::ZERO);
+    for &(t, value) in samples {
+        let b = basis(t);
+        normal += DMat3::from_cols(b * b.x, b * b.y, b * b.z);
+        rhs += b * value as f64;
+    }
+    if normal.determinant().abs() < 1e-9 {
+        return None;
+    }
+    let fit = normal.inverse() * rhs;
+
+    let signal_power = (fit.x * fit.x + fit.y * fit.y) / 2.;
+    let noise_power = samples
+        .iter()
+        .map(|&(t, value)| (value as f64 - basis(t).dot(fit)).powi(2))
+        .sum::<f64>()
+        / samples.len() as f64;
+    Some((10. * (signal_power / noise_power).log10()) as f32)
+}
+
+/// Period in seconds of a wave at `frequency`, or `None` if it never repeats (zero) or repeats
+/// infinitely fast, like the shock of a source outrunning its waves.
+pub fn period(frequency: f32) -> Option<f32> {
+    (frequency.is_finite() && frequency > 0.).then(|| 1. / frequency)
+}
+
+/// Running frequency estimate from `samples` of `(time, value)` in time order: one `(time,
+/// frequency)` pair at each upward zero crossing after the first, from the time since the one
+/// before. Crossing times are interpolated between the samples either side, so the estimate isn't
+/// limited to the sample spacing.
+///
+/// A crossing only counts once the signal has gone below `-hysteresis` and then come all the way
+/// up to `hysteresis`, so noise wobbling around zero doesn't add false crossings. It's timed at
+/// the last time the signal rose through zero on the way.
+pub fn zero_crossing_frequencies(samples: &[(f32, f32)], hysteresis: f32) -> Vec<(f32, f32)> {
+    let mut below = false;
+    let mut rose_at = None;
+    let mut crossings = Vec::new();
+    for pair in samples.windows(2) {
+        let [(t0, v0), (t1, v1)] = [pair[0], pair[1]];
+        if v0 < -hysteresis {
+            below = true;
+            rose_at = None;
+        }
+        if v0 < 0. && v1 >= 0. {
+            rose_at = Some(t0 + (t1 - t0) * -v0 / (v1 - v0));
+        }
+        if below && v1 >= hysteresis {
+            if let Some(t) = rose_at.take() {
+                crossings.push(t);
+                below = false;
+            }
+        }
+    }
+    let mut previous = None;
+    crossings
+        .into_iter()
+        .filter_map(|t| {
+            let period = t - previous.replace(t)?;
+            (period > 0.).then(|| (t, 1. / period))
+        })
+        .collect()
+}
+
+/// Whether sampling at `sample_rate` is too slow to capture `frequency`, i.e. it's below the
+/// Nyquist rate of twice the frequency.
+pub fn is_aliased(frequency: f32, sample_rate: f32) -> bool {
+    sample_rate < 2. * frequency
+}
+
+/// Frequency that samples taken at `sample_rate` appear to have when the signal is at
+/// `frequency`: the distance to the nearest multiple of the sample rate. Frequencies below the
+/// Nyquist limit come out unchanged; above it they fold back, so 9 Hz sampled at 10 Hz looks like
+/// 1 Hz.
+pub fn alias_frequency(frequency: f32, sample_rate: f32) -> f32 {
+    if sample_rate <= 0. {
+        return 0.;
+    }
+    (frequency - sample_rate * (frequency / sample_rate).round()).abs()
+}
+
+/// Phase, in `[0, 2π)`, of a wave at `frequency` that took `travel_secs` to arrive, relative to an
+/// oscillator at the source frequency kept running at the receiver. A stationary pair sees a
+/// constant phase; a Doppler shift makes it advance or retreat at `2π` times the shift.
+pub fn relative_phase(frequency: f32, travel_secs: f32) -> f32 {
+    (-TAU * frequency * travel_secs).rem_euclid(TAU)
+}
+
+/// Fraction of the speed limit at which a speed counts as approaching it.
+const APPROACHING_LIMIT_FRACTION: f32 = 0.9;
+
+/// How a speed compares to a speed limit, such as the wave speed or speed of light.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpeedCheck {
+    Safe,
+    /// Within 10% of the limit, where the classical formula starts to blow up.
+    Approaching,
+    /// At or beyond the limit, where neither formula gives a meaningful frequency.
+    Exceeded,
+}
+
+/// Compares the magnitude of `speed` to `limit`.
+pub fn check_speed(speed: f32, limit: f32) -> SpeedCheck {
+    let (speed, limit) = (speed.abs(), limit.abs());
+    if speed >= limit {
+        SpeedCheck::Exceeded
+    } else if speed >= APPROACHING_LIMIT_FRACTION * limit {
+        SpeedCheck::Approaching
+    } else {
+        SpeedCheck::Safe
+    }
+}
+
+/// Slowest non-zero wave speed allowed. Slower waves have such short wavelengths that particles
+/// barely move and the wave number overflows.
+pub const MIN_WAVE_SPEED: f32 = 1.;
+
+/// One sine component of a periodic signal.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+pub struct Harmonic {
+    /// Multiple of the transmitter's frequency, 1 for the fundamental.
+    pub number: u32,
+    /// Amplitude relative to the other harmonics. The sum is scaled so its peak reaches the
+    /// transmitter's amplitude.
+    pub amplitude: f32,
+    /// Phase in degrees at the start of each period of the fundamental.
+    #[serde(default)]
+    pub phase: f32,
+}
+
+/// Points per period the sum of harmonics is checked at when finding its peak.
+const HARMONIC_PEAK_SAMPLES: usize = 1024;
+
+/// One period of the signal a transmitter sends, as a function of phase.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum WaveShape {
+    #[default]
+    Sine,
+    /// Samples evenly spaced over one period, peaking at ±1, interpolated linearly and wrapping
+    /// around from the last back to the first. Phase zero is the first sample.
+    Table(Arc<[f32]>),
+    /// A sum of sines at whole multiples of the frequency, multiplied by `scale` so it peaks at
+    /// ±1.
+    Harmonics {
+        harmonics: Arc<[Harmonic]>,
+        scale: f32,
+    },
+}
+
+impl WaveShape {
+    /// Builds a table from `samples` of one period, scaled so the largest reaches ±1. Fails if
+    /// there are fewer than two samples, any isn't finite, or they're all zero.
+    pub fn from_samples(samples: &[f32]) -> Result<WaveShape, String> {
+        if samples.len() < 2 {
+            return Err(format!("need at least 2 samples, got {}", samples.len()));
+        }
+        if let Some(bad) = samples.iter().find(|sample| !sample.is_finite()) {
+            return Err(format!("sample {bad} isn't a finite number"));
+        }
+        let peak = samples.iter().fold(0., |peak: f32, v| peak.max(v.abs()));
+        if peak == 0. {
+            return Err("every sample is zero".to_string());
+        }
+        Ok(WaveShape::Table(samples.iter().map(|v| v / peak).collect()))
+    }
+
+    /// Builds a sum of `harmonics`, scaled so its peak reaches ±1. Fails if there are none, any
+    /// has a harmonic number of 0 or an amplitude or phase that isn't finite, or they add up to
+    /// nothing.
+    pub fn from_harmonics(harmonics: &[Harmonic]) -> Result<WaveShape, String> {
+        if harmonics.is_empty() {
+            return Err("need at least 1 harmonic".to_string());
+        }
+        for harmonic in harmonics {
+            if harmonic.number == 0 {
+                return Err("harmonic numbers start at 1, for the fundamental".to_string());
+            }
+            if !harmonic.amplitude.is_finite() || !harmonic.phase.is_finite() {
+                return Err(format!("harmonic {} isn't finite", harmonic.number));
+            }
+        }
+        let harmonics: Arc<[Harmonic]> = harmonics.into();
+        let sum = WaveShape::Harmonics {
+            harmonics: harmonics.clone(),
+            scale: 1.,
+        };
+        let peak = (0..HARMONIC_PEAK_SAMPLES)
+            .map(|i| {
+                sum.value(TAU * i as f32 / HARMONIC_PEAK_SAMPLES as f32)
+                    .abs()
+            })
+            .fold(0., f32::max);
+        if peak == 0. {
+            return Err("the harmonics add up to zero".to_string());
+        }
+        Ok(WaveShape::Harmonics {
+            harmonics,
+            scale: 1. / peak,
+        })
+    }
+
+    /// Value at `phase` radians, between -1 and 1. `Sine` is just `sin(phase)`.
+    pub fn value(&self, phase: f32) -> f32 {
+        match self {
+            WaveShape::Sine => phase.sin(),
+            WaveShape::Table(samples) => {
+                let position = (phase / TAU).rem_euclid(1.) * samples.len() as f32;
+                let index = position as usize % samples.len();
+                let next = (index + 1) % samples.len();
+                let fraction = position.fract();
+                samples[index] * (1. - fraction) + samples[next] * fraction
+            }
+            WaveShape::Harmonics { harmonics, scale } => {
+                let sum: f32 = harmonics
+                    .iter()
+                    .map(|h| h.amplitude * (h.number as f32 * phase + h.phase.to_radians()).sin())
+                    .sum();
+                // The peak is found from samples, so the true one can poke out very slightly.
+                (sum * scale).clamp(-1., 1.)
+            }
+        }
+    }
+
+    /// Steepest the shape gets, per radian of phase: 1 for a sine.
+    pub fn max_slope(&self) -> f32 {
+        match self {
+            WaveShape::Sine => 1.,
+            WaveShape::Table(samples) => {
+                let step = TAU / samples.len() as f32;
+                (0..samples.len())
+                    .map(|i| (samples[(i + 1) % samples.len()] - samples[i]).abs() / step)
+                    .fold(0., f32::max)
+            }
+            // Every harmonic at its steepest at once, which bounds the real slope.
+            WaveShape::Harmonics { harmonics, scale } => {
+                harmonics
+                    .iter()
+                    .map(|h| (h.amplitude * h.number as f32).abs())
+                    .sum::<f32>()
+                    * scale
+            }
+        }
+    }
+}
+
+/// Displacement of a travelling wave `x` along its direction of travel at time `t`:
+/// `-amplitude * sin(kx - 2πft)` with `k = 2πf / speed`.
+///
+/// A speed of zero is the standing wave limit: nothing travels, so every point simply oscillates
+/// in place with the source's phase.
+pub fn wave_displacement(amplitude: f32, frequency: f32, speed: f32, x: f32, t: f32) -> f32 {
+    shaped_displacement(amplitude, frequency, speed, &WaveShape::Sine, x, t)
+}
+
+/// [`wave_displacement`] with `shape` in place of the sine: `-amplitude * shape(kx - 2πft)`.
+pub fn shaped_displacement(
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+    shape: &WaveShape,
+    x: f32,
+    t: f32,
+) -> f32 {
+    let omega = TAU * frequency;
+    let kx = if speed == 0. { 0. } else { omega / speed * x };
+    -amplitude * shape.value(kx - omega * t)
+}
+
+/// Rate of change at a fixed point `x` of [`wave_displacement`] with the [`envelope`] as its
+/// amplitude. Without dispersion that's `amplitude * 2πf * cos(kx - 2πft)`. This is the
+/// transverse velocity of the medium at `x`, not of a particle riding the phase past it.
+pub fn wave_velocity(
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+    dispersion: Dispersion,
+    x: f32,
+    t: f32,
+) -> f32 {
+    let omega = TAU * frequency;
+    let kx = if speed == 0. { 0. } else { omega / speed * x };
+    let local_amplitude = envelope(amplitude, frequency, speed, dispersion, x, t);
+    let carrier = local_amplitude * omega * (kx - omega * t).cos();
+    // The envelope itself changes too, scaling the carrier's `-sin(kx - 2πft)`.
+    let modulation = match modulation(frequency, speed, dispersion) {
+        Some((dk, d_omega)) => amplitude * d_omega * (dk * x - d_omega * t).sin(),
+        None => 0.,
+    };
+    carrier - modulation * (kx - omega * t).sin()
+}
+
+/// How far a particle `distance` along from its source has jumped away from the wave there at
+/// time `t`, or 0 if it hasn't.
+///
+/// The wave changes by at most `amplitude * k` per unit of distance (times the steepest slope of
+/// its shape), so a freshly emitted particle can only differ from the source's displacement by
+/// `amplitude * k * distance`. Anything beyond that means the particle started out of phase with
+/// its source, e.g. from a phase offset applied to one but not the other. A standing wave has no
+/// such allowance: every particle should match the source exactly. A dispersive wave's envelope
+/// changes by up to another `amplitude * dk` per unit of distance.
+pub fn emission_discontinuity(
+    displacement: f32,
+    amplitude: f32,
+    frequency: f32,
+    speed: f32,
+    dispersion: Dispersion,
+    shape: &WaveShape,
+    distance: f32,
+    t: f32,
+) -> f32 {
+    let source_amplitude = envelope(amplitude, frequency, speed, dispersion, 0., t);
+    let source = shaped_displacement(source_amplitude, frequency, speed, shape, 0., t);
+    let k = if speed == 0. {
+        0.
+    } else {
+        TAU * frequency / speed.abs() * shape.max_slope()
+    };
+    let dk = modulation(frequency, speed, dispersion).map_or(0., |(dk, _)| dk.abs());
+    let allowed = amplitude.abs() * (k + dk) * distance.abs();
+    ((displacement - source).abs() - allowed).max(0.)
+}
+
+/// Direction along x that a wave from [`wave_displacement`] travels in, for a signed `speed`.
+///
+/// Points of constant phase satisfy `kx - 2πft = c`, and with `k = 2πf / speed` that gives
+/// `x = speed * t + c / k`: crests move at `speed`, towards +x when it's positive and -x when it's
+/// negative. Particles are moved at the same signed speed so they ride along with the wave rather
+/// than against it. A standing wave (speed 0) doesn't travel.
+pub fn direction_of_travel(speed: f32) -> Vec2 {
+    if speed == 0. {
+        Vec2::ZERO
+    } else {
+        Vec2::X * speed.signum()
+    }
+}
+
+/// Returns `speed`, unless it's non-zero but slower than [`MIN_WAVE_SPEED`], in which case it's
+/// raised to that speed in the same direction.
+pub fn clamp_wave_speed(speed: f32) -> f32 {
+    if speed != 0. && speed.abs() < MIN_WAVE_SPEED {
+        MIN_WAVE_SPEED.copysign(speed)
+    } else {
+        speed
+    }
+}
+
+/// A transmitter as seen by [`sample_field`]: a point radiating circular waves.
+#[derive(Clone, Copy, Debug)]
+pub struct FieldSource {
+    pub position: Vec2,
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub wave_speed: f32,
+    /// Seconds the source's wave lags behind.
+    pub delay: f32,
+}
+
+/// Summed displacement at `point` and time `t` of the waves from every source, using the same
+/// wave equation as the particles with the distance from each source as the distance travelled.
+///
+/// Waves are assumed to have already filled the space, so this is the steady state pattern.
+pub fn sample_field(point: Vec2, sources: &[FieldSource], t: f32) -> f32 {
+    sources
+        .iter()
+        .map(|source| {
+            wave_displacement(
+                source.amplitude,
+                source.frequency,
+                source.wave_speed.abs(),
+                point.distance(source.position),
+                t - source.delay,
+            )
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standing_wave_displacement_is_finite() {
+        let shapes = [
+            WaveShape::Sine,
+            WaveShape::from_samples(&[0., 1., 0., -1.]).unwrap(),
+        ];
+        for shape in &shapes {
+            for x in [-500., 0., 0.001, 250.] {
+                for t in [0., 0.125, 3.7] {
+                    let y = shaped_displacement(2., 5., 0., shape, x, t);
+                    assert!(y.is_finite(), "{y} at x = {x}, t = {t}");
+                    // Every point oscillates in place with the source's phase.
+                    assert_eq!(y, shaped_displacement(2., 5., 0., shape, 0., t));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn harmonics_are_scaled_to_peak_at_one() {
+        // The first odd harmonics of a square wave, whose sum peaks above 1 before scaling.
+        let harmonics = [1, 3, 5].map(|number| Harmonic {
+            number,
+            amplitude: 1. / number as f32,
+            phase: 0.,
+        });
+        let shape = WaveShape::from_harmonics(&harmonics).unwrap();
+        let peak = (0..4096)
+            .map(|i| shape.value(TAU * i as f32 / 4096.).abs())
+            .fold(0., f32::max);
+        assert!((peak - 1.).abs() < 1e-3, "peak {peak}");
+
+        // Amplitudes are relative, so scaling them all leaves the shape as it was.
+        let louder = harmonics.map(|h| Harmonic {
+            amplitude: h.amplitude * 10.,
+            ..h
+        });
+        let louder = WaveShape::from_harmonics(&louder).unwrap();
+        for phase in [0.3, 1., 2.5, 4.] {
+            assert!((louder.value(phase) - shape.value(phase)).abs() < 1e-5);
+        }
+
+        let silent = [Harmonic {
+            number: 2,
+            amplitude: 0.,
+            phase: 0.,
+        }];
+        assert!(WaveShape::from_harmonics(&silent).is_err());
+        assert!(WaveShape::from_harmonics(&[]).is_err());
+    }
+
+    #[test]
+    fn wavefront_moves_in_the_direction_of_travel() {
+        for speed in [200., -200., 35.] {
+            let direction = direction_of_travel(speed);
+            assert_eq!(direction.length(), 1.);
+            // The displacement at each point turns up a distance `|speed| * dt` along the
+            // direction of travel after `dt`, and not the other way.
+            let dt = 0.01;
+            let moved = direction.x * speed.abs() * dt;
+            for x in [-40., 0., 12.5, 90.] {
+                let now = wave_displacement(1., 3., speed, x, 0.);
+                let ahead = wave_displacement(1., 3., speed, x + moved, dt);
+                let behind = wave_displacement(1., 3., speed, x - moved, dt);
+                assert!(
+                    (now - ahead).abs() < 1e-4,
+                    "speed {speed}: {now} vs {ahead}"
+                );
+                assert!(
+                    (now - behind).abs() > 1e-3,
+                    "speed {speed}: {now} vs {behind}"
+                );
+            }
+        }
+        assert_eq!(direction_of_travel(0.), Vec2::ZERO);
+    }
+
+    #[test]
+    fn snr_of_a_sine_with_added_noise() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        // Uniform noise over ±a has an RMS of a / √3, so this is a tenth of the amplitude.
+        let spread = 0.1 * 3f32.sqrt();
+        let sine = |t: f32| 0.3 + (TAU * 4. * t + 1.).sin();
+        let clean: Vec<_> = (0..2000)
+            .map(|i| (i as f32 / 500., sine(i as f32 / 500.)))
+            .collect();
+        let noisy: Vec<_> = clean
+            .iter()
+            .map(|&(t, value)| (t, value + rng.gen_range(-spread..=spread)))
+            .collect();
+
+        let snr = estimate_snr(&noisy, 4.).unwrap();
+        assert!((snr - 17.).abs() < 0.5, "{snr} dB");
+        assert!(estimate_snr(&clean, 4.).unwrap() > 60.);
+        assert_eq!(estimate_snr(&noisy[..3], 4.), None);
+    }
+
+    #[test]
+    fn formulas_agree_at_small_speeds() {
+        for (source_velocity, observer_velocity) in [(2., 0.), (0., -3.), (1.5, 1.), (-2., 2.5)] {
+            let [classical, relativistic] =
+                [DopplerFormula::Classical, DopplerFormula::Relativistic].map(|formula| {
+                    doppler_shift(formula, 440., 1000., source_velocity, observer_velocity)
+                });
+            // Beta is at most 0.005, so they only differ at second order, by around 0.01 Hz.
+            assert!(
+                (classical - relativistic).abs() < 0.05,
+                "{classical} vs {relativistic}"
+            );
+            assert_ne!(classical, 440.);
+        }
+    }
+
+    #[test]
+    fn mach_cone_only_forms_ahead_of_a_travelling_wave() {
+        let angle = mach_angle(-400., 200.).unwrap();
+        assert!((angle.to_degrees() - 30.).abs() < 1e-3, "{angle}");
+        assert_eq!(mach_angle(150., 200.), None);
+        assert_eq!(mach_angle(150., 0.), None);
+    }
+
+    #[test]
+    fn standing_wave_keeps_the_source_frequency() {
+        for formula in [DopplerFormula::Classical, DopplerFormula::Relativistic] {
+            assert_eq!(doppler_shift(formula, 440., 0., 0., 0.), 440.);
+            assert_eq!(doppler_shift(formula, 440., 0., 5., -5.), 440.);
+        }
+    }
+
+    /// A 2 Hz sine from 0.1 s to 3 s, sampled every millisecond, with deterministic noise that
+    /// flips sign from one sample to the next, like jitter from the particle positions.
+    fn noisy_sine() -> Vec<(f32, f32)> {
+        (100..3000)
+            .map(|ms| {
+                let t = ms as f32 / 1000.;
+                let noise =
+                    if ms % 2 == 0 { 0.05 } else { -0.05 } * ((ms * 7919) % 13) as f32 / 12.;
+                (t, (TAU * 2. * t).sin() + noise)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hysteresis_drops_false_crossings_from_noise() {
+        let samples = noisy_sine();
+        assert!(zero_crossing_frequencies(&samples, 0.).len() > 4);
+
+        // True upward crossings at 0.5, 1, 1.5, 2 and 2.5 s give four frequencies.
+        let frequencies = zero_crossing_frequencies(&samples, 0.2);
+        assert_eq!(frequencies.len(), 4);
+        for (i, (t, frequency)) in frequencies.into_iter().enumerate() {
+            assert!((t - (i + 2) as f32 * 0.5).abs() < 0.01, "crossing at {t}");
+            assert!((frequency - 2.).abs() < 0.1, "frequency {frequency}");
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Pinning the particles in flight where they are, so a wavefront can be kept as a reference
+//! while the parameters for the next one are changed.
+
+use bevy::{prelude::*, transform::commands::BuildChildrenTransformExt};
+
+use crate::{
+    keybindings::{key_name, KeyBindings},
+    SignalParticle,
+};
+
+const PINNED_COLOR: Color = Color::rgba(0.6, 0.6, 0.6, 0.5);
+
+/// A particle frozen in place. It's no longer a [`SignalParticle`], so it neither moves nor gets
+/// received, and it's detached from its transmitter so it outlives resets.
+#[derive(Component)]
+pub struct Pinned;
+
+pub fn pin_help(bindings: &KeyBindings) -> String {
+    format!(
+        "Press {} to pin the particles in place, {} to clear pinned ones",
+        key_name(bindings.pin_particles),
+        key_name(bindings.clear_pins)
+    )
+}
+
+pub fn pin_particles(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    particle_query: Query<Entity, With<SignalParticle>>,
+    pinned_query: Query<Entity, With<Pinned>>,
+    mut pinned_material: Local<Option<Handle<ColorMaterial>>>,
+) {
+    if input.just_pressed(bindings.clear_pins) {
+        for entity in pinned_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if !input.just_pressed(bindings.pin_particles) {
+        return;
+    }
+    let material = pinned_material
+        .get_or_insert_with(|| materials.add(PINNED_COLOR))
+        .clone();
+    for entity in particle_query.iter() {
+        commands
+            .entity(entity)
+            .remove::<SignalParticle>()
+            .insert((Pinned, material.clone()))
+            .remove_parent_in_place();
+    }
+}
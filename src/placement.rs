@@ -0,0 +1,170 @@
+//! Automatic placement of transmitter/receiver pairs, as an alternative to the positions each
+//! scenario lays out by hand.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SimConfig, SimulationSpec, Transmitter, RECEIVER_SIZE, RES_HEIGHT, RES_WIDTH};
+
+/// Colour of the arc drawn through the receivers of an equal-path layout.
+const ARC_COLOR: Color = Color::rgba(1., 1., 1., 0.15);
+/// Line segments per degree of arc drawn.
+const ARC_SEGMENTS_PER_DEGREE: f32 = 0.5;
+
+/// Space left between neighbouring cells, so labels and plots of adjacent pairs don't touch.
+const CELL_MARGIN: f32 = 30.;
+/// Where in its cell a receiver starts and its transmitter sits, as fractions of the cell width.
+const RECEIVER_POSITION: f32 = 0.25;
+const TRANSMITTER_POSITION: f32 = 0.8;
+
+/// How the pairs of a scenario are positioned.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Placement {
+    /// Where the scenario puts them.
+    #[default]
+    Manual,
+    /// Spread evenly over the world bounds, whatever the scenario says.
+    Auto,
+}
+
+/// Region the simulations are placed in by [`Placement::Auto`], centred on the origin.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldBounds {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            width: RES_WIDTH as f32,
+            height: RES_HEIGHT as f32,
+        }
+    }
+}
+
+/// Receivers laid out on an arc around their transmitter, so the waves travel exactly the same
+/// distance to each. With nothing moving they all hear the same phase; moving one breaks the
+/// symmetry.
+#[derive(Clone, Debug, Reflect, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathArc {
+    /// Distance in pixels from the transmitter to the side of each receiver facing it.
+    pub radius: f32,
+    /// Where each receiver sits on the arc, in degrees anticlockwise from +x. Receivers catch
+    /// particles on their side facing the transmitter, so angles within about 60° of 180 (or of
+    /// 0) work best.
+    pub angles: Vec<f32>,
+}
+
+impl Default for PathArc {
+    fn default() -> Self {
+        Self {
+            radius: 350.,
+            angles: vec![150., 180., 210.],
+        }
+    }
+}
+
+impl PathArc {
+    /// Centre of each receiver on the arc around a transmitter at `source`, placed so the middle
+    /// of its side facing the transmitter is `radius` away from it.
+    pub fn receiver_positions(&self, source: Vec2) -> Vec<Vec2> {
+        self.angles
+            .iter()
+            .map(|angle| {
+                let edge = source + Vec2::from_angle(angle.to_radians()) * self.radius;
+                // Receivers face the transmitter's side, which is where particles are caught.
+                let away = if edge.x > source.x { 1. } else { -1. };
+                edge + Vec2::X * away * RECEIVER_SIZE.x / 2.
+            })
+            .collect()
+    }
+}
+
+/// Faintly draws the arc the receivers of an equal-path layout lie on, around each transmitter
+/// as it is now, from the first angle to the last.
+pub fn draw_path_arcs(
+    config: Res<SimConfig>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if !config.scenario.uses_path_arc() {
+        return;
+    }
+    let arc = &config.path_arc;
+    let (Some(first), Some(last)) = (
+        arc.angles.iter().copied().reduce(f32::min),
+        arc.angles.iter().copied().reduce(f32::max),
+    ) else {
+        return;
+    };
+    let segments = (((last - first) * ARC_SEGMENTS_PER_DEGREE).ceil() as usize).max(1);
+    for (tx, transform) in tx_query.iter() {
+        let source = transform.translation().xy() + tx.spawn_point;
+        gizmos.linestrip_2d(
+            (0..=segments).map(|step| {
+                let angle = first + (last - first) * step as f32 / segments as f32;
+                source + Vec2::from_angle(angle.to_radians()) * arc.radius
+            }),
+            ARC_COLOR,
+        );
+    }
+}
+
+/// Number of columns needed to fit `count` pairs in `bounds`: one, unless stacking them all in
+/// a single column would squash the rows shorter than a receiver.
+fn column_count(count: usize, bounds: &WorldBounds) -> usize {
+    let min_row_height = RECEIVER_SIZE.y + CELL_MARGIN;
+    let rows_per_column = ((bounds.height / min_row_height).floor() as usize).max(1);
+    count.div_ceil(rows_per_column).max(1)
+}
+
+/// Overwrites the positions of `specs` so they fill `bounds` in a grid of equal cells, filled
+/// from the top down and then left to right, keeping the order the scenario listed them in.
+/// Each receiver starts far enough into its cell that it doesn't overlap the cell to its left.
+pub fn auto_place(specs: &mut [SimulationSpec], bounds: &WorldBounds) {
+    if specs.is_empty() {
+        return;
+    }
+    let columns = column_count(specs.len(), bounds);
+    let rows = specs.len().div_ceil(columns);
+    let cell = Vec2::new(bounds.width / columns as f32, bounds.height / rows as f32);
+    let top_left = Vec2::new(-bounds.width, bounds.height) / 2.;
+
+    for (index, spec) in specs.iter_mut().enumerate() {
+        let (column, row) = (index / rows, index % rows);
+        let left = top_left.x + cell.x * column as f32;
+        spec.rx_start_x =
+            (left + cell.x * RECEIVER_POSITION).max(left + RECEIVER_SIZE.x / 2. + CELL_MARGIN / 2.);
+        spec.tx_x = left + cell.x * TRANSMITTER_POSITION;
+        spec.y_pos = top_left.y - cell.y * (row as f32 + 0.5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_arc_receivers_are_all_the_same_distance_away() {
+        let arc = PathArc {
+            radius: 300.,
+            angles: vec![0., 30., 150., 180., 210.],
+        };
+        let source = Vec2::new(120., -40.);
+        let positions = arc.receiver_positions(source);
+        assert_eq!(positions.len(), arc.angles.len());
+        for position in positions {
+            // The middle of the side facing the transmitter, half a receiver back towards it.
+            let towards = if position.x > source.x { -1. } else { 1. };
+            let side = position + Vec2::X * towards * RECEIVER_SIZE.x / 2.;
+            let distance = side.distance(source);
+            assert!(
+                (distance - arc.radius).abs() < 1e-3,
+                "{distance} at {position}"
+            );
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Numbered slots the whole config can be saved to and loaded back from during a session, to jump
+//! between hand-tuned setups without editing `doppl.ron`. Each slot is a RON file in
+//! `preset_dir`, in the same format as `doppl.ron`.
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    SimulationReset,
+};
+
+/// Slot last saved to or loaded from, shown in the HUD.
+#[derive(Resource, Default)]
+pub struct ActivePreset(Option<usize>);
+
+fn slot_path(config: &SimConfig, slot: usize) -> PathBuf {
+    config.preset_dir.join(format!("slot{}.ron", slot + 1))
+}
+
+pub fn preset_label(config: &SimConfig, bindings: &KeyBindings, active: &ActivePreset) -> String {
+    let (Some(&first), Some(&last)) = (bindings.preset_slots.first(), bindings.preset_slots.last())
+    else {
+        return String::new();
+    };
+    let saved: Vec<String> = (0..bindings.preset_slots.len())
+        .filter(|&slot| slot_path(config, slot).is_file())
+        .map(|slot| (slot + 1).to_string())
+        .collect();
+    let saved = if saved.is_empty() {
+        "none saved".to_string()
+    } else {
+        format!("{} saved", saved.join(", "))
+    };
+    let active = active
+        .0
+        .map(|slot| format!(", using {}", slot + 1))
+        .unwrap_or_default();
+    format!(
+        "Presets: {saved}{active} (press {} to {} to save, with shift to load)",
+        key_name(first),
+        key_name(last)
+    )
+}
+
+/// Saves the config to a slot on its key, or with shift loads the slot and restarts with it.
+/// Settings only read at startup, such as the font, keep their values when a slot is loaded.
+pub fn save_or_load_preset(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut active: ResMut<ActivePreset>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    let Some(slot) = bindings
+        .preset_slots
+        .iter()
+        .position(|&key| input.just_pressed(key))
+    else {
+        return;
+    };
+    if cfg!(target_arch = "wasm32") {
+        warn!("Presets can't be saved on the web");
+        return;
+    }
+    let path = slot_path(&config, slot);
+    if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        let loaded = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                ron::from_str::<SimConfig>(&contents).map_err(|err| err.to_string())
+            });
+        match loaded {
+            Ok(loaded) => {
+                *config = loaded.validated();
+                active.0 = Some(slot);
+                info!("Loaded preset {}", path.display());
+                reset_events.send(SimulationReset);
+            }
+            Err(err) => error!("Cannot load preset {}: {err}", path.display()),
+        }
+    } else {
+        let saved = ron::ser::to_string_pretty(&*config, default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                fs::create_dir_all(&config.preset_dir)
+                    .and_then(|()| fs::write(&path, contents))
+                    .map_err(|err| err.to_string())
+            });
+        match saved {
+            Ok(()) => {
+                active.0 = Some(slot);
+                info!("Saved preset {}", path.display());
+            }
+            Err(err) => error!("Cannot save preset {}: {err}", path.display()),
+        }
+    }
+    hud.set("presets", preset_label(&config, &bindings, &active));
+}
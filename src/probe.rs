@@ -0,0 +1,135 @@
+//! Probing the world with the mouse: each left click logs where it landed and the state of the
+//! nearest particle, receiver and transmitter within `probe_radius`, so values can be copied from
+//! the console into notes.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    config::SimConfig, cursor_world_position, measure::MeasureMode, physics,
+    settings::PointerOverUi, stopwatch::PeriodMeasure, InGameCamera, Mover, OuterCamera, Receiver,
+    SignalParticle, Transmitter, TransmitterBody,
+};
+
+/// The entity in `candidates` nearest `cursor`, if any is within `radius`.
+fn nearest<T>(
+    cursor: Vec2,
+    radius: f32,
+    candidates: impl Iterator<Item = (T, Vec2)>,
+) -> Option<(T, Vec2)> {
+    candidates
+        .map(|(item, position)| (item, position, position.distance(cursor)))
+        .filter(|&(_, _, distance)| distance <= radius)
+        .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+        .map(|(item, position, _)| (item, position))
+}
+
+/// Logs the world position of each left click while `click_probe` is on, along with whatever
+/// particle, receiver and transmitter is nearest it within `probe_radius`.
+pub fn log_probe(
+    config: Res<SimConfig>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    measure: Res<MeasureMode>,
+    period: Res<PeriodMeasure>,
+    pointer: Res<PointerOverUi>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    in_game_camera_query: Query<&Transform, With<InGameCamera>>,
+    particle_query: Query<(&SignalParticle, &GlobalTransform)>,
+    rx_query: Query<(&Receiver, &GlobalTransform, Option<&Mover>)>,
+    tx_query: Query<(&Transmitter, &GlobalTransform, &Children)>,
+    body_query: Query<&Mover, With<TransmitterBody>>,
+) {
+    if !config.click_probe
+        || !mouse.just_pressed(MouseButton::Left)
+        || measure.is_active()
+        || period.is_active()
+        || pointer.0
+    {
+        return;
+    }
+    let Some(cursor) = cursor_world_position(&windows, &camera_query, &in_game_camera_query) else {
+        return;
+    };
+    info!("Probe at ({:.1}, {:.1})", cursor.x, cursor.y);
+    let radius = config.probe_radius;
+
+    let particle = nearest(
+        cursor,
+        radius,
+        particle_query
+            .iter()
+            .map(|(particle, transform)| (particle, transform.translation().xy())),
+    );
+    if let Some((particle, position)) = particle {
+        let velocity = particle.direction * particle.speed;
+        let travelled = (position - particle.origin).dot(particle.direction);
+        let phase = physics::relative_phase(
+            particle.frequency,
+            travelled / particle.speed.abs().max(f32::EPSILON),
+        );
+        info!(
+            "  Particle at ({:.1}, {:.1}): displacement {:.2}, velocity ({:.1}, {:.1}) px/s, \
+             {:.2} Hz, phase {phase:.2} rad",
+            position.x,
+            position.y,
+            particle.displacement,
+            velocity.x,
+            velocity.y,
+            particle.frequency
+        );
+    }
+
+    let receiver = nearest(
+        cursor,
+        radius,
+        rx_query
+            .iter()
+            .map(|(rx, transform, mover)| ((rx, mover), transform.translation().xy())),
+    );
+    if let Some(((rx, mover), position)) = receiver {
+        let velocity = mover.map_or(0., |mover| mover.0.direction() * rx.speed);
+        let frequency = rx
+            .smoothed_frequency
+            .map_or("not yet measured".to_string(), |frequency| {
+                format!("{frequency:.2} Hz")
+            });
+        let phase = rx
+            .samples
+            .back()
+            .and_then(|sample| sample.phase)
+            .map_or("none".to_string(), |phase| format!("{phase:.2} rad"));
+        info!(
+            "  Receiver {} at ({:.1}, {:.1}): velocity {velocity:.1} px/s, observed {frequency}, \
+             sampling at {:.1} Hz, last phase {phase}",
+            rx.index + 1,
+            position.x,
+            position.y,
+            rx.sample_rate()
+        );
+    }
+
+    let transmitter = nearest(
+        cursor,
+        radius,
+        tx_query.iter().map(|(tx, transform, children)| {
+            (
+                (tx, children),
+                transform.translation().xy() + tx.spawn_point,
+            )
+        }),
+    );
+    if let Some(((tx, children), position)) = transmitter {
+        let velocity = body_query
+            .iter_many(children)
+            .next()
+            .map_or(0., |mover| mover.0.direction() * tx.speed);
+        info!(
+            "  Transmitter {} at ({:.1}, {:.1}): velocity {velocity:.1} px/s, {:.2} Hz, gain {:.2}",
+            tx.index + 1,
+            position.x,
+            position.y,
+            tx.frequency,
+            tx.gain
+        );
+    }
+}
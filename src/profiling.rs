@@ -0,0 +1,83 @@
+//! Per-frame timings of the busiest systems, enabled with the `debug_profiling` feature and
+//! printed while switched on with the profiling key. Without the feature the timers aren't
+//! compiled in at all.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+use crate::keybindings::{key_name, KeyBindings};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Time spent in each timed system since the last report, in the order they first ran.
+static TIMINGS: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+/// Adds the time from its creation to when it's dropped to the named system's total. Does
+/// nothing while profiling is switched off.
+#[cfg(feature = "debug_profiling")]
+pub struct SystemTimer {
+    name: &'static str,
+    start: Option<std::time::Instant>,
+}
+
+#[cfg(feature = "debug_profiling")]
+impl Drop for SystemTimer {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        let Ok(mut timings) = TIMINGS.lock() else {
+            return;
+        };
+        match timings.iter_mut().find(|(name, _)| *name == self.name) {
+            Some((_, total)) => *total += elapsed,
+            None => timings.push((self.name, elapsed)),
+        }
+    }
+}
+
+/// Times the rest of the calling scope as `name`.
+#[cfg(feature = "debug_profiling")]
+pub fn time(name: &'static str) -> SystemTimer {
+    let start = ENABLED
+        .load(Ordering::Relaxed)
+        .then(std::time::Instant::now);
+    SystemTimer { name, start }
+}
+
+pub fn toggle_profiling(input: Res<ButtonInput<KeyCode>>, bindings: Res<KeyBindings>) {
+    if input.just_pressed(bindings.profiling) {
+        let enabled = !ENABLED.fetch_xor(true, Ordering::Relaxed);
+        info!(
+            "System profiling {} (press {} to toggle)",
+            if enabled { "on" } else { "off" },
+            key_name(bindings.profiling)
+        );
+    }
+}
+
+/// Prints and clears the timings gathered over the last frame.
+pub fn report_timings(frames: Res<bevy::core::FrameCount>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(mut timings) = TIMINGS.lock() else {
+        return;
+    };
+    if timings.is_empty() {
+        return;
+    }
+    let report: Vec<_> = timings
+        .iter()
+        .map(|(name, total)| format!("{name} {:.3} ms", total.as_secs_f64() * 1000.))
+        .collect();
+    info!("Frame {}: {}", frames.0, report.join(", "));
+    timings.clear();
+}
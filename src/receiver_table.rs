@@ -0,0 +1,172 @@
+//! Table of every receiver's position, velocity, observed frequency and sample count in the
+//! bottom right corner, for screenshots that need exact numbers beside the picture.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    Mover, OverlayFont, Receiver, HIGH_RES_LAYERS,
+};
+
+const TABLE_FONT_SIZE: f32 = 18.;
+const HEADER_COLOR: Color = Color::rgb(1., 0.85, 0.3);
+const CELL_COLOR: Color = Color::WHITE;
+const BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
+const COLUMNS: [&str; 5] = ["Receiver", "x (px)", "v (px/s)", "f (Hz)", "Samples"];
+
+#[derive(Component)]
+pub struct ReceiverTable;
+
+/// One cell below the header. Rows are kept when receivers go away and hidden, to be reused.
+#[derive(Component)]
+pub struct TableCell {
+    row: usize,
+    column: usize,
+}
+
+fn cell_style(font: &OverlayFont, color: Color) -> TextStyle {
+    TextStyle {
+        font: font.0.clone(),
+        font_size: TABLE_FONT_SIZE,
+        color,
+    }
+}
+
+/// Spawns the table with just its header. Rows are added as receivers turn up.
+pub fn spawn_receiver_table(
+    mut commands: Commands,
+    font: Res<OverlayFont>,
+    config: Res<SimConfig>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(15.),
+                    right: Val::Px(15.),
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::auto(COLUMNS.len() as u16),
+                    column_gap: Val::Px(12.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                visibility: if config.receiver_table {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..default()
+            },
+            ReceiverTable,
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|table| {
+            for heading in COLUMNS {
+                table.spawn(TextBundle::from_section(
+                    heading,
+                    cell_style(&font, HEADER_COLOR),
+                ));
+            }
+        });
+}
+
+pub fn receiver_table_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.receiver_table {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press {} to {action} the receiver table",
+        key_name(bindings.receiver_table)
+    )
+}
+
+pub fn toggle_receiver_table(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut table_query: Query<&mut Visibility, With<ReceiverTable>>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.receiver_table) {
+        return;
+    }
+    config.receiver_table = !config.receiver_table;
+    for mut visibility in table_query.iter_mut() {
+        *visibility = if config.receiver_table {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    hud.set("receiver_table", receiver_table_label(&config, &bindings));
+}
+
+/// Fills in a row per receiver, in index order, while the table is shown. Cells are only
+/// rewritten when their text changes.
+pub fn update_receiver_table(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    rx_query: Query<(&Receiver, &GlobalTransform, Option<&Mover>)>,
+    table_query: Query<Entity, With<ReceiverTable>>,
+    mut cell_query: Query<(&TableCell, &mut Text, &mut Style)>,
+) {
+    if !config.receiver_table {
+        return;
+    }
+    let Ok(table) = table_query.get_single() else {
+        return;
+    };
+
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by_key(|(rx, _, _)| rx.index);
+    let rows: Vec<[String; COLUMNS.len()]> = receivers
+        .into_iter()
+        .map(|(rx, transform, mover)| {
+            let velocity = mover.map_or(0., |mover| mover.0.direction() * rx.speed);
+            [
+                (rx.index + 1).to_string(),
+                format!("{:.0}", transform.translation().x),
+                format!("{velocity:.0}"),
+                rx.smoothed_frequency
+                    .map_or("-".to_string(), |frequency| format!("{frequency:.2}")),
+                rx.next_sample_index().to_string(),
+            ]
+        })
+        .collect();
+
+    let mut existing_rows = 0;
+    for (cell, mut text, mut style) in cell_query.iter_mut() {
+        existing_rows = existing_rows.max(cell.row + 1);
+        let display = match rows.get(cell.row) {
+            Some(row) => {
+                if text.sections[0].value != row[cell.column] {
+                    text.sections[0].value.clone_from(&row[cell.column]);
+                }
+                Display::Flex
+            }
+            None => Display::None,
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+
+    // Cells are laid out in the order they were spawned, so new rows go after the old ones.
+    for (row, values) in rows.iter().enumerate().skip(existing_rows) {
+        commands.entity(table).with_children(|table| {
+            for (column, value) in values.iter().enumerate() {
+                table.spawn((
+                    TextBundle::from_section(value.clone(), cell_style(&font, CELL_COLOR)),
+                    TableCell { row, column },
+                ));
+            }
+        });
+    }
+}
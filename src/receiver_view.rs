@@ -0,0 +1,600 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig,
+    follow::WorldLabel,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics, OverlayFont, PlotPoint, Receiver, SelectedReceiver, Transmitter, HIGH_RES_LAYERS,
+    RECEIVER_DELTA_X_PER_SECOND, RECEIVER_PLOT_COLOR, RECEIVER_PLOT_RADIUS, RECEIVER_SIZE,
+};
+
+const RMS_COLOR: Color = Color::YELLOW;
+const ENVELOPE_COLOR: Color = Color::CYAN;
+const FREQUENCY_COLOR: Color = Color::ORANGE;
+const DISTANCE_COLOR: Color = Color::LIME_GREEN;
+const PHASE_COLOR: Color = Color::VIOLET;
+const DISTANCE_LABEL_FONT_SIZE: f32 = 16.;
+/// Gap between the bottom of a receiver and its distance axis label.
+const DISTANCE_LABEL_GAP: f32 = 20.;
+/// Smallest spread of distances, in pixels, worth plotting against.
+const MIN_DISTANCE_SPAN: f32 = 1.;
+
+/// What a receiver plots from the samples it captures.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiverView {
+    /// The instantaneous amplitude of each sample.
+    #[default]
+    Raw,
+    /// Root mean square of the samples in a sliding window.
+    Rms,
+    /// Peak absolute amplitude in a sliding window, drawn above and below the axis.
+    Envelope,
+    /// Frequency estimated from the time between upward zero crossings, as a line over time
+    /// centred on the source frequency, showing how the Doppler shift changes.
+    Frequency,
+    /// Peak absolute amplitude in a sliding window against the receiver's distance from its
+    /// transmitter, nearest on the left, as the receiver moves. Shows attenuation or
+    /// interference against position.
+    Distance,
+    /// Phase of each sample against the source's, wrapped to `[0, 2π)` from bottom to top. It's
+    /// flat when nothing's moving and ramps up or down at a slope set by the Doppler shift.
+    Phase,
+}
+
+/// Which way a receiver lies. Everything about a receiver, its plot included, is laid out as if
+/// it were horizontal and then rotated, so only its footprint in the world depends on this.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiverOrientation {
+    /// Wider than it is tall, with the trace sweeping from right to left.
+    #[default]
+    Horizontal,
+    /// Turned a quarter turn anticlockwise, so the trace sweeps down it like a waterfall and
+    /// positive values are plotted to the left.
+    Vertical,
+}
+
+impl ReceiverOrientation {
+    pub fn rotation(self) -> Quat {
+        match self {
+            ReceiverOrientation::Horizontal => Quat::IDENTITY,
+            ReceiverOrientation::Vertical => Quat::from_rotation_z(FRAC_PI_2),
+        }
+    }
+
+    /// Width and height the receiver takes up in the world.
+    pub fn size(self) -> Vec2 {
+        match self {
+            ReceiverOrientation::Horizontal => RECEIVER_SIZE,
+            ReceiverOrientation::Vertical => RECEIVER_SIZE.yx(),
+        }
+    }
+}
+
+/// Which side of a receiver particles reach it from, the side facing its transmitter.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq)]
+pub enum ReceiverFace {
+    /// Catches particles travelling left, from a transmitter to its right.
+    #[default]
+    Right,
+    /// Catches particles travelling right, on the far side of a bidirectional transmitter.
+    Left,
+}
+
+impl ReceiverFace {
+    /// 1 facing right and -1 facing left.
+    pub fn sign(self) -> f32 {
+        match self {
+            ReceiverFace::Right => 1.,
+            ReceiverFace::Left => -1.,
+        }
+    }
+}
+
+impl ReceiverView {
+    fn next(self) -> Self {
+        match self {
+            ReceiverView::Raw => ReceiverView::Rms,
+            ReceiverView::Rms => ReceiverView::Envelope,
+            ReceiverView::Envelope => ReceiverView::Frequency,
+            ReceiverView::Frequency => ReceiverView::Distance,
+            ReceiverView::Distance => ReceiverView::Phase,
+            ReceiverView::Phase => ReceiverView::Raw,
+        }
+    }
+}
+
+/// Root mean square of `values`, or zero if there are none.
+pub fn rms(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.;
+    }
+    (values.iter().map(|v| v * v).sum::<f32>() / values.len() as f32).sqrt()
+}
+
+/// Largest absolute value in `values`, or zero if there are none.
+pub fn envelope(values: &[f32]) -> f32 {
+    values.iter().fold(0., |peak: f32, v| peak.max(v.abs()))
+}
+
+/// Uniform Catmull-Rom spline through `points`, with `steps` segments between each pair of
+/// neighbours. The curve passes through every point and starts and ends at the first and last;
+/// the end tangents come from repeating the end points. Fewer than three points are returned as
+/// they are, since there's nothing to smooth.
+pub fn catmull_rom(points: &[Vec2], steps: u32) -> Vec<Vec2> {
+    if points.len() < 3 || steps < 2 {
+        return points.to_vec();
+    }
+    let mut curve = Vec::with_capacity((points.len() - 1) * steps as usize + 1);
+    for i in 0..points.len() - 1 {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(points.len() - 1)];
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            curve.push(
+                0.5 * (2. * p1
+                    + (p2 - p0) * t
+                    + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2
+                    + (3. * p1 - p0 - 3. * p2 + p3) * t3),
+            );
+        }
+    }
+    curve.push(points[points.len() - 1]);
+    curve
+}
+
+pub fn smooth_trace_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.smooth_trace { "hide" } else { "show" };
+    format!(
+        "Press {} to {action} the smooth trace",
+        key_name(bindings.smooth_trace)
+    )
+}
+
+pub fn toggle_smooth_trace(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.smooth_trace) {
+        config.smooth_trace = !config.smooth_trace;
+        hud.set("smooth_trace", smooth_trace_label(&config, &bindings));
+    }
+}
+
+/// Factor that stretches or squashes a trace whose largest value is `peak` so it just fits inside
+/// a receiver, plot markers included. `None` for a flat trace, which has nothing to fit.
+pub fn fit_scale(peak: f32) -> Option<f32> {
+    (peak > f32::EPSILON).then(|| (RECEIVER_SIZE.y / 2. - RECEIVER_PLOT_RADIUS) / peak)
+}
+
+pub fn auto_scale_label(config: &SimConfig, bindings: &KeyBindings, scales: &[f32]) -> String {
+    let key = key_name(bindings.auto_scale);
+    if !config.auto_scale_plots {
+        return format!("Press {key} to scale the plots to fit their traces");
+    }
+    let scales: Vec<_> = scales.iter().map(|scale| format!("x{scale:.2}")).collect();
+    format!(
+        "Plots auto-scaled to fit, not to a common scale: {} (press {key} for fixed scale)",
+        if scales.is_empty() {
+            "-".to_string()
+        } else {
+            scales.join(", ")
+        }
+    )
+}
+
+pub fn toggle_auto_scale(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.auto_scale) {
+        config.auto_scale_plots = !config.auto_scale_plots;
+        hud.set("auto_scale", auto_scale_label(&config, &bindings, &[]));
+    }
+}
+
+/// Rescales each receiver's plot vertically to fit the largest sample it's holding while
+/// auto-scaling is on, and back to the samples' own values once it's off.
+pub fn auto_scale_plots(
+    config: Res<SimConfig>,
+    bindings: Res<KeyBindings>,
+    mut rx_query: Query<(&mut Receiver, Option<&Children>)>,
+    mut point_query: Query<(&PlotPoint, &mut Transform)>,
+    mut hud: ResMut<Hud>,
+) {
+    let mut rescaled = false;
+    let mut scales = Vec::new();
+    for (mut rx, children) in rx_query.iter_mut() {
+        let scale = if config.auto_scale_plots {
+            let peak = rx
+                .samples
+                .iter()
+                .map(|sample| sample.value.abs())
+                .fold(0., f32::max);
+            fit_scale(peak)
+        } else {
+            None
+        };
+        scales.push((rx.index, scale.unwrap_or(1.)));
+        if scale == rx.auto_scale {
+            continue;
+        }
+        rx.auto_scale = scale;
+        rescaled = true;
+        let mut points = point_query.iter_many_mut(children.into_iter().flatten());
+        while let Some((point, mut transform)) = points.fetch_next() {
+            if let Some(sample) = rx.sample(point.index) {
+                transform.translation.y = rx.plot_y(sample.value);
+            }
+        }
+    }
+    if rescaled && config.auto_scale_plots {
+        scales.sort_by_key(|&(index, _)| index);
+        let scales: Vec<_> = scales.into_iter().map(|(_, scale)| scale).collect();
+        hud.set("auto_scale", auto_scale_label(&config, &bindings, &scales));
+    }
+}
+
+/// Draws a smooth curve through the samples of each receiver showing its raw plot.
+pub fn draw_smooth_trace(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if !config.smooth_trace {
+        return;
+    }
+    for (rx, rx_transform) in rx_query.iter() {
+        if rx.view != ReceiverView::Raw {
+            continue;
+        }
+        let points: Vec<Vec2> = rx
+            .samples
+            .iter()
+            .map(|sample| Vec2::new(sample.plot_x, rx.plot_y(sample.value)))
+            .collect();
+        let curve = catmull_rom(&points, config.smooth_trace_steps);
+        gizmos.linestrip_2d(
+            curve
+                .iter()
+                .map(|&p| rx_transform.transform_point(p.extend(0.)).xy()),
+            RECEIVER_PLOT_COLOR,
+        );
+    }
+}
+
+pub fn cycle_receiver_view(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    selected: Res<SelectedReceiver>,
+    mut rx_query: Query<&mut Receiver>,
+    mut hud: ResMut<Hud>,
+) {
+    for mut rx in rx_query.iter_mut() {
+        if rx.index != selected.0 {
+            continue;
+        }
+        if input.just_pressed(bindings.receiver_view) {
+            rx.view = rx.view.next();
+        }
+        hud.set(
+            "receiver_view",
+            format!(
+                "Receiver {} view: {:?} (press {} to change)",
+                rx.index + 1,
+                rx.view,
+                key_name(bindings.receiver_view)
+            ),
+        );
+    }
+}
+
+/// Hides the raw plot points of receivers that are showing a derived view.
+pub fn show_raw_points(
+    rx_query: Query<(&Receiver, &Children), Changed<Receiver>>,
+    mut point_query: Query<&mut Visibility, With<PlotPoint>>,
+) {
+    for (rx, children) in rx_query.iter() {
+        let visibility = if rx.view == ReceiverView::Raw {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let mut points = point_query.iter_many_mut(children);
+        while let Some(mut point_visibility) = points.fetch_next() {
+            if *point_visibility != visibility {
+                *point_visibility = visibility;
+            }
+        }
+    }
+}
+
+/// Translucent band over the samples that make up a receiver's latest RMS or envelope value.
+#[derive(Component)]
+pub struct WindowBand;
+
+pub fn window_band_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.window_band { "hide" } else { "show" };
+    format!(
+        "Press {} to {action} the RMS/envelope window",
+        key_name(bindings.window_band)
+    )
+}
+
+pub fn toggle_window_band(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.window_band) {
+        config.window_band = !config.window_band;
+        hud.set("window_band", window_band_label(&config, &bindings));
+    }
+}
+
+/// Stretches each receiver's window band over the last `receiver_window` samples it captured,
+/// hiding it unless the receiver shows its RMS or envelope and has samples.
+pub fn update_window_band(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &Children)>,
+    mut band_query: Query<(&mut Transform, &mut Visibility), With<WindowBand>>,
+) {
+    let window = config.receiver_window.max(1);
+    for (rx, children) in rx_query.iter() {
+        let extent = rx
+            .samples
+            .back()
+            .filter(|_| {
+                config.window_band && matches!(rx.view, ReceiverView::Rms | ReceiverView::Envelope)
+            })
+            .map(|last| {
+                let oldest = &rx.samples[rx.samples.len().saturating_sub(window)];
+                (oldest.plot_x, last.plot_x)
+            });
+        let mut bands = band_query.iter_many_mut(children);
+        while let Some((mut transform, mut visibility)) = bands.fetch_next() {
+            let Some((oldest, newest)) = extent else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+            // Plots grow towards -x, so the oldest sample in the window is on the right.
+            *visibility = Visibility::Inherited;
+            transform.translation.x = (oldest + newest) / 2.;
+            transform.scale.x = (oldest - newest).max(RECEIVER_PLOT_RADIUS);
+        }
+    }
+}
+
+/// Points of `rx`'s frequency view, in its plot's coordinates: the frequency at each zero crossing
+/// of its trace, at the height that puts `source` in the middle and a fraction `span` above or
+/// below it at the edges. Crossings are found with `hysteresis`, see
+/// [`physics::zero_crossing_frequencies`].
+fn frequency_curve(rx: &Receiver, source: f32, span: f32, hysteresis: f32) -> Vec<Vec2> {
+    // Plots grow towards -x as time goes on.
+    let samples: Vec<(f32, f32)> = rx
+        .samples
+        .iter()
+        .map(|sample| (-sample.plot_x / RECEIVER_DELTA_X_PER_SECOND, sample.value))
+        .collect();
+    let half_height = RECEIVER_SIZE.y / 2. - RECEIVER_PLOT_RADIUS;
+    physics::zero_crossing_frequencies(&samples, hysteresis)
+        .into_iter()
+        .map(|(t, frequency)| {
+            let shift = (frequency / source - 1.) / span.max(f32::EPSILON);
+            Vec2::new(
+                -t * RECEIVER_DELTA_X_PER_SECOND,
+                shift.clamp(-1., 1.) * half_height,
+            )
+        })
+        .collect()
+}
+
+/// Runs of points of `rx`'s phase view, in its plot's coordinates, with `0` at the bottom and
+/// `2π` at the top. A new run starts wherever the phase wraps round, so no line is drawn across
+/// the plot there.
+fn phase_curves(rx: &Receiver) -> Vec<Vec<Vec2>> {
+    let half_height = RECEIVER_SIZE.y / 2. - RECEIVER_PLOT_RADIUS;
+    let mut curves: Vec<Vec<Vec2>> = Vec::new();
+    let mut last_phase: Option<f32> = None;
+    for sample in &rx.samples {
+        let Some(phase) = sample.phase else {
+            continue;
+        };
+        let point = Vec2::new(sample.plot_x, (phase / PI - 1.) * half_height);
+        match curves.last_mut() {
+            Some(curve) if last_phase.is_some_and(|last| (phase - last).abs() <= PI) => {
+                curve.push(point)
+            }
+            _ => curves.push(vec![point]),
+        }
+        last_phase = Some(phase);
+    }
+    curves
+}
+
+/// Points of `rx`'s distance view, in its plot's coordinates, and the nearest and furthest
+/// distances its width spans. Each sample's height is the peak of the last `window` samples.
+/// `None` until the receiver has moved far enough to have a range to plot against.
+fn distance_curve(rx: &Receiver, window: usize) -> Option<(Vec<Vec2>, (f32, f32))> {
+    let (distances, values): (Vec<f32>, Vec<f32>) = rx
+        .samples
+        .iter()
+        .filter_map(|sample| Some((sample.distance?, sample.value)))
+        .unzip();
+    let nearest = distances.iter().copied().fold(f32::INFINITY, f32::min);
+    let furthest = distances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    // Also catches having no distances at all, when the span is -∞.
+    if furthest - nearest < MIN_DISTANCE_SPAN {
+        return None;
+    }
+    let half_width = RECEIVER_SIZE.x / 2.;
+    let curve = distances
+        .iter()
+        .enumerate()
+        .map(|(i, &distance)| {
+            let start = (i + 1).saturating_sub(window);
+            let x = ((distance - nearest) / (furthest - nearest) * 2. - 1.) * half_width;
+            Vec2::new(x, rx.plot_y(envelope(&values[start..=i])))
+        })
+        .collect();
+    Some((curve, (nearest, furthest)))
+}
+
+/// Text child of a receiver labelling the distance axis of its distance view.
+#[derive(Component)]
+pub struct DistanceLabel;
+
+/// Gives each new receiver a hidden label under it for its distance view, turned back so it's
+/// level whichever way the receiver lies.
+pub fn spawn_distance_labels(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    rx_query: Query<(Entity, &Receiver), Added<Receiver>>,
+) {
+    for (rx_entity, rx) in rx_query.iter() {
+        let unrotate = rx.orientation.rotation().inverse();
+        let offset = -Vec3::Y * (rx.orientation.size().y / 2. + DISTANCE_LABEL_GAP);
+        let label = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: DISTANCE_LABEL_FONT_SIZE,
+                            color: DISTANCE_COLOR,
+                        },
+                    ),
+                    transform: Transform::from_translation(
+                        (unrotate * offset)
+                            .truncate()
+                            .extend(config.z_layers.relative_to_receiver(config.z_layers.labels)),
+                    )
+                    .with_rotation(unrotate),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                DistanceLabel,
+                WorldLabel,
+                HIGH_RES_LAYERS,
+            ))
+            .id();
+        commands.entity(rx_entity).add_child(label);
+    }
+}
+
+/// Labels the distance axis of each receiver showing its distance view with the range it spans.
+pub fn label_distance_axis(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &Children)>,
+    mut label_query: Query<(&mut Text, &mut Visibility), With<DistanceLabel>>,
+) {
+    let window = config.receiver_window.max(1);
+    for (rx, children) in rx_query.iter() {
+        let mut labels = label_query.iter_many_mut(children);
+        let Some((mut text, mut visibility)) = labels.fetch_next() else {
+            continue;
+        };
+        if rx.view != ReceiverView::Distance {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        let value = match distance_curve(rx, window) {
+            Some((_, (nearest, furthest))) => {
+                format!("Distance from transmitter: {nearest:.0} px ← → {furthest:.0} px")
+            }
+            None => "Move the receiver to plot amplitude against distance".to_string(),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+/// Draws the RMS or envelope curve over each receiver using a sliding window of its samples, or
+/// its frequency or phase over time.
+pub fn draw_receiver_view(
+    config: Res<SimConfig>,
+    rx_query: Query<(&Receiver, &GlobalTransform)>,
+    tx_query: Query<&Transmitter>,
+    mut gizmos: Gizmos,
+) {
+    let window = config.receiver_window.max(1);
+    for (rx, rx_transform) in rx_query.iter() {
+        let (statistic, color): (fn(&[f32]) -> f32, _) = match rx.view {
+            ReceiverView::Raw => continue,
+            ReceiverView::Frequency => {
+                let source = tx_query.iter().find(|tx| tx.index == rx.index);
+                if let Some(tx) = source.filter(|tx| tx.frequency > 0.) {
+                    let curve = frequency_curve(
+                        rx,
+                        tx.frequency,
+                        config.frequency_plot_span,
+                        config.crossing_hysteresis,
+                    );
+                    gizmos.linestrip_2d(
+                        curve
+                            .iter()
+                            .map(|&p| rx_transform.transform_point(p.extend(0.)).xy()),
+                        FREQUENCY_COLOR,
+                    );
+                }
+                continue;
+            }
+            ReceiverView::Distance => {
+                if let Some((curve, _)) = distance_curve(rx, window) {
+                    gizmos.linestrip_2d(
+                        curve
+                            .iter()
+                            .map(|&p| rx_transform.transform_point(p.extend(0.)).xy()),
+                        DISTANCE_COLOR,
+                    );
+                }
+                continue;
+            }
+            ReceiverView::Phase => {
+                for curve in phase_curves(rx) {
+                    gizmos.linestrip_2d(
+                        curve
+                            .iter()
+                            .map(|&p| rx_transform.transform_point(p.extend(0.)).xy()),
+                        PHASE_COLOR,
+                    );
+                }
+                continue;
+            }
+            ReceiverView::Rms => (rms, RMS_COLOR),
+            ReceiverView::Envelope => (envelope, ENVELOPE_COLOR),
+        };
+
+        let values: Vec<f32> = rx.samples.iter().map(|sample| sample.value).collect();
+        let curve: Vec<Vec2> = rx
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let start = (i + 1).saturating_sub(window);
+                Vec2::new(sample.plot_x, rx.plot_y(statistic(&values[start..=i])))
+            })
+            .collect();
+
+        let to_world = |point: Vec2| rx_transform.transform_point(point.extend(0.)).xy();
+        gizmos.linestrip_2d(curve.iter().map(|&p| to_world(p)), color);
+        if rx.view == ReceiverView::Envelope {
+            gizmos.linestrip_2d(curve.iter().map(|&p| to_world(Vec2::new(p.x, -p.y))), color);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Short replays: the positions of everything that moves are recorded a few times a second, and
+//! the recent past can be scrubbed back through while the simulation is paused. Particles caught
+//! by receivers and plot points cleared from their plots are retired rather than despawned, kept
+//! hidden until they're older than the history, so going back shows them again.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    Despawning, PlotPoint, Receiver, SignalParticle, SimulationReset, TransmitterBody,
+};
+
+/// Seconds between snapshots, in simulation time.
+const SNAPSHOT_INTERVAL_SECS: f32 = 0.05;
+/// Snapshots kept, bounding the history to the last few seconds.
+const MAX_SNAPSHOTS: usize = 100;
+/// Seconds of simulation time the snapshots cover.
+const HISTORY_SECS: f32 = SNAPSHOT_INTERVAL_SECS * MAX_SNAPSHOTS as f32;
+
+/// Where everything tracked was, and whether it was shown, at one moment.
+struct Snapshot {
+    entities: HashMap<Entity, (Transform, Visibility)>,
+}
+
+/// A particle or plot point that's gone from the simulation, hidden but kept for as long as the
+/// history could still go back to when it was there, then despawned.
+#[derive(Component)]
+pub struct Retired(Timer);
+
+impl Retired {
+    /// What to insert into an entity in place of despawning it.
+    pub fn bundle() -> (Retired, Visibility) {
+        (
+            Retired(Timer::from_seconds(HISTORY_SECS, TimerMode::Once)),
+            Visibility::Hidden,
+        )
+    }
+}
+
+#[derive(Resource)]
+pub struct Rewind {
+    snapshots: VecDeque<Snapshot>,
+    timer: Timer,
+    /// Index of the snapshot being shown while rewinding, `None` while running live.
+    cursor: Option<usize>,
+    /// Whether the simulation was already paused when rewinding started.
+    was_paused: bool,
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            timer: Timer::from_seconds(SNAPSHOT_INTERVAL_SECS, TimerMode::Repeating),
+            cursor: None,
+            was_paused: false,
+        }
+    }
+}
+
+/// Run condition for systems that must not touch positions while a snapshot is being shown.
+pub fn is_live(rewind: Res<Rewind>) -> bool {
+    rewind.cursor.is_none()
+}
+
+type Tracked = Or<(
+    With<SignalParticle>,
+    With<Receiver>,
+    With<TransmitterBody>,
+    With<PlotPoint>,
+    With<Despawning>,
+    With<Retired>,
+)>;
+
+fn take_snapshot(query: &Query<(Entity, &mut Transform, &mut Visibility), Tracked>) -> Snapshot {
+    Snapshot {
+        entities: query
+            .iter()
+            .map(|(entity, transform, visibility)| (entity, (*transform, *visibility)))
+            .collect(),
+    }
+}
+
+fn push_snapshot(rewind: &mut Rewind, snapshot: Snapshot) {
+    if rewind.snapshots.len() == MAX_SNAPSHOTS {
+        rewind.snapshots.pop_front();
+    }
+    rewind.snapshots.push_back(snapshot);
+}
+
+pub fn record_history(
+    mut rewind: ResMut<Rewind>,
+    mut reset_events: EventReader<SimulationReset>,
+    query: Query<(Entity, &mut Transform, &mut Visibility), Tracked>,
+    time: Res<Time>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    bindings: Res<KeyBindings>,
+    mut hud: ResMut<Hud>,
+) {
+    // Snapshots from before a reset refer to entities that are gone, so a rewind in progress
+    // ends there too.
+    if reset_events.read().count() > 0 {
+        rewind.snapshots.clear();
+        if rewind.cursor.take().is_some() {
+            if !rewind.was_paused {
+                virtual_time.unpause();
+            }
+            hud.set("rewind", rewind_label(&rewind, &bindings));
+        }
+    }
+    if rewind.cursor.is_some() || !rewind.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let snapshot = take_snapshot(&query);
+    push_snapshot(&mut rewind, snapshot);
+}
+
+/// Home starts and stops rewinding. While rewinding, ',' steps back and '.' steps forward through
+/// the recorded snapshots; both can be held to scrub. Stopping returns everything to where it
+/// was when rewinding started and carries on from there.
+pub fn control_rewind(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut rewind: ResMut<Rewind>,
+    mut time: ResMut<Time<Virtual>>,
+    mut query: Query<(Entity, &mut Transform, &mut Visibility), Tracked>,
+    mut hud: ResMut<Hud>,
+) {
+    let rewind = &mut *rewind;
+    let toggled = input.just_pressed(bindings.rewind);
+    let Some(cursor) = rewind.cursor else {
+        if toggled {
+            // Record the present so there's something to return to.
+            let snapshot = take_snapshot(&query);
+            push_snapshot(rewind, snapshot);
+            rewind.cursor = Some(rewind.snapshots.len() - 1);
+            rewind.was_paused = time.is_paused();
+            time.pause();
+            hud.set("rewind", rewind_label(rewind, &bindings));
+        }
+        return;
+    };
+    if rewind.snapshots.is_empty() {
+        return;
+    }
+
+    let new_cursor = if toggled {
+        rewind.snapshots.len() - 1
+    } else if input.pressed(bindings.rewind_back) {
+        cursor.saturating_sub(1)
+    } else if input.pressed(bindings.rewind_forward) {
+        (cursor + 1).min(rewind.snapshots.len() - 1)
+    } else {
+        return;
+    };
+    rewind.cursor = Some(new_cursor);
+
+    // Nothing is spawned while paused, so the last snapshot, of the present, has everything in it
+    // and resuming puts all of it back as it was.
+    let snapshot = &rewind.snapshots[new_cursor];
+    for (entity, mut transform, mut visibility) in query.iter_mut() {
+        match snapshot.entities.get(&entity) {
+            Some((shown_transform, shown_visibility)) => {
+                *transform = *shown_transform;
+                *visibility = *shown_visibility;
+            }
+            // Didn't exist yet.
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    if toggled {
+        rewind.cursor = None;
+        if !rewind.was_paused {
+            time.unpause();
+        }
+    }
+    hud.set("rewind", rewind_label(rewind, &bindings));
+}
+
+/// Despawns whatever was retired longer ago than the history goes back.
+pub fn despawn_retired(
+    mut commands: Commands,
+    mut query: Query<(Entity, Option<&Parent>, &mut Retired)>,
+    time: Res<Time>,
+) {
+    for (entity, parent, mut retired) in query.iter_mut() {
+        if retired.0.tick(time.delta()).finished() {
+            if let Some(parent) = parent {
+                commands.entity(parent.get()).remove_children(&[entity]);
+            }
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn rewind_label(rewind: &Rewind, bindings: &KeyBindings) -> String {
+    let toggle = key_name(bindings.rewind);
+    match rewind.cursor {
+        Some(cursor) => {
+            let behind = (rewind.snapshots.len() - 1 - cursor) as f32 * SNAPSHOT_INTERVAL_SECS;
+            format!(
+                "Rewound {behind:.2}s ({} and {} to scrub, {toggle} to resume)",
+                key_name(bindings.rewind_back),
+                key_name(bindings.rewind_forward)
+            )
+        }
+        None => format!("Press {toggle} to rewind"),
+    }
+}
+
+pub fn rewind_help(bindings: &KeyBindings) -> String {
+    rewind_label(&Rewind::default(), bindings)
+}
@@ -0,0 +1,143 @@
+//! A ruler one wavelength long laid along each transmitter's waves, to check the spacing of the
+//! particles against `λ = |speed| / frequency`.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{EmissionMode, SimConfig},
+    follow::WorldLabel,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics, OverlayFont, Transmitter, HIGH_RES_LAYERS, PARTICLE_AMPLITUDE,
+};
+
+const RULER_COLOR: Color = Color::rgba(1., 0.85, 0.3, 0.8);
+/// Gap between the edge of the wave and the ruler.
+const RULER_OFFSET: f32 = 15.;
+const TICK_HALF_LENGTH: f32 = 5.;
+const LABEL_FONT_SIZE: f32 = 16.;
+/// Gap between the ruler and its label.
+const LABEL_OFFSET: f32 = 12.;
+
+/// Whether the wavelength rulers are drawn.
+#[derive(Resource, Default)]
+pub struct ShowRuler(pub bool);
+
+/// Text child of a transmitter showing its wavelength.
+#[derive(Component)]
+pub struct RulerLabel;
+
+pub fn toggle_ruler(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut show: ResMut<ShowRuler>,
+    mut hud: ResMut<Hud>,
+) {
+    // With shift it's the wavelength grid's key instead.
+    if input.just_pressed(bindings.wavelength_ruler)
+        && !input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        show.0 = !show.0;
+        hud.set("wavelength_ruler", ruler_label(show.0, &bindings));
+    }
+}
+
+pub fn ruler_label(shown: bool, bindings: &KeyBindings) -> String {
+    let action = if shown { "hide" } else { "show" };
+    let key = key_name(bindings.wavelength_ruler);
+    format!("Press {key} to {action} a one wavelength ruler")
+}
+
+/// Gives each new transmitter a hidden label for its ruler. As a child of the transmitter it
+/// goes away with it on reset.
+pub fn spawn_ruler_labels(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    tx_query: Query<Entity, Added<Transmitter>>,
+) {
+    for tx_entity in tx_query.iter() {
+        let label = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: LABEL_FONT_SIZE,
+                            color: RULER_COLOR,
+                        },
+                    ),
+                    transform: Transform::from_xyz(
+                        0.,
+                        0.,
+                        config.z_layers.labels - config.z_layers.transmitters,
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                RulerLabel,
+                WorldLabel,
+                HIGH_RES_LAYERS,
+            ))
+            .id();
+        commands.entity(tx_entity).add_child(label);
+    }
+}
+
+/// Direction `tx`'s waves travel in from its spawn point: along the stream, or down the middle
+/// of its beam in point-source mode. `None` for a standing wave, which doesn't travel.
+fn direction_of_travel(config: &SimConfig, tx: &Transmitter) -> Option<Vec2> {
+    let direction = match config.emission_mode() {
+        EmissionMode::Stream | EmissionMode::PlaneWave => {
+            physics::direction_of_travel(config.wave_speed)
+        }
+        EmissionMode::PointSource => Vec2::from_angle(tx.beam_direction),
+    }
+    .rotate(config.propagation_axis());
+    (direction != Vec2::ZERO && config.wave_speed != 0.).then_some(direction)
+}
+
+/// Draws a ruler exactly one wavelength long from each transmitter's spawn point along the way
+/// its waves travel, just clear of the wave, with the wavelength written under it. Follows the
+/// current wave speed and frequencies, so it can be compared with the particles at any time.
+pub fn draw_rulers(
+    show: Res<ShowRuler>,
+    config: Res<SimConfig>,
+    tx_query: Query<(&Transmitter, &GlobalTransform, &Children)>,
+    mut label_query: Query<(&mut Text, &mut Transform, &mut Visibility), With<RulerLabel>>,
+    mut gizmos: Gizmos,
+) {
+    for (tx, tx_transform, children) in tx_query.iter() {
+        let mut labels = label_query.iter_many_mut(children);
+        let Some((mut text, mut transform, mut visibility)) = labels.fetch_next() else {
+            continue;
+        };
+        let direction = direction_of_travel(&config, tx).filter(|_| show.0 && tx.frequency > 0.);
+        let Some(direction) = direction else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let wavelength = config.wave_speed.abs() / tx.frequency;
+        // Beneath the wave, on the side its displacement is negative.
+        let side = -direction.perp() * (PARTICLE_AMPLITUDE + RULER_OFFSET);
+        let start = tx.spawn_point + side;
+        let end = start + direction * wavelength;
+        let origin = tx_transform.translation().xy();
+        gizmos.line_2d(origin + start, origin + end, RULER_COLOR);
+        for point in [start, end] {
+            let tick = direction.perp() * TICK_HALF_LENGTH;
+            gizmos.line_2d(origin + point - tick, origin + point + tick, RULER_COLOR);
+        }
+
+        let value = format!("λ = {wavelength:.1} px");
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        let label_position = (start + end) / 2. - direction.perp() * LABEL_OFFSET;
+        transform.translation.x = label_position.x;
+        transform.translation.y = label_position.y;
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{EmissionPattern, SimConfig},
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics::Dispersion,
+    receiver_view::ReceiverOrientation,
+    Canvas, Movement, SimulationReset, SimulationSpec,
+};
+
+/// Burst used by [`Scenario::Pulsed`] when the configured emission pattern is continuous.
+const PULSED_BURST: EmissionPattern = EmissionPattern::Burst { count: 8, gap: 1.5 };
+
+/// Speed of the transmitter in [`Scenario::Supersonic`], Mach 1.5 at the default wave speed.
+const SUPERSONIC_SPEED: f32 = 300.;
+
+/// One period of the signal sent in [`Scenario::CustomWaveform`]: a sharp double-sided pulse
+/// that rings down, then silence.
+const SAMPLE_PULSE: [f32; 16] = [
+    0., 0.6, 1., 0.4, -0.5, -0.8, -0.3, 0.25, 0.3, 0.05, -0.1, -0.05, 0., 0., 0., 0.,
+];
+
+/// Seconds taken to fade the canvas out and back in when stepping through scenarios.
+const TRANSITION_SECS: f32 = 0.3;
+
+/// Preset arrangements of transmitters and receivers.
+#[derive(Clone, Copy, Debug, Reflect, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scenario {
+    /// A stationary, an approaching and a receding receiver, each with its own stationary
+    /// transmitter.
+    #[default]
+    ThreeReceivers,
+    /// A transmitter and receiver moving towards each other, so both the source and the observer
+    /// contribute to the shift.
+    Converging,
+    /// A transmitter emitting short bursts at an approaching receiver, so the plot shows separate
+    /// wave packets like a pulsed radar or sonar.
+    Pulsed,
+    /// Two dispersive waves at stationary receivers, one whose packets travel slower than its
+    /// crests and one whose packets travel faster, to compare group and phase velocity.
+    Dispersive,
+    /// A transmitter flying towards a stationary receiver faster than its waves, so it trails a
+    /// Mach cone and the receiver hears nothing until it has passed.
+    Supersonic,
+    /// An approaching horizontal receiver above a stationary vertical one, whose trace runs down
+    /// it like a waterfall.
+    Orientations,
+    /// A transmitter sending a short recorded-style pulse instead of a sine to an approaching
+    /// receiver, so the whole shape is seen squeezed by the Doppler shift.
+    CustomWaveform,
+    /// The same source heard by an approaching and a receding receiver, with the difference
+    /// between the frequencies they hear shown large. Each receiver has its own copy of the
+    /// stationary transmitter, which is indistinguishable from sharing one.
+    Differential,
+    /// A transmitter streaming both ways between two stationary receivers while it drifts
+    /// right, so the one ahead of it hears a higher frequency and the one behind a lower one.
+    Bidirectional,
+    /// A receiver moving back and forth between two points, alternately approaching and
+    /// receding, with a fresh plot for each leg.
+    PingPong,
+    /// A receiver approaching a transmitter that sends plane waves, flat wavefronts across the
+    /// whole height as if from a source far away, unless another emission mode is configured.
+    PlaneWave,
+    /// A point source with stationary receivers on an arc around it, all the same distance
+    /// away, so they hear the same phase until one is moved. See [`crate::placement::PathArc`].
+    EqualPaths,
+    /// A phased array: a line of elements each lagging the one before, so their waves add up to
+    /// a beam tilted off the array's axis, heard loudest by the receiver on the arc it points
+    /// at. See `array_phase_step`.
+    BeamSteering,
+}
+
+impl Scenario {
+    const ALL: [Scenario; 13] = [
+        Scenario::ThreeReceivers,
+        Scenario::Converging,
+        Scenario::Pulsed,
+        Scenario::Dispersive,
+        Scenario::Supersonic,
+        Scenario::Orientations,
+        Scenario::CustomWaveform,
+        Scenario::Differential,
+        Scenario::Bidirectional,
+        Scenario::PingPong,
+        Scenario::PlaneWave,
+        Scenario::EqualPaths,
+        Scenario::BeamSteering,
+    ];
+
+    /// Whether its receivers are laid out on the configured `path_arc`.
+    pub fn uses_path_arc(self) -> bool {
+        matches!(self, Scenario::EqualPaths | Scenario::BeamSteering)
+    }
+
+    /// The scenario `step` places after this one in [`Scenario::ALL`], wrapping around.
+    fn step(self, step: isize) -> Scenario {
+        let index = Scenario::ALL.iter().position(|s| *s == self).unwrap_or(0);
+        let len = Scenario::ALL.len() as isize;
+        Scenario::ALL[(index as isize + step).rem_euclid(len) as usize]
+    }
+
+    pub fn simulations(self) -> Vec<SimulationSpec> {
+        match self {
+            Scenario::ThreeReceivers => vec![
+                SimulationSpec {
+                    label: Some("Stationary"),
+                    y_pos: 200.,
+                    ..default()
+                },
+                SimulationSpec {
+                    label: Some("Approaching"),
+                    rx_movement: Movement::Right,
+                    ..default()
+                },
+                SimulationSpec {
+                    label: Some("Receding"),
+                    rx_start_x: 100.,
+                    y_pos: -200.,
+                    rx_movement: Movement::Left,
+                    ..default()
+                },
+            ],
+            Scenario::Converging => vec![SimulationSpec {
+                label: Some("Converging"),
+                rx_movement: Movement::Right,
+                tx_movement: Movement::Left,
+                ..default()
+            }],
+            Scenario::Pulsed => vec![SimulationSpec {
+                label: Some("Pulsed"),
+                rx_movement: Movement::Right,
+                emission_pattern: Some(PULSED_BURST),
+                ..default()
+            }],
+            Scenario::Dispersive => vec![
+                SimulationSpec {
+                    label: Some("Groups slower than crests"),
+                    y_pos: 150.,
+                    dispersion: Some(Dispersion::PowerLaw { exponent: 0.5 }),
+                    ..default()
+                },
+                SimulationSpec {
+                    label: Some("Groups faster than crests"),
+                    y_pos: -150.,
+                    dispersion: Some(Dispersion::PowerLaw { exponent: 2. }),
+                    ..default()
+                },
+            ],
+            Scenario::Supersonic => vec![SimulationSpec {
+                label: Some("Supersonic"),
+                tx_movement: Movement::Left,
+                tx_speed: SUPERSONIC_SPEED,
+                ..default()
+            }],
+            Scenario::Orientations => vec![
+                SimulationSpec {
+                    label: Some("Horizontal"),
+                    y_pos: 200.,
+                    rx_movement: Movement::Right,
+                    ..default()
+                },
+                SimulationSpec {
+                    label: Some("Vertical"),
+                    y_pos: -100.,
+                    rx_orientation: Some(ReceiverOrientation::Vertical),
+                    ..default()
+                },
+            ],
+            Scenario::CustomWaveform => vec![SimulationSpec {
+                label: Some("Custom waveform"),
+                rx_movement: Movement::Right,
+                waveform: Some(&SAMPLE_PULSE),
+                ..default()
+            }],
+            Scenario::Differential => vec![
+                SimulationSpec {
+                    label: Some("Approaching"),
+                    y_pos: 150.,
+                    rx_movement: Movement::Right,
+                    ..default()
+                },
+                SimulationSpec {
+                    label: Some("Receding"),
+                    rx_start_x: 100.,
+                    y_pos: -150.,
+                    rx_movement: Movement::Left,
+                    ..default()
+                },
+            ],
+            Scenario::Bidirectional => vec![SimulationSpec {
+                label: Some("Both ways"),
+                rx_start_x: -450.,
+                tx_x: -50.,
+                tx_movement: Movement::Right,
+                bidirectional: true,
+                mirrored_rx: Some(Movement::Stationary),
+                ..default()
+            }],
+            Scenario::PingPong => vec![SimulationSpec {
+                label: Some("Ping-pong"),
+                rx_movement: Movement::PingPong {
+                    left: -500.,
+                    right: 0.,
+                    rightward: true,
+                },
+                ..default()
+            }],
+            Scenario::PlaneWave => vec![SimulationSpec {
+                label: Some("Plane wave"),
+                rx_movement: Movement::Right,
+                ..default()
+            }],
+            Scenario::EqualPaths => vec![SimulationSpec {
+                label: Some("Equal paths"),
+                tx_x: 350.,
+                on_arc: true,
+                ..default()
+            }],
+            Scenario::BeamSteering => vec![SimulationSpec {
+                label: Some("Beam steering"),
+                tx_x: 350.,
+                on_arc: true,
+                ..default()
+            }],
+        }
+    }
+}
+
+/// A fade out, rebuild and fade in of the canvas while switching to `target`.
+struct Transition {
+    target: Scenario,
+    elapsed: f32,
+    rebuilt: bool,
+}
+
+/// The scenario transition in progress, if any. Scenario keys are ignored until it finishes.
+#[derive(Resource, Default)]
+pub struct ScenarioTransition(Option<Transition>);
+
+impl ScenarioTransition {
+    /// Starts a transition to `target`, unless one is already under way.
+    pub fn switch_to(&mut self, target: Scenario) {
+        self.0.get_or_insert(Transition {
+            target,
+            elapsed: 0.,
+            rebuilt: false,
+        });
+    }
+}
+
+pub fn select_scenario(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut transition: ResMut<ScenarioTransition>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    if transition.0.is_some() {
+        return;
+    }
+
+    let step = if input.just_pressed(bindings.next_scenario) {
+        1
+    } else if input.just_pressed(bindings.previous_scenario) {
+        -1
+    } else {
+        0
+    };
+    if step != 0 {
+        transition.switch_to(config.scenario.step(step));
+        return;
+    }
+
+    for (scenario, key) in Scenario::ALL.into_iter().zip(&bindings.scenarios) {
+        if input.just_pressed(*key) {
+            config.scenario = scenario;
+            reset_events.send(SimulationReset);
+            hud.set("scenario", scenario_label(scenario, &bindings));
+        }
+    }
+}
+
+/// Fades the canvas out, switches scenario once it's hidden, then fades it back in. Runs on real
+/// time so it still completes while the simulation is paused.
+pub fn run_scenario_transition(
+    time: Res<Time<Real>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut transition: ResMut<ScenarioTransition>,
+    mut canvas_query: Query<&mut Sprite, With<Canvas>>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    let Some(current) = transition.0.as_mut() else {
+        return;
+    };
+    current.elapsed += time.delta_seconds();
+
+    let half = TRANSITION_SECS / 2.;
+    if current.elapsed >= half && !current.rebuilt {
+        current.rebuilt = true;
+        config.scenario = current.target;
+        reset_events.send(SimulationReset);
+        hud.set("scenario", scenario_label(current.target, &bindings));
+    }
+
+    let alpha = ((current.elapsed - half).abs() / half).min(1.);
+    for mut sprite in canvas_query.iter_mut() {
+        sprite.color.set_a(alpha);
+    }
+    if current.elapsed >= TRANSITION_SECS {
+        transition.0 = None;
+    }
+}
+
+pub fn scenario_label(scenario: Scenario, bindings: &KeyBindings) -> String {
+    let keys: Vec<_> = bindings
+        .scenarios
+        .iter()
+        .take(Scenario::ALL.len())
+        .map(|key| key_name(*key))
+        .collect();
+    format!(
+        "Scenario: {scenario:?} (press {}, {} or {} to change)",
+        keys.join("/"),
+        key_name(bindings.previous_scenario),
+        key_name(bindings.next_scenario)
+    )
+}
@@ -0,0 +1,639 @@
+//! A panel of sliders and toggles for the settings that can be changed at runtime, so they can be
+//! found and set precisely without remembering their keys. Hidden until its key is pressed, so it
+//! stays out of screenshots.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    amplitude_label,
+    config::{EmissionMode, SimConfig},
+    crests_only_label,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics, CurrentAmplitude, OverlayFont, SelectedReceiver, Transmitter, AMPLITUDE_STEP,
+    HIGH_RES_LAYERS, PARTICLE_AMPLITUDE,
+};
+
+const PANEL_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.75);
+const PANEL_WIDTH: f32 = 260.;
+const FONT_SIZE: f32 = 16.;
+const TRACK_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+const TRACK_HEIGHT: f32 = 4.;
+const HANDLE_COLOR: Color = Color::WHITE;
+const HANDLE_SIZE: f32 = 14.;
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_ON_COLOR: Color = Color::rgb(0.2, 0.45, 0.2);
+/// A toggle that's on because the scenario decides it, so can't be switched off.
+const BUTTON_LOCKED_COLOR: Color = Color::rgb(0.15, 0.3, 0.15);
+
+/// Root node of the settings panel.
+#[derive(Component)]
+pub struct SettingsPanel;
+
+/// A setting adjusted with a slider.
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SliderSetting {
+    /// Frequency of the selected receiver's transmitter.
+    Frequency,
+    WaveSpeed,
+    Amplitude,
+    /// Milliseconds between emissions in the current emission mode.
+    SpawnInterval,
+}
+
+impl SliderSetting {
+    const ALL: [SliderSetting; 4] = [
+        SliderSetting::Frequency,
+        SliderSetting::WaveSpeed,
+        SliderSetting::Amplitude,
+        SliderSetting::SpawnInterval,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SliderSetting::Frequency => "Frequency (selected)",
+            SliderSetting::WaveSpeed => "Wave speed",
+            SliderSetting::Amplitude => "Amplitude",
+            SliderSetting::SpawnInterval => "Time between emissions",
+        }
+    }
+
+    /// Values at the left and right ends of the slider.
+    fn range(self) -> (f32, f32) {
+        match self {
+            SliderSetting::Frequency => (0.25, 5.),
+            SliderSetting::WaveSpeed => (-400., 400.),
+            SliderSetting::Amplitude => (AMPLITUDE_STEP, PARTICLE_AMPLITUDE),
+            SliderSetting::SpawnInterval => (5., 100.),
+        }
+    }
+
+    pub fn format(self, value: f32) -> String {
+        match self {
+            SliderSetting::Frequency => format!("{value:.2} Hz"),
+            SliderSetting::WaveSpeed => format!("{value:.0} px/s"),
+            SliderSetting::Amplitude => format!("{value:.0} px"),
+            SliderSetting::SpawnInterval => format!("{value:.0} ms"),
+        }
+    }
+
+    /// How far along the slider `value` sits, from 0 at the left to 1 at the right.
+    fn fraction(self, value: f32) -> f32 {
+        let (min, max) = self.range();
+        ((value - min) / (max - min)).clamp(0., 1.)
+    }
+
+    fn value_at(self, fraction: f32) -> f32 {
+        let (min, max) = self.range();
+        min + (max - min) * fraction.clamp(0., 1.)
+    }
+}
+
+/// A setting switched on and off with a button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToggleSetting {
+    CrestsOnly,
+    SpectrumColors,
+    PointSource,
+    PlaneWave,
+    PersistTraces,
+}
+
+impl ToggleSetting {
+    const ALL: [ToggleSetting; 5] = [
+        ToggleSetting::CrestsOnly,
+        ToggleSetting::SpectrumColors,
+        ToggleSetting::PointSource,
+        ToggleSetting::PlaneWave,
+        ToggleSetting::PersistTraces,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ToggleSetting::CrestsOnly => "Crests only",
+            ToggleSetting::SpectrumColors => "Spectrum colors",
+            ToggleSetting::PointSource => "Point source",
+            ToggleSetting::PlaneWave => "Plane wave",
+            ToggleSetting::PersistTraces => "Keep old traces",
+        }
+    }
+
+    fn get(self, config: &SimConfig) -> bool {
+        match self {
+            ToggleSetting::CrestsOnly => config.crests_only,
+            ToggleSetting::SpectrumColors => config.spectrum_colors,
+            ToggleSetting::PointSource => config.emission_mode() == EmissionMode::PointSource,
+            ToggleSetting::PlaneWave => config.emission_mode() == EmissionMode::PlaneWave,
+            ToggleSetting::PersistTraces => config.persist_traces,
+        }
+    }
+
+    /// Whether the toggle is on only because the scenario picks its emission mode while
+    /// `emission_mode` is left at `Stream`, so switching it off wouldn't change anything.
+    fn locked(self, config: &SimConfig) -> bool {
+        matches!(self, ToggleSetting::PointSource | ToggleSetting::PlaneWave)
+            && config.emission_mode == EmissionMode::Stream
+            && self.get(config)
+    }
+
+    fn set(self, config: &mut SimConfig, on: bool) {
+        match self {
+            ToggleSetting::CrestsOnly => config.crests_only = on,
+            ToggleSetting::SpectrumColors => config.spectrum_colors = on,
+            ToggleSetting::PointSource => {
+                config.emission_mode = if on {
+                    EmissionMode::PointSource
+                } else {
+                    EmissionMode::Stream
+                }
+            }
+            ToggleSetting::PlaneWave => {
+                config.emission_mode = if on {
+                    EmissionMode::PlaneWave
+                } else {
+                    EmissionMode::Stream
+                }
+            }
+            ToggleSetting::PersistTraces => config.persist_traces = on,
+        }
+    }
+}
+
+/// An action whose default key is on the numpad, which compact keyboards don't have, so the panel
+/// gives it a button that holds its key down while pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    GainUp,
+    GainDown,
+    FieldMode,
+    ReceiverTable,
+    Nyquist,
+    LabelSpacing,
+    StandingWaveNodes,
+    TimeAxis,
+    TempoDown,
+    TempoUp,
+    Explode,
+    FirePulse,
+    /// Saves to the preset slot with this index, or with shift loads it.
+    PresetSlot(usize),
+}
+
+impl KeyAction {
+    const ALL: [KeyAction; 16] = [
+        KeyAction::GainUp,
+        KeyAction::GainDown,
+        KeyAction::TempoUp,
+        KeyAction::TempoDown,
+        KeyAction::FirePulse,
+        KeyAction::FieldMode,
+        KeyAction::ReceiverTable,
+        KeyAction::Nyquist,
+        KeyAction::LabelSpacing,
+        KeyAction::StandingWaveNodes,
+        KeyAction::TimeAxis,
+        KeyAction::Explode,
+        KeyAction::PresetSlot(0),
+        KeyAction::PresetSlot(1),
+        KeyAction::PresetSlot(2),
+        KeyAction::PresetSlot(3),
+    ];
+
+    fn label(self) -> String {
+        match self {
+            KeyAction::GainUp => "Gain +".into(),
+            KeyAction::GainDown => "Gain -".into(),
+            KeyAction::FieldMode => "Field mode".into(),
+            KeyAction::ReceiverTable => "Table".into(),
+            KeyAction::Nyquist => "Nyquist".into(),
+            KeyAction::LabelSpacing => "Label spacing".into(),
+            KeyAction::StandingWaveNodes => "Nodes".into(),
+            KeyAction::TimeAxis => "Time axis".into(),
+            KeyAction::TempoDown => "Tempo -".into(),
+            KeyAction::TempoUp => "Tempo +".into(),
+            KeyAction::Explode => "Explode".into(),
+            KeyAction::FirePulse => "Fire".into(),
+            KeyAction::PresetSlot(slot) => format!("Preset {}", slot + 1),
+        }
+    }
+
+    /// The key the action is bound to, if it's bound to one.
+    fn key(self, bindings: &KeyBindings) -> Option<KeyCode> {
+        Some(match self {
+            KeyAction::GainUp => bindings.gain_up,
+            KeyAction::GainDown => bindings.gain_down,
+            KeyAction::FieldMode => bindings.field_mode,
+            KeyAction::ReceiverTable => bindings.receiver_table,
+            KeyAction::Nyquist => bindings.nyquist,
+            KeyAction::LabelSpacing => bindings.label_spacing,
+            KeyAction::StandingWaveNodes => bindings.standing_wave_nodes,
+            KeyAction::TimeAxis => bindings.time_axis,
+            KeyAction::TempoDown => bindings.tempo_down,
+            KeyAction::TempoUp => bindings.tempo_up,
+            KeyAction::Explode => bindings.explode,
+            KeyAction::FirePulse => bindings.fire_pulse,
+            KeyAction::PresetSlot(slot) => return bindings.preset_slots.get(slot).copied(),
+        })
+    }
+}
+
+/// The draggable area of a slider.
+#[derive(Component)]
+pub struct Slider(SliderSetting);
+
+#[derive(Component)]
+pub struct SliderHandle(SliderSetting);
+
+/// Text showing a slider's current value.
+#[derive(Component)]
+pub struct SliderValue(SliderSetting);
+
+#[derive(Component)]
+pub struct ToggleButton(ToggleSetting);
+
+/// A button for an action, with the key it's holding down while pressed.
+#[derive(Component)]
+pub struct ActionButton {
+    action: KeyAction,
+    held: Option<KeyCode>,
+}
+
+pub fn setup_settings_panel(mut commands: Commands, font: Res<OverlayFont>) {
+    let text_style = TextStyle {
+        font: font.0.clone(),
+        font_size: FONT_SIZE,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(15.),
+                    right: Val::Px(15.),
+                    width: Val::Px(PANEL_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.),
+                    padding: UiRect::all(Val::Px(10.)),
+                    ..default()
+                },
+                background_color: PANEL_BACKGROUND.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            SettingsPanel,
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|panel| {
+            for setting in SliderSetting::ALL {
+                panel.spawn((
+                    TextBundle::from_sections([
+                        TextSection::new(format!("{}: ", setting.label()), text_style.clone()),
+                        TextSection::new("", text_style.clone()),
+                    ]),
+                    SliderValue(setting),
+                ));
+                panel
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.),
+                                height: Val::Px(HANDLE_SIZE),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        Interaction::default(),
+                        Slider(setting),
+                    ))
+                    .with_children(|slider| {
+                        slider.spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::Percent(100.),
+                                height: Val::Px(TRACK_HEIGHT),
+                                top: Val::Px((HANDLE_SIZE - TRACK_HEIGHT) / 2.),
+                                ..default()
+                            },
+                            background_color: TRACK_COLOR.into(),
+                            ..default()
+                        });
+                        slider.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    width: Val::Px(HANDLE_SIZE),
+                                    height: Val::Px(HANDLE_SIZE),
+                                    margin: UiRect::left(Val::Px(-HANDLE_SIZE / 2.)),
+                                    ..default()
+                                },
+                                background_color: HANDLE_COLOR.into(),
+                                ..default()
+                            },
+                            SliderHandle(setting),
+                        ));
+                    });
+            }
+            for setting in ToggleSetting::ALL {
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(4.)),
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        ToggleButton(setting),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            setting.label(),
+                            text_style.clone(),
+                        ));
+                    });
+            }
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_wrap: FlexWrap::Wrap,
+                        row_gap: Val::Px(4.),
+                        column_gap: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for action in KeyAction::ALL {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(4.)),
+                                    ..default()
+                                },
+                                background_color: BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            ActionButton { action, held: None },
+                        ))
+                        .with_children(|button| {
+                            button.spawn(TextBundle::from_section(
+                                action.label(),
+                                text_style.clone(),
+                            ));
+                        });
+                    }
+                });
+        });
+}
+
+/// Whether the cursor is over the shown settings panel or using one of its controls. Left clicks
+/// are then meant for the panel, so nothing in the world underneath reacts to them.
+#[derive(Resource, Default)]
+pub struct PointerOverUi(pub bool);
+
+/// Updates [`PointerOverUi`] once the UI has worked out what the cursor is interacting with.
+pub fn track_pointer_over_ui(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    panel_query: Query<(&Node, &GlobalTransform, &Visibility), With<SettingsPanel>>,
+    interaction_query: Query<&Interaction>,
+    mut pointer: ResMut<PointerOverUi>,
+) {
+    let cursor = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let over_panel = cursor.is_some_and(|cursor| {
+        panel_query.iter().any(|(node, transform, visibility)| {
+            *visibility != Visibility::Hidden && node.logical_rect(transform).contains(cursor)
+        })
+    });
+    let interacting = interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    pointer.0 = over_panel || interacting;
+}
+
+pub fn settings_label(shown: bool, bindings: &KeyBindings) -> String {
+    let action = if shown { "hide" } else { "show" };
+    format!(
+        "Press {} to {action} the settings panel",
+        key_name(bindings.settings)
+    )
+}
+
+pub fn toggle_settings_panel(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut panel_query: Query<&mut Visibility, With<SettingsPanel>>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.settings) {
+        return;
+    }
+    for mut visibility in panel_query.iter_mut() {
+        let shown = *visibility == Visibility::Hidden;
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        hud.set("settings", settings_label(shown, &bindings));
+    }
+}
+
+/// Sets the slider's setting from where along it the cursor is while it's pressed. Holding the
+/// button and dragging off the end pins the setting to that end of its range.
+pub fn drag_sliders(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    slider_query: Query<(&Slider, &Interaction, &Node, &GlobalTransform)>,
+    mut config: ResMut<SimConfig>,
+    mut amplitude: ResMut<CurrentAmplitude>,
+    selected: Res<SelectedReceiver>,
+    mut tx_query: Query<&mut Transmitter>,
+    bindings: Res<KeyBindings>,
+    mut hud: ResMut<Hud>,
+) {
+    let Some(cursor) = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    for (slider, interaction, node, transform) in slider_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let rect = node.logical_rect(transform);
+        let value = slider
+            .0
+            .value_at((cursor.x - rect.min.x) / rect.width().max(1.));
+        apply_setting(
+            slider.0,
+            value,
+            &mut config,
+            &mut amplitude,
+            selected.0,
+            &mut tx_query,
+            &bindings,
+            &mut hud,
+        );
+    }
+}
+
+/// Sets `setting` to `value`, for the transmitter of the receiver with index `selected` where it
+/// only affects one.
+pub fn apply_setting(
+    setting: SliderSetting,
+    value: f32,
+    config: &mut SimConfig,
+    amplitude: &mut CurrentAmplitude,
+    selected: usize,
+    tx_query: &mut Query<&mut Transmitter>,
+    bindings: &KeyBindings,
+    hud: &mut Hud,
+) {
+    match setting {
+        SliderSetting::Frequency => {
+            for mut tx in tx_query.iter_mut().filter(|tx| tx.index == selected) {
+                tx.frequency = value;
+            }
+        }
+        SliderSetting::WaveSpeed => config.wave_speed = physics::clamp_wave_speed(value),
+        SliderSetting::Amplitude => {
+            amplitude.0 = value.clamp(AMPLITUDE_STEP, PARTICLE_AMPLITUDE);
+            hud.set("amplitude", amplitude_label(amplitude.0, bindings));
+        }
+        SliderSetting::SpawnInterval => {
+            let ms = value.round() as u64;
+            match config.emission_mode() {
+                EmissionMode::Stream => config.spawn_rate_ms = ms,
+                EmissionMode::PointSource => config.point_source_spawn_rate_ms = ms,
+                EmissionMode::PlaneWave => config.plane_wave_spawn_rate_ms = ms,
+            }
+            set_spawn_intervals(config, tx_query);
+        }
+    }
+}
+
+/// Flips a toggle's setting when its button is clicked.
+pub fn press_toggles(
+    button_query: Query<(&ToggleButton, &Interaction), Changed<Interaction>>,
+    mut config: ResMut<SimConfig>,
+    mut tx_query: Query<&mut Transmitter>,
+    bindings: Res<KeyBindings>,
+    mut hud: ResMut<Hud>,
+) {
+    for (button, interaction) in button_query.iter() {
+        if *interaction != Interaction::Pressed || button.0.locked(&config) {
+            continue;
+        }
+        let on = !button.0.get(&config);
+        button.0.set(&mut config, on);
+        match button.0 {
+            ToggleSetting::PointSource | ToggleSetting::PlaneWave => {
+                set_spawn_intervals(&config, &mut tx_query)
+            }
+            ToggleSetting::CrestsOnly => {
+                hud.set("crests_only", crests_only_label(on, &bindings));
+            }
+            ToggleSetting::SpectrumColors | ToggleSetting::PersistTraces => {}
+        }
+    }
+}
+
+/// Holds an action button's key down for as long as the button is pressed, so the key's own
+/// system handles it, shift and all, the same as if it were typed. Runs once the UI has updated
+/// the buttons and before anything reads the keys.
+pub fn press_action_buttons(
+    mut button_query: Query<(&mut ActionButton, &Interaction)>,
+    bindings: Res<KeyBindings>,
+    mut input: ResMut<ButtonInput<KeyCode>>,
+) {
+    for (mut button, interaction) in button_query.iter_mut() {
+        let key = if *interaction == Interaction::Pressed {
+            button.action.key(&bindings)
+        } else {
+            None
+        };
+        if key == button.held {
+            continue;
+        }
+        if let Some(held) = button.held {
+            input.release(held);
+        }
+        if let Some(key) = key {
+            input.press(key);
+        }
+        button.held = key;
+    }
+}
+
+/// Restarts every transmitter's spawn timer at the configured interval, which
+/// `target_in_flight` then tunes from afresh.
+fn set_spawn_intervals(config: &SimConfig, tx_query: &mut Query<&mut Transmitter>) {
+    let interval = config.spawn_interval();
+    for mut tx in tx_query.iter_mut() {
+        tx.spawn_rate.set_duration(interval);
+        tx.adaptive_interval = None;
+    }
+}
+
+/// Moves the sliders and buttons to match the settings, which keys can change too, while the
+/// panel is shown.
+pub fn sync_settings_panel(
+    panel_query: Query<&Visibility, With<SettingsPanel>>,
+    config: Res<SimConfig>,
+    amplitude: Res<CurrentAmplitude>,
+    selected: Res<SelectedReceiver>,
+    tx_query: Query<&Transmitter>,
+    mut handle_query: Query<(&SliderHandle, &mut Style)>,
+    mut value_query: Query<(&SliderValue, &mut Text)>,
+    mut button_query: Query<(&ToggleButton, &mut BackgroundColor)>,
+) {
+    if panel_query
+        .get_single()
+        .map_or(true, |visibility| *visibility == Visibility::Hidden)
+    {
+        return;
+    }
+    let value = |setting: SliderSetting| match setting {
+        SliderSetting::Frequency => tx_query
+            .iter()
+            .find(|tx| tx.index == selected.0)
+            .map(|tx| tx.frequency),
+        SliderSetting::WaveSpeed => Some(config.wave_speed),
+        SliderSetting::Amplitude => Some(amplitude.0),
+        SliderSetting::SpawnInterval => Some(config.spawn_interval().as_millis() as f32),
+    };
+
+    for (handle, mut style) in handle_query.iter_mut() {
+        let fraction = value(handle.0).map_or(0., |value| handle.0.fraction(value));
+        let left = Val::Percent(fraction * 100.);
+        if style.left != left {
+            style.left = left;
+        }
+    }
+    for (slider_value, mut text) in value_query.iter_mut() {
+        let formatted =
+            value(slider_value.0).map_or("-".into(), |value| slider_value.0.format(value));
+        if text.sections[1].value != formatted {
+            text.sections[1].value = formatted;
+        }
+    }
+    for (button, mut background) in button_query.iter_mut() {
+        let color = if button.0.locked(&config) {
+            BUTTON_LOCKED_COLOR
+        } else if button.0.get(&config) {
+            BUTTON_ON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
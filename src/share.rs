@@ -0,0 +1,181 @@
+//! Shareable parameter strings. The current setup is written as `key=value` pairs joined by `&`,
+//! like a URL query string, with each value in RON so it reads the same as in the config file.
+//!
+//! On native builds the string is also copied to the clipboard, and another key applies a string
+//! pasted back from the clipboard. The clipboard is kept open for as long as the app runs, since
+//! on X11 and Wayland copied text is served by the app that copied it and goes when it's closed.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    amplitude_label,
+    compare::{compare_label, CompareMode},
+    config::SimConfig,
+    hud::Hud,
+    keybindings::KeyBindings,
+    physics, CurrentAmplitude, SimulationReset, AMPLITUDE_STEP, PARTICLE_AMPLITUDE,
+};
+
+pub use clipboard::SharedClipboard;
+
+const PAIR_SEPARATOR: char = '&';
+
+/// Writes the parameters that shape the wave and what the receivers see. Frequencies are set by
+/// the scenario, or by each side of compare mode while it's on.
+pub fn parameter_string(config: &SimConfig, amplitude: f32, compare: &CompareMode) -> String {
+    let pairs = [
+        ("scenario", to_ron(&config.scenario)),
+        ("compare", to_ron(&compare.active_sides())),
+        ("wave_speed", to_ron(&config.wave_speed)),
+        ("amplitude", to_ron(&amplitude)),
+        ("waveform", to_ron(&config.waveform)),
+        (
+            "transmitter_waveforms",
+            to_ron(&config.transmitter_waveforms),
+        ),
+        ("emission_mode", to_ron(&config.emission_mode)),
+        ("beam_width", to_ron(&config.beam_width)),
+        ("beam_direction", to_ron(&config.beam_direction)),
+        ("propagation_angle", to_ron(&config.propagation_angle)),
+        ("doppler_formula", to_ron(&config.doppler_formula)),
+        ("trigger", to_ron(&config.trigger)),
+        ("plot_decimation", to_ron(&config.plot_decimation)),
+        ("seed", to_ron(&config.seed)),
+    ];
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(&PAIR_SEPARATOR.to_string())
+}
+
+fn to_ron<T: Serialize>(value: &T) -> String {
+    ron::to_string(value).unwrap_or_default()
+}
+
+/// Applies a string from [`parameter_string`]. Any subset of the keys can be given; nothing is
+/// changed unless every pair parses.
+pub fn apply_parameter_string(
+    parameters: &str,
+    config: &mut SimConfig,
+    amplitude: &mut f32,
+    compare: &mut CompareMode,
+) -> Result<(), String> {
+    let mut new_config = config.clone();
+    let mut new_amplitude = *amplitude;
+    let mut new_sides = compare.active_sides();
+    for pair in parameters.trim().split(PAIR_SEPARATOR) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got {pair:?}"))?;
+        match key {
+            "scenario" => new_config.scenario = parse(key, value)?,
+            "compare" => new_sides = parse(key, value)?,
+            "wave_speed" => {
+                new_config.wave_speed = physics::clamp_wave_speed(parse(key, value)?);
+            }
+            "amplitude" => {
+                new_amplitude = parse::<f32>(key, value)?.clamp(AMPLITUDE_STEP, PARTICLE_AMPLITUDE);
+            }
+            "waveform" => new_config.waveform = parse(key, value)?,
+            "transmitter_waveforms" => new_config.transmitter_waveforms = parse(key, value)?,
+            "emission_mode" => new_config.emission_mode = parse(key, value)?,
+            "beam_width" => new_config.beam_width = parse(key, value)?,
+            "beam_direction" => new_config.beam_direction = parse(key, value)?,
+            "propagation_angle" => new_config.propagation_angle = parse(key, value)?,
+            "doppler_formula" => new_config.doppler_formula = parse(key, value)?,
+            "trigger" => new_config.trigger = parse(key, value)?,
+            "plot_decimation" => new_config.plot_decimation = parse::<u32>(key, value)?.max(1),
+            "seed" => new_config.seed = parse(key, value)?,
+            _ => return Err(format!("unknown parameter {key:?}")),
+        }
+    }
+    *config = new_config;
+    *amplitude = new_amplitude;
+    compare.set_active_sides(new_sides);
+    Ok(())
+}
+
+fn parse<T: DeserializeOwned>(key: &str, value: &str) -> Result<T, String> {
+    ron::from_str(value).map_err(|err| format!("bad value for {key}: {err}"))
+}
+
+/// Prints the current parameters, and copies them to the clipboard on native builds. The apply
+/// key reads parameters back from the clipboard and restarts the simulation with them.
+pub fn share_parameters(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut amplitude: ResMut<CurrentAmplitude>,
+    mut compare: ResMut<CompareMode>,
+    mut clipboard: NonSendMut<SharedClipboard>,
+    mut reset_events: EventWriter<SimulationReset>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.copy_parameters) {
+        let parameters = parameter_string(&config, amplitude.0, &compare);
+        info!("Parameters: {parameters}");
+        match clipboard.set(parameters) {
+            Ok(()) => info!("Parameters copied to the clipboard"),
+            Err(err) => warn!("Cannot copy parameters to the clipboard: {err}"),
+        }
+    }
+
+    if input.just_pressed(bindings.paste_parameters) {
+        let result = clipboard.get().and_then(|parameters| {
+            apply_parameter_string(&parameters, &mut config, &mut amplitude.0, &mut compare)
+        });
+        match result {
+            Ok(()) => {
+                info!("Parameters applied from the clipboard");
+                hud.set("amplitude", amplitude_label(amplitude.0, &bindings));
+                hud.set("compare", compare_label(&compare, &bindings));
+                reset_events.send(SimulationReset);
+            }
+            Err(err) => error!("Cannot apply parameters from the clipboard: {err}"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod clipboard {
+    use arboard::Clipboard;
+
+    /// The system clipboard, opened the first time it's used.
+    #[derive(Default)]
+    pub struct SharedClipboard(Option<Clipboard>);
+
+    impl SharedClipboard {
+        fn open(&mut self) -> Result<&mut Clipboard, String> {
+            if self.0.is_none() {
+                self.0 = Some(Clipboard::new().map_err(|err| err.to_string())?);
+            }
+            Ok(self.0.as_mut().expect("the clipboard was just opened"))
+        }
+
+        pub fn set(&mut self, text: String) -> Result<(), String> {
+            self.open()?.set_text(text).map_err(|err| err.to_string())
+        }
+
+        pub fn get(&mut self) -> Result<String, String> {
+            self.open()?.get_text().map_err(|err| err.to_string())
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod clipboard {
+    #[derive(Default)]
+    pub struct SharedClipboard;
+
+    impl SharedClipboard {
+        pub fn set(&mut self, _text: String) -> Result<(), String> {
+            Err("no clipboard access on the web".to_string())
+        }
+
+        pub fn get(&mut self) -> Result<String, String> {
+            Err("no clipboard access on the web".to_string())
+        }
+    }
+}
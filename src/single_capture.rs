@@ -0,0 +1,94 @@
+//! Single captures: a receiver waits for its trigger, plots exactly one sweep, then saves it as a
+//! CSV of its samples and a PNG of the canvas and stops, for documenting one particular waveform
+//! the same way every time.
+
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{capture::SnapshotCanvas, config::SimConfig, Receiver, RxSample, RECEIVER_WIDTH};
+
+/// How far a receiver has got with its single capture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum CaptureStage {
+    /// Waiting for the trigger.
+    #[default]
+    Armed,
+    /// Triggered and plotting its one sweep.
+    Capturing,
+    /// The sweep is complete and waiting to be saved.
+    Done,
+    /// Saved. Nothing more happens until the next reset arms it again.
+    Exported,
+}
+
+/// A receiver's single capture, from [`crate::config::SingleCapture`].
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct SingleCaptureState {
+    /// File name the sweep is saved under in `screenshot_dir`, without an extension.
+    pub name: String,
+    pub stage: CaptureStage,
+}
+
+/// Moves each receiver's single capture along: it starts capturing once the trigger has fired,
+/// or its first sample without one, is done once it has plotted across its full width, and is
+/// then saved.
+pub fn run_single_captures(
+    config: Res<SimConfig>,
+    mut rx_query: Query<&mut Receiver>,
+    mut snapshots: EventWriter<SnapshotCanvas>,
+) {
+    for mut rx in rx_query.iter_mut() {
+        let Some(stage) = rx.single_capture.as_ref().map(|capture| capture.stage) else {
+            continue;
+        };
+        let next = match stage {
+            CaptureStage::Armed
+                if rx.triggered || (rx.trigger.is_none() && !rx.samples.is_empty()) =>
+            {
+                CaptureStage::Capturing
+            }
+            CaptureStage::Capturing if rx.current_draw_position > 2. * RECEIVER_WIDTH => {
+                CaptureStage::Done
+            }
+            CaptureStage::Done => {
+                let Some(capture) = &rx.single_capture else {
+                    continue;
+                };
+                let csv_path = config.screenshot_dir.join(format!("{}.csv", capture.name));
+                match write_sweep(&csv_path, &rx.samples) {
+                    Ok(()) => info!(
+                        "Receiver {} sweep saved to {}",
+                        rx.index + 1,
+                        csv_path.display()
+                    ),
+                    Err(err) => error!("Cannot save sweep {}: {err}", csv_path.display()),
+                }
+                let png_path = config.screenshot_dir.join(format!("{}.png", capture.name));
+                snapshots.send(SnapshotCanvas(png_path));
+                CaptureStage::Exported
+            }
+            stage => stage,
+        };
+        if next != stage {
+            if let Some(capture) = rx.single_capture.as_mut() {
+                capture.stage = next;
+            }
+        }
+    }
+}
+
+/// Writes `samples` as CSV, one row per sample in the order they were plotted.
+fn write_sweep<'a>(path: &Path, samples: impl IntoIterator<Item = &'a RxSample>) -> io::Result<()> {
+    let mut csv = String::from("sample,plot_x,value,distance\n");
+    for (index, sample) in samples.into_iter().enumerate() {
+        let distance = sample
+            .distance
+            .map_or(String::new(), |distance| distance.to_string());
+        csv.push_str(&format!(
+            "{index},{},{},{distance}\n",
+            sample.plot_x, sample.value
+        ));
+    }
+    fs::write(path, csv)
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+};
+
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 4.;
+
+/// Simulation speed multiplier. Key presses set `target` and `current` eases towards it, so
+/// speed changes ramp rather than jump.
+///
+/// The speed is applied to virtual time, whose elapsed time stays continuous while the speed
+/// changes, so waves keep their phase through a ramp.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SimSpeed {
+    target: f32,
+    current: f32,
+    /// Speed the current ramp started from.
+    from: f32,
+    /// Seconds of real time since the current ramp started.
+    elapsed: f32,
+}
+
+impl Default for SimSpeed {
+    fn default() -> Self {
+        Self {
+            target: 1.,
+            current: 1.,
+            from: 1.,
+            elapsed: 0.,
+        }
+    }
+}
+
+/// The speed up key (']' by default) doubles the simulation speed and slow down ('[') halves it.
+pub fn change_sim_speed(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut speed: ResMut<SimSpeed>,
+    mut hud: ResMut<Hud>,
+) {
+    let factor = if input.just_pressed(bindings.speed_up) {
+        2.
+    } else if input.just_pressed(bindings.slow_down) {
+        0.5
+    } else {
+        return;
+    };
+    let target = (speed.target * factor).clamp(MIN_SPEED, MAX_SPEED);
+    if target != speed.target {
+        speed.target = target;
+        speed.from = speed.current;
+        speed.elapsed = 0.;
+        hud.set("sim_speed", sim_speed_label(target, &bindings));
+    }
+}
+
+pub fn sim_speed_label(speed: f32, bindings: &KeyBindings) -> String {
+    format!(
+        "Speed: {speed}x (press {} or {} to change)",
+        key_name(bindings.slow_down),
+        key_name(bindings.speed_up)
+    )
+}
+
+/// Moves the current speed towards the target over `sim_speed_easing_secs` of real time, with a
+/// smoothstep curve so the ramp starts and ends gently.
+pub fn ease_sim_speed(
+    config: Res<SimConfig>,
+    real_time: Res<Time<Real>>,
+    mut speed: ResMut<SimSpeed>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if speed.current == speed.target {
+        return;
+    }
+    speed.elapsed += real_time.delta_seconds();
+    let progress = if config.sim_speed_easing_secs > 0. {
+        (speed.elapsed / config.sim_speed_easing_secs).min(1.)
+    } else {
+        1.
+    };
+    let eased = progress * progress * (3. - 2. * progress);
+    speed.current = if progress >= 1. {
+        speed.target
+    } else {
+        speed.from + (speed.target - speed.from) * eased
+    };
+    time.set_relative_speed(speed.current);
+}
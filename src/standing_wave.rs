@@ -0,0 +1,198 @@
+//! Nodes and antinodes of the standing wave between two transmitters sending towards each other:
+//! the field is sampled along the line between them over a period, and the points where it stays
+//! near zero or swings furthest are marked and labelled, so the half-wavelength spacing can be
+//! measured off the display.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    follow, heatmap,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics::{self, FieldSource},
+    CurrentAmplitude, InGameCamera, OverlayFont, Transmitter, HIGH_RES_LAYERS,
+};
+
+const NODE_COLOR: Color = Color::CYAN;
+const ANTINODE_COLOR: Color = Color::ORANGE;
+const MARKER_SIZE: f32 = 10.;
+const LABEL_FONT_SIZE: f32 = 14.;
+/// Gap between a marker and its label, across the line between the transmitters.
+const LABEL_GAP: f32 = 16.;
+/// Distance between the points the field is sampled at along the line, in pixels.
+const SAMPLE_SPACING: f32 = 2.;
+/// Times over one period the field is sampled at to find how far each point swings.
+const TIME_SAMPLES: usize = 24;
+
+/// One of the labels naming the markers. Spare ones are hidden.
+#[derive(Component)]
+pub struct NodeLabel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extremum {
+    /// The field stays close to zero.
+    Node,
+    /// The field swings close to its furthest.
+    Antinode,
+}
+
+pub fn standing_wave_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.standing_wave_nodes {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press {} to {action} standing wave nodes and antinodes",
+        key_name(bindings.standing_wave_nodes)
+    )
+}
+
+pub fn toggle_standing_wave_nodes(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.standing_wave_nodes) {
+        return;
+    }
+    config.standing_wave_nodes = !config.standing_wave_nodes;
+    hud.set(
+        "standing_wave_nodes",
+        standing_wave_label(&config, &bindings),
+    );
+}
+
+/// Furthest the field at `point` swings from zero over the `period` up to `t`.
+fn swing(point: Vec2, sources: &[FieldSource], t: f32, period: f32) -> f32 {
+    (0..TIME_SAMPLES)
+        .map(|i| {
+            let t = t - period * i as f32 / TIME_SAMPLES as f32;
+            physics::sample_field(point, sources, t).abs()
+        })
+        .fold(0., f32::max)
+}
+
+/// Indices of the nodes and antinodes in `swings`, the swing of the field at evenly spaced
+/// points. A node is a local minimum within `threshold` of the largest swing's worth of zero,
+/// and an antinode a local maximum within `threshold` of the largest swing.
+pub fn find_extrema(swings: &[f32], threshold: f32) -> Vec<(usize, Extremum)> {
+    let peak = swings.iter().copied().fold(0., f32::max);
+    if peak <= 0. {
+        return Vec::new();
+    }
+    swings
+        .windows(3)
+        .enumerate()
+        .filter_map(|(i, window)| {
+            let [before, value, after] = [window[0], window[1], window[2]];
+            // Strict on one side only, so a flat bottom or top is marked once.
+            if value < before && value <= after && value <= threshold * peak {
+                Some((i + 1, Extremum::Node))
+            } else if value > before && value >= after && value >= (1. - threshold) * peak {
+                Some((i + 1, Extremum::Antinode))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// While `standing_wave_nodes` is on, samples the field along the line between the two lowest
+/// numbered emitting transmitters over a period of the first one's wave, and marks the nodes with
+/// circles and the antinodes with squares, each labelled.
+pub fn draw_standing_wave_nodes(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    amplitude: Res<CurrentAmplitude>,
+    font: Res<OverlayFont>,
+    time: Res<Time>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    in_game_camera_query: Query<&Transform, (With<InGameCamera>, Without<NodeLabel>)>,
+    mut label_query: Query<(&mut Text, &mut Transform, &mut Visibility), With<NodeLabel>>,
+    mut gizmos: Gizmos,
+) {
+    let mut extrema = Vec::new();
+    let mut ends = tx_query
+        .iter()
+        .filter(|(tx, _)| tx.emitting)
+        .collect::<Vec<_>>();
+    ends.sort_by_key(|(tx, _)| tx.index);
+    if let [(first, first_transform), (second, second_transform), ..] = ends[..] {
+        let start = first_transform.translation().xy() + first.spawn_point;
+        let end = second_transform.translation().xy() + second.spawn_point;
+        let length = start.distance(end);
+        if config.standing_wave_nodes && first.frequency > 0. && length > SAMPLE_SPACING {
+            let sources = heatmap::field_sources(tx_query.iter(), &config, amplitude.0);
+            let period = first.frequency.recip();
+            let t = time.elapsed().as_millis() as f32 / 1000.;
+            let steps = (length / SAMPLE_SPACING) as usize;
+            let points: Vec<Vec2> = (0..=steps)
+                .map(|i| start.lerp(end, i as f32 / steps as f32))
+                .collect();
+            let swings: Vec<f32> = points
+                .iter()
+                .map(|&point| swing(point, &sources, t, period))
+                .collect();
+            let across = (end - start).normalize().perp();
+            let angle = Vec2::X.angle_between(across);
+            for (i, extremum) in find_extrema(&swings, config.standing_wave_threshold) {
+                extrema.push((points[i], across, extremum));
+                match extremum {
+                    Extremum::Node => {
+                        gizmos.circle_2d(points[i], MARKER_SIZE / 2., NODE_COLOR);
+                    }
+                    Extremum::Antinode => {
+                        gizmos.rect_2d(points[i], angle, Vec2::splat(MARKER_SIZE), ANTINODE_COLOR);
+                    }
+                }
+            }
+        }
+    }
+
+    let camera = in_game_camera_query.get_single().ok();
+    let mut labels = label_query.iter_mut();
+    for (point, across, extremum) in extrema {
+        let (value, color) = match extremum {
+            Extremum::Node => ("N", NODE_COLOR),
+            Extremum::Antinode => ("A", ANTINODE_COLOR),
+        };
+        // Labels are drawn on the high-res layer, which doesn't pan or zoom with the world.
+        let position = (follow::world_to_canvas(camera, point) + across * LABEL_GAP)
+            .extend(config.z_layers.labels);
+        match labels.next() {
+            Some((mut text, mut transform, mut visibility)) => {
+                let section = &mut text.sections[0];
+                if section.value != value {
+                    section.value = value.to_string();
+                    section.style.color = color;
+                }
+                transform.translation = position;
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            value,
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: LABEL_FONT_SIZE,
+                                color,
+                            },
+                        ),
+                        transform: Transform::from_translation(position),
+                        ..default()
+                    },
+                    NodeLabel,
+                    HIGH_RES_LAYERS,
+                ));
+            }
+        }
+    }
+    for (_, _, mut visibility) in labels {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+}
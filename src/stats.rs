@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::{config::SimConfig, Receiver, SignalParticle};
+
+const STATS_INTERVAL_SECS: f32 = 1.;
+
+/// Estimates the frequency of `values`, sampled evenly over `secs` seconds, from how often they
+/// cross zero. Each full cycle crosses twice. A crossing only counts once the values have left
+/// the band `hysteresis` either side of zero on the other side, so noise around zero doesn't
+/// add crossings.
+pub fn crossing_frequency(values: &[f32], secs: f32, hysteresis: f32) -> f32 {
+    let mut above = None;
+    let mut crossings = 0;
+    for &value in values {
+        let side = if value >= hysteresis {
+            Some(true)
+        } else if value < -hysteresis {
+            Some(false)
+        } else {
+            None
+        };
+        if let Some(side) = side {
+            if above.replace(side).is_some_and(|above| above != side) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings as f32 / 2. / secs
+}
+
+/// Frequency `rx` has been hearing, from the zero crossings of its samples over the last
+/// [`crate::SAMPLE_RATE_WINDOW_SECS`], or `None` before it has heard enough. See
+/// [`crossing_frequency`] for `hysteresis`.
+pub fn measured_frequency(rx: &Receiver, hysteresis: f32) -> Option<f32> {
+    let (first, last) = (rx.sample_times.front()?, rx.sample_times.back()?);
+    let secs = last - first;
+    let count = rx.sample_times.len().min(rx.samples.len());
+    if secs <= 0. || count < 2 {
+        return None;
+    }
+    let values: Vec<f32> = rx
+        .samples
+        .range(rx.samples.len() - count..)
+        .map(|sample| sample.value)
+        .collect();
+    Some(crossing_frequency(&values, secs, hysteresis))
+}
+
+/// Folds a new frequency `estimate` into the running `average`, an exponential moving average
+/// keeping `smoothing` of the old average each time: 0 just takes the latest estimate, and closer
+/// to 1 settles more slowly but jitters less. Without an estimate the average stays as it was.
+pub fn smooth_frequency(
+    average: Option<f32>,
+    estimate: Option<f32>,
+    smoothing: f32,
+) -> Option<f32> {
+    match (average, estimate) {
+        (Some(average), Some(estimate)) => Some(average * smoothing + estimate * (1. - smoothing)),
+        (average, estimate) => estimate.or(average),
+    }
+}
+
+/// Logs a summary of the simulation every second when `stats_log` is on: live particles, and how
+/// many particles each receiver took in since the last summary along with the frequency they
+/// suggest.
+pub fn log_stats(
+    config: Res<SimConfig>,
+    real_time: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    particle_query: Query<(), With<SignalParticle>>,
+    rx_query: Query<&Receiver>,
+    mut timer: Local<Option<Timer>>,
+    // Number of samples each receiver had at the last summary, by receiver index.
+    mut sample_counts: Local<Vec<usize>>,
+) {
+    if !config.stats_log {
+        return;
+    }
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(STATS_INTERVAL_SECS, TimerMode::Repeating));
+    if !timer.tick(real_time.delta()).just_finished() {
+        return;
+    }
+
+    let mut receivers: Vec<_> = rx_query.iter().collect();
+    receivers.sort_by_key(|rx| rx.index);
+    let elapsed = STATS_INTERVAL_SECS * virtual_time.relative_speed();
+    let summaries: Vec<_> = receivers
+        .iter()
+        .map(|rx| {
+            if sample_counts.len() <= rx.index {
+                sample_counts.resize(rx.index + 1, 0);
+            }
+            // Receivers are rebuilt on reset, so a lower count means a new run.
+            let taken = rx.next_sample_index();
+            let start = sample_counts[rx.index].min(taken);
+            sample_counts[rx.index] = taken;
+            // Skip whatever of those has already been dropped from the buffer.
+            let first = start.saturating_sub(rx.dropped_samples);
+            let values: Vec<_> = rx.samples.range(first..).map(|s| s.value).collect();
+            let frequency = if virtual_time.is_paused() || elapsed <= 0. {
+                0.
+            } else {
+                crossing_frequency(&values, elapsed, config.crossing_hysteresis)
+            };
+            format!(
+                "rx{} {} received ~{frequency:.2} Hz",
+                rx.index,
+                values.len()
+            )
+        })
+        .collect();
+
+    let speed = if virtual_time.is_paused() {
+        "paused".to_string()
+    } else {
+        format!("{:.2}x", virtual_time.relative_speed())
+    };
+    info!(
+        "{} particles | {} | speed {speed}",
+        particle_query.iter().len(),
+        summaries.join(" | ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoothed_frequency_converges_on_a_noisy_estimate() {
+        // Estimates alternating either side of 2 Hz, off by up to half a hertz.
+        let noise = [0.5, -0.4, 0.3, -0.5, 0.2, -0.1, 0.4, -0.3];
+        let mut average = None;
+        for offset in noise.iter().cycle().take(200) {
+            average = smooth_frequency(average, Some(2. + offset), 0.9);
+        }
+        let average = average.unwrap();
+        // Much closer than any single estimate strays.
+        assert!((average - 2.).abs() < 0.05, "{average}");
+    }
+
+    #[test]
+    fn smoothed_frequency_keeps_the_average_without_an_estimate() {
+        assert_eq!(smooth_frequency(Some(3.), None, 0.9), Some(3.));
+        assert_eq!(smooth_frequency(None, Some(2.), 0.9), Some(2.));
+        assert_eq!(smooth_frequency(Some(3.), Some(2.), 0.), Some(2.));
+    }
+}
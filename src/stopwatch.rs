@@ -0,0 +1,101 @@
+//! A stopwatch for timing things on the live display by hand, such as a wave's period or a
+//! particle's time of flight: click once to start it and again to stop it.
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    settings::PointerOverUi,
+};
+
+/// Time between two clicks, read from the simulation clock so it stands still while paused and
+/// runs at the simulation speed.
+#[derive(Resource, Default)]
+pub struct PeriodMeasure {
+    active: bool,
+    start: Option<f32>,
+    stop: Option<f32>,
+}
+
+impl PeriodMeasure {
+    /// Whether clicks go to the stopwatch rather than dragging or inspecting.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn reset(&mut self) {
+        self.start = None;
+        self.stop = None;
+    }
+}
+
+pub fn period_measure_help(bindings: &KeyBindings) -> String {
+    format!(
+        "Press {} to time between two clicks",
+        key_name(bindings.period_measure)
+    )
+}
+
+/// The stopwatch key opens or closes the stopwatch, and with shift clears the time while leaving
+/// it open.
+pub fn toggle_period_measure(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut period: ResMut<PeriodMeasure>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.period_measure) {
+        return;
+    }
+    let shift = input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !(shift && period.active) {
+        period.active = !period.active;
+    }
+    period.reset();
+    if !period.active {
+        hud.set("period_measure", period_measure_help(&bindings));
+    }
+}
+
+/// Starts the stopwatch on a left click, or stops it if it's running, and shows the time so far.
+/// Starting again after a stop begins a new measurement.
+pub fn measure_period(
+    mouse: Res<ButtonInput<MouseButton>>,
+    pointer: Res<PointerOverUi>,
+    bindings: Res<KeyBindings>,
+    time: Res<Time>,
+    mut period: ResMut<PeriodMeasure>,
+    mut hud: ResMut<Hud>,
+) {
+    if !period.active {
+        return;
+    }
+    let now = time.elapsed_seconds();
+    if mouse.just_pressed(MouseButton::Left) && !pointer.0 {
+        if period.start.is_some() && period.stop.is_none() {
+            period.stop = Some(now);
+        } else {
+            period.start = Some(now);
+            period.stop = None;
+        }
+    }
+
+    let key = key_name(bindings.period_measure);
+    let label = match (period.start, period.stop) {
+        (None, _) => format!("Stopwatch: click to start (press {key} to close)"),
+        (Some(start), None) => format!("Stopwatch: {:.3}s (click to stop)", now - start),
+        (Some(start), Some(stop)) => {
+            let elapsed = stop - start;
+            let frequency = if elapsed > 0. {
+                format!(", {:.2} Hz if that's one period", 1. / elapsed)
+            } else {
+                String::new()
+            };
+            format!(
+                "Stopwatch: {elapsed:.3}s{frequency} (click to start again, shift+{key} to clear)"
+            )
+        }
+    };
+    hud.set("period_measure", label);
+}
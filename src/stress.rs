@@ -0,0 +1,51 @@
+//! Visual stress test, enabled with the `stress` feature, that fills the screen with a grid of
+//! transmitter/receiver pairs instead of the configured scenario.
+
+use std::env;
+
+use bevy::prelude::*;
+
+use crate::{Movement, SimulationSpec, RES_HEIGHT, RES_WIDTH};
+
+const DEFAULT_ROWS: usize = 8;
+const DEFAULT_COLUMNS: usize = 3;
+
+/// Reads a grid dimension from the environment, falling back to `default` when unset or invalid.
+fn grid_size(var: &str, default: usize) -> usize {
+    env::var(var)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(default)
+}
+
+/// `DOPPL_STRESS_ROWS` x `DOPPL_STRESS_COLUMNS` pairs spread evenly over the canvas. Each pair
+/// gets a cell with its transmitter on the right and its receiver on the left, and alternating
+/// receivers move so the collision handling sees both stationary and moving receivers.
+pub fn grid_simulations() -> Vec<SimulationSpec> {
+    let rows = grid_size("DOPPL_STRESS_ROWS", DEFAULT_ROWS);
+    let columns = grid_size("DOPPL_STRESS_COLUMNS", DEFAULT_COLUMNS);
+    let cell = Vec2::new(
+        RES_WIDTH as f32 / columns as f32,
+        RES_HEIGHT as f32 / rows as f32,
+    );
+    let origin = -Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / 2.;
+
+    (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let left = origin.x + cell.x * column as f32;
+            SimulationSpec {
+                rx_start_x: left + cell.x * 0.2,
+                tx_x: left + cell.x * 0.8,
+                y_pos: origin.y + cell.y * (row as f32 + 0.5),
+                rx_movement: if (row + column) % 2 == 0 {
+                    Movement::Stationary
+                } else {
+                    Movement::Right
+                },
+                ..default()
+            }
+        })
+        .collect()
+}
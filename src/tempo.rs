@@ -0,0 +1,94 @@
+//! Emitting in time with music: transmitters with an [`EmissionPattern::OnBeat`] pattern send
+//! one wavefront per beat of a tempo that can be nudged up and down while presenting, each
+//! optionally marked by a ring flashing out from the transmitter.
+//!
+//! [`EmissionPattern::OnBeat`]: crate::config::EmissionPattern::OnBeat
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{EmissionPattern, SimConfig},
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    Transmitter, TRANSMITTER_SIZE,
+};
+
+/// Slowest and fastest tempos, in beats per minute.
+pub const TEMPO_RANGE: (f32, f32) = (20., 300.);
+/// Beats per minute each press of the tempo keys changes it by.
+const TEMPO_STEP: f32 = 5.;
+/// Seconds a beat's flash takes to spread out and fade away.
+const FLASH_SECS: f32 = 0.25;
+const FLASH_COLOR: Color = Color::WHITE;
+/// How far the flash spreads, as a multiple of the transmitter's size.
+const FLASH_SPREAD: f32 = 2.;
+
+/// Beats per minute transmitters with an [`EmissionPattern::OnBeat`] pattern emit at.
+#[derive(Resource)]
+pub struct Tempo(pub f32);
+
+impl Tempo {
+    /// Time between beats.
+    pub fn beat(&self) -> Duration {
+        Duration::from_secs_f32(60. / self.0)
+    }
+}
+
+pub fn tempo_label(bpm: f32, bindings: &KeyBindings) -> String {
+    format!(
+        "Tempo: {bpm:.0} BPM (press {} or {} to change)",
+        key_name(bindings.tempo_down),
+        key_name(bindings.tempo_up)
+    )
+}
+
+/// Speeds the tempo up or slows it down a step, within [`TEMPO_RANGE`]. Transmitters pick the
+/// new tempo up from their next beat.
+pub fn change_tempo(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut tempo: ResMut<Tempo>,
+    mut hud: ResMut<Hud>,
+) {
+    let step = if input.just_pressed(bindings.tempo_up) {
+        TEMPO_STEP
+    } else if input.just_pressed(bindings.tempo_down) {
+        -TEMPO_STEP
+    } else {
+        return;
+    };
+    let (slowest, fastest) = TEMPO_RANGE;
+    tempo.0 = (tempo.0 + step).clamp(slowest, fastest);
+    hud.set("tempo", tempo_label(tempo.0, &bindings));
+}
+
+/// Draws a ring spreading out from and fading around each transmitter just after it emits on a
+/// beat, while `beat_flash` is on.
+pub fn draw_beat_flashes(
+    config: Res<SimConfig>,
+    time: Res<Time>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if !config.beat_flash {
+        return;
+    }
+    let now = time.elapsed().as_millis() as f32 / 1000.;
+    for (tx, transform) in tx_query.iter() {
+        let Some(emitted) = tx.last_emission else {
+            continue;
+        };
+        let age = (now - emitted) / FLASH_SECS;
+        if tx.pattern != EmissionPattern::OnBeat || !(0. ..1.).contains(&age) {
+            continue;
+        }
+        let radius = TRANSMITTER_SIZE / 2. * (1. + age * (FLASH_SPREAD - 1.));
+        gizmos.circle_2d(
+            transform.translation().xy() + tx.spawn_point,
+            radius,
+            FLASH_COLOR.with_a(1. - age),
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Oscilloscope style labels along the bottom of each receiver's plot, counting off half periods
+//! of its transmitter's wave from where the plot starts, so times can be read off the trace.
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    config::SimConfig,
+    follow::WorldLabel,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    physics,
+    receiver_view::{ReceiverOrientation, ReceiverView},
+    OverlayFont, Receiver, Transmitter, HIGH_RES_LAYERS, RECEIVER_DELTA_X_PER_SECOND,
+    RECEIVER_SIZE, RECEIVER_WIDTH,
+};
+
+const TICK_LABEL_FONT_SIZE: f32 = 12.;
+const TICK_LABEL_COLOR: Color = Color::rgba(1., 1., 1., 0.8);
+/// Gap between the bottom of a receiver and its labels.
+const TICK_LABEL_GAP: f32 = 4.;
+/// Closest labels are put together along the plot. Closer than this, only every other one is
+/// kept, and so on.
+const MIN_TICK_LABEL_SPACING: f32 = 30.;
+
+/// One of the labels along a receiver's time axis.
+#[derive(Component)]
+pub struct TimeTickLabel;
+
+/// The period a receiver's time axis labels were laid out for.
+#[derive(Component)]
+pub struct TimeAxis(f32);
+
+pub fn time_axis_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.time_axis_labels {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press {} to {action} time axis labels under each receiver",
+        key_name(bindings.time_axis)
+    )
+}
+
+pub fn toggle_time_axis(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if !input.just_pressed(bindings.time_axis) {
+        return;
+    }
+    config.time_axis_labels = !config.time_axis_labels;
+    hud.set("time_axis", time_axis_label(&config, &bindings));
+}
+
+/// `half_periods` halves of a period T, written as on a scope: "0", "T/2", "T", "3T/2", "2T"...
+fn half_period_text(half_periods: u32) -> String {
+    match (half_periods, half_periods % 2) {
+        (0, _) => "0".to_string(),
+        (1, _) => "T/2".to_string(),
+        (2, _) => "T".to_string(),
+        (n, 0) => format!("{}T", n / 2),
+        (n, _) => format!("{n}T/2"),
+    }
+}
+
+/// Each label along a plot of a wave with `period`, with how far along from the start of the
+/// plot it goes. Labels are every half period, or every whole period, two periods and so on if
+/// they'd be closer than [`MIN_TICK_LABEL_SPACING`].
+fn time_ticks(period: f32) -> Vec<(f32, String)> {
+    let half = period / 2. * RECEIVER_DELTA_X_PER_SECOND;
+    let mut step = 1;
+    while half * (step as f32) < MIN_TICK_LABEL_SPACING {
+        step *= 2;
+    }
+    (0..)
+        .step_by(step)
+        .map(|n| (n as f32 * half, n))
+        .take_while(|&(along, _)| along <= 2. * RECEIVER_WIDTH)
+        .map(|(along, n)| (along, half_period_text(n)))
+        .collect()
+}
+
+/// Shows or hides the time axis labels among a receiver's `children`.
+fn set_visibility(
+    label_query: &mut Query<(Entity, &mut Visibility), With<TimeTickLabel>>,
+    children: &[Entity],
+    value: Visibility,
+) {
+    let mut labels = label_query.iter_many_mut(children);
+    while let Some((_, mut visibility)) = labels.fetch_next() {
+        visibility.set_if_neq(value);
+    }
+}
+
+/// Lays out the time axis labels of each receiver for the period of its transmitter's wave, again
+/// whenever that changes, and shows them while `time_axis_labels` is on and the receiver's plot
+/// runs over time. They're children of the receiver, so they move with it and are replaced with
+/// it on a restart.
+pub fn label_time_axes(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    font: Res<OverlayFont>,
+    rx_query: Query<(Entity, &Receiver, Option<&TimeAxis>, Option<&Children>)>,
+    tx_query: Query<&Transmitter>,
+    mut label_query: Query<(Entity, &mut Visibility), With<TimeTickLabel>>,
+) {
+    for (rx_entity, rx, axis, children) in rx_query.iter() {
+        let period = tx_query
+            .iter()
+            .find(|tx| tx.index == rx.index)
+            .and_then(|tx| physics::period(tx.frequency));
+        let labels = children.map_or(&[][..], |children| &children[..]);
+        let period = match period {
+            Some(period) if config.time_axis_labels && rx.view != ReceiverView::Distance => period,
+            _ => {
+                set_visibility(&mut label_query, labels, Visibility::Hidden);
+                continue;
+            }
+        };
+        if axis.is_some_and(|axis| axis.0 == period) {
+            set_visibility(&mut label_query, labels, Visibility::Inherited);
+            continue;
+        }
+
+        for (label, _) in label_query.iter_many(labels) {
+            commands.entity(label).despawn_recursive();
+        }
+        // Labels are laid out as if the receiver were horizontal, then turned back so they're
+        // level, hanging below it or, stood on end, out to its right.
+        let unrotate = rx.orientation.rotation().inverse();
+        let anchor = match rx.orientation {
+            ReceiverOrientation::Horizontal => Anchor::TopCenter,
+            ReceiverOrientation::Vertical => Anchor::CenterLeft,
+        };
+        let y = -(RECEIVER_SIZE.y / 2. + TICK_LABEL_GAP);
+        let z = config.z_layers.relative_to_receiver(config.z_layers.labels);
+        let new_labels: Vec<Entity> = time_ticks(period)
+            .into_iter()
+            .map(|(along, text)| {
+                // Plots start at the right and grow towards -x as time goes on.
+                let x = RECEIVER_WIDTH - along;
+                commands
+                    .spawn((
+                        Text2dBundle {
+                            text: Text::from_section(
+                                text,
+                                TextStyle {
+                                    font: font.0.clone(),
+                                    font_size: TICK_LABEL_FONT_SIZE,
+                                    color: TICK_LABEL_COLOR,
+                                },
+                            ),
+                            text_anchor: anchor,
+                            transform: Transform::from_xyz(x, y, z).with_rotation(unrotate),
+                            ..default()
+                        },
+                        TimeTickLabel,
+                        WorldLabel,
+                        HIGH_RES_LAYERS,
+                    ))
+                    .id()
+            })
+            .collect();
+        commands
+            .entity(rx_entity)
+            .push_children(&new_labels)
+            .insert(TimeAxis(period));
+    }
+}
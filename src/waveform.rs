@@ -0,0 +1,226 @@
+//! Saving receiver traces to a compact binary file and loading them back.
+//!
+//! A capture file is a [`CaptureHeader`] followed by the traces, both encoded with `bincode`. The
+//! header is read on its own first so a file from an incompatible version is rejected with a
+//! clear message instead of failing halfway through decoding.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capture::write_via_temp,
+    config::{SimConfig, SweepMode},
+    keybindings::KeyBindings,
+    marker::PlotMarkerMesh,
+    spawn_plot_point, PlotPoint, Receiver, RxSample, Transmitter, RECEIVER_DELTA_X_PER_SECOND,
+    RECEIVER_WIDTH,
+};
+
+const CAPTURE_PATH: &str = "captures/capture.bin";
+
+/// Bumped whenever the layout of a capture file changes.
+const CAPTURE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct CaptureHeader {
+    version: u32,
+    /// How far the plot advances per second, in pixels. Traces recorded at a different rate would
+    /// be stretched or squashed if loaded as they are.
+    sweep_rate: f32,
+    receiver_count: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReceiverCapture {
+    index: u32,
+    /// `(value, plot_x)` for every sample, oldest first.
+    samples: Vec<(f32, f32)>,
+}
+
+/// Writes every receiver's samples to `path` with [`write_via_temp`], so a capture is either
+/// saved whole or not at all.
+fn write_capture(path: &Path, receivers: &[ReceiverCapture]) -> Result<(), String> {
+    write_via_temp(path, |temp| {
+        let mut writer = BufWriter::new(File::create(temp).map_err(|err| err.to_string())?);
+        let header = CaptureHeader {
+            version: CAPTURE_VERSION,
+            sweep_rate: RECEIVER_DELTA_X_PER_SECOND,
+            receiver_count: receivers.len() as u32,
+        };
+        bincode::serialize_into(&mut writer, &header).map_err(|err| err.to_string())?;
+        bincode::serialize_into(&mut writer, receivers).map_err(|err| err.to_string())?;
+        // Dropping the writer would flush it too, but silently ignore any error doing so.
+        writer.flush().map_err(|err| err.to_string())
+    })
+}
+
+/// Reads the receiver samples back from `path`, rejecting files from another version or sweep
+/// rate.
+fn read_capture(path: &Path) -> Result<Vec<ReceiverCapture>, String> {
+    let mut reader = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+    let header: CaptureHeader =
+        bincode::deserialize_from(&mut reader).map_err(|err| err.to_string())?;
+    if header.version != CAPTURE_VERSION {
+        return Err(format!(
+            "capture version {} is not supported (expected {CAPTURE_VERSION})",
+            header.version
+        ));
+    }
+    if header.sweep_rate != RECEIVER_DELTA_X_PER_SECOND {
+        return Err(format!(
+            "capture was recorded at {} px/s but the plots sweep at {RECEIVER_DELTA_X_PER_SECOND} \
+             px/s",
+            header.sweep_rate
+        ));
+    }
+    let receivers: Vec<ReceiverCapture> =
+        bincode::deserialize_from(&mut reader).map_err(|err| err.to_string())?;
+    if receivers.len() != header.receiver_count as usize {
+        return Err("capture is truncated".to_string());
+    }
+    Ok(receivers)
+}
+
+pub fn save_capture(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    rx_query: Query<&Receiver>,
+) {
+    // With shift it stores a trace for the cross-fade instead.
+    if !input.just_pressed(bindings.save_capture)
+        || input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+
+    let receivers: Vec<_> = rx_query
+        .iter()
+        .map(|rx| ReceiverCapture {
+            index: rx.index as u32,
+            samples: rx
+                .samples
+                .iter()
+                .map(|sample| (sample.value, sample.plot_x))
+                .collect(),
+        })
+        .collect();
+    let path = Path::new(CAPTURE_PATH);
+    match write_capture(path, &receivers) {
+        Ok(()) => info!("Capture saved to {}", path.display()),
+        Err(err) => error!("Cannot save capture {}: {err}", path.display()),
+    }
+}
+
+/// Replaces each receiver's trace with the one saved for the receiver at the same index. Loaded
+/// traces are shown complete, so the receivers stop plotting until the next reset.
+pub fn load_capture(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<SimConfig>,
+    marker: Res<PlotMarkerMesh>,
+    mut commands: Commands,
+    mut rx_query: Query<(Entity, &mut Receiver, Option<&Children>)>,
+    tx_query: Query<&Transmitter>,
+    plot_query: Query<Entity, With<PlotPoint>>,
+) {
+    if !input.just_pressed(bindings.load_capture) {
+        return;
+    }
+
+    let path = Path::new(CAPTURE_PATH);
+    let captures = match read_capture(path) {
+        Ok(captures) => captures,
+        Err(err) => {
+            error!("Cannot load capture {}: {err}", path.display());
+            return;
+        }
+    };
+
+    for (rx_entity, mut rx, children) in rx_query.iter_mut() {
+        let Some(capture) = captures.iter().find(|c| c.index as usize == rx.index) else {
+            continue;
+        };
+        let Some(tx) = tx_query.iter().find(|tx| tx.index == rx.index) else {
+            continue;
+        };
+
+        if let Some(children) = children {
+            for plot_point in plot_query.iter_many(children) {
+                commands.entity(plot_point).despawn_recursive();
+            }
+        }
+        rx.samples = capture
+            .samples
+            .iter()
+            .map(|&(value, plot_x)| RxSample {
+                value,
+                plot_x,
+                distance: None,
+                phase: None,
+            })
+            .collect();
+        for (index, sample) in rx.samples.iter().enumerate() {
+            let plot_point = spawn_plot_point(
+                &mut commands,
+                marker.0.clone(),
+                tx.plot_material.clone(),
+                &rx,
+                sample,
+                index,
+                config
+                    .z_layers
+                    .relative_to_receiver(config.z_layers.plot_points),
+            );
+            commands.entity(rx_entity).add_child(plot_point);
+        }
+        rx.current_draw_position = 2. * RECEIVER_WIDTH + 1.;
+        // A repeating sweep would clear the loaded trace as soon as anything arrives.
+        rx.sweep = SweepMode::OneShot;
+    }
+    info!("Capture loaded from {}", path.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, panic};
+
+    use super::*;
+
+    fn capture(samples: usize) -> Vec<ReceiverCapture> {
+        vec![ReceiverCapture {
+            index: 0,
+            samples: (0..samples).map(|i| (i as f32, -(i as f32))).collect(),
+        }]
+    }
+
+    #[test]
+    fn shutdown_mid_save_leaves_the_last_capture_readable() {
+        let dir = env::temp_dir().join(format!("doppl-waveform-{}", std::process::id()));
+        let path = dir.join("capture.bin");
+        write_capture(&path, &capture(100)).unwrap();
+
+        // The app going away half way through the next save, after part of it is written.
+        let interrupted = panic::catch_unwind(|| {
+            write_via_temp(&path, |temp| {
+                let mut file = File::create(temp).unwrap();
+                file.write_all(&[1, 0, 0, 0]).unwrap();
+                panic!("shut down mid-save");
+            })
+        });
+        assert!(interrupted.is_err());
+
+        let loaded = read_capture(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].samples, capture(100)[0].samples);
+        // What was written of the new capture is left aside, and rejected if it's read.
+        assert!(read_capture(&dir.join("capture.bin.partial")).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
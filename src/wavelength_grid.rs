@@ -0,0 +1,155 @@
+//! Gridlines across the propagation axis one wavelength apart, so wavelengths can be counted off
+//! against the particles. Unlike the snapping grid the spacing comes from the wave, and follows
+//! the wave speed and frequency as they change.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::SimConfig,
+    follow,
+    hud::Hud,
+    keybindings::{key_name, KeyBindings},
+    InGameCamera, OverlayFont, SelectedReceiver, Transmitter, HIGH_RES_LAYERS, RES_HEIGHT,
+    RES_WIDTH,
+};
+
+const GRID_COLOR: Color = Color::rgba(0.6, 0.8, 1., 0.25);
+const LABEL_COLOR: Color = Color::rgba(0.6, 0.8, 1., 0.8);
+const LABEL_FONT_SIZE: f32 = 14.;
+/// Closest the lines are drawn together, in pixels. Any closer and they'd fill the view.
+const MIN_SPACING: f32 = 4.;
+/// Gap between a label and the edge of the view.
+const LABEL_MARGIN: f32 = 20.;
+
+/// One of the labels counting wavelengths along the grid. Spare ones are hidden.
+#[derive(Component)]
+pub struct GridLabel;
+
+pub fn wavelength_grid_label(config: &SimConfig, bindings: &KeyBindings) -> String {
+    let action = if config.wavelength_grid {
+        "hide"
+    } else {
+        "show"
+    };
+    format!(
+        "Press shift+{} to {action} gridlines a wavelength apart",
+        key_name(bindings.wavelength_ruler)
+    )
+}
+
+/// Shift with the ruler key shows or hides the grid.
+pub fn toggle_wavelength_grid(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SimConfig>,
+    mut hud: ResMut<Hud>,
+) {
+    if input.just_pressed(bindings.wavelength_ruler)
+        && input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        config.wavelength_grid = !config.wavelength_grid;
+        hud.set("wavelength_grid", wavelength_grid_label(&config, &bindings));
+    }
+}
+
+/// Draws lines across the propagation axis every wavelength of the selected receiver's
+/// transmitter, counted from its spawn point, over the whole view. Every
+/// `wavelength_grid_label_every`th line is labelled with its count.
+pub fn draw_wavelength_grid(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    selected: Res<SelectedReceiver>,
+    font: Res<OverlayFont>,
+    tx_query: Query<(&Transmitter, &GlobalTransform)>,
+    in_game_camera_query: Query<&Transform, (With<InGameCamera>, Without<GridLabel>)>,
+    mut label_query: Query<(&mut Text, &mut Transform, &mut Visibility), With<GridLabel>>,
+    mut gizmos: Gizmos,
+) {
+    let source = tx_query
+        .iter()
+        .find(|(tx, _)| tx.index == selected.0)
+        .or_else(|| tx_query.iter().min_by_key(|(tx, _)| tx.index));
+    let wavelength = source
+        .filter(|(tx, _)| config.wavelength_grid && tx.frequency > 0.)
+        .map(|(tx, _)| config.wave_speed.abs() / tx.frequency)
+        .filter(|&wavelength| wavelength >= MIN_SPACING);
+    let (Some((tx, tx_transform)), Some(wavelength)) = (source, wavelength) else {
+        for (_, _, mut visibility) in label_query.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        return;
+    };
+
+    let direction = config.propagation_axis();
+    let across = direction.perp();
+    let anchor = tx_transform.translation().xy() + tx.spawn_point;
+    let camera = in_game_camera_query.get_single().ok();
+    let (pan, zoom) = camera.map_or((Vec2::ZERO, 1.), |transform| {
+        (transform.translation.xy(), transform.scale.x)
+    });
+    let half_view = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32) / 2. * zoom;
+    // Far enough along each line to cross the whole view whichever way it's turned.
+    let half_length = half_view.length();
+    let reach = (half_view.x * direction.x).abs() + (half_view.y * direction.y).abs();
+    let middle = (pan - anchor).dot(direction) / wavelength;
+    let first = (middle - reach / wavelength).floor() as i32;
+    let last = (middle + reach / wavelength).ceil() as i32;
+    // The view's centre, slid along to each line.
+    let centre_across = anchor + across * (pan - anchor).dot(across);
+    // Labels go towards the top of the view, or its left if the lines are level.
+    let label_side = if across.y.abs() > f32::EPSILON {
+        across * across.y.signum()
+    } else {
+        -across * across.x.signum()
+    };
+    let label_distance = (half_view.y * label_side.y).abs() + (half_view.x * label_side.x).abs()
+        - LABEL_MARGIN * zoom;
+
+    let every = config.wavelength_grid_label_every.max(1) as i32;
+    let mut labels = label_query.iter_mut();
+    for n in first..=last {
+        let centre = centre_across + direction * n as f32 * wavelength;
+        gizmos.line_2d(
+            centre - across * half_length,
+            centre + across * half_length,
+            GRID_COLOR,
+        );
+        if n % every != 0 {
+            continue;
+        }
+        // Labels are drawn on the high-res layer, which doesn't pan or zoom with the world.
+        let position = follow::world_to_canvas(camera, centre + label_side * label_distance)
+            .extend(config.z_layers.labels);
+        let value = format!("{n}λ");
+        match labels.next() {
+            Some((mut text, mut transform, mut visibility)) => {
+                if text.sections[0].value != value {
+                    text.sections[0].value = value;
+                }
+                transform.translation = position;
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            value,
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: LABEL_FONT_SIZE,
+                                color: LABEL_COLOR,
+                            },
+                        ),
+                        transform: Transform::from_translation(position),
+                        ..default()
+                    },
+                    GridLabel,
+                    HIGH_RES_LAYERS,
+                ));
+            }
+        }
+    }
+    for (_, _, mut visibility) in labels {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+}